@@ -0,0 +1,52 @@
+// programs/anchor-amm/fuzz/fuzz_targets/math.rs
+//! Fixed-point helpers: no panics, and each result within its documented rounding
+#![no_main]
+
+use anchor_amm::instructions::strategy::math::{
+    amount_after_fee, exp_neg, fee_amount, ln, mul_div, Rounding, U256, LN_MAX_ERROR, WAD,
+};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    a: u128,
+    b: u128,
+    c: u128,
+    amount: u64,
+    fee_bps: u16,
+    x: u128,
+}
+
+fuzz_target!(|input: Input| {
+    let Input { a, b, c, amount, fee_bps, x } = input;
+
+    // mul_div is the exact quotient, rounded as asked
+    if let (Ok(down), Ok(up)) = (mul_div(a, b, c, Rounding::Down), mul_div(a, b, c, Rounding::Up)) {
+        let product = U256::from(a) * U256::from(b);
+        assert!(U256::from(down) * U256::from(c) <= product);
+        assert!(U256::from(up) * U256::from(c) >= product);
+        assert!(up - down <= 1);
+    }
+
+    // The fee and the rest of the input add back up to it
+    let fee_bps = fee_bps as u64;
+    if fee_bps <= 10_000 {
+        let fee = fee_amount(amount, fee_bps).unwrap();
+        assert_eq!(fee + amount_after_fee(amount, fee_bps).unwrap(), amount);
+        assert!(fee as u128 * 10_000 >= amount as u128 * fee_bps as u128);
+    }
+
+    // e^(-x) is at most 1 and falls as x grows
+    let exp = exp_neg(x).unwrap();
+    assert!(exp <= WAD);
+    assert!(exp_neg(x.saturating_add(WAD / 1_000)).unwrap() <= exp);
+
+    // ln(e^(-x)) comes back to -x: exp_neg is off by about a unit, which ln
+    // scales by 1 / e^(-x), and ln itself by LN_MAX_ERROR plus its truncated ln(2)
+    if x < 40 * WAD && exp > 0 {
+        let log = ln(exp).unwrap();
+        let error = (log + x as i128).unsigned_abs();
+        assert!(error <= LN_MAX_ERROR + 100 + WAD / exp, "ln(exp_neg({x})) = {log}");
+    }
+});
@@ -7,6 +7,94 @@ pub enum AmmError {
     InsufficientLiquidity,
     #[msg("Slippage tolerance exceeded")]
     SlippageExceeded,
-    #[msg("Overflow")]
-    Overflow,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Invalid fee")]
+    InvalidFee,
+    #[msg("Tick out of range")]
+    InvalidTick,
+    #[msg("Invalid limit order")]
+    InvalidLimitOrder,
+    #[msg("Limit order is not filled")]
+    OrderNotFilled,
+    #[msg("Invalid farm schedule")]
+    InvalidFarmSchedule,
+    #[msg("Invalid LP mint")]
+    InvalidLpMint,
+    #[msg("Insufficient staked amount")]
+    InsufficientStake,
+    #[msg("Pool still has liquidity or open orders")]
+    PoolNotEmpty,
+    #[msg("Invalid oracle account or configuration")]
+    InvalidOracle,
+    #[msg("Oracle price is stale")]
+    OracleStale,
+    #[msg("Oracle confidence interval too wide")]
+    OracleConfidence,
+    #[msg("Invalid liquidity parameter")]
+    InvalidLiquidityParameter,
+    #[msg("Market is already resolved")]
+    MarketResolved,
+    #[msg("Invalid market outcome")]
+    InvalidOutcome,
+    #[msg("Launch has already graduated")]
+    LaunchGraduated,
+    #[msg("Launch has not reached its graduation market cap")]
+    GraduationNotReached,
+    #[msg("Invalid weight schedule")]
+    InvalidWeightSchedule,
+    #[msg("Pool is paused")]
+    PoolPaused,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Sale has not ended")]
+    SaleNotEnded,
+    #[msg("Pool reserve is zero")]
+    ZeroReserve,
+    #[msg("Invalid token weights")]
+    InvalidWeights,
+    #[msg("Invalid hook program or response")]
+    InvalidHook,
+    #[msg("Hook fee is outside the pool's bounds")]
+    HookFeeOutOfBounds,
+    #[msg("Price move exceeds the circuit breaker bound")]
+    PriceMoveExceeded,
+    #[msg("Native SOL needs the native mint and the pool's WSOL account")]
+    InvalidNativeSol,
+    #[msg("Invalid rate provider account or configuration")]
+    InvalidRateProvider,
+    #[msg("Exchange rate is stale")]
+    RateStale,
+    #[msg("Signer is not on the pool's allowlist")]
+    NotAllowlisted,
+    #[msg("Invalid allowlist kind or account")]
+    InvalidAllowlist,
+    #[msg("Invalid pool registry page")]
+    InvalidRegistryPage,
+    #[msg("Invalid hybrid pool parameters")]
+    InvalidHybridParams,
+    #[msg("Gamma was cranked too recently or has no price samples")]
+    CrankTooEarly,
+    #[msg("Referral fee exceeds the referrer's cap")]
+    ReferralFeeTooHigh,
+    #[msg("Referral needs the pool's fee tier and a referrer account of the input token")]
+    InvalidReferral,
+    #[msg("Vault does not belong to the pool")]
+    InvalidVault,
+    #[msg("LP token name, symbol or URI base is empty or too long")]
+    InvalidLpMetadata,
+    #[msg("Token is still sold on its launch curve")]
+    LaunchInProgress,
+    #[msg("Launch curve has no tokens left beyond its graduation liquidity")]
+    LaunchSoldOut,
+    #[msg("Farm has ended")]
+    FarmEnded,
+    #[msg("Farm has not ended yet")]
+    FarmNotEnded,
+    #[msg("Market does not match the pool's outcome tokens")]
+    InvalidMarket,
+    #[msg("Order book of the pool is full")]
+    OrderBookFull,
+    #[msg("Token A mint must sort before token B")]
+    InvalidTokenOrder,
 }
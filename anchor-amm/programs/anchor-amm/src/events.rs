@@ -0,0 +1,16 @@
+// programs/amm/src/events.rs
+//! Events logged by the program, for indexers and integrators
+use anchor_lang::prelude::*;
+
+/// Logged by every `swap`
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub a_to_b: bool,          // Token A in, token B out
+    pub amount_in: u64,        // Input paid by the user, fee included
+    pub amount_out: u64,       // Output received by the user
+    pub fee: u64,              // Swap fee charged on the input, referral included
+    pub referrer: Option<Pubkey>, // Token account paid the referral, if any
+    pub referral_fee: u64,     // Part of `fee` paid to `referrer` instead of the LPs
+}
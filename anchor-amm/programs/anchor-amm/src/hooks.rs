@@ -0,0 +1,209 @@
+// programs/amm/src/hooks.rs
+//! Per-pool trade hooks
+//!
+//! A pool can register a hook program at `initialize`. `swap`, `deposit` and
+//! `withdraw` then call it before and after they move tokens, in the style of
+//! Uniswap v4 hooks. The hook receives the pool and the user as its first two
+//! accounts, followed by the instruction's remaining accounts, and its data is
+//! `HOOK_DISCRIMINATOR` + a borsh `HookPayload`.
+//!
+//! A hook vetoes a swap or deposit by failing. Before a swap it can also set a
+//! `HookResponse` as return data to change the fee, within the pool's
+//! `[min_fee_bps, max_fee_bps]`.
+//!
+//! Withdraw hooks are non-binding, so a hook can't lock liquidity in the pool:
+//! `withdraw` only calls the hook when the LP passes `hook_program`, and
+//! ignores any response. A failing hook fails that withdrawal, and the LP can
+//! withdraw again without it.
+//!
+//! Every account is passed unsigned: the pool read-only, so the hook never gets
+//! authority over the vaults, and the user and remaining accounts without the
+//! signatures of this transaction, so the hook can't act on the user's behalf.
+//! The runtime rejects re-entry into this program.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{get_return_data, invoke};
+
+use crate::errors::AmmError;
+use crate::instructions::strategy::math::validate_fee;
+use crate::state::Pool;
+
+/// Data prefix of the hook's entry point: the Anchor sighash of `global:on_hook`
+pub const HOOK_DISCRIMINATOR: [u8; 8] = [130, 102, 45, 171, 29, 78, 198, 204];
+
+/// Hook registration stored on the pool; `program` is the default key when there is none
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HookParams {
+    pub program: Pubkey,   // Hook program
+    pub min_fee_bps: u64,  // Lowest fee the hook can set on a swap
+    pub max_fee_bps: u64,  // Highest fee the hook can set on a swap
+}
+
+impl HookParams {
+    pub const LEN: usize = 32 + 8 + 8;
+
+    /// Validate the registration
+    pub fn validate(&self) -> Result<()> {
+        require!(self.is_set(), AmmError::InvalidHook);
+        validate_fee(self.max_fee_bps)?;
+        require!(self.min_fee_bps <= self.max_fee_bps, AmmError::InvalidFee);
+        Ok(())
+    }
+
+    /// Whether the pool has a hook
+    pub fn is_set(&self) -> bool {
+        self.program != Pubkey::default()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookPoint {
+    BeforeSwap,
+    AfterSwap,
+    BeforeDeposit,
+    AfterDeposit,
+    BeforeWithdraw,
+    AfterWithdraw,
+}
+
+/// Token amounts of the trade a hook is called for
+///
+/// - Swaps: the input amount on its side; `After*` adds the output on the other side
+/// - Deposits: the maximum amounts, then the amounts taken and `lp_amount` minted
+/// - Withdrawals: the amounts paid out and `lp_amount` burned
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HookTrade {
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_amount: u64,
+    pub fee: u64,      // Fee of the swap in basis points
+    pub a_to_b: bool,  // Swap direction
+}
+
+/// Instruction data of a hook call, after `HOOK_DISCRIMINATOR`
+///
+/// Pool state is taken before the trade on `Before*` calls and after it on `After*` calls.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HookPayload {
+    pub point: HookPoint,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_amount: u64,
+    pub fee: u64,
+    pub a_to_b: bool,
+}
+
+impl HookPayload {
+    pub fn new(point: HookPoint, pool: &Pool, trade: HookTrade) -> Self {
+        Self {
+            point,
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            lp_supply: pool.lp_supply,
+            amount_a: trade.amount_a,
+            amount_b: trade.amount_b,
+            lp_amount: trade.lp_amount,
+            fee: trade.fee,
+            a_to_b: trade.a_to_b,
+        }
+    }
+}
+
+/// Return data a hook can set on `BeforeSwap`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HookResponse {
+    pub fee_bps: Option<u64>, // Fee to charge instead of the pool's
+}
+
+/// Call the pool's hook, if it has one
+///
+/// Returns the fee the hook set on `BeforeSwap`, checked against the pool's bounds.
+pub fn invoke_hook<'info>(
+    pool: &Account<'info, Pool>,
+    hook_program: Option<&UncheckedAccount<'info>>,
+    user: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    point: HookPoint,
+    trade: HookTrade,
+) -> Result<Option<u64>> {
+    if !pool.hook.is_set() {
+        return Ok(None);
+    }
+    // The account constraint checks the address when it is passed
+    let hook_program = hook_program.ok_or(AmmError::InvalidHook)?;
+
+    // Signatures of the trade stay with this program: a signer forwarded here
+    // would let the hook move the user's tokens
+    let mut accounts = vec![
+        AccountMeta::new_readonly(pool.key(), false),
+        AccountMeta::new_readonly(user.key(), false),
+    ];
+    accounts.extend(remaining_accounts.iter().map(|info| AccountMeta {
+        pubkey: info.key(),
+        is_signer: false,
+        is_writable: info.is_writable,
+    }));
+
+    let mut data = HOOK_DISCRIMINATOR.to_vec();
+    HookPayload::new(point, pool, trade).serialize(&mut data)?;
+
+    let mut account_infos = vec![pool.to_account_info(), user.clone()];
+    account_infos.extend_from_slice(remaining_accounts);
+    account_infos.push(hook_program.to_account_info());
+
+    invoke(
+        &Instruction { program_id: pool.hook.program, accounts, data },
+        &account_infos,
+    )?;
+
+    if point != HookPoint::BeforeSwap {
+        return Ok(None);
+    }
+    let response = match get_return_data() {
+        Some((program_id, data)) if program_id == pool.hook.program => {
+            HookResponse::try_from_slice(&data).map_err(|_| AmmError::InvalidHook)?
+        }
+        _ => return Ok(None),
+    };
+
+    if let Some(fee) = response.fee_bps {
+        require!(
+            (pool.hook.min_fee_bps..=pool.hook.max_fee_bps).contains(&fee),
+            AmmError::HookFeeOutOfBounds
+        );
+    }
+    Ok(response.fee_bps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::hash::hash;
+
+    #[test]
+    fn test_discriminator_is_anchor_sighash() {
+        assert_eq!(HOOK_DISCRIMINATOR, hash(b"global:on_hook").to_bytes()[..8]);
+    }
+
+    #[test]
+    fn test_hook_params_validation() {
+        let params = HookParams {
+            program: Pubkey::new_unique(),
+            min_fee_bps: 5,
+            max_fee_bps: 100,
+        };
+        assert!(params.validate().is_ok());
+
+        let no_program = HookParams { program: Pubkey::default(), ..params };
+        assert_eq!(no_program.validate().unwrap_err(), AmmError::InvalidHook.into());
+
+        let inverted = HookParams { min_fee_bps: 200, ..params };
+        assert_eq!(inverted.validate().unwrap_err(), AmmError::InvalidFee.into());
+
+        let too_high = HookParams { max_fee_bps: 10_001, ..params };
+        assert_eq!(too_high.validate().unwrap_err(), AmmError::InvalidFee.into());
+    }
+}
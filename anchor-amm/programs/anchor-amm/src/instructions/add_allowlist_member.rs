@@ -0,0 +1,36 @@
+// programs/amm/src/instructions/add_allowlist_member.rs
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct AddAllowlistMember<'info> {
+    #[account(has_one = creator)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(seeds = [b"allowlist", pool.key().as_ref()], bump = allowlist.bump)]
+    pub allowlist: Account<'info, Allowlist>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = AllowlistEntry::LEN,
+        seeds = [b"allowlist_entry", pool.key().as_ref(), member.as_ref()],
+        bump,
+    )]
+    pub entry: Account<'info, AllowlistEntry>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<AddAllowlistMember>, member: Pubkey) -> Result<()> {
+    require!(ctx.accounts.allowlist.kind == AllowlistKind::Members, AmmError::InvalidAllowlist);
+
+    let entry = &mut ctx.accounts.entry;
+    entry.pool = ctx.accounts.pool.key();
+    entry.member = member;
+    entry.bump = ctx.bumps.entry;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+// programs/amm/src/instructions/cancel_limit_order.rs
+use crate::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        close = owner,
+        seeds = [b"limit_order", pool.key().as_ref(), owner.key().as_ref(), &order.tick.to_le_bytes()],
+        bump = order.bump,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    #[account(mut, seeds = [b"order_book", pool.key().as_ref()], bump = order_book.bump)]
+    pub order_book: Box<Account<'info, OrderBook>>,
+
+    #[account(mut, address = pool.vault_a @ AmmError::InvalidVault)]
+    pub vault_a: Account<'info, TokenAccount>,
+
+    #[account(mut, address = pool.vault_b @ AmmError::InvalidVault)]
+    pub vault_b: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = pool.token_a)]
+    pub user_a: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = pool.token_b)]
+    pub user_b: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub(crate) fn handler(ctx: Context<CancelLimitOrder>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let order = ctx.accounts.order_book.remove(&ctx.accounts.order.key())?;
+
+    // Unfilled remainder is returned in the sold token, partial fills in the bought token
+    let (amount_a, amount_b) = match order.side {
+        OrderSide::Ask => (order.remaining, order.proceeds),
+        OrderSide::Bid => (order.proceeds, order.remaining),
+    };
+
+    let seeds = &[
+        b"pool",
+        pool.token_a.as_ref(),
+        pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
+        &[pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    if amount_a > 0 {
+        let cpi_accounts = token::Transfer {
+            from: ctx.accounts.vault_a.to_account_info(),
+            to: ctx.accounts.user_a.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            amount_a,
+        )?;
+    }
+
+    if amount_b > 0 {
+        let cpi_accounts = token::Transfer {
+            from: ctx.accounts.vault_b.to_account_info(),
+            to: ctx.accounts.user_b.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            amount_b,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.open_orders = pool.open_orders.checked_sub(1).ok_or(AmmError::MathOverflow)?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+// programs/amm/src/instructions/claim_filled_order.rs
+use crate::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct ClaimFilledOrder<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        close = owner,
+        seeds = [b"limit_order", pool.key().as_ref(), owner.key().as_ref(), &order.tick.to_le_bytes()],
+        bump = order.bump,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    #[account(mut, seeds = [b"order_book", pool.key().as_ref()], bump = order_book.bump)]
+    pub order_book: Box<Account<'info, OrderBook>>,
+
+    /// Pool vault of the token the order bought (B for asks, A for bids)
+    #[account(
+        mut,
+        address = if order.side == OrderSide::Ask { pool.vault_b } else { pool.vault_a } @ AmmError::InvalidVault,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = vault.mint)]
+    pub user_destination: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub(crate) fn handler(ctx: Context<ClaimFilledOrder>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let order = &ctx.accounts.order;
+    let filled = ctx.accounts.order_book.remove(&order.key())?;
+    require!(filled.remaining == 0, AmmError::OrderNotFilled);
+
+    let seeds = &[
+        b"pool",
+        pool.token_a.as_ref(),
+        pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
+        &[pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.vault.to_account_info(),
+        to: ctx.accounts.user_destination.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        filled.proceeds,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.open_orders = pool.open_orders.checked_sub(1).ok_or(AmmError::MathOverflow)?;
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// programs/amm/src/instructions/close_pool.rs
+use crate::*;
+use crate::program::Amm;
+use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+use anchor_spl::token_interface::{self, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(mut, has_one = creator, close = creator)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut, address = pool.vault_a @ AmmError::InvalidVault)]
+    pub vault_a: Account<'info, TokenAccount>,
+
+    #[account(mut, address = pool.vault_b @ AmmError::InvalidVault)]
+    pub vault_b: Account<'info, TokenAccount>,
+
+    /// Closed when the pool is its close authority, which LP mints of older pools lack
+    #[account(mut, address = pool.lp_mint @ AmmError::InvalidLpMint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Receives any dust or donations left in vault A
+    #[account(mut, token::mint = pool.token_a, token::authority = creator)]
+    pub creator_a: Account<'info, TokenAccount>,
+
+    /// Receives any dust or donations left in vault B
+    #[account(mut, token::mint = pool.token_b, token::authority = creator)]
+    pub creator_b: Account<'info, TokenAccount>,
+
+    /// Registry page listing the pool, whose entry is tombstoned; pools created before the registry pass none
+    #[account(
+        mut,
+        seeds = [b"registry_page".as_ref(), &registry_page.index.to_le_bytes()],
+        bump = registry_page.bump,
+    )]
+    pub registry_page: Option<Box<Account<'info, RegistryPage>>>,
+
+    /// CHECK: `MintPools` of token A, raw data handled by `registry::remove_mint_pool`
+    #[account(mut, seeds = [b"mint_pools", pool.token_a.as_ref()], bump)]
+    pub mint_pools_a: UncheckedAccount<'info>,
+
+    /// CHECK: `MintPools` of token B, raw data handled by `registry::remove_mint_pool`
+    #[account(mut, seeds = [b"mint_pools", pool.token_b.as_ref()], bump)]
+    pub mint_pools_b: UncheckedAccount<'info>,
+
+    /// Range order book, if an order was ever placed on the pool
+    #[account(
+        mut,
+        seeds = [b"order_book", pool.key().as_ref()],
+        bump = order_book.bump,
+        close = creator,
+    )]
+    pub order_book: Option<Box<Account<'info, OrderBook>>>,
+
+    /// Allowlist of a permissioned pool, which must be passed for those
+    #[account(mut, seeds = [b"allowlist", pool.key().as_ref()], bump = allowlist.bump, close = creator)]
+    pub allowlist: Option<Account<'info, Allowlist>>,
+
+    /// CHECK: Pool creator, checked by `has_one`; receives the rent of every closed account
+    #[account(mut)]
+    pub creator: UncheckedAccount<'info>,
+
+    /// The pool creator, or the program's upgrade authority to clean up abandoned pools
+    pub authority: Signer<'info>,
+
+    pub program: Program<'info, Amm>,
+
+    /// Only passed when the upgrade authority closes the pool
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ AmmError::Unauthorized,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ AmmError::Unauthorized,
+    )]
+    pub program_data: Option<Account<'info, ProgramData>>,
+
+    pub token_program: Program<'info, Token>,
+    pub lp_token_program: Interface<'info, TokenInterface>,
+}
+
+pub(crate) fn handler(ctx: Context<ClosePool>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    require!(
+        ctx.accounts.authority.key() == pool.creator || ctx.accounts.program_data.is_some(),
+        AmmError::Unauthorized
+    );
+    require!(pool.lp_supply == 0, AmmError::PoolNotEmpty);
+    require!(pool.open_orders == 0, AmmError::PoolNotEmpty);
+    require!(pool.permissioned == ctx.accounts.allowlist.is_some(), AmmError::InvalidAllowlist);
+
+    // Take the pool out of discovery
+    if let Some(page) = ctx.accounts.registry_page.as_mut() {
+        page.remove(&pool.key())?;
+    }
+    let creator = ctx.accounts.creator.to_account_info();
+    remove_mint_pool(&ctx.accounts.mint_pools_a, &pool.key(), &creator)?;
+    if pool.token_b != pool.token_a {
+        remove_mint_pool(&ctx.accounts.mint_pools_b, &pool.key(), &creator)?;
+    }
+
+    let seeds = &[
+        b"pool",
+        pool.token_a.as_ref(),
+        pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
+        &[pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Skim whatever is left in the vaults, then close them
+    for (vault, destination) in [
+        (&ctx.accounts.vault_a, &ctx.accounts.creator_a),
+        (&ctx.accounts.vault_b, &ctx.accounts.creator_b),
+    ] {
+        if vault.amount > 0 {
+            let cpi_accounts = token::Transfer {
+                from: vault.to_account_info(),
+                to: destination.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds,
+                ),
+                vault.amount,
+            )?;
+        }
+
+        let cpi_accounts = token::CloseAccount {
+            account: vault.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ))?;
+    }
+
+    // SPL Token mints can't be closed, nor Token-2022 mints without a close authority
+    let lp_mint = ctx.accounts.lp_mint.to_account_info();
+    let closable = *lp_mint.owner == spl_token_2022::ID
+        && token_interface::get_mint_extension_data::<MintCloseAuthority>(&lp_mint)
+            .is_ok_and(|extension| Option::<Pubkey>::from(extension.close_authority) == Some(pool.key()));
+    if closable {
+        let cpi_accounts = token_interface::CloseAccount {
+            account: lp_mint,
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.lp_token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+// programs/amm/src/instructions/crank_gamma.rs
+use crate::*;
+use crate::instructions::strategy::hybrid_cfmm::GAMMA_CRANK_BOUNTY_LAMPORTS;
+
+#[derive(Accounts)]
+pub struct CrankGamma<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    /// Receives the bounty
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<CrankGamma>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    require!(pool.curve == CurveType::HybridCfmm, AmmError::InvalidHybridParams);
+
+    let moved = pool.hybrid.crank(Clock::get()?.slot, HybridParams::default())?;
+    if !moved {
+        return Ok(());
+    }
+
+    // The bounty comes out of lamports sent to the pool on top of its rent, and is skipped once they run out
+    let pool_info = pool.to_account_info();
+    let rent = Rent::get()?.minimum_balance(pool_info.data_len());
+    if pool_info.lamports().saturating_sub(rent) >= GAMMA_CRANK_BOUNTY_LAMPORTS {
+        pool_info.sub_lamports(GAMMA_CRANK_BOUNTY_LAMPORTS)?;
+        ctx.accounts.keeper.add_lamports(GAMMA_CRANK_BOUNTY_LAMPORTS)?;
+    }
+    Ok(())
+}
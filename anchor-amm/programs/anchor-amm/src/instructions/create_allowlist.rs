@@ -0,0 +1,37 @@
+// programs/amm/src/instructions/create_allowlist.rs
+use crate::*;
+
+#[derive(Accounts)]
+pub struct CreateAllowlist<'info> {
+    #[account(mut, has_one = creator)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = Allowlist::LEN,
+        seeds = [b"allowlist", pool.key().as_ref()],
+        bump,
+    )]
+    pub allowlist: Account<'info, Allowlist>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(ctx: Context<CreateAllowlist>, kind: AllowlistKind, merkle_root: [u8; 32]) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    // Existing LPs and order owners could otherwise be locked out of their funds
+    require!(pool.lp_supply == 0, AmmError::PoolNotEmpty);
+    require!(pool.open_orders == 0, AmmError::PoolNotEmpty);
+    pool.permissioned = true;
+
+    let allowlist = &mut ctx.accounts.allowlist;
+    allowlist.pool = pool.key();
+    allowlist.kind = kind;
+    allowlist.merkle_root = merkle_root;
+    allowlist.bump = ctx.bumps.allowlist;
+    Ok(())
+}
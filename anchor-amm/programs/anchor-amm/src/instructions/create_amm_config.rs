@@ -0,0 +1,50 @@
+// programs/amm/src/instructions/create_amm_config.rs
+use crate::*;
+use crate::program::Amm;
+
+#[derive(Accounts)]
+#[instruction(index: u16)]
+pub struct CreateAmmConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = AmmConfig::LEN,
+        seeds = [b"amm_config".as_ref(), &index.to_le_bytes()],
+        bump,
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// The program's upgrade authority administers the fee tiers
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AmmError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn handler(
+    ctx: Context<CreateAmmConfig>,
+    index: u16,
+    trade_fee_bps: u64,
+    tick_spacing: u16,
+    lp_uri_base: String,
+) -> Result<()> {
+    strategy::math::validate_fee(trade_fee_bps)?;
+    require!(tick_spacing > 0, AmmError::InvalidTick);
+    require!(lp_uri_base.len() <= MAX_LP_URI_BASE_LEN, AmmError::InvalidLpMetadata);
+
+    let amm_config = &mut ctx.accounts.amm_config;
+    amm_config.index = index;
+    amm_config.trade_fee_bps = trade_fee_bps;
+    amm_config.tick_spacing = tick_spacing;
+    amm_config.bump = ctx.bumps.amm_config;
+    amm_config.max_referral_fee_bps = 0;
+    amm_config.referrer_caps = [ReferrerCap::default(); MAX_REFERRER_CAPS];
+    amm_config.lp_uri_base = lp_uri_base;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+// programs/amm/src/instructions/create_farm.rs
+use crate::*;
+use crate::program::Amm;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{self, TokenInterface};
+
+#[derive(Accounts)]
+pub struct CreateFarm<'info> {
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Farm::LEN,
+        seeds = [b"farm", pool.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    /// LP mint of the pool
+    #[account(address = pool.lp_mint @ AmmError::InvalidLpMint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub reward_mint: Account<'info, Mint>,
+
+    /// Vault holding staked LP tokens
+    #[account(
+        init,
+        payer = authority,
+        token::mint = lp_mint,
+        token::authority = farm,
+        token::token_program = lp_token_program,
+    )]
+    pub staked_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Vault holding the reward budget
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = farm,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = reward_mint)]
+    pub authority_reward: Account<'info, TokenAccount>,
+
+    /// The pool creator, or the program's upgrade authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub program: Program<'info, Amm>,
+
+    /// Only passed when the upgrade authority creates the farm
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ AmmError::Unauthorized,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ AmmError::Unauthorized,
+    )]
+    pub program_data: Option<Account<'info, ProgramData>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub lp_token_program: Interface<'info, TokenInterface>,
+}
+
+pub(crate) fn handler(ctx: Context<CreateFarm>, reward_rate: u64, start_time: i64, end_time: i64) -> Result<()> {
+    // Farms hold one PDA per pool and reward mint, so only the pool's owners may take it
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.pool.creator || ctx.accounts.program_data.is_some(),
+        AmmError::Unauthorized
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(reward_rate > 0, AmmError::InvalidFarmSchedule);
+    require!(start_time >= now, AmmError::InvalidFarmSchedule);
+    require!(start_time < end_time, AmmError::InvalidFarmSchedule);
+
+    // Fund the whole campaign up front
+    let budget = reward_rate
+        .checked_mul((end_time - start_time) as u64)
+        .ok_or(AmmError::MathOverflow)?;
+
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.authority_reward.to_account_info(),
+        to: ctx.accounts.reward_vault.to_account_info(),
+        authority: ctx.accounts.authority.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+        budget,
+    )?;
+
+    let farm = &mut ctx.accounts.farm;
+    farm.pool = ctx.accounts.pool.key();
+    farm.lp_mint = ctx.accounts.lp_mint.key();
+    farm.reward_mint = ctx.accounts.reward_mint.key();
+    farm.staked_vault = ctx.accounts.staked_vault.key();
+    farm.reward_vault = ctx.accounts.reward_vault.key();
+    farm.authority = ctx.accounts.authority.key();
+    farm.reward_rate = reward_rate;
+    farm.start_time = start_time;
+    farm.end_time = end_time;
+    farm.total_staked = 0;
+    farm.acc_reward_per_share = 0;
+    farm.last_update_time = start_time;
+    farm.bump = ctx.bumps.farm;
+    farm.idle_rewards = 0;
+    Ok(())
+}
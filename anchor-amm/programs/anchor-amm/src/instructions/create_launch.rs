@@ -0,0 +1,139 @@
+// programs/amm/src/instructions/create_launch.rs
+use crate::*;
+use anchor_spl::token::{self, spl_token::instruction::AuthorityType, Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+#[instruction(decimals: u8)]
+pub struct CreateLaunch<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = Launch::LEN,
+        seeds = [b"launch", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+
+    /// Newly minted token; the whole supply is minted to the curve, then the authority is revoked
+    #[account(
+        init,
+        payer = creator,
+        mint::decimals = decimals,
+        mint::authority = launch,
+    )]
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    /// Fee tier of the pool the launch graduates into
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"launch_tokens", launch.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = launch,
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"launch_quote", launch.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = launch,
+    )]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub(crate) fn handler(
+    ctx: Context<CreateLaunch>,
+    _decimals: u8,
+    total_supply: u64,
+    virtual_token_reserve: u64,
+    virtual_quote_reserve: u64,
+    fee: u64,
+    graduation_market_cap: u64,
+) -> Result<()> {
+    strategy::math::validate_fee(fee)?;
+    require!(total_supply > 0, AmmError::InsufficientLiquidity);
+    require!(virtual_quote_reserve > 0, AmmError::InsufficientLiquidity);
+
+    let launch = &mut ctx.accounts.launch;
+    launch.creator = ctx.accounts.creator.key();
+    launch.token_mint = ctx.accounts.token_mint.key();
+    launch.quote_mint = ctx.accounts.quote_mint.key();
+    launch.token_vault = ctx.accounts.token_vault.key();
+    launch.quote_vault = ctx.accounts.quote_vault.key();
+    launch.total_supply = total_supply;
+    launch.real_token_reserve = total_supply;
+    launch.real_quote_reserve = 0;
+    launch.virtual_token_reserve = virtual_token_reserve;
+    launch.virtual_quote_reserve = virtual_quote_reserve;
+    launch.fee = fee;
+    launch.graduation_market_cap = graduation_market_cap;
+    launch.amm_config = ctx.accounts.amm_config.key();
+    launch.graduated = false;
+    launch.bump = ctx.bumps.launch;
+
+    // The threshold must lie above the starting market cap
+    require!(
+        graduation_market_cap > launch.market_cap()?,
+        AmmError::GraduationNotReached
+    );
+
+    // Buys stop once the price reaches virtual_quote / virtual_token, where the tokens left
+    // only cover the graduation liquidity, so the threshold must lie below that market cap
+    if virtual_token_reserve > 0 {
+        require!(
+            graduation_market_cap
+                < BondingCurveStrategy::market_cap(virtual_token_reserve, virtual_quote_reserve, total_supply)?,
+            AmmError::GraduationNotReached
+        );
+    }
+
+    let token_mint = ctx.accounts.token_mint.key();
+    let seeds = &[b"launch", token_mint.as_ref(), &[launch.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    // Mint the whole supply onto the curve
+    let cpi_accounts = token::MintTo {
+        mint: ctx.accounts.token_mint.to_account_info(),
+        to: ctx.accounts.token_vault.to_account_info(),
+        authority: launch.to_account_info(),
+    };
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        total_supply,
+    )?;
+
+    // Revoke the mint authority so the supply is fixed
+    let cpi_accounts = token::SetAuthority {
+        current_authority: launch.to_account_info(),
+        account_or_mint: ctx.accounts.token_mint.to_account_info(),
+    };
+    token::set_authority(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+
+    Ok(())
+}
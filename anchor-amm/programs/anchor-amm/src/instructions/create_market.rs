@@ -0,0 +1,69 @@
+// programs/amm/src/instructions/create_market.rs
+use crate::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = PredictionMarket::LEN,
+        seeds = [b"market", creator.key().as_ref(), &market_id.to_le_bytes()],
+        bump,
+    )]
+    pub market: Account<'info, PredictionMarket>,
+
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// Vault holding the collateral behind every outcome token
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"collateral", market.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = market,
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::decimals = collateral_mint.decimals,
+        mint::authority = market,
+    )]
+    pub yes_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::decimals = collateral_mint.decimals,
+        mint::authority = market,
+    )]
+    pub no_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub(crate) fn handler(ctx: Context<CreateMarket>, market_id: u64, resolver: Pubkey) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.creator = ctx.accounts.creator.key();
+    market.resolver = resolver;
+    market.collateral_mint = ctx.accounts.collateral_mint.key();
+    market.collateral_vault = ctx.accounts.collateral_vault.key();
+    market.yes_mint = ctx.accounts.yes_mint.key();
+    market.no_mint = ctx.accounts.no_mint.key();
+    market.market_id = market_id;
+    market.outcome = MarketOutcome::Unresolved;
+    market.bump = ctx.bumps.market;
+    Ok(())
+}
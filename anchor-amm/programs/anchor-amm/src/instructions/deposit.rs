@@ -1,51 +1,137 @@
 // programs/amm/src/instructions/deposit.rs
 use crate::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{self, TokenInterface};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(mut)]
     pub pool: Account<'info, Pool>,
 
-    #[account(mut)]
+    #[account(mut, address = pool.vault_a @ AmmError::InvalidVault)]
     pub vault_a: Account<'info, TokenAccount>,
 
-    #[account(mut)]
+    #[account(mut, address = pool.vault_b @ AmmError::InvalidVault)]
     pub vault_b: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub lp_mint: Account<'info, Mint>,
+    /// SPL Token LP mint of older pools, Token-2022 otherwise
+    #[account(mut, address = pool.lp_mint @ AmmError::InvalidLpMint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, token_interface::Mint>,
 
+    /// Left out to pay native SOL from `user`
     #[account(mut)]
-    pub user_a: Account<'info, TokenAccount>,
+    pub user_a: Option<Account<'info, TokenAccount>>,
 
+    /// Left out to pay native SOL from `user`
     #[account(mut)]
-    pub user_b: Account<'info, TokenAccount>,
+    pub user_b: Option<Account<'info, TokenAccount>>,
 
-    #[account(mut)]
-    pub user_lp: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = lp_mint, token::token_program = lp_token_program)]
+    pub user_lp: InterfaceAccount<'info, token_interface::TokenAccount>,
 
+    #[account(mut)]
     pub user: Signer<'info>,
 
+    /// CHECK: Program registered with `initialize`, called by `invoke_hook`; only used by hooked pools
+    #[account(address = pool.hook.program @ AmmError::InvalidHook)]
+    pub hook_program: Option<UncheckedAccount<'info>>,
+
+    /// Allowlist of the pool; only used by permissioned pools
+    #[account(seeds = [b"allowlist", pool.key().as_ref()], bump = allowlist.bump)]
+    pub allowlist: Option<Account<'info, Allowlist>>,
+
+    /// Entry of `user` in a `Members` allowlist
+    #[account(
+        seeds = [b"allowlist_entry", pool.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump,
+    )]
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
+
     pub token_program: Program<'info, Token>,
+    pub lp_token_program: Interface<'info, TokenInterface>,
+
+    /// Temporary WSOL account of the pool, only passed when a user side is native SOL
+    #[account(
+        init,
+        payer = user,
+        seeds = [WSOL_SEED, pool.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = pool,
+    )]
+    pub wsol_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(address = NATIVE_MINT @ AmmError::InvalidNativeSol)]
+    pub native_mint: Option<Account<'info, Mint>>,
+
+    pub system_program: Option<Program<'info, System>>,
 }
 
-pub fn handler(ctx: Context<Deposit>, max_a: u64, max_b: u64) -> Result<()> {
+/// On hooked pools, the remaining accounts are passed on to the hook.
+pub(crate) fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+    max_a: u64,
+    max_b: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
+
+    // The creator seeds an LBP sale alone, so nobody else holds its LP tokens
+    if pool.curve == CurveType::Lbp {
+        require_keys_eq!(ctx.accounts.user.key(), pool.creator, AmmError::Unauthorized);
+    }
+
+    // Permissioned pools only serve the wallets on their allowlist
+    pool.check_allowed(
+        ctx.accounts.allowlist.as_deref(),
+        ctx.accounts.allowlist_entry.as_deref(),
+        &ctx.accounts.user.key(),
+        &proof,
+    )?;
+
+    // The temporary WSOL account is created by this instruction and must be closed by it
+    require!(
+        ctx.accounts.wsol_account.is_some()
+            == (ctx.accounts.user_a.is_none() || ctx.accounts.user_b.is_none()),
+        AmmError::InvalidNativeSol
+    );
+
+    invoke_hook(
+        pool,
+        ctx.accounts.hook_program.as_ref(),
+        &ctx.accounts.user,
+        ctx.remaining_accounts,
+        HookPoint::BeforeDeposit,
+        HookTrade { amount_a: max_a, amount_b: max_b, ..HookTrade::default() },
+    )?;
+
     let reserve_a = pool.reserve_a;
     let reserve_b = pool.reserve_b;
 
     let (amount_a, amount_b, lp_mint_amount) = if reserve_a == 0 && reserve_b == 0 {
-        // First deposit - use strategy to calculate initial LP supply
-        let lp_supply = ConstantProductStrategy::calculate_initial_lp_supply(max_a, max_b)?;
+        // First deposit - an LMSR pool must be seeded with its worst-case subsidy
+        if pool.curve == CurveType::Lmsr {
+            let subsidy = LmsrStrategy::worst_case_subsidy(pool.lmsr_b)?;
+            require!(max_a.min(max_b) >= subsidy, AmmError::InsufficientLiquidity);
+        }
+
+        // Use strategy to calculate initial LP supply
+        let lp_supply = pool.curve.calculate_initial_lp_supply(max_a, max_b)?;
         (max_a, max_b, lp_supply)
     } else {
-        // Calculate proportional amounts
-        let amount_a = max_a.min(reserve_a * max_b / reserve_b);
-        let amount_b = max_b.min(reserve_b * max_a / reserve_a);
+        // Calculate proportional amounts: the paired token rounds up so the
+        // deposit never dilutes existing LPs
+        let needed_b = strategy::math::mul_div_u64(max_a, reserve_b, reserve_a, Rounding::Up)?;
+        let (amount_a, amount_b) = if needed_b <= max_b {
+            (max_a, needed_b)
+        } else {
+            let amount_a = strategy::math::mul_div_u64(max_b, reserve_a, reserve_b, Rounding::Down)?;
+            let amount_b = strategy::math::mul_div_u64(amount_a, reserve_b, reserve_a, Rounding::Up)?;
+            (amount_a, amount_b)
+        };
         
         // Use strategy to calculate LP tokens to mint
-        let lp_mint_amount = ConstantProductStrategy::calculate_lp_tokens_to_mint(
+        let lp_mint_amount = pool.curve.calculate_lp_tokens_to_mint(
             amount_a,
             reserve_a,
             pool.lp_supply,
@@ -54,62 +140,84 @@ pub fn handler(ctx: Context<Deposit>, max_a: u64, max_b: u64) -> Result<()> {
         (amount_a, amount_b, lp_mint_amount)
     };
 
-    // Transfer tokens in
-    let cpi_accounts = token::Transfer {
-        from: ctx.accounts.user_a.to_account_info(),
-        to: ctx.accounts.vault_a.to_account_info(),
-        authority: ctx.accounts.user.to_account_info(),
-    };
-    token::transfer(
-        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-        amount_a,
-    )?;
-
-    let cpi_accounts = token::Transfer {
-        from: ctx.accounts.user_b.to_account_info(),
-        to: ctx.accounts.vault_b.to_account_info(),
-        authority: ctx.accounts.user.to_account_info(),
-    };
-    token::transfer(
-        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-        amount_b,
-    )?;
-
-    // Mint LP tokens
     let seeds = &[
         b"pool",
         pool.token_a.as_ref(),
         pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
         &[pool.bump],
     ];
     let signer_seeds = &[&seeds[..]];
+    let native_sol = NativeSol {
+        pool: pool.to_account_info(),
+        user: ctx.accounts.user.to_account_info(),
+        wsol_account: ctx.accounts.wsol_account.as_ref().map(|account| account.to_account_info()),
+        system_program: ctx.accounts.system_program.as_ref().map(|program| program.to_account_info()),
+        token_program: ctx.accounts.token_program.to_account_info(),
+        signer_seeds,
+    };
 
-    let cpi_accounts = token::MintTo {
+    // Transfer tokens in, wrapping native SOL
+    for (user_account, vault, amount) in [
+        (&ctx.accounts.user_a, &ctx.accounts.vault_a, amount_a),
+        (&ctx.accounts.user_b, &ctx.accounts.vault_b, amount_b),
+    ] {
+        match user_account {
+            Some(user_account) => {
+                let cpi_accounts = token::Transfer {
+                    from: user_account.to_account_info(),
+                    to: vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                };
+                token::transfer(
+                    CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+                    amount,
+                )?;
+            }
+            None => native_sol.wrap(vault, amount)?,
+        }
+    }
+
+    // Mint LP tokens
+    let cpi_accounts = token_interface::MintTo {
         mint: ctx.accounts.lp_mint.to_account_info(),
         to: ctx.accounts.user_lp.to_account_info(),
         authority: pool.to_account_info(),
     };
-    token::mint_to(
+    token_interface::mint_to(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.lp_token_program.to_account_info(),
             cpi_accounts,
             signer_seeds,
         ),
         lp_mint_amount,
     )?;
 
+    if pool.curve == CurveType::OraclePeg {
+        let lp_supply = pool.lp_supply;
+        pool.peg_target.deposit(amount_a, amount_b, lp_mint_amount, lp_supply)?;
+    }
     pool.reserve_a = pool
         .reserve_a
         .checked_add(amount_a)
-        .ok_or(AmmError::Overflow)?;
+        .ok_or(AmmError::MathOverflow)?;
     pool.reserve_b = pool
         .reserve_b
         .checked_add(amount_b)
-        .ok_or(AmmError::Overflow)?;
+        .ok_or(AmmError::MathOverflow)?;
     pool.lp_supply = pool
         .lp_supply
         .checked_add(lp_mint_amount)
-        .ok_or(AmmError::Overflow)?;
+        .ok_or(AmmError::MathOverflow)?;
+
+    invoke_hook(
+        pool,
+        ctx.accounts.hook_program.as_ref(),
+        &ctx.accounts.user,
+        ctx.remaining_accounts,
+        HookPoint::AfterDeposit,
+        HookTrade { amount_a, amount_b, lp_amount: lp_mint_amount, ..HookTrade::default() },
+    )?;
 
     Ok(())
 }
@@ -0,0 +1,307 @@
+// programs/amm/src/instructions/graduate.rs
+use crate::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface;
+
+#[derive(Accounts)]
+pub struct Graduate<'info> {
+    #[account(
+        mut,
+        has_one = token_mint,
+        has_one = quote_mint,
+        has_one = token_vault,
+        has_one = quote_vault,
+        has_one = amm_config,
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+
+    #[account(mut)]
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Constant product pool the launch migrates into, over the two mints sorted like `initialize`'s
+    #[account(
+        init,
+        payer = payer,
+        space = Pool::LEN,
+        seeds = [
+            b"pool",
+            token_mint.key().min(quote_mint.key()).as_ref(),
+            token_mint.key().max(quote_mint.key()).as_ref(),
+            &amm_config.index.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = token_mint,
+        token::authority = pool,
+    )]
+    pub pool_token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = quote_mint,
+        token::authority = pool,
+    )]
+    pub pool_quote_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Token-2022 LP mint, holding its own metadata; `close_pool` closes it
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = lp_decimals(token_mint.decimals, quote_mint.decimals),
+        mint::authority = pool,
+        mint::token_program = lp_token_program,
+        extensions::metadata_pointer::authority = pool,
+        extensions::metadata_pointer::metadata_address = lp_mint,
+        extensions::close_authority::authority = pool,
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// Receives the pool's initial LP tokens; the launch has no instruction to move them
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"locked_lp", launch.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = launch,
+        token::token_program = lp_token_program,
+    )]
+    pub locked_lp: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    /// Registry of every pool, created with the first pool
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PoolRegistry::LEN,
+        seeds = [b"pool_registry"],
+        bump,
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    /// Registry page the pool is appended to, created once the previous page is full
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = RegistryPage::LEN,
+        seeds = [b"registry_page".as_ref(), &pool_registry.next_page().to_le_bytes()],
+        bump,
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+
+    /// CHECK: `MintPools` of the launched token, created or grown by `append_mint_pool`
+    #[account(mut, seeds = [b"mint_pools", token_mint.key().as_ref()], bump)]
+    pub mint_pools_token: UncheckedAccount<'info>,
+
+    /// CHECK: `MintPools` of the quote token, created or grown by `append_mint_pool`
+    #[account(mut, seeds = [b"mint_pools", quote_mint.key().as_ref()], bump)]
+    pub mint_pools_quote: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub lp_token_program: Program<'info, Token2022>,
+}
+
+/// Migrate a launch that reached its market cap threshold into a constant product pool
+///
+/// All raised quote is paired with launched tokens at the final curve price.
+/// Tokens left over are burned and the pool's LP tokens are locked for good.
+pub(crate) fn handler(ctx: Context<Graduate>) -> Result<()> {
+    let launch = &mut ctx.accounts.launch;
+    require!(!launch.graduated, AmmError::LaunchGraduated);
+    require!(
+        launch.market_cap()? >= launch.graduation_market_cap,
+        AmmError::GraduationNotReached
+    );
+
+    let quote_amount = launch.real_quote_reserve;
+    let token_amount = launch.graduation_token_amount()?.min(launch.real_token_reserve);
+    let burn_amount = launch.real_token_reserve - token_amount;
+
+    let seeds = &[b"launch", launch.token_mint.as_ref(), &[launch.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    // Move the reserves into the pool vaults
+    for (from, to, amount) in [
+        (&ctx.accounts.token_vault, &ctx.accounts.pool_token_vault, token_amount),
+        (&ctx.accounts.quote_vault, &ctx.accounts.pool_quote_vault, quote_amount),
+    ] {
+        let cpi_accounts = token::Transfer {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            authority: launch.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    // Burn the tokens that did not make it into the pool
+    if burn_amount > 0 {
+        let cpi_accounts = token::Burn {
+            mint: ctx.accounts.token_mint.to_account_info(),
+            from: ctx.accounts.token_vault.to_account_info(),
+            authority: launch.to_account_info(),
+        };
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            burn_amount,
+        )?;
+    }
+
+    launch.real_token_reserve = 0;
+    launch.real_quote_reserve = 0;
+    launch.graduated = true;
+
+    let lp_amount = ConstantProductStrategy::calculate_initial_lp_supply(token_amount, quote_amount)?;
+
+    // Token A is the mint that sorts first, as in every pool
+    let token_side = (
+        ctx.accounts.token_mint.key(),
+        ctx.accounts.pool_token_vault.key(),
+        token_amount,
+        ctx.accounts.token_mint.decimals,
+    );
+    let quote_side = (
+        ctx.accounts.quote_mint.key(),
+        ctx.accounts.pool_quote_vault.key(),
+        quote_amount,
+        ctx.accounts.quote_mint.decimals,
+    );
+    let ((token_a, vault_a, reserve_a, decimals_a), (token_b, vault_b, reserve_b, decimals_b)) =
+        if token_side.0 < quote_side.0 {
+            (token_side, quote_side)
+        } else {
+            (quote_side, token_side)
+        };
+
+    let pool = &mut ctx.accounts.pool;
+    pool.token_a = token_a;
+    pool.token_b = token_b;
+    pool.reserve_a = reserve_a;
+    pool.reserve_b = reserve_b;
+    pool.lp_supply = lp_amount;
+    pool.fee = ctx.accounts.amm_config.trade_fee_bps;
+    pool.bump = ctx.bumps.pool;
+    pool.fee_mode = FeeMode::Static;
+    pool.dynamic_fee = DynamicFeeParams::default();
+    pool.volatility = VolatilityState::default();
+    pool.creator = ctx.accounts.payer.key();
+    pool.open_orders = 0;
+    pool.curve = CurveType::ConstantProduct;
+    pool.decimals_a = decimals_a;
+    pool.decimals_b = decimals_b;
+    pool.oracle_peg = OraclePegParams::default();
+    pool.lmsr_b = 0;
+    pool.lbp = LbpParams::default();
+    pool.paused = false;
+    pool.amm_config = ctx.accounts.amm_config.key();
+    pool.fee_tier = ctx.accounts.amm_config.index;
+    pool.tick_spacing = ctx.accounts.amm_config.tick_spacing;
+    pool.vault_a = vault_a;
+    pool.vault_b = vault_b;
+    pool.hook = HookParams::default();
+    pool.circuit_breaker = CircuitBreaker::default();
+    pool.stats = PoolStats::default();
+    pool.rate_a = RateProvider::default();
+    pool.rate_b = RateProvider::default();
+    pool.permissioned = false;
+    pool.hybrid = HybridState::default();
+    pool.lp_mint = ctx.accounts.lp_mint.key();
+    pool.peg_target = PegTarget::default();
+    pool.legacy_seeds = false;
+
+    // List the pool for discovery
+    let mint_pools_token = (&ctx.accounts.mint_pools_token, ctx.bumps.mint_pools_token);
+    let mint_pools_quote = (&ctx.accounts.mint_pools_quote, ctx.bumps.mint_pools_quote);
+    let ((mint_pools_a, mint_pools_a_bump), (mint_pools_b, mint_pools_b_bump)) = if pool.token_a == token_side.0 {
+        (mint_pools_token, mint_pools_quote)
+    } else {
+        (mint_pools_quote, mint_pools_token)
+    };
+    let entry = RegistryEntry {
+        pool: pool.key(),
+        token_a: pool.token_a,
+        token_b: pool.token_b,
+        curve: pool.curve,
+        fee_tier: pool.fee_tier,
+    };
+    RegisterPool {
+        registry: &mut ctx.accounts.pool_registry,
+        page: &mut ctx.accounts.registry_page,
+        page_bump: ctx.bumps.registry_page,
+        mint_pools_a: mint_pools_a.to_account_info(),
+        mint_pools_a_bump,
+        mint_pools_b: mint_pools_b.to_account_info(),
+        mint_pools_b_bump,
+        payer: ctx.accounts.payer.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+    }
+    .register(entry)?;
+
+    // Name the LP token, then mint the initial LP tokens into the locked account
+    let seeds = &[
+        b"pool",
+        pool.token_a.as_ref(),
+        pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
+        &[pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Anyone can graduate a launch, so the LP token is named after the pair
+    let (name, symbol) = LpMetadata::pair_label(&pool.token_a, &pool.token_b);
+    InitLpMetadata {
+        lp_mint: ctx.accounts.lp_mint.to_account_info(),
+        pool: pool.to_account_info(),
+        payer: ctx.accounts.payer.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        token_program: ctx.accounts.lp_token_program.to_account_info(),
+        signer_seeds,
+    }
+    .initialize(LpMetadata::new(name, symbol, &ctx.accounts.amm_config.lp_uri_base, &pool.key())?)?;
+
+    let cpi_accounts = token_interface::MintTo {
+        mint: ctx.accounts.lp_mint.to_account_info(),
+        to: ctx.accounts.locked_lp.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.lp_token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        lp_amount,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+// programs/amm/src/instructions/harvest.rs
+use crate::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct Harvest<'info> {
+    #[account(mut, has_one = reward_vault)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        has_one = farm,
+        has_one = owner,
+        seeds = [b"farm_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, FarmPosition>,
+
+    #[account(mut)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = farm.reward_mint)]
+    pub user_reward: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub(crate) fn handler(ctx: Context<Harvest>) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+    let position = &mut ctx.accounts.position;
+
+    farm.update(Clock::get()?.unix_timestamp)?;
+    let staked = position.amount;
+    position.settle(farm, staked)?;
+
+    let rewards = position.rewards_owed;
+    if rewards == 0 {
+        return Ok(());
+    }
+    position.rewards_owed = 0;
+
+    let seeds = &[
+        b"farm",
+        farm.pool.as_ref(),
+        farm.reward_mint.as_ref(),
+        &[farm.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.reward_vault.to_account_info(),
+        to: ctx.accounts.user_reward.to_account_info(),
+        authority: farm.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        rewards,
+    )?;
+
+    Ok(())
+}
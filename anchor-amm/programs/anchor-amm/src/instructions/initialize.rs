@@ -1,6 +1,8 @@
 // programs/amm/src/instructions/initialize.rs
 use crate::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface;
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -8,12 +10,15 @@ pub struct Initialize<'info> {
         init,
         payer = payer,
         space = Pool::LEN,
-        seeds = [b"pool", token_a.key().as_ref(), token_b.key().as_ref()],
+        seeds = [b"pool", token_a.key().as_ref(), token_b.key().as_ref(), &amm_config.index.to_le_bytes()],
         bump,
     )]
     pub pool: Account<'info, Pool>,
 
-    /// Token mints (order must be consistent for PDA)
+    /// Fee tier of the pool
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// Token mints, sorted so that each pair has a single pool per fee tier
     pub token_a: Account<'info, Mint>,
     pub token_b: Account<'info, Mint>,
 
@@ -34,23 +39,180 @@ pub struct Initialize<'info> {
     )]
     pub vault_b: Account<'info, TokenAccount>,
 
-    /// LP mint
+    /// Token-2022 LP mint, holding its own metadata; `close_pool` closes it
     #[account(
         init,
         payer = payer,
-        mint::decimals = 6,
+        mint::decimals = lp_decimals(token_a.decimals, token_b.decimals),
         mint::authority = pool,
+        mint::token_program = lp_token_program,
+        extensions::metadata_pointer::authority = pool,
+        extensions::metadata_pointer::metadata_address = lp_mint,
+        extensions::close_authority::authority = pool,
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// Registry of every pool, created with the first pool
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PoolRegistry::LEN,
+        seeds = [b"pool_registry"],
+        bump,
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    /// Registry page the pool is appended to, created once the previous page is full
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = RegistryPage::LEN,
+        seeds = [b"registry_page".as_ref(), &pool_registry.next_page().to_le_bytes()],
+        bump,
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+
+    /// CHECK: `MintPools` of token A, created or grown by `append_mint_pool`
+    #[account(mut, seeds = [b"mint_pools", token_a.key().as_ref()], bump)]
+    pub mint_pools_a: UncheckedAccount<'info>,
+
+    /// CHECK: `MintPools` of token B, created or grown by `append_mint_pool`
+    #[account(mut, seeds = [b"mint_pools", token_b.key().as_ref()], bump)]
+    pub mint_pools_b: UncheckedAccount<'info>,
+
+    /// Allowlist of a permissioned pool, only passed with `allowlist_params`
+    #[account(
+        init,
+        payer = payer,
+        space = Allowlist::LEN,
+        seeds = [b"allowlist", pool.key().as_ref()],
+        bump,
     )]
-    pub lp_mint: Account<'info, Mint>,
+    pub allowlist: Option<Box<Account<'info, Allowlist>>>,
+
+    /// CHECK: Launch PDA of token A, checked by `Launch::check_not_launching`
+    #[account(seeds = [b"launch", token_a.key().as_ref()], bump)]
+    pub launch_a: UncheckedAccount<'info>,
+
+    /// CHECK: Launch PDA of token B, checked by `Launch::check_not_launching`
+    #[account(seeds = [b"launch", token_b.key().as_ref()], bump)]
+    pub launch_b: UncheckedAccount<'info>,
+
+    /// CHECK: Stake pool or interest-bearing mint of token A, only passed with `rate_a`; checked by `RateProviderParams::validate`
+    pub rate_provider_a: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Stake pool or interest-bearing mint of token B, only passed with `rate_b`; checked by `RateProviderParams::validate`
+    pub rate_provider_b: Option<UncheckedAccount<'info>>,
+
+    /// Prediction market of an `Lmsr` pool, whose YES and NO mints are token A and token B
+    pub market: Option<Box<Account<'info, PredictionMarket>>>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub lp_token_program: Program<'info, Token2022>,
 }
 
-pub fn handler(ctx: Context<Initialize>, fee: u64) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handler(
+    ctx: Context<Initialize>,
+    dynamic_fee: Option<DynamicFeeParams>,
+    curve: CurveType,
+    oracle_peg: Option<OraclePegParams>,
+    lmsr_b: Option<u64>,
+    lbp: Option<LbpParams>,
+    hook: Option<HookParams>,
+    rate_a: Option<RateProviderParams>,
+    rate_b: Option<RateProviderParams>,
+    hybrid_target_price: Option<u64>,
+    lp_name: String,
+    lp_symbol: String,
+    allowlist_params: Option<AllowlistParams>,
+) -> Result<()> {
+    // The pair (B, A) would otherwise get a second pool in the tier
+    require!(
+        ctx.accounts.token_a.key() < ctx.accounts.token_b.key(),
+        AmmError::InvalidTokenOrder
+    );
+
+    // Launched tokens trade on their curve until `graduate` creates their pool
+    Launch::check_not_launching(&ctx.accounts.launch_a)?;
+    Launch::check_not_launching(&ctx.accounts.launch_b)?;
+
+    let amm_config = &ctx.accounts.amm_config;
+    let fee = amm_config.trade_fee_bps;
+    if let Some(params) = dynamic_fee {
+        params.validate(fee)?;
+    }
+
+    // Oracle configuration is required by, and only accepted for, oracle-pegged pools
+    require!(
+        oracle_peg.is_some() == (curve == CurveType::OraclePeg),
+        AmmError::InvalidOracle
+    );
+    if let Some(params) = oracle_peg {
+        params.validate()?;
+    }
+
+    // Likewise for the liquidity parameter of LMSR pools
+    require!(
+        lmsr_b.is_some() == (curve == CurveType::Lmsr),
+        AmmError::InvalidLiquidityParameter
+    );
+    require!(lmsr_b != Some(0), AmmError::InvalidLiquidityParameter);
+
+    // LMSR prices only mean something for the two outcomes of an open market
+    require!(
+        ctx.accounts.market.is_some() == (curve == CurveType::Lmsr),
+        AmmError::InvalidMarket
+    );
+    if let Some(market) = &ctx.accounts.market {
+        market.check_outcome_mints(&ctx.accounts.token_a.key(), &ctx.accounts.token_b.key())?;
+        require!(market.outcome == MarketOutcome::Unresolved, AmmError::MarketResolved);
+    }
+
+    // And for the weight schedule of liquidity bootstrapping pools
+    require!(
+        lbp.is_some() == (curve == CurveType::Lbp),
+        AmmError::InvalidWeightSchedule
+    );
+    if let Some(params) = lbp {
+        params.validate(Clock::get()?.unix_timestamp)?;
+    }
+
+    if let Some(params) = hook {
+        params.validate()?;
+    }
+
+    // Exchange rates only scale the balances of stable pools
+    require!(
+        (rate_a.is_none() && rate_b.is_none()) || curve == CurveType::StableSwap,
+        AmmError::InvalidRateProvider
+    );
+    for (params, account, mint) in [
+        (rate_a, &ctx.accounts.rate_provider_a, ctx.accounts.token_a.key()),
+        (rate_b, &ctx.accounts.rate_provider_b, ctx.accounts.token_b.key()),
+    ] {
+        match (params, account) {
+            (Some(params), Some(account)) => params.validate(mint, account)?,
+            (None, None) => {}
+            _ => return err!(AmmError::InvalidRateProvider),
+        }
+    }
+
+    // Gamma only adapts on hybrid pools
+    require!(
+        hybrid_target_price.is_none() || curve == CurveType::HybridCfmm,
+        AmmError::InvalidHybridParams
+    );
+    let hybrid = if curve == CurveType::HybridCfmm {
+        HybridState::new(hybrid_target_price.unwrap_or(HybridState::DEFAULT_TARGET_PRICE))?
+    } else {
+        HybridState::default()
+    };
+
     let pool = &mut ctx.accounts.pool;
     pool.token_a = ctx.accounts.token_a.key();
     pool.token_b = ctx.accounts.token_b.key();
@@ -59,5 +221,86 @@ pub fn handler(ctx: Context<Initialize>, fee: u64) -> Result<()> {
     pool.lp_supply = 0;
     pool.fee = fee;
     pool.bump = ctx.bumps.pool;
-    Ok(())
+    pool.fee_mode = if dynamic_fee.is_some() {
+        FeeMode::Dynamic
+    } else {
+        FeeMode::Static
+    };
+    pool.dynamic_fee = dynamic_fee.unwrap_or_default();
+    pool.volatility = VolatilityState::default();
+    pool.creator = ctx.accounts.payer.key();
+    pool.open_orders = 0;
+    pool.curve = curve;
+    pool.decimals_a = ctx.accounts.token_a.decimals;
+    pool.decimals_b = ctx.accounts.token_b.decimals;
+    pool.oracle_peg = oracle_peg.unwrap_or_default();
+    pool.lmsr_b = lmsr_b.unwrap_or_default();
+    pool.lbp = lbp.unwrap_or_default();
+    pool.paused = false;
+    pool.amm_config = amm_config.key();
+    pool.fee_tier = amm_config.index;
+    pool.tick_spacing = amm_config.tick_spacing;
+    pool.vault_a = ctx.accounts.vault_a.key();
+    pool.vault_b = ctx.accounts.vault_b.key();
+    pool.hook = hook.unwrap_or_default();
+    pool.circuit_breaker = CircuitBreaker::default();
+    pool.stats = PoolStats::default();
+    pool.rate_a = rate_a.map(RateProvider::new).unwrap_or_default();
+    pool.rate_b = rate_b.map(RateProvider::new).unwrap_or_default();
+    pool.permissioned = allowlist_params.is_some();
+    pool.hybrid = hybrid;
+    pool.lp_mint = ctx.accounts.lp_mint.key();
+    pool.peg_target = PegTarget::default();
+    pool.legacy_seeds = false;
+
+    // Permissioned pools get their allowlist before anyone can deposit
+    require!(
+        ctx.accounts.allowlist.is_some() == allowlist_params.is_some(),
+        AmmError::InvalidAllowlist
+    );
+    if let (Some(allowlist), Some(params)) = (ctx.accounts.allowlist.as_mut(), allowlist_params) {
+        allowlist.pool = pool.key();
+        allowlist.kind = params.kind;
+        allowlist.merkle_root = params.merkle_root;
+        allowlist.bump = ctx.bumps.allowlist.ok_or(AmmError::InvalidAllowlist)?;
+    }
+
+    // List the pool for discovery
+    let entry = RegistryEntry {
+        pool: pool.key(),
+        token_a: pool.token_a,
+        token_b: pool.token_b,
+        curve: pool.curve,
+        fee_tier: pool.fee_tier,
+    };
+    RegisterPool {
+        registry: &mut ctx.accounts.pool_registry,
+        page: &mut ctx.accounts.registry_page,
+        page_bump: ctx.bumps.registry_page,
+        mint_pools_a: ctx.accounts.mint_pools_a.to_account_info(),
+        mint_pools_a_bump: ctx.bumps.mint_pools_a,
+        mint_pools_b: ctx.accounts.mint_pools_b.to_account_info(),
+        mint_pools_b_bump: ctx.bumps.mint_pools_b,
+        payer: ctx.accounts.payer.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+    }
+    .register(entry)?;
+
+    // Name the LP token so wallets can show it
+    let seeds = &[
+        b"pool",
+        pool.token_a.as_ref(),
+        pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
+        &[pool.bump],
+    ];
+    InitLpMetadata {
+        lp_mint: ctx.accounts.lp_mint.to_account_info(),
+        pool: pool.to_account_info(),
+        payer: ctx.accounts.payer.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        token_program: ctx.accounts.lp_token_program.to_account_info(),
+        signer_seeds: &[&seeds[..]],
+    }
+    .initialize(LpMetadata::new(lp_name, lp_symbol, &ctx.accounts.amm_config.lp_uri_base, &pool.key())?)
 }
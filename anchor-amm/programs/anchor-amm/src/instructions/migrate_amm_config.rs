@@ -0,0 +1,49 @@
+// programs/amm/src/instructions/migrate_amm_config.rs
+use crate::*;
+use anchor_lang::system_program;
+
+#[derive(Accounts)]
+pub struct MigrateAmmConfig<'info> {
+    /// CHECK: Fee tier written by an older version of the program, shorter than `AmmConfig::LEN`;
+    /// the owner is checked here and the discriminator in the handler
+    #[account(mut, owner = crate::ID)]
+    pub amm_config: UncheckedAccount<'info>,
+
+    /// Pays the rent of the added space
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Like `migrate_pool`: fields are only ever appended to `AmmConfig`, so
+/// zero-extending an older tier leaves referrals off until they are set.
+pub(crate) fn handler(ctx: Context<MigrateAmmConfig>) -> Result<()> {
+    let amm_config = ctx.accounts.amm_config.to_account_info();
+    require!(
+        amm_config.try_borrow_data()?.starts_with(AmmConfig::DISCRIMINATOR),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    if amm_config.data_len() >= AmmConfig::LEN {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?.minimum_balance(AmmConfig::LEN);
+    let top_up = rent.saturating_sub(amm_config.lamports());
+    if top_up > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.payer.to_account_info(),
+            to: amm_config.clone(),
+        };
+        system_program::transfer(
+            CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts),
+            top_up,
+        )?;
+    }
+
+    amm_config.resize(AmmConfig::LEN)?;
+
+    // The migrated account must deserialize as a current fee tier
+    AmmConfig::try_deserialize(&mut &amm_config.try_borrow_data()?[..])?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+// programs/amm/src/instructions/migrate_pool.rs
+use crate::*;
+use crate::program::Amm;
+use anchor_lang::system_program;
+use anchor_spl::token::{Mint, TokenAccount};
+use anchor_spl::token_interface;
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// CHECK: Pool written by an older version of the program, shorter than `Pool::LEN`;
+    /// the owner is checked here and the discriminator and address in the handler
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+
+    /// Token A reserve of the pool, checked in the handler against its mint and the pool authority
+    pub vault_a: Account<'info, TokenAccount>,
+
+    /// Token B reserve of the pool, checked like `vault_a`
+    pub vault_b: Account<'info, TokenAccount>,
+
+    /// LP mint of the pool, an SPL Token mint for pools from before Token-2022 LP mints
+    pub lp_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub token_a: Account<'info, Mint>,
+    pub token_b: Account<'info, Mint>,
+
+    /// Fee tier charging the pool's fee, which a pool from before fee tiers joins
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// The program's upgrade authority vouches for the accounts above, as anyone can
+    /// create token accounts and mints with the pool as their authority; pays the rent
+    /// of the added space and becomes the creator of pools that didn't record one
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AmmError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Fields are only ever appended to `Pool`, so zero-extending an older pool
+/// leaves its state in place. Pools from before fee tiers also get the fields
+/// that later instructions check: vaults, LP mint, fee tier, creator and their
+/// PDA layout.
+pub(crate) fn handler(ctx: Context<MigratePool>) -> Result<()> {
+    let info = ctx.accounts.pool.to_account_info();
+    require!(
+        info.try_borrow_data()?.starts_with(Pool::DISCRIMINATOR),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    if info.data_len() >= Pool::LEN {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?.minimum_balance(Pool::LEN);
+    let top_up = rent.saturating_sub(info.lamports());
+    if top_up > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.admin.to_account_info(),
+            to: info.clone(),
+        };
+        system_program::transfer(
+            CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts),
+            top_up,
+        )?;
+    }
+
+    info.resize(Pool::LEN)?;
+
+    // The migrated account must deserialize as a current pool
+    let mut pool = Pool::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    if pool.vault_a == Pubkey::default() {
+        fill_legacy_fields(&mut pool, ctx.accounts, info.key)?;
+    }
+    pool.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Record the fields a pool from before fee tiers is missing
+fn fill_legacy_fields(pool: &mut Pool, accounts: &MigratePool, pool_key: &Pubkey) -> Result<()> {
+    // Those pools live at `[b"pool", token_a, token_b]`
+    let seeds: &[&[u8]] = &[b"pool", pool.token_a.as_ref(), pool.token_b.as_ref(), &[pool.bump]];
+    require_keys_eq!(
+        Pubkey::create_program_address(seeds, &crate::ID).map_err(|_| ErrorCode::ConstraintSeeds)?,
+        *pool_key,
+        ErrorCode::ConstraintSeeds
+    );
+
+    // Each vault belongs to the pool and holds at least its reserve
+    for (vault, mint, token, reserve) in [
+        (&accounts.vault_a, &accounts.token_a, pool.token_a, pool.reserve_a),
+        (&accounts.vault_b, &accounts.token_b, pool.token_b, pool.reserve_b),
+    ] {
+        require_keys_eq!(mint.key(), token, ErrorCode::ConstraintAddress);
+        require_keys_eq!(vault.owner, *pool_key, AmmError::InvalidVault);
+        require_keys_eq!(vault.mint, token, AmmError::InvalidVault);
+        require!(vault.amount >= reserve, AmmError::InvalidVault);
+    }
+
+    let lp_mint = &accounts.lp_mint;
+    require!(
+        lp_mint.mint_authority == Some(*pool_key).into() && lp_mint.supply == pool.lp_supply,
+        AmmError::InvalidLpMint
+    );
+
+    // The pool keeps its own fee, in the tier that charges it
+    let amm_config = &accounts.amm_config;
+    require!(amm_config.trade_fee_bps == pool.fee, AmmError::InvalidFee);
+
+    pool.vault_a = accounts.vault_a.key();
+    pool.vault_b = accounts.vault_b.key();
+    pool.lp_mint = lp_mint.key();
+    pool.decimals_a = accounts.token_a.decimals;
+    pool.decimals_b = accounts.token_b.decimals;
+    pool.amm_config = amm_config.key();
+    pool.fee_tier = amm_config.index;
+    pool.tick_spacing = amm_config.tick_spacing;
+    pool.creator = accounts.admin.key();
+    pool.legacy_seeds = true;
+    Ok(())
+}
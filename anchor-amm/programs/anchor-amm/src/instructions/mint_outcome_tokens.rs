@@ -0,0 +1,78 @@
+// programs/amm/src/instructions/mint_outcome_tokens.rs
+use crate::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct MintOutcomeTokens<'info> {
+    #[account(has_one = collateral_vault, has_one = yes_mint, has_one = no_mint)]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(mut)]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub yes_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub no_mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = market.collateral_mint)]
+    pub user_collateral: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = yes_mint)]
+    pub user_yes: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = no_mint)]
+    pub user_no: Account<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Deposit `amount` collateral and receive `amount` YES and `amount` NO tokens
+pub(crate) fn handler(ctx: Context<MintOutcomeTokens>, amount: u64) -> Result<()> {
+    let market = &ctx.accounts.market;
+    require!(amount > 0, AmmError::InsufficientLiquidity);
+    require!(market.outcome == MarketOutcome::Unresolved, AmmError::MarketResolved);
+
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.user_collateral.to_account_info(),
+        to: ctx.accounts.collateral_vault.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+        amount,
+    )?;
+
+    let market_id = market.market_id.to_le_bytes();
+    let seeds = &[
+        b"market",
+        market.creator.as_ref(),
+        market_id.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    for (mint, to) in [
+        (&ctx.accounts.yes_mint, &ctx.accounts.user_yes),
+        (&ctx.accounts.no_mint, &ctx.accounts.user_no),
+    ] {
+        let cpi_accounts = token::MintTo {
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: market.to_account_info(),
+        };
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    Ok(())
+}
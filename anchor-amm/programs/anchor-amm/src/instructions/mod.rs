@@ -1,11 +1,69 @@
+pub mod add_allowlist_member;
+pub mod cancel_limit_order;
+pub mod claim_filled_order;
+pub mod close_pool;
+pub mod crank_gamma;
+pub mod create_allowlist;
+pub mod create_amm_config;
+pub mod create_farm;
+pub mod create_launch;
+pub mod create_market;
 pub mod deposit;
+pub mod graduate;
+pub mod harvest;
+pub mod migrate_amm_config;
+pub mod migrate_pool;
 pub mod initialize;
+pub mod mint_outcome_tokens;
+pub mod place_limit_order;
+pub mod reclaim_farm_rewards;
+pub mod redeem_outcome_tokens;
+pub mod remove_allowlist_member;
+pub mod repeg_pool;
+pub mod resolve_market;
+pub mod set_allowlist_root;
+pub mod set_circuit_breaker;
+pub mod set_lp_uri_base;
+pub mod set_pool_paused;
+pub mod set_referral_fees;
+pub mod stake_lp;
 pub mod swap;
+pub mod swap_launch;
+pub mod unstake_lp;
 pub mod withdraw;
 pub mod strategy;
 
+pub use add_allowlist_member::*;
+pub use cancel_limit_order::*;
+pub use claim_filled_order::*;
+pub use close_pool::*;
+pub use crank_gamma::*;
+pub use create_allowlist::*;
+pub use create_amm_config::*;
+pub use create_farm::*;
+pub use create_launch::*;
+pub use create_market::*;
 pub use deposit::*;
+pub use graduate::*;
+pub use harvest::*;
+pub use migrate_amm_config::*;
+pub use migrate_pool::*;
 pub use initialize::*;
+pub use mint_outcome_tokens::*;
+pub use place_limit_order::*;
+pub use reclaim_farm_rewards::*;
+pub use redeem_outcome_tokens::*;
+pub use remove_allowlist_member::*;
+pub use repeg_pool::*;
+pub use resolve_market::*;
+pub use set_allowlist_root::*;
+pub use set_circuit_breaker::*;
+pub use set_lp_uri_base::*;
+pub use set_pool_paused::*;
+pub use set_referral_fees::*;
+pub use stake_lp::*;
 pub use swap::*;
+pub use swap_launch::*;
+pub use unstake_lp::*;
 pub use withdraw::*;
 pub use strategy::*;
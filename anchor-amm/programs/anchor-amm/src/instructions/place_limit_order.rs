@@ -0,0 +1,98 @@
+// programs/amm/src/instructions/place_limit_order.rs
+use crate::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+#[instruction(tick: i32, amount: u64, side: OrderSide)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = LimitOrder::LEN,
+        seeds = [b"limit_order", pool.key().as_ref(), owner.key().as_ref(), &tick.to_le_bytes()],
+        bump,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    /// Order book of the pool, created with its first order
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = OrderBook::LEN,
+        seeds = [b"order_book", pool.key().as_ref()],
+        bump,
+    )]
+    pub order_book: Box<Account<'info, OrderBook>>,
+
+    /// Pool vault of the token being sold (A for asks, B for bids)
+    #[account(
+        mut,
+        address = if side == OrderSide::Ask { pool.vault_a } else { pool.vault_b } @ AmmError::InvalidVault,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = vault.mint)]
+    pub user_source: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub(crate) fn handler(ctx: Context<PlaceLimitOrder>, tick: i32, amount: u64, side: OrderSide) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidLimitOrder);
+
+    let pool = &ctx.accounts.pool;
+    // Fills move the price along the pool's curve onto the order (see `swap`)
+    require!(pool.curve.fills_limit_orders(), AmmError::InvalidLimitOrder);
+    // Orders would let wallets off the allowlist trade against the pool
+    require!(!pool.permissioned, AmmError::NotAllowlisted);
+    require!(tick % pool.tick_spacing as i32 == 0, AmmError::InvalidTick);
+    let tick_price = ConcentratedLiquidityStrategy::tick_to_price(tick)?;
+    let spot_price = VolatilityState::spot_price(pool.reserve_a, pool.reserve_b)?;
+
+    // Orders rest on the far side of the current price so they fill when it moves onto them
+    match side {
+        OrderSide::Ask => require!(tick_price > spot_price, AmmError::InvalidLimitOrder),
+        OrderSide::Bid => require!(tick_price < spot_price, AmmError::InvalidLimitOrder),
+    }
+
+    // Escrow the order amount in the pool vault, outside of the reserves
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.user_source.to_account_info(),
+        to: ctx.accounts.vault.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+        amount,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.open_orders = pool.open_orders.checked_add(1).ok_or(AmmError::MathOverflow)?;
+
+    let order = &mut ctx.accounts.order;
+    order.pool = pool.key();
+    order.owner = ctx.accounts.owner.key();
+    order.tick = tick;
+    order.side = side;
+    order.amount = amount;
+    order.bump = ctx.bumps.order;
+
+    let order_book = &mut ctx.accounts.order_book;
+    order_book.pool = pool.key();
+    order_book.bump = ctx.bumps.order_book;
+    order_book.push(BookOrder {
+        order: order.key(),
+        tick,
+        side,
+        remaining: amount,
+        proceeds: 0,
+    })
+
+}
@@ -0,0 +1,60 @@
+// programs/amm/src/instructions/reclaim_farm_rewards.rs
+use crate::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct ReclaimFarmRewards<'info> {
+    #[account(mut, has_one = reward_vault, has_one = authority)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(mut)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = farm.reward_mint)]
+    pub authority_reward: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Return the rewards emitted while nothing was staked to the farm authority
+///
+/// Rewards already emitted stay in the vault for the stakers to harvest.
+pub(crate) fn handler(ctx: Context<ReclaimFarmRewards>) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= farm.end_time, AmmError::FarmNotEnded);
+
+    farm.update(now)?;
+    let amount = farm.unemitted_rewards()?;
+    farm.last_update_time = farm.end_time;
+    farm.idle_rewards = 0;
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let seeds = &[
+        b"farm",
+        farm.pool.as_ref(),
+        farm.reward_mint.as_ref(),
+        &[farm.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.reward_vault.to_account_info(),
+        to: ctx.accounts.authority_reward.to_account_info(),
+        authority: farm.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// programs/amm/src/instructions/redeem_outcome_tokens.rs
+use crate::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct RedeemOutcomeTokens<'info> {
+    #[account(has_one = collateral_vault, has_one = yes_mint, has_one = no_mint)]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(mut)]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub yes_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub no_mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = market.collateral_mint)]
+    pub user_collateral: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = yes_mint)]
+    pub user_yes: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = no_mint)]
+    pub user_no: Account<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Burn outcome tokens for `amount` collateral
+///
+/// Before resolution a complete set (`amount` YES and `amount` NO) is burned;
+/// after resolution only `amount` of the winning token.
+pub(crate) fn handler(ctx: Context<RedeemOutcomeTokens>, amount: u64) -> Result<()> {
+    let market = &ctx.accounts.market;
+    require!(amount > 0, AmmError::InsufficientLiquidity);
+
+    let burn_yes = market.outcome != MarketOutcome::No;
+    let burn_no = market.outcome != MarketOutcome::Yes;
+
+    for (burn, mint, from) in [
+        (burn_yes, &ctx.accounts.yes_mint, &ctx.accounts.user_yes),
+        (burn_no, &ctx.accounts.no_mint, &ctx.accounts.user_no),
+    ] {
+        if !burn {
+            continue;
+        }
+        let cpi_accounts = token::Burn {
+            mint: mint.to_account_info(),
+            from: from.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        token::burn(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount,
+        )?;
+    }
+
+    let market_id = market.market_id.to_le_bytes();
+    let seeds = &[
+        b"market",
+        market.creator.as_ref(),
+        market_id.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.collateral_vault.to_account_info(),
+        to: ctx.accounts.user_collateral.to_account_info(),
+        authority: market.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+// programs/amm/src/instructions/remove_allowlist_member.rs
+use crate::*;
+
+#[derive(Accounts)]
+pub struct RemoveAllowlistMember<'info> {
+    #[account(has_one = creator)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        seeds = [b"allowlist_entry", pool.key().as_ref(), entry.member.as_ref()],
+        bump = entry.bump,
+        close = creator,
+    )]
+    pub entry: Account<'info, AllowlistEntry>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+pub(crate) fn handler(_ctx: Context<RemoveAllowlistMember>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,40 @@
+// programs/amm/src/instructions/repeg_pool.rs
+use crate::*;
+use crate::program::Amm;
+
+#[derive(Accounts)]
+pub struct RepegPool<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    /// The pool creator, or the program's upgrade authority
+    pub authority: Signer<'info>,
+
+    pub program: Program<'info, Amm>,
+
+    /// Only passed when the upgrade authority repegs the pool
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ AmmError::Unauthorized,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ AmmError::Unauthorized,
+    )]
+    pub program_data: Option<Account<'info, ProgramData>>,
+}
+
+/// Reset the target reserves of an oracle-pegged pool to its current reserves
+///
+/// Targets otherwise only move with deposits and withdrawals, so this settles
+/// a pool whose reserves drifted from them, e.g. after the oracle price moved.
+pub(crate) fn handler(ctx: Context<RepegPool>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    require!(
+        ctx.accounts.authority.key() == pool.creator || ctx.accounts.program_data.is_some(),
+        AmmError::Unauthorized
+    );
+    require!(pool.curve == CurveType::OraclePeg, AmmError::InvalidOracle);
+
+    pool.peg_target = PegTarget {
+        target_a: pool.reserve_a,
+        target_b: pool.reserve_b,
+    };
+    Ok(())
+}
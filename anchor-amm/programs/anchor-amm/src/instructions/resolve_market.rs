@@ -0,0 +1,19 @@
+// programs/amm/src/instructions/resolve_market.rs
+use crate::*;
+
+#[derive(Accounts)]
+pub struct ResolveMarket<'info> {
+    #[account(mut, has_one = resolver)]
+    pub market: Account<'info, PredictionMarket>,
+
+    pub resolver: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<ResolveMarket>, outcome: MarketOutcome) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    require!(market.outcome == MarketOutcome::Unresolved, AmmError::MarketResolved);
+    require!(outcome != MarketOutcome::Unresolved, AmmError::InvalidOutcome);
+
+    market.outcome = outcome;
+    Ok(())
+}
@@ -0,0 +1,21 @@
+// programs/amm/src/instructions/set_allowlist_root.rs
+use crate::*;
+
+#[derive(Accounts)]
+pub struct SetAllowlistRoot<'info> {
+    #[account(has_one = creator)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut, seeds = [b"allowlist", pool.key().as_ref()], bump = allowlist.bump)]
+    pub allowlist: Account<'info, Allowlist>,
+
+    pub creator: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<SetAllowlistRoot>, merkle_root: [u8; 32]) -> Result<()> {
+    let allowlist = &mut ctx.accounts.allowlist;
+    require!(allowlist.kind == AllowlistKind::MerkleRoot, AmmError::InvalidAllowlist);
+
+    allowlist.merkle_root = merkle_root;
+    Ok(())
+}
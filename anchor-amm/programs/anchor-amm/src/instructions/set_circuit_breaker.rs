@@ -0,0 +1,35 @@
+// programs/amm/src/instructions/set_circuit_breaker.rs
+use crate::*;
+use crate::program::Amm;
+
+#[derive(Accounts)]
+pub struct SetCircuitBreaker<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    /// The program's upgrade authority administers the circuit breakers
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AmmError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+pub(crate) fn handler(
+    ctx: Context<SetCircuitBreaker>,
+    max_slot_move_bps: u64,
+    max_trade_move_bps: u64,
+    overridden: bool,
+) -> Result<()> {
+    let breaker = &mut ctx.accounts.pool.circuit_breaker;
+    breaker.max_slot_move_bps = max_slot_move_bps;
+    breaker.max_trade_move_bps = max_trade_move_bps;
+    breaker.overridden = overridden;
+
+    // The next swap takes a fresh reference price
+    breaker.reference_price = 0;
+    breaker.reference_slot = 0;
+    Ok(())
+}
@@ -0,0 +1,25 @@
+// programs/amm/src/instructions/set_lp_uri_base.rs
+use crate::*;
+use crate::program::Amm;
+
+#[derive(Accounts)]
+pub struct SetLpUriBase<'info> {
+    #[account(mut)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// The program's upgrade authority administers the fee tiers
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AmmError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+/// Only LP mints created afterwards take the new base
+pub(crate) fn handler(ctx: Context<SetLpUriBase>, lp_uri_base: String) -> Result<()> {
+    require!(lp_uri_base.len() <= MAX_LP_URI_BASE_LEN, AmmError::InvalidLpMetadata);
+    ctx.accounts.amm_config.lp_uri_base = lp_uri_base;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+// programs/amm/src/instructions/set_pool_paused.rs
+use crate::*;
+
+#[derive(Accounts)]
+pub struct SetPoolPaused<'info> {
+    #[account(mut, has_one = creator)]
+    pub pool: Account<'info, Pool>,
+
+    pub creator: Signer<'info>,
+}
+
+pub(crate) fn handler(ctx: Context<SetPoolPaused>, paused: bool) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    require!(pool.curve == CurveType::Lbp, AmmError::Unauthorized);
+
+    pool.paused = paused;
+    Ok(())
+}
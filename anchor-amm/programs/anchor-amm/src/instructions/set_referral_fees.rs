@@ -0,0 +1,40 @@
+// programs/amm/src/instructions/set_referral_fees.rs
+use crate::*;
+use crate::program::Amm;
+
+#[derive(Accounts)]
+pub struct SetReferralFees<'info> {
+    #[account(mut)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// The program's upgrade authority administers the referral caps
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AmmError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+/// Replaces the tier's bound and all of its referrers
+pub(crate) fn handler(
+    ctx: Context<SetReferralFees>,
+    max_referral_fee_bps: u64,
+    referrer_caps: Vec<ReferrerCap>,
+) -> Result<()> {
+    strategy::math::validate_fee(max_referral_fee_bps)?;
+    require!(referrer_caps.len() <= MAX_REFERRER_CAPS, AmmError::InvalidReferral);
+
+    let mut caps = [ReferrerCap::default(); MAX_REFERRER_CAPS];
+    for (slot, cap) in caps.iter_mut().zip(referrer_caps) {
+        require!(cap.referrer != Pubkey::default(), AmmError::InvalidReferral);
+        require!(cap.max_fee_bps <= max_referral_fee_bps, AmmError::ReferralFeeTooHigh);
+        *slot = cap;
+    }
+
+    let amm_config = &mut ctx.accounts.amm_config;
+    amm_config.max_referral_fee_bps = max_referral_fee_bps;
+    amm_config.referrer_caps = caps;
+    Ok(())
+}
@@ -0,0 +1,69 @@
+// programs/amm/src/instructions/stake_lp.rs
+use crate::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct StakeLp<'info> {
+    #[account(mut, has_one = staked_vault)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = FarmPosition::LEN,
+        seeds = [b"farm_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub position: Account<'info, FarmPosition>,
+
+    #[account(mut)]
+    pub staked_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = farm.lp_mint @ AmmError::InvalidLpMint)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, token::mint = farm.lp_mint)]
+    pub user_lp: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program of the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub(crate) fn handler(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+    require!(amount > 0, AmmError::InsufficientStake);
+
+    let farm = &mut ctx.accounts.farm;
+    let now = Clock::get()?.unix_timestamp;
+    // Late stakers would collect the rewards left for `reclaim_farm_rewards`
+    require!(now < farm.end_time, AmmError::FarmEnded);
+    farm.update(now)?;
+
+    let position = &mut ctx.accounts.position;
+    if position.farm == Pubkey::default() {
+        position.farm = farm.key();
+        position.owner = ctx.accounts.owner.key();
+        position.bump = ctx.bumps.position;
+    }
+
+    let new_amount = position.amount.checked_add(amount).ok_or(AmmError::MathOverflow)?;
+    position.settle(farm, new_amount)?;
+    farm.total_staked = farm.total_staked.checked_add(amount).ok_or(AmmError::MathOverflow)?;
+
+    let cpi_accounts = token_interface::TransferChecked {
+        from: ctx.accounts.user_lp.to_account_info(),
+        mint: ctx.accounts.lp_mint.to_account_info(),
+        to: ctx.accounts.staked_vault.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+        amount,
+        ctx.accounts.lp_mint.decimals,
+    )?;
+
+    Ok(())
+}
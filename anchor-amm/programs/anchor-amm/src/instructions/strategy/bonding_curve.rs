@@ -0,0 +1,155 @@
+// programs/anchor-amm/src/instructions/strategy/bonding_curve.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use super::constant_product::ConstantProductStrategy;
+use super::math::{mul_div_u64, Rounding};
+use super::AmmStrategy;
+
+/// Bonding Curve Strategy for token launches (pump.fun style)
+///
+/// A constant product curve over `real + virtual` reserves. The virtual
+/// reserves set the starting price without anyone providing the other side,
+/// so a freshly minted token can be sold against nothing but the curve.
+/// Trades are one-sided: the output is always paid from the real reserves.
+///
+/// Formula: (real_in + virtual_in + dx) * (real_out + virtual_out - dy) = k
+/// Where: dy <= real_out
+pub struct BondingCurveStrategy;
+
+impl BondingCurveStrategy {
+    /// Calculate swap output against real plus virtual reserves
+    ///
+    /// Fails if the output would exceed the real reserve.
+    pub fn calculate_amount_out_with_virtual(
+        amount_in: u64,
+        real_in: u64,
+        real_out: u64,
+        virtual_in: u64,
+        virtual_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        let reserve_in = real_in.checked_add(virtual_in).ok_or(AmmError::MathOverflow)?;
+        let reserve_out = real_out.checked_add(virtual_out).ok_or(AmmError::MathOverflow)?;
+
+        let amount_out =
+            ConstantProductStrategy::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps)?;
+
+        // Virtual reserves only shape the price, they can't be paid out
+        require!(amount_out <= real_out, AmmError::InsufficientLiquidity);
+
+        Ok(amount_out)
+    }
+
+    /// Market cap in quote units: total_supply * quote_reserve / token_reserve, rounded down
+    ///
+    /// Reserves include the virtual part.
+    pub fn market_cap(token_reserve: u64, quote_reserve: u64, total_supply: u64) -> Result<u64> {
+        require!(token_reserve > 0, AmmError::InsufficientLiquidity);
+        mul_div_u64(total_supply, quote_reserve, token_reserve, Rounding::Down)
+    }
+
+    /// Tokens to pair with `quote_amount` at the curve price, rounded down
+    ///
+    /// Formula: quote_amount * token_reserve / quote_reserve
+    pub fn tokens_at_price(quote_amount: u64, token_reserve: u64, quote_reserve: u64) -> Result<u64> {
+        require!(quote_reserve > 0, AmmError::InsufficientLiquidity);
+        mul_div_u64(quote_amount, token_reserve, quote_reserve, Rounding::Down)
+    }
+}
+
+impl AmmStrategy for BondingCurveStrategy {
+    /// Calculate swap output without virtual reserves
+    ///
+    /// With no virtual reserves the bonding curve is the constant product curve.
+    /// Launches use `calculate_amount_out_with_virtual`.
+    fn calculate_amount_out(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        Self::calculate_amount_out_with_virtual(amount_in, reserve_in, reserve_out, 0, 0, fee_bps)
+    }
+
+    /// LP tokens are accounted as in the constant product pool the launch graduates into
+    fn calculate_initial_lp_supply(amount_a: u64, amount_b: u64) -> Result<u64> {
+        ConstantProductStrategy::calculate_initial_lp_supply(amount_a, amount_b)
+    }
+
+    fn calculate_lp_tokens_to_mint(
+        amount_a: u64,
+        reserve_a: u64,
+        lp_supply: u64,
+    ) -> Result<u64> {
+        ConstantProductStrategy::calculate_lp_tokens_to_mint(amount_a, reserve_a, lp_supply)
+    }
+
+    fn calculate_withdraw_amounts(
+        lp_amount: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        lp_supply: u64,
+    ) -> Result<(u64, u64)> {
+        ConstantProductStrategy::calculate_withdraw_amounts(lp_amount, reserve_a, reserve_b, lp_supply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1B tokens on the curve with 30 quote of virtual liquidity (6 decimals)
+    const SUPPLY: u64 = 1_000_000_000_000_000;
+    const VIRTUAL_QUOTE: u64 = 30_000_000;
+    const VIRTUAL_TOKEN: u64 = 70_000_000_000_000;
+
+    #[test]
+    fn test_first_buy_uses_virtual_reserves() {
+        // 1 quote against 30 virtual quote buys about 1/31 of the effective token reserve
+        let tokens = BondingCurveStrategy::calculate_amount_out_with_virtual(
+            1_000_000, 0, SUPPLY, VIRTUAL_QUOTE, VIRTUAL_TOKEN, 0,
+        )
+        .unwrap();
+        let expected = (SUPPLY + VIRTUAL_TOKEN) / 31;
+        assert!(tokens.abs_diff(expected) <= 1);
+    }
+
+    #[test]
+    fn test_sell_is_paid_from_real_reserve_only() {
+        // Nothing has been bought yet, so there is no real quote to pay a seller
+        let result = BondingCurveStrategy::calculate_amount_out_with_virtual(
+            1_000_000_000, SUPPLY, 0, VIRTUAL_TOKEN, VIRTUAL_QUOTE, 0,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_buy_then_sell_never_profits() {
+        let tokens = BondingCurveStrategy::calculate_amount_out_with_virtual(
+            5_000_000, 0, SUPPLY, VIRTUAL_QUOTE, VIRTUAL_TOKEN, 100,
+        )
+        .unwrap();
+        let quote = BondingCurveStrategy::calculate_amount_out_with_virtual(
+            tokens, SUPPLY - tokens, 5_000_000, VIRTUAL_TOKEN, VIRTUAL_QUOTE, 100,
+        )
+        .unwrap();
+        assert!(quote < 5_000_000);
+    }
+
+    #[test]
+    fn test_market_cap_grows_with_buys() {
+        let before =
+            BondingCurveStrategy::market_cap(SUPPLY + VIRTUAL_TOKEN, VIRTUAL_QUOTE, SUPPLY).unwrap();
+        let tokens = BondingCurveStrategy::calculate_amount_out_with_virtual(
+            10_000_000, 0, SUPPLY, VIRTUAL_QUOTE, VIRTUAL_TOKEN, 0,
+        )
+        .unwrap();
+        let after = BondingCurveStrategy::market_cap(
+            SUPPLY + VIRTUAL_TOKEN - tokens,
+            VIRTUAL_QUOTE + 10_000_000,
+            SUPPLY,
+        )
+        .unwrap();
+        assert!(after > before);
+    }
+}
@@ -0,0 +1,127 @@
+// programs/anchor-amm/src/instructions/strategy/circuit_breaker.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+
+/// Price-impact circuit breaker stored on the pool
+///
+/// Bounds how far swaps can move the spot price (the reserve ratio that other
+/// protocols read, scaled by `PRICE_SCALE`): within a single trade, and from
+/// the price at the start of the slot. This caps what one transaction, or a
+/// bundle of them in one slot, can do to a price that someone reads as an
+/// oracle.
+///
+/// - `max_slot_move_bps`: largest move from `reference_price` within a slot (0 = no bound)
+/// - `max_trade_move_bps`: largest move of a single swap (0 = no bound)
+/// - `overridden`: checks suspended by the program authority during an incident
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub max_slot_move_bps: u64,
+    pub max_trade_move_bps: u64,
+    pub overridden: bool,
+    pub reference_price: u128, // Spot price before the first swap of `reference_slot`
+    pub reference_slot: u64,
+}
+
+impl CircuitBreaker {
+    pub const LEN: usize = 8 + 8 + 1 + 16 + 8;
+
+    /// Whether swaps are checked
+    pub fn is_active(&self) -> bool {
+        !self.overridden && (self.max_slot_move_bps > 0 || self.max_trade_move_bps > 0)
+    }
+
+    /// Take `price` as the reference if this is the first swap of `current_slot`
+    pub fn update_reference(&mut self, current_slot: u64, price: u128) {
+        if self.reference_price == 0 || current_slot != self.reference_slot {
+            self.reference_price = price;
+            self.reference_slot = current_slot;
+        }
+    }
+
+    /// Check a swap that moved the spot price from `price_before` to `price_after`
+    pub fn check(&self, price_before: u128, price_after: u128) -> Result<()> {
+        if self.max_trade_move_bps > 0 {
+            let trade_move = Self::move_bps(price_before, price_after)?;
+            require!(trade_move <= self.max_trade_move_bps as u128, AmmError::PriceMoveExceeded);
+        }
+        if self.max_slot_move_bps > 0 {
+            let slot_move = Self::move_bps(self.reference_price, price_after)?;
+            require!(slot_move <= self.max_slot_move_bps as u128, AmmError::PriceMoveExceeded);
+        }
+        Ok(())
+    }
+
+    /// Price move in basis points, rounded up
+    ///
+    /// Formula: |to - from| * 10_000 / from
+    fn move_bps(from: u128, to: u128) -> Result<u128> {
+        require!(from > 0, AmmError::ZeroReserve);
+        let delta = to.abs_diff(from).checked_mul(10_000).ok_or(AmmError::MathOverflow)?;
+        Ok(delta.div_ceil(from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRICE: u128 = 1_000_000_000_000;
+
+    fn breaker(max_slot_move_bps: u64, max_trade_move_bps: u64) -> CircuitBreaker {
+        CircuitBreaker {
+            max_slot_move_bps,
+            max_trade_move_bps,
+            ..CircuitBreaker::default()
+        }
+    }
+
+    #[test]
+    fn test_reference_is_taken_once_per_slot() {
+        let mut breaker = breaker(100, 0);
+        breaker.update_reference(10, PRICE);
+        breaker.update_reference(10, PRICE * 2);
+        assert_eq!(breaker.reference_price, PRICE);
+
+        breaker.update_reference(11, PRICE * 2);
+        assert_eq!(breaker.reference_price, PRICE * 2);
+        assert_eq!(breaker.reference_slot, 11);
+    }
+
+    #[test]
+    fn test_trade_bound() {
+        let mut breaker = breaker(0, 100);
+        breaker.update_reference(10, PRICE);
+        assert!(breaker.check(PRICE, PRICE * 101 / 100).is_ok());
+        assert!(breaker.check(PRICE, PRICE * 99 / 100).is_ok());
+        assert_eq!(
+            breaker.check(PRICE, PRICE * 102 / 100).unwrap_err(),
+            AmmError::PriceMoveExceeded.into()
+        );
+    }
+
+    #[test]
+    fn test_slot_bound_accumulates_across_trades() {
+        // Each trade moves the price 1%, the slot allows 1.5%
+        let mut breaker = breaker(150, 100);
+        breaker.update_reference(10, PRICE);
+        let first = PRICE * 101 / 100;
+        assert!(breaker.check(PRICE, first).is_ok());
+
+        breaker.update_reference(10, first);
+        let second = first * 101 / 100;
+        assert_eq!(breaker.check(first, second).unwrap_err(), AmmError::PriceMoveExceeded.into());
+
+        // The next slot starts from the new price
+        breaker.update_reference(11, first);
+        assert!(breaker.check(first, second).is_ok());
+    }
+
+    #[test]
+    fn test_override_and_zero_bounds_disable_checks() {
+        assert!(!breaker(0, 0).is_active());
+        assert!(breaker(100, 0).is_active());
+
+        let overridden = CircuitBreaker { overridden: true, ..breaker(100, 100) };
+        assert!(!overridden.is_active());
+    }
+}
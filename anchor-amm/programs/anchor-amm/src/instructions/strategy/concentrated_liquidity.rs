@@ -1,8 +1,17 @@
 // programs/anchor-amm/src/instructions/strategy/concentrated_liquidity.rs
 use anchor_lang::prelude::*;
 use crate::errors::AmmError;
+use super::dynamic_fee::PRICE_SCALE;
+use super::math::{amount_after_fee, mul_div, mul_div_u64, Rounding, U256};
 use super::AmmStrategy;
 
+/// Tick bounds: 1.0001^±221_818 keeps prices (scaled by `PRICE_SCALE`) inside u128
+pub const MIN_TICK: i32 = -221_818;
+pub const MAX_TICK: i32 = 221_818;
+
+/// 1.0001 scaled by `PRICE_SCALE`
+const TICK_BASE: u128 = 1_000_100_000_000;
+
 /// Concentrated Liquidity AMM Strategy (Uniswap V3 style)
 /// 
 /// This implements concentrated liquidity where providers can specify price ranges
@@ -24,6 +33,48 @@ pub struct PriceRange {
 }
 
 impl ConcentratedLiquidityStrategy {
+    /// Price of token A in token B at a tick, scaled by `PRICE_SCALE`
+    ///
+    /// Formula: price = 1.0001^tick, computed by binary exponentiation
+    pub fn tick_to_price(tick: i32) -> Result<u128> {
+        require!(
+            (MIN_TICK..=MAX_TICK).contains(&tick),
+            AmmError::InvalidTick
+        );
+
+        let mut exponent = tick.unsigned_abs();
+        let mut base = TICK_BASE;
+        let mut price = PRICE_SCALE;
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                price = price
+                    .checked_mul(base)
+                    .ok_or(AmmError::MathOverflow)?
+                    .checked_div(PRICE_SCALE)
+                    .ok_or(AmmError::MathOverflow)?;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base
+                    .checked_mul(base)
+                    .ok_or(AmmError::MathOverflow)?
+                    .checked_div(PRICE_SCALE)
+                    .ok_or(AmmError::MathOverflow)?;
+            }
+        }
+
+        if tick < 0 {
+            price = PRICE_SCALE
+                .checked_mul(PRICE_SCALE)
+                .ok_or(AmmError::MathOverflow)?
+                .checked_div(price)
+                .ok_or(AmmError::MathOverflow)?;
+        }
+
+        Ok(price)
+    }
+
     /// Calculate the amount of token0 for a given liquidity and price range
     /// 
     /// Formula: x = L * (sqrt(pb) - sqrt(p)) / (sqrt(p) * sqrt(pb))
@@ -38,26 +89,26 @@ impl ConcentratedLiquidityStrategy {
             // Price is below range, all liquidity is in token0
             let numerator = liquidity
                 .checked_mul(range.sqrt_price_upper as u128)
-                .ok_or(AmmError::Overflow)?
-                .checked_sub(liquidity.checked_mul(range.sqrt_price_lower as u128).ok_or(AmmError::Overflow)?)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?
+                .checked_sub(liquidity.checked_mul(range.sqrt_price_lower as u128).ok_or(AmmError::MathOverflow)?)
+                .ok_or(AmmError::MathOverflow)?;
             
             let denominator = (range.sqrt_price_lower as u128)
                 .checked_mul(range.sqrt_price_upper as u128)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             Ok((numerator / denominator) as u64)
         } else {
             // Price is within range
             let numerator = liquidity
                 .checked_mul(range.sqrt_price_upper as u128)
-                .ok_or(AmmError::Overflow)?
-                .checked_sub(liquidity.checked_mul(range.sqrt_price_current as u128).ok_or(AmmError::Overflow)?)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?
+                .checked_sub(liquidity.checked_mul(range.sqrt_price_current as u128).ok_or(AmmError::MathOverflow)?)
+                .ok_or(AmmError::MathOverflow)?;
             
             let denominator = (range.sqrt_price_current as u128)
                 .checked_mul(range.sqrt_price_upper as u128)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             Ok((numerator / denominator) as u64)
         }
@@ -77,18 +128,18 @@ impl ConcentratedLiquidityStrategy {
             // Price is above range, all liquidity is in token1
             let amount = liquidity
                 .checked_mul(range.sqrt_price_upper as u128)
-                .ok_or(AmmError::Overflow)?
-                .checked_sub(liquidity.checked_mul(range.sqrt_price_lower as u128).ok_or(AmmError::Overflow)?)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?
+                .checked_sub(liquidity.checked_mul(range.sqrt_price_lower as u128).ok_or(AmmError::MathOverflow)?)
+                .ok_or(AmmError::MathOverflow)?;
             
             Ok(amount as u64)
         } else {
             // Price is within range
             let amount = liquidity
                 .checked_mul(range.sqrt_price_current as u128)
-                .ok_or(AmmError::Overflow)?
-                .checked_sub(liquidity.checked_mul(range.sqrt_price_lower as u128).ok_or(AmmError::Overflow)?)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?
+                .checked_sub(liquidity.checked_mul(range.sqrt_price_lower as u128).ok_or(AmmError::MathOverflow)?)
+                .ok_or(AmmError::MathOverflow)?;
             
             Ok(amount as u64)
         }
@@ -112,13 +163,13 @@ impl ConcentratedLiquidityStrategy {
         let liquidity0 = if range.sqrt_price_current < range.sqrt_price_upper {
             let numerator = (amount0 as u128)
                 .checked_mul(range.sqrt_price_current as u128)
-                .ok_or(AmmError::Overflow)?
+                .ok_or(AmmError::MathOverflow)?
                 .checked_mul(range.sqrt_price_upper as u128)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             let denominator = (range.sqrt_price_upper as u128)
                 .checked_sub(range.sqrt_price_current as u128)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             numerator / denominator
         } else {
@@ -129,11 +180,11 @@ impl ConcentratedLiquidityStrategy {
         let liquidity1 = if range.sqrt_price_current > range.sqrt_price_lower {
             let numerator = (amount1 as u128)
                 .checked_mul(1_000_000) // Scale factor for precision
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             let denominator = (range.sqrt_price_current as u128)
                 .checked_sub(range.sqrt_price_lower as u128)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             numerator / denominator
         } else {
@@ -143,6 +194,83 @@ impl ConcentratedLiquidityStrategy {
         Ok(liquidity0.min(liquidity1))
     }
     
+    /// Liquidity a swap trades against, standing in for the input reserve
+    ///
+    /// For this simplified version, assume we have a single concentrated range
+    /// around the current price (±10%). In reality, this would iterate through
+    /// multiple price ranges. One unit over the rounded down value, so outputs
+    /// computed with it round down.
+    fn swap_liquidity(reserve_in: u64, reserve_out: u64) -> Result<u128> {
+        // Calculate current sqrt price (simplified)
+        let sqrt_price_current = u64::try_from((reserve_out as u128 * 1_000_000) / reserve_in as u128)
+            .map_err(|_| AmmError::MathOverflow)?;
+        
+        let sqrt_price_lower = (sqrt_price_current as u128 * 900 / 1000) as u64;
+        let sqrt_price_upper = u64::try_from(sqrt_price_current as u128 * 1100 / 1000)
+            .map_err(|_| AmmError::MathOverflow)?;
+        
+        let range = PriceRange {
+            sqrt_price_lower,
+            sqrt_price_upper,
+            sqrt_price_current,
+        };
+        
+        Self::calculate_liquidity(reserve_in, reserve_out, range)?
+            .checked_add(1)
+            .ok_or(AmmError::MathOverflow.into())
+    }
+
+    /// Calculate the gross input needed to move the pool to a target price
+    ///
+    /// `target_price` is the output-per-input spot price (`reserve_out / reserve_in`)
+    /// scaled by `PRICE_SCALE`, and must be below the current one. A net input dx
+    /// leaves reserves of (x + dx, y * L / (L + dx)), so the target is reached at
+    /// the positive root of (L + dx) * (x + dx) = y * L / target_price.
+    ///
+    /// Formula: dx = (sqrt((L - x)^2 + 4 * y * L / target_price) - (L + x)) / 2
+    /// Where: amount_in = dx * 10000 / (10000 - fee_bps), rounded up
+    pub fn calculate_amount_in_to_price(
+        reserve_in: u64,
+        reserve_out: u64,
+        target_price: u128,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_out > 0, AmmError::InsufficientLiquidity);
+        require!(target_price > 0, AmmError::InsufficientLiquidity);
+        require!(fee_bps < 10_000, AmmError::InvalidFee);
+
+        let liquidity = U256::from(Self::swap_liquidity(reserve_in, reserve_out)?);
+        let reserve_in = U256::from(reserve_in);
+        let spread = if liquidity > reserve_in { liquidity - reserve_in } else { reserve_in - liquidity };
+        let discriminant = U256::from(reserve_out)
+            .checked_mul(liquidity)
+            .and_then(|product| product.checked_mul(U256::from(4u128 * PRICE_SCALE)))
+            .ok_or(AmmError::MathOverflow)?
+            .checked_div(U256::from(target_price))
+            .ok_or(AmmError::MathOverflow)?
+            .checked_add(spread * spread)
+            .ok_or(AmmError::MathOverflow)?;
+
+        // The root rounds up so the input reaches the target
+        let mut root = discriminant.integer_sqrt();
+        if root * root < discriminant {
+            root += U256::one();
+        }
+
+        // Price is already at or past the target
+        let sum = liquidity + reserve_in;
+        if root <= sum {
+            return Ok(0);
+        }
+
+        let net_in = (root - sum + 1) / 2;
+        require!(net_in <= U256::from(u64::MAX), AmmError::MathOverflow);
+
+        // Gross input rounds up so the fee-adjusted input reaches the target
+        mul_div_u64(net_in.as_u64(), 10_000, 10_000 - fee_bps, Rounding::Up)
+    }
+
     /// Calculate new price after swap
     /// 
     /// For token0 -> token1: sqrt(p') = sqrt(p) / (1 + dx / L)
@@ -157,7 +285,7 @@ impl ConcentratedLiquidityStrategy {
             // Swapping token0 for token1, price decreases
             let denominator = liquidity
                 .checked_add(amount_in as u128)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             let new_sqrt_price = (liquidity * current_sqrt_price as u128) / denominator;
             Ok(new_sqrt_price as u64)
@@ -165,13 +293,13 @@ impl ConcentratedLiquidityStrategy {
             // Swapping token1 for token0, price increases
             let delta = (amount_in as u128)
                 .checked_mul(1_000_000) // Scale factor
-                .ok_or(AmmError::Overflow)?
+                .ok_or(AmmError::MathOverflow)?
                 .checked_div(liquidity)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             let new_sqrt_price = (current_sqrt_price as u128)
                 .checked_add(delta)
-                .ok_or(AmmError::Overflow)?;
+                .ok_or(AmmError::MathOverflow)?;
             
             Ok(new_sqrt_price as u64)
         }
@@ -195,42 +323,20 @@ impl AmmStrategy for ConcentratedLiquidityStrategy {
         require!(reserve_in > 0, AmmError::InsufficientLiquidity);
         require!(reserve_out > 0, AmmError::InsufficientLiquidity);
         
-        // Apply fee
-        let amount_in_with_fee = amount_in
-            .checked_mul(10_000 - fee_bps)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(10_000)
-            .ok_or(AmmError::Overflow)?;
-        
-        // For this simplified version, assume we have a single concentrated range
-        // In reality, this would iterate through multiple price ranges
+        // Apply fee (rounded up)
+        let amount_in_with_fee = amount_after_fee(amount_in, fee_bps)?;
         
-        // Calculate current sqrt price (simplified)
-        let sqrt_price_current = ((reserve_out as u128 * 1_000_000) / reserve_in as u128) as u64;
-        
-        // Assume a concentrated range around current price (±10%)
-        let sqrt_price_lower = (sqrt_price_current * 900) / 1000;
-        let sqrt_price_upper = (sqrt_price_current * 1100) / 1000;
-        
-        let range = PriceRange {
-            sqrt_price_lower,
-            sqrt_price_upper,
-            sqrt_price_current,
-        };
-        
-        // Calculate liquidity (simplified)
-        let liquidity = Self::calculate_liquidity(
-            reserve_in,
-            reserve_out,
-            range,
-        )?;
+        let liquidity = Self::swap_liquidity(reserve_in, reserve_out)?;
         
         // Calculate output amount using concentrated liquidity formula
         // This is simplified - real implementation would handle tick crossing
-        let output_ratio = (amount_in_with_fee as u128 * liquidity) / 
-                          (liquidity + amount_in_with_fee as u128);
-        
-        let amount_out = (output_ratio * reserve_out as u128 / liquidity) as u64;
+        // dy = dx * y / (L + dx), rounded down once so dust pools keep their output
+        let amount_out = u64::try_from(mul_div(
+            amount_in_with_fee as u128,
+            reserve_out as u128,
+            liquidity.checked_add(amount_in_with_fee as u128).ok_or(AmmError::MathOverflow)?,
+            Rounding::Down,
+        )?).map_err(|_| AmmError::MathOverflow)?;
         
         // Ensure we don't drain reserves
         require!(amount_out < reserve_out, AmmError::InsufficientLiquidity);
@@ -283,10 +389,7 @@ impl AmmStrategy for ConcentratedLiquidityStrategy {
         // For concentrated liquidity, LP tokens are proportional to liquidity added
         // This is simplified - real implementation would consider active ranges
         
-        let proportion = (amount_a as u128 * 1_000_000) / reserve_a as u128;
-        let lp_tokens = (lp_supply as u128 * proportion) / 1_000_000;
-        
-        Ok(lp_tokens as u64)
+        mul_div_u64(amount_a, lp_supply, reserve_a, Rounding::Down)
     }
     
     /// Calculate withdraw amounts for concentrated liquidity
@@ -308,13 +411,11 @@ impl AmmStrategy for ConcentratedLiquidityStrategy {
         // 2. Current price
         // 3. Amount of liquidity in range
         
-        // Simplified calculation - proportional to LP tokens
-        let proportion = (lp_amount as u128 * 1_000_000) / lp_supply as u128;
-        
-        let amount_a = (reserve_a as u128 * proportion) / 1_000_000;
-        let amount_b = (reserve_b as u128 * proportion) / 1_000_000;
+        // Simplified calculation - proportional to LP tokens, rounded down
+        let amount_a = mul_div_u64(lp_amount, reserve_a, lp_supply, Rounding::Down)?;
+        let amount_b = mul_div_u64(lp_amount, reserve_b, lp_supply, Rounding::Down)?;
         
-        Ok((amount_a as u64, amount_b as u64))
+        Ok((amount_a, amount_b))
     }
 }
 
@@ -322,6 +423,24 @@ impl AmmStrategy for ConcentratedLiquidityStrategy {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tick_to_price() {
+        assert_eq!(ConcentratedLiquidityStrategy::tick_to_price(0).unwrap(), PRICE_SCALE);
+        assert_eq!(ConcentratedLiquidityStrategy::tick_to_price(1).unwrap(), TICK_BASE);
+
+        // 1.0001^6932 ≈ 2.0
+        let price = ConcentratedLiquidityStrategy::tick_to_price(6_932).unwrap();
+        assert!(price > 1_999_000_000_000 && price < 2_001_000_000_000);
+
+        // Negative ticks are the reciprocal
+        let price = ConcentratedLiquidityStrategy::tick_to_price(-6_932).unwrap();
+        assert!(price > 499_000_000_000 && price < 501_000_000_000);
+
+        assert!(ConcentratedLiquidityStrategy::tick_to_price(MAX_TICK).is_ok());
+        assert!(ConcentratedLiquidityStrategy::tick_to_price(MIN_TICK).unwrap() > 0);
+        assert!(ConcentratedLiquidityStrategy::tick_to_price(MAX_TICK + 1).is_err());
+    }
+
     #[test]
     fn test_token0_amount_calculation() {
         let range = PriceRange {
@@ -393,6 +512,18 @@ mod tests {
         assert!(amount_out > 0);
     }
     
+    #[test]
+    fn test_dust_pool_output_rounds_once() {
+        // L = 10 rounds up to 11, so 1 in pays floor(1 * 12 / 12) = 1 rather than flooring 11 / 12 first
+        assert_eq!(ConcentratedLiquidityStrategy::calculate_amount_out(1, 1, 12, 0).unwrap(), 1);
+
+        // A price too large for the fixed-point scale is an error rather than a wrapped price
+        assert_eq!(
+            ConcentratedLiquidityStrategy::calculate_amount_out(1, 1, u64::MAX, 0).unwrap_err(),
+            AmmError::MathOverflow.into()
+        );
+    }
+    
     #[test]
     fn test_new_sqrt_price_calculation() {
         let current_sqrt_price = 1_000_000;
@@ -423,4 +554,44 @@ mod tests {
         let new_sqrt_price = result.unwrap();
         assert!(new_sqrt_price > current_sqrt_price); // Price should increase
     }
+
+    #[test]
+    fn test_calculate_amount_in_to_price() {
+        let amount_in = ConcentratedLiquidityStrategy::calculate_amount_in_to_price(
+            100_000_000,
+            100_000_000,
+            810_000_000_000,
+            0,
+        ).unwrap();
+        assert!(amount_in > 0);
+
+        // Swapping that amount lands on the target spot price
+        let amount_out = ConcentratedLiquidityStrategy::calculate_amount_out(
+            amount_in,
+            100_000_000,
+            100_000_000,
+            0,
+        ).unwrap();
+        let price = (100_000_000 - amount_out) as u128 * PRICE_SCALE
+            / (100_000_000 + amount_in) as u128;
+        assert!(price <= 810_000_000_000 && price.abs_diff(810_000_000_000) < 100_000);
+
+        // Fee scales the gross input up
+        let with_fee = ConcentratedLiquidityStrategy::calculate_amount_in_to_price(
+            100_000_000,
+            100_000_000,
+            810_000_000_000,
+            30,
+        ).unwrap();
+        assert!(with_fee > amount_in);
+
+        // Target above the current price needs no input
+        let zero = ConcentratedLiquidityStrategy::calculate_amount_in_to_price(
+            100_000_000,
+            100_000_000,
+            2 * PRICE_SCALE,
+            30,
+        ).unwrap();
+        assert_eq!(zero, 0);
+    }
 }
@@ -1,106 +1,196 @@
+use super::math::{amount_after_fee, exp_neg, ln, mul_div, mul_div_u64, Rounding, LN_MAX_ERROR, WAD};
 use super::AmmStrategy;
+use crate::errors::AmmError;
 use anchor_lang::prelude::*;
 
+/// Scale of token weights (1_000_000 = 100%)
+pub const WEIGHT_SCALE: u64 = 1_000_000;
+
+/// Smallest weight either token may have in a weight schedule (1%)
+pub const MIN_WEIGHT: u64 = 10_000;
+
 pub struct ConstantMeanStrategy;
 
+/// Liquidity bootstrapping weight schedule stored on the pool
+///
+/// The weight of token A moves linearly from `start_weight_a` at `start_time`
+/// to `end_weight_a` at `end_time` (unix timestamps); token B gets the rest.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LbpParams {
+    pub start_weight_a: u64,
+    pub end_weight_a: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+impl LbpParams {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+
+    /// Validate the schedule, which must not have ended by `now`
+    pub fn validate(&self, now: i64) -> Result<()> {
+        for weight in [self.start_weight_a, self.end_weight_a] {
+            require!(
+                (MIN_WEIGHT..=WEIGHT_SCALE - MIN_WEIGHT).contains(&weight),
+                AmmError::InvalidWeightSchedule
+            );
+        }
+        require!(self.start_time < self.end_time, AmmError::InvalidWeightSchedule);
+        require!(self.end_time > now, AmmError::InvalidWeightSchedule);
+        Ok(())
+    }
+
+    /// Weights `(weight_a, weight_b)` at `now`, clamped to the schedule
+    ///
+    /// Formula: weight_a = start + (end - start) * (now - start_time) / (end_time - start_time)
+    pub fn weights_at(&self, now: i64) -> Result<(u64, u64)> {
+        let now = now.clamp(self.start_time, self.end_time);
+        let elapsed = (now - self.start_time) as u64;
+        let duration = (self.end_time - self.start_time) as u64;
+
+        let weight_a = if self.end_weight_a >= self.start_weight_a {
+            self.start_weight_a
+                + mul_div_u64(self.end_weight_a - self.start_weight_a, elapsed, duration, Rounding::Down)?
+        } else {
+            self.start_weight_a
+                - mul_div_u64(self.start_weight_a - self.end_weight_a, elapsed, duration, Rounding::Down)?
+        };
+
+        Ok((weight_a, WEIGHT_SCALE - weight_a))
+    }
+}
+
 impl ConstantMeanStrategy {
     // Default weights for balanced 50/50 pool
-    const DEFAULT_WEIGHT_A: u64 = 500000; // 50%
-    const DEFAULT_WEIGHT_B: u64 = 500000; // 50%
-    
-    // Helper function to calculate weighted product approximation
-    fn calculate_weighted_product(reserve_a: u64, reserve_b: u64, weight_a: u64, weight_b: u64) -> Result<u64> {
-        // Simplified weighted product calculation
-        // For safety, we'll use a simplified approach avoiding complex exponentiation
-        // This is a rough approximation: (Ra * Wa + Rb * Wb) / (Wa + Wb)
-        let total_weight = weight_a + weight_b;
-        if total_weight == 0 {
-            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds));
-        }
-        let weighted_sum = (reserve_a * weight_a + reserve_b * weight_b) / total_weight;
-        Ok(weighted_sum)
+    const DEFAULT_WEIGHT_A: u64 = 500_000; // 50%
+    const DEFAULT_WEIGHT_B: u64 = 500_000; // 50%
+
+    /// Check that both weights are non-zero and sum to `WEIGHT_SCALE`
+    pub fn validate_weights(weight_a: u64, weight_b: u64) -> Result<()> {
+        require!(weight_a > 0 && weight_b > 0, AmmError::InvalidWeights);
+        require!(
+            weight_a.checked_add(weight_b) == Some(WEIGHT_SCALE),
+            AmmError::InvalidWeights
+        );
+        Ok(())
     }
-    
-    // Helper function to calculate spot price: (reserve_b / weight_b) / (reserve_a / weight_a)
-    fn calculate_spot_price(reserve_a: u64, reserve_b: u64, weight_a: u64, weight_b: u64) -> Result<u64> {
-        if reserve_a == 0 || weight_a == 0 {
-            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds));
-        }
-        
-        let price_numerator = reserve_b * weight_a;
-        let price_denominator = reserve_a * weight_b;
-        
-        if price_denominator == 0 {
-            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds));
-        }
-        
-        Ok(price_numerator / price_denominator)
+
+    /// Weighted average of the reserves: (Ra * Wa + Rb * Wb) / (Wa + Wb), rounded down
+    ///
+    /// A linear stand-in for the weighted product ∏(Ri^Wi), used to size LP supply.
+    pub fn calculate_weighted_product(reserve_a: u64, reserve_b: u64, weight_a: u64, weight_b: u64) -> Result<u64> {
+        Self::validate_weights(weight_a, weight_b)?;
+
+        let weighted_a = (reserve_a as u128)
+            .checked_mul(weight_a as u128)
+            .ok_or(AmmError::MathOverflow)?;
+        let weighted_b = (reserve_b as u128)
+            .checked_mul(weight_b as u128)
+            .ok_or(AmmError::MathOverflow)?;
+        let weighted_sum = weighted_a.checked_add(weighted_b).ok_or(AmmError::MathOverflow)?;
+
+        // The average of two u64 values always fits in u64
+        Ok((weighted_sum / WEIGHT_SCALE as u128) as u64)
+    }
+
+    /// Spot price of token A in token B: (reserve_b / weight_b) / (reserve_a / weight_a), rounded down
+    pub fn calculate_spot_price(reserve_a: u64, reserve_b: u64, weight_a: u64, weight_b: u64) -> Result<u64> {
+        Self::validate_weights(weight_a, weight_b)?;
+        require!(reserve_a > 0 && reserve_b > 0, AmmError::ZeroReserve);
+
+        let numerator = (reserve_b as u128)
+            .checked_mul(weight_a as u128)
+            .ok_or(AmmError::MathOverflow)?;
+        let denominator = (reserve_a as u128)
+            .checked_mul(weight_b as u128)
+            .ok_or(AmmError::MathOverflow)?;
+
+        u64::try_from(numerator / denominator).map_err(|_| AmmError::MathOverflow.into())
+    }
+
+    /// Calculate swap output for arbitrary weights (Balancer out-given-in)
+    ///
+    /// Formula: amount_out = reserve_out * (1 - (reserve_in / (reserve_in + dx))^(weight_in / weight_out))
+    ///
+    /// Equal weights reduce to the constant product formula, which is computed
+    /// exactly. Otherwise each step rounds so the output rounds down.
+    pub fn calculate_amount_out_weighted(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        weight_in: u64,
+        weight_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(amount_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_in > 0 && reserve_out > 0, AmmError::ZeroReserve);
+        Self::validate_weights(weight_in, weight_out)?;
+
+        let adjusted_amount_in = amount_after_fee(amount_in, fee_bps)?;
+        let new_reserve_in = (reserve_in as u128)
+            .checked_add(adjusted_amount_in as u128)
+            .ok_or(AmmError::MathOverflow)?;
+
+        let amount_out = if weight_in == weight_out {
+            // The remaining reserve rounds up so the output rounds down
+            let new_reserve_out = mul_div(reserve_in as u128, reserve_out as u128, new_reserve_in, Rounding::Up)?;
+            reserve_out as u128 - new_reserve_out
+        } else {
+            // ratio^(w_in / w_out) = e^(-(w_in / w_out) * -ln(ratio)), with ratio <= 1
+            let ratio = mul_div(reserve_in as u128, WAD, new_reserve_in, Rounding::Up)?;
+            // ln rounds down, so -ln(ratio) backs off its error to keep the output rounding down
+            let neg_ln = ln(ratio)?.min(0).unsigned_abs().saturating_sub(LN_MAX_ERROR);
+            let exponent = mul_div(neg_ln, weight_in as u128, weight_out as u128, Rounding::Down)?;
+
+            // One extra unit covers the rounding of exp_neg
+            let power = (exp_neg(exponent)? + 1).min(WAD);
+
+            mul_div(reserve_out as u128, WAD - power, WAD, Rounding::Down)?
+        };
+
+        // Ensure we don't drain the entire reserve
+        require!(amount_out < reserve_out as u128, AmmError::InsufficientLiquidity);
+
+        Ok(amount_out as u64)
     }
 }
 
 impl AmmStrategy for ConstantMeanStrategy {
+    /// Calculate swap output with the default 50/50 weights
+    ///
+    /// LBP pools use `calculate_amount_out_weighted` with their scheduled weights.
     fn calculate_amount_out(
         amount_in: u64,
         reserve_in: u64,
         reserve_out: u64,
         fee_bps: u64
     ) -> Result<u64> {
-        if reserve_in == 0 || reserve_out == 0 {
-            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds));
-        }
-        
-        // Apply fee
-        let fee_adjustment = 10000 - fee_bps;
-        let adjusted_amount_in = amount_in * fee_adjustment / 10000;
-        
-        // Use default 50/50 weights for this implementation
-        let weight_a = Self::DEFAULT_WEIGHT_A;
-        let weight_b = Self::DEFAULT_WEIGHT_B;
-        
-        // For balanced pools (50/50), this simplifies to constant product
-        if weight_a == weight_b {
-            let new_reserve_in = reserve_in + adjusted_amount_in;
-            let invariant = reserve_in * reserve_out;
-            let new_reserve_out = invariant / new_reserve_in;
-            let amount_out = reserve_out - new_reserve_out;
-            return Ok(amount_out);
-        }
-        
-        // For weighted pools, use approximation based on price impact
-        // This is a simplified version of the complex Balancer math
-        let price_impact = adjusted_amount_in * 1000000 / (reserve_in * 1000000 / weight_a);
-        let adjusted_price_impact = price_impact * weight_b / 1000000;
-        let amount_out = reserve_out * adjusted_price_impact / 1000000;
-        
-        Ok(amount_out)
+        Self::calculate_amount_out_weighted(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            Self::DEFAULT_WEIGHT_A,
+            Self::DEFAULT_WEIGHT_B,
+            fee_bps,
+        )
     }
 
+    /// Initial LP supply is the weighted average of the deposits
     fn calculate_initial_lp_supply(amount_a: u64, amount_b: u64) -> Result<u64> {
-        // For constant mean, LP supply is based on weighted geometric mean
-        // Simplified: use weighted average of deposits
-        let weight_a = Self::DEFAULT_WEIGHT_A;
-        let weight_b = Self::DEFAULT_WEIGHT_B;
-        let total_weight = weight_a + weight_b;
-        let weighted_value = (amount_a * weight_a + amount_b * weight_b) / total_weight;
-        Ok(weighted_value)
+        require!(amount_a > 0 && amount_b > 0, AmmError::InsufficientLiquidity);
+        Self::calculate_weighted_product(amount_a, amount_b, Self::DEFAULT_WEIGHT_A, Self::DEFAULT_WEIGHT_B)
     }
 
+    /// Deposits add both tokens at the pool ratio, so the LP share equals the
+    /// share of reserve A added, rounded down
     fn calculate_lp_tokens_to_mint(
         amount_a: u64,
         reserve_a: u64,
         lp_supply: u64,
     ) -> Result<u64> {
-        if reserve_a == 0 {
-            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds));
-        }
-        
-        // Proportional to the contribution weighted by token weight
-        let weight_a = Self::DEFAULT_WEIGHT_A;
-        let contribution_ratio = amount_a * 1000000 / reserve_a;
-        let weighted_contribution = contribution_ratio * weight_a / 1000000;
-        let lp_tokens = lp_supply * weighted_contribution / 1000000;
-        
-        Ok(lp_tokens)
+        require!(reserve_a > 0, AmmError::ZeroReserve);
+        require!(lp_supply > 0, AmmError::InsufficientLiquidity);
+
+        mul_div_u64(amount_a, lp_supply, reserve_a, Rounding::Down)
     }
 
     fn calculate_withdraw_amounts(
@@ -109,15 +199,13 @@ impl AmmStrategy for ConstantMeanStrategy {
         reserve_b: u64,
         lp_supply: u64,
     ) -> Result<(u64, u64)> {
-        if lp_supply == 0 {
-            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds));
-        }
-        
-        // Proportional withdrawal based on LP token ownership
-        let withdrawal_ratio = lp_amount * 1000000 / lp_supply;
-        let amount_a = reserve_a * withdrawal_ratio / 1000000;
-        let amount_b = reserve_b * withdrawal_ratio / 1000000;
-        
+        require!(lp_supply > 0, AmmError::InsufficientLiquidity);
+        require!(lp_amount <= lp_supply, AmmError::InsufficientLiquidity);
+
+        // Proportional withdrawal based on LP token ownership, rounded down
+        let amount_a = mul_div_u64(lp_amount, reserve_a, lp_supply, Rounding::Down)?;
+        let amount_b = mul_div_u64(lp_amount, reserve_b, lp_supply, Rounding::Down)?;
+
         Ok((amount_a, amount_b))
     }
 }
@@ -172,10 +260,93 @@ mod tests {
         assert!(price > 0);
     }
     
+    #[test]
+    fn test_weighted_swap_matches_constant_product_at_50_50() {
+        let weighted = ConstantMeanStrategy::calculate_amount_out_weighted(
+            100_000, 1_000_000, 1_000_000, 500_000, 500_000, 30,
+        ).unwrap();
+        let balanced = ConstantMeanStrategy::calculate_amount_out(100_000, 1_000_000, 1_000_000, 30).unwrap();
+        assert!(weighted.abs_diff(balanced) <= 1);
+        assert!(weighted <= balanced);
+    }
+
+    #[test]
+    fn test_weighted_swap_follows_weights() {
+        // 80/20 pool with equal reserves: token in is worth 4x token out
+        let amount_out = ConstantMeanStrategy::calculate_amount_out_weighted(
+            1_000, 1_000_000_000, 1_000_000_000, 800_000, 200_000, 0,
+        ).unwrap();
+        assert!((3_990..4_000).contains(&amount_out));
+    }
+
+    #[test]
+    fn test_weighted_swap_backs_off_ln_error() {
+        // Exactly 759_741_763.9; without backing off the ln error this paid 759_741_764
+        let amount_out = ConstantMeanStrategy::calculate_amount_out_weighted(
+            827, 745_828_556, 178_637_644_690_149, 800_000, 200_000, 400,
+        ).unwrap();
+        assert!(amount_out <= 759_741_763);
+    }
+
+    #[test]
+    fn test_lbp_weights_move_linearly() {
+        let schedule = LbpParams {
+            start_weight_a: 960_000,
+            end_weight_a: 500_000,
+            start_time: 1_000,
+            end_time: 2_000,
+        };
+        assert!(schedule.validate(1_999).is_ok());
+        assert_eq!(schedule.weights_at(0).unwrap(), (960_000, 40_000));
+        assert_eq!(schedule.weights_at(1_500).unwrap(), (730_000, 270_000));
+        assert_eq!(schedule.weights_at(5_000).unwrap(), (500_000, 500_000));
+
+        assert!(LbpParams { start_weight_a: 995_000, ..schedule }.validate(0).is_err());
+        assert!(LbpParams { end_time: 1_000, ..schedule }.validate(0).is_err());
+
+        // A schedule that has already ended would open the pool at its final weights
+        assert!(schedule.validate(2_000).is_err());
+    }
+
     #[test]
     fn test_weighted_product() {
         let product = ConstantMeanStrategy::calculate_weighted_product(1000000, 1000000, 600000, 400000).unwrap();
         assert!(product > 0);
     }
+
+    #[test]
+    fn test_large_reserves_do_not_overflow() {
+        let reserve = u64::MAX / 2;
+        let product = ConstantMeanStrategy::calculate_weighted_product(reserve, reserve, 600_000, 400_000).unwrap();
+        assert_eq!(product, reserve);
+
+        let amount_out = ConstantMeanStrategy::calculate_amount_out_weighted(
+            reserve / 10, reserve, reserve, 800_000, 200_000, 30,
+        ).unwrap();
+        assert!(amount_out > 0 && amount_out < reserve);
+
+        let lp_tokens = ConstantMeanStrategy::calculate_lp_tokens_to_mint(reserve, reserve, u64::MAX).unwrap();
+        assert_eq!(lp_tokens, u64::MAX);
+    }
+
+    #[test]
+    fn test_precise_errors() {
+        assert_eq!(
+            ConstantMeanStrategy::calculate_amount_out(1_000, 0, 1_000_000, 30).unwrap_err(),
+            AmmError::ZeroReserve.into()
+        );
+        assert_eq!(
+            ConstantMeanStrategy::calculate_spot_price(1_000_000, 1_000_000, 600_000, 600_000).unwrap_err(),
+            AmmError::InvalidWeights.into()
+        );
+        assert_eq!(
+            ConstantMeanStrategy::calculate_amount_out(1_000, 1_000_000, 1_000_000, 10_001).unwrap_err(),
+            AmmError::InvalidFee.into()
+        );
+        assert_eq!(
+            ConstantMeanStrategy::calculate_spot_price(1, u64::MAX, 990_000, 10_000).unwrap_err(),
+            AmmError::MathOverflow.into()
+        );
+    }
 }
 
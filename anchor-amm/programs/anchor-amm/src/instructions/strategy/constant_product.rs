@@ -1,6 +1,8 @@
 // programs/anchor-amm/src/instructions/strategy/constant_product.rs
 use anchor_lang::prelude::*;
 use crate::errors::AmmError;
+use super::dynamic_fee::PRICE_SCALE;
+use super::math::{amount_after_fee, mul_div, mul_div_u64, Rounding, U256};
 use super::AmmStrategy;
 
 /// Constant Product AMM Strategy (x * y = k)
@@ -9,11 +11,56 @@ use super::AmmStrategy;
 /// where the product of reserves remains constant before and after each trade.
 pub struct ConstantProductStrategy;
 
+impl ConstantProductStrategy {
+    /// Calculate the gross input needed to move the pool to a target price
+    ///
+    /// `target_price` is the output-per-input price (`reserve_out / reserve_in`)
+    /// scaled by `PRICE_SCALE`, and must be below the current one.
+    ///
+    /// Formula: reserve_in' = sqrt(k / target_price), dx = reserve_in' - reserve_in
+    /// Where: amount_in = dx * 10000 / (10000 - fee_bps), rounded up
+    pub fn calculate_amount_in_to_price(
+        reserve_in: u64,
+        reserve_out: u64,
+        target_price: u128,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_out > 0, AmmError::InsufficientLiquidity);
+        require!(target_price > 0, AmmError::InsufficientLiquidity);
+        require!(fee_bps < 10_000, AmmError::InvalidFee);
+
+        let k_scaled = U256::from(reserve_in)
+            .checked_mul(U256::from(reserve_out))
+            .ok_or(AmmError::MathOverflow)?
+            .checked_mul(U256::from(PRICE_SCALE))
+            .ok_or(AmmError::MathOverflow)?;
+
+        let new_reserve_in = k_scaled
+            .checked_div(U256::from(target_price))
+            .ok_or(AmmError::MathOverflow)?
+            .integer_sqrt();
+
+        // Price is already at or past the target
+        if new_reserve_in <= U256::from(reserve_in) {
+            return Ok(0);
+        }
+
+        require!(new_reserve_in <= U256::from(u64::MAX), AmmError::MathOverflow);
+        let net_in = new_reserve_in.as_u64() - reserve_in;
+
+        // Gross input rounds up so the fee-adjusted input reaches the target
+        mul_div_u64(net_in, 10_000, 10_000 - fee_bps, Rounding::Up)
+    }
+}
+
 impl AmmStrategy for ConstantProductStrategy {
     /// Calculate swap output using constant product formula
     /// 
     /// Formula: amount_out = (amount_in_with_fee * reserve_out) / (reserve_in + amount_in_with_fee)
-    /// Where: amount_in_with_fee = amount_in * (10000 - fee_bps) / 10000
+    /// Where: amount_in_with_fee = amount_in - ceil(amount_in * fee_bps / 10000)
+    ///
+    /// The fee rounds up and the output rounds down.
     fn calculate_amount_out(
         amount_in: u64,
         reserve_in: u64,
@@ -25,25 +72,20 @@ impl AmmStrategy for ConstantProductStrategy {
         require!(reserve_out > 0, AmmError::InsufficientLiquidity);
         
         // Calculate amount after fee deduction
-        let amount_in_with_fee = amount_in
-            .checked_mul(10_000 - fee_bps)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(10_000)
-            .ok_or(AmmError::Overflow)?;
+        let amount_in_with_fee = amount_after_fee(amount_in, fee_bps)?;
         
         // Apply constant product formula: (x + dx) * (y - dy) = x * y
         // Solving for dy: dy = (dx * y) / (x + dx)
-        let numerator = (amount_in_with_fee as u128)
-            .checked_mul(reserve_out as u128)
-            .ok_or(AmmError::Overflow)?;
-        
         let denominator = (reserve_in as u128)
             .checked_add(amount_in_with_fee as u128)
-            .ok_or(AmmError::Overflow)?;
+            .ok_or(AmmError::MathOverflow)?;
         
-        let amount_out = numerator
-            .checked_div(denominator)
-            .ok_or(AmmError::Overflow)?;
+        let amount_out = mul_div(
+            amount_in_with_fee as u128,
+            reserve_out as u128,
+            denominator,
+            Rounding::Down,
+        )?;
         
         // Ensure we don't drain the entire reserve
         let amount_out = amount_out as u64;
@@ -52,23 +94,45 @@ impl AmmStrategy for ConstantProductStrategy {
         Ok(amount_out)
     }
 
+    /// Calculate the input required for an exact output
+    ///
+    /// Formula: dx = (x * dy) / (y - dy), amount_in = dx * 10000 / (10000 - fee_bps)
+    ///
+    /// Both divisions round up.
+    fn calculate_amount_in(
+        amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(amount_out > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+        require!(amount_out < reserve_out, AmmError::InsufficientLiquidity);
+        require!(fee_bps < 10_000, AmmError::InvalidFee);
+
+        let amount_in_with_fee =
+            mul_div_u64(reserve_in, amount_out, reserve_out - amount_out, Rounding::Up)?;
+
+        mul_div_u64(amount_in_with_fee, 10_000, 10_000 - fee_bps, Rounding::Up)
+    }
+
     /// Calculate initial LP supply using geometric mean
     /// 
-    /// Formula: sqrt(amount_a * amount_b)
+    /// Formula: sqrt(amount_a * amount_b), rounded down
     fn calculate_initial_lp_supply(amount_a: u64, amount_b: u64) -> Result<u64> {
         require!(amount_a > 0, AmmError::InsufficientLiquidity);
         require!(amount_b > 0, AmmError::InsufficientLiquidity);
         
         let product = (amount_a as u128)
             .checked_mul(amount_b as u128)
-            .ok_or(AmmError::Overflow)?;
+            .ok_or(AmmError::MathOverflow)?;
         
         Ok(product.isqrt() as u64)
     }
 
     /// Calculate LP tokens to mint proportionally
     /// 
-    /// Formula: lp_tokens = (amount_a * lp_supply) / reserve_a
+    /// Formula: lp_tokens = (amount_a * lp_supply) / reserve_a, rounded down
     fn calculate_lp_tokens_to_mint(
         amount_a: u64,
         reserve_a: u64,
@@ -78,13 +142,7 @@ impl AmmStrategy for ConstantProductStrategy {
         require!(reserve_a > 0, AmmError::InsufficientLiquidity);
         require!(lp_supply > 0, AmmError::InsufficientLiquidity);
         
-        let lp_tokens = (amount_a as u128)
-            .checked_mul(lp_supply as u128)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(reserve_a as u128)
-            .ok_or(AmmError::Overflow)?;
-        
-        Ok(lp_tokens as u64)
+        mul_div_u64(amount_a, lp_supply, reserve_a, Rounding::Down)
     }
 
     /// Calculate proportional withdraw amounts
@@ -92,6 +150,8 @@ impl AmmStrategy for ConstantProductStrategy {
     /// Formula: 
     /// - amount_a = (lp_amount * reserve_a) / lp_supply
     /// - amount_b = (lp_amount * reserve_b) / lp_supply
+    ///
+    /// Both amounts round down.
     fn calculate_withdraw_amounts(
         lp_amount: u64,
         reserve_a: u64,
@@ -102,17 +162,8 @@ impl AmmStrategy for ConstantProductStrategy {
         require!(lp_supply > 0, AmmError::InsufficientLiquidity);
         require!(lp_amount <= lp_supply, AmmError::InsufficientLiquidity);
         
-        let amount_a = (lp_amount as u128)
-            .checked_mul(reserve_a as u128)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(lp_supply as u128)
-            .ok_or(AmmError::Overflow)? as u64;
-        
-        let amount_b = (lp_amount as u128)
-            .checked_mul(reserve_b as u128)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(lp_supply as u128)
-            .ok_or(AmmError::Overflow)? as u64;
+        let amount_a = mul_div_u64(lp_amount, reserve_a, lp_supply, Rounding::Down)?;
+        let amount_b = mul_div_u64(lp_amount, reserve_b, lp_supply, Rounding::Down)?;
         
         Ok((amount_a, amount_b))
     }
@@ -139,6 +190,83 @@ mod tests {
         assert!(amount_out > 9_000_000 && amount_out < 9_100_000);
     }
 
+    #[test]
+    fn test_calculate_amount_in_to_price() {
+        // Moving a 1:1 pool to a 0.81 out/in price needs ~11.1% more reserve_in
+        let amount_in = ConstantProductStrategy::calculate_amount_in_to_price(
+            100_000_000,
+            100_000_000,
+            810_000_000_000,
+            0,
+        ).unwrap();
+        assert_eq!(amount_in, 11_111_111);
+
+        // Swapping that amount lands on the target price
+        let amount_out = ConstantProductStrategy::calculate_amount_out(
+            amount_in,
+            100_000_000,
+            100_000_000,
+            0,
+        ).unwrap();
+        let price = (100_000_000 - amount_out) as u128 * PRICE_SCALE
+            / (100_000_000 + amount_in) as u128;
+        assert!(price.abs_diff(810_000_000_000) < 100_000);
+
+        // Fee scales the gross input up
+        let with_fee = ConstantProductStrategy::calculate_amount_in_to_price(
+            100_000_000,
+            100_000_000,
+            810_000_000_000,
+            30,
+        ).unwrap();
+        assert!(with_fee > amount_in);
+
+        // Target above the current price needs no input
+        let zero = ConstantProductStrategy::calculate_amount_in_to_price(
+            100_000_000,
+            100_000_000,
+            2 * PRICE_SCALE,
+            30,
+        ).unwrap();
+        assert_eq!(zero, 0);
+    }
+
+    #[test]
+    fn test_calculate_amount_in_is_exact_out() {
+        for amount_out in [1, 999, 9_066_108, 50_000_000] {
+            let amount_in = ConstantProductStrategy::calculate_amount_in(
+                amount_out,
+                100_000_000,
+                100_000_000,
+                30,
+            ).unwrap();
+
+            // Enough input for the requested output, but not a unit more than needed
+            let out = ConstantProductStrategy::calculate_amount_out(
+                amount_in, 100_000_000, 100_000_000, 30,
+            ).unwrap();
+            assert!(out >= amount_out);
+
+            let out_less = ConstantProductStrategy::calculate_amount_out(
+                amount_in - 1, 100_000_000, 100_000_000, 30,
+            ).unwrap_or(0);
+            assert!(out_less < amount_out);
+        }
+    }
+
+    #[test]
+    fn test_amount_out_rounding_keeps_k() {
+        let (reserve_in, reserve_out) = (1_000_003u64, 999_983u64);
+        for amount_in in [1u64, 7, 333, 10_007, 123_457] {
+            let amount_out = ConstantProductStrategy::calculate_amount_out(
+                amount_in, reserve_in, reserve_out, 30,
+            ).unwrap_or(0);
+            let k_before = reserve_in as u128 * reserve_out as u128;
+            let k_after = (reserve_in + amount_in) as u128 * (reserve_out - amount_out) as u128;
+            assert!(k_after >= k_before);
+        }
+    }
+
     #[test]
     fn test_calculate_initial_lp_supply() {
         let result = ConstantProductStrategy::calculate_initial_lp_supply(
@@ -0,0 +1,261 @@
+// programs/anchor-amm/src/instructions/strategy/dynamic_fee.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use super::math::validate_fee;
+
+/// Fixed-point scale used for spot prices tracked by the volatility accumulator
+pub const PRICE_SCALE: u128 = 1_000_000_000_000;
+
+/// Precision divisor for the quadratic variable fee (see `VolatilityState::variable_fee`)
+pub const VARIABLE_FEE_PRECISION: u128 = 1_000_000;
+
+/// Dynamic fee parameters (Meteora DLMM style)
+///
+/// The swap fee is `base_fee + variable_fee`, where the variable part grows with
+/// the square of the volatility accumulator and the total is clamped to
+/// `[base_fee, max_fee_bps]`.
+///
+/// - `filter_period`: slots during which the references are frozen (high frequency trades)
+/// - `decay_period`: slots after which the accumulated volatility is fully reset
+/// - `reduction_factor`: share of the accumulator carried over between windows (basis points)
+/// - `variable_fee_control`: multiplier applied to `volatility_accumulator^2`
+/// - `max_volatility_accumulator`: cap on the accumulator (basis points of price move)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DynamicFeeParams {
+    pub max_fee_bps: u64,
+    pub filter_period: u64,
+    pub decay_period: u64,
+    pub reduction_factor: u64,
+    pub variable_fee_control: u64,
+    pub max_volatility_accumulator: u64,
+}
+
+impl DynamicFeeParams {
+    pub const LEN: usize = 8 * 6;
+
+    /// Validate the parameters against the pool base fee
+    pub fn validate(&self, base_fee_bps: u64) -> Result<()> {
+        validate_fee(self.max_fee_bps)?;
+        require!(self.max_fee_bps >= base_fee_bps, AmmError::InvalidFee);
+        require!(self.reduction_factor <= 10_000, AmmError::InvalidFee);
+        require!(self.filter_period <= self.decay_period, AmmError::InvalidFee);
+        Ok(())
+    }
+}
+
+/// Volatility tracking state stored on the pool
+///
+/// Prices are spot prices of token A in token B, scaled by `PRICE_SCALE`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VolatilityState {
+    pub volatility_accumulator: u64, // Basis points of price move, decayed over time
+    pub volatility_reference: u64,   // Accumulator carried over from the previous window
+    pub price_reference: u128,       // Spot price at the start of the current window
+    pub last_update_slot: u64,       // Slot of the last swap that touched the state
+}
+
+impl VolatilityState {
+    pub const LEN: usize = 8 + 8 + 16 + 8;
+
+    /// Spot price of token A expressed in token B, scaled by `PRICE_SCALE`
+    pub fn spot_price(reserve_a: u64, reserve_b: u64) -> Result<u128> {
+        require!(reserve_a > 0, AmmError::InsufficientLiquidity);
+
+        let price = (reserve_b as u128)
+            .checked_mul(PRICE_SCALE)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_div(reserve_a as u128)
+            .ok_or(AmmError::MathOverflow)?;
+
+        Ok(price)
+    }
+
+    /// Roll the references forward at the start of a swap
+    ///
+    /// Within `filter_period` of the last update nothing changes. Past it, the
+    /// reference price is reset to the current price and the reference
+    /// volatility decays by `reduction_factor`, or is cleared entirely once
+    /// `decay_period` has elapsed.
+    pub fn update_references(
+        &mut self,
+        current_slot: u64,
+        current_price: u128,
+        params: &DynamicFeeParams,
+    ) -> Result<()> {
+        let elapsed = current_slot.saturating_sub(self.last_update_slot);
+
+        if self.price_reference == 0 || elapsed >= params.filter_period {
+            self.price_reference = current_price;
+
+            self.volatility_reference = if elapsed < params.decay_period {
+                (self.volatility_accumulator as u128)
+                    .checked_mul(params.reduction_factor as u128)
+                    .ok_or(AmmError::MathOverflow)?
+                    .checked_div(10_000)
+                    .ok_or(AmmError::MathOverflow)? as u64
+            } else {
+                0
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Update the accumulator from the move between `price_reference` and `current_price`
+    ///
+    /// Formula: va = min(vr + |p - p_ref| / p_ref * 10_000, max_volatility_accumulator)
+    pub fn update_accumulator(
+        &mut self,
+        current_slot: u64,
+        current_price: u128,
+        params: &DynamicFeeParams,
+    ) -> Result<()> {
+        require!(self.price_reference > 0, AmmError::InsufficientLiquidity);
+
+        let delta = current_price.abs_diff(self.price_reference);
+        let move_bps = delta
+            .checked_mul(10_000)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_div(self.price_reference)
+            .ok_or(AmmError::MathOverflow)?;
+
+        let accumulator = (self.volatility_reference as u128)
+            .checked_add(move_bps)
+            .ok_or(AmmError::MathOverflow)?
+            .min(params.max_volatility_accumulator as u128);
+
+        self.volatility_accumulator = accumulator as u64;
+        self.last_update_slot = current_slot;
+
+        Ok(())
+    }
+
+    /// Variable fee in basis points
+    ///
+    /// Formula: variable_fee = variable_fee_control * va^2 / VARIABLE_FEE_PRECISION
+    pub fn variable_fee(&self, params: &DynamicFeeParams) -> Result<u64> {
+        let va = self.volatility_accumulator as u128;
+
+        let fee = va
+            .checked_mul(va)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_mul(params.variable_fee_control as u128)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_div(VARIABLE_FEE_PRECISION)
+            .ok_or(AmmError::MathOverflow)?;
+
+        Ok(fee.min(10_000) as u64)
+    }
+
+    /// Effective fee in basis points, clamped between the base and max fee
+    pub fn effective_fee(&self, base_fee_bps: u64, params: &DynamicFeeParams) -> Result<u64> {
+        let fee = base_fee_bps
+            .checked_add(self.variable_fee(params)?)
+            .ok_or(AmmError::MathOverflow)?;
+
+        Ok(fee.clamp(base_fee_bps, params.max_fee_bps.max(base_fee_bps)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> DynamicFeeParams {
+        DynamicFeeParams {
+            max_fee_bps: 300,
+            filter_period: 10,
+            decay_period: 100,
+            reduction_factor: 5_000,
+            variable_fee_control: 1_000,
+            max_volatility_accumulator: 2_000,
+        }
+    }
+
+    #[test]
+    fn test_fee_is_base_fee_without_volatility() {
+        let state = VolatilityState::default();
+        let fee = state.effective_fee(30, &params()).unwrap();
+        assert_eq!(fee, 30);
+    }
+
+    #[test]
+    fn test_price_move_increases_fee() {
+        let params = params();
+        let mut state = VolatilityState::default();
+
+        let start = VolatilityState::spot_price(1_000_000, 1_000_000).unwrap();
+        state.update_references(1, start, &params).unwrap();
+
+        // 5% price move within the same window
+        let moved = VolatilityState::spot_price(1_000_000, 1_050_000).unwrap();
+        state.update_accumulator(2, moved, &params).unwrap();
+
+        assert_eq!(state.volatility_accumulator, 500);
+        // 1000 * 500^2 / 1M = 250 bps of variable fee, below the 300 bps cap
+        assert_eq!(state.effective_fee(30, &params).unwrap(), 280);
+    }
+
+    #[test]
+    fn test_fee_clamped_to_max() {
+        let params = params();
+        let state = VolatilityState {
+            volatility_accumulator: params.max_volatility_accumulator,
+            ..Default::default()
+        };
+
+        assert_eq!(state.effective_fee(30, &params).unwrap(), params.max_fee_bps);
+    }
+
+    #[test]
+    fn test_references_frozen_within_filter_period() {
+        let params = params();
+        let mut state = VolatilityState {
+            volatility_accumulator: 400,
+            price_reference: PRICE_SCALE,
+            last_update_slot: 100,
+            ..Default::default()
+        };
+
+        state.update_references(105, 2 * PRICE_SCALE, &params).unwrap();
+        assert_eq!(state.price_reference, PRICE_SCALE);
+        assert_eq!(state.volatility_reference, 0);
+    }
+
+    #[test]
+    fn test_volatility_decays_then_resets() {
+        let params = params();
+        let mut state = VolatilityState {
+            volatility_accumulator: 400,
+            price_reference: PRICE_SCALE,
+            last_update_slot: 100,
+            ..Default::default()
+        };
+
+        // Past the filter period: reference is carried over at 50%
+        state.update_references(150, 2 * PRICE_SCALE, &params).unwrap();
+        assert_eq!(state.price_reference, 2 * PRICE_SCALE);
+        assert_eq!(state.volatility_reference, 200);
+
+        // Past the decay period: reference is cleared
+        state.update_references(1_000, PRICE_SCALE, &params).unwrap();
+        assert_eq!(state.volatility_reference, 0);
+    }
+
+    #[test]
+    fn test_invalid_params_rejected() {
+        let mut bad = params();
+        bad.max_fee_bps = 10;
+        assert!(bad.validate(30).is_err());
+
+        let mut bad = params();
+        bad.max_fee_bps = 10_001;
+        assert!(bad.validate(30).is_err());
+
+        let mut bad = params();
+        bad.filter_period = 1_000;
+        assert!(bad.validate(30).is_err());
+
+        assert!(params().validate(30).is_ok());
+    }
+}
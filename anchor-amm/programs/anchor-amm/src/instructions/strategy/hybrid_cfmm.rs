@@ -1,6 +1,7 @@
 // programs/anchor-amm/src/instructions/strategy/hybrid_cfmm.rs
 use anchor_lang::prelude::*;
 use crate::errors::AmmError;
+use super::math::{amount_after_fee, exp_neg, mul_div, mul_div_u64, validate_fee, Rounding, LN_2, U256, WAD};
 use super::AmmStrategy;
 
 /// Hybrid CFMM Strategy (Curve v2 style)
@@ -46,6 +47,152 @@ impl Default for HybridParams {
     }
 }
 
+/// Scale of `gamma` and of the intermediate ratios (1_000_000 = 1.0)
+const SCALE: u128 = 1_000_000;
+
+impl HybridParams {
+    /// Check that gamma is at most 1 and the fees are ordered and at most 100%
+    pub fn validate(&self) -> Result<()> {
+        require!(self.gamma as u128 <= SCALE, AmmError::InvalidWeights);
+        validate_fee(self.out_fee)?;
+        require!(self.mid_fee <= self.out_fee, AmmError::InvalidFee);
+        Ok(())
+    }
+}
+
+/// Slots a pool's gamma stays put after a crank (~1 minute)
+pub const GAMMA_CRANK_INTERVAL_SLOTS: u64 = 150;
+
+/// Largest change of gamma in one crank (0.05)
+pub const MAX_GAMMA_STEP: u64 = 50_000;
+
+/// Lamports paid to the keeper of a crank that moved gamma, out of the pool's lamports above rent
+pub const GAMMA_CRANK_BOUNTY_LAMPORTS: u64 = 10_000;
+
+/// Adaptive gamma of a `CurveType::HybridCfmm` pool
+///
+/// Swaps sample the spot price (token B per token A, scaled by 1M) into an
+/// exponential moving average with a half-life of `HybridParams::ma_half_time`.
+/// The permissionless `crank_gamma` moves gamma with `update_gamma`: up
+/// (towards constant product) while the average is more than 1% away from
+/// `target_price`, down (towards stable swap) otherwise.
+///
+/// Pools from before gamma tracking have a zero `target_price` and trade at
+/// the default gamma around `DEFAULT_TARGET_PRICE` until their first crank.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HybridState {
+    pub gamma: u64,              // Weight of the constant product output, scaled by 1M
+    pub target_price: u64,       // Price gamma adapts around, scaled by 1M
+    pub ema_price: u64,          // Moving average of the spot price, scaled by 1M (0 = no sample yet)
+    pub last_sample_time: i64,   // Unix timestamp of the last price sample
+    pub last_crank_slot: u64,    // Slot of the last crank
+}
+
+impl HybridState {
+    pub const LEN: usize = 8 * 5;
+
+    /// Target of pools created without one, and of pools from before gamma tracking (1.0)
+    pub const DEFAULT_TARGET_PRICE: u64 = SCALE as u64;
+
+    /// State of a new pool, at the default gamma
+    pub fn new(target_price: u64) -> Result<Self> {
+        require!(target_price > 0, AmmError::InvalidHybridParams);
+        Ok(Self {
+            gamma: HybridParams::default().gamma,
+            target_price,
+            ..Self::default()
+        })
+    }
+
+    /// Gamma swaps are priced at
+    pub fn gamma(&self) -> u64 {
+        self.resolved().gamma
+    }
+
+    /// Fill in the defaults of a pool that predates gamma tracking
+    fn resolved(&self) -> Self {
+        if self.target_price > 0 {
+            return *self;
+        }
+        Self {
+            gamma: HybridParams::default().gamma,
+            target_price: Self::DEFAULT_TARGET_PRICE,
+            ..*self
+        }
+    }
+
+    /// Swap fee of the pool in dynamic fee mode, before the volatility surcharge
+    ///
+    /// Rises from `base_fee` at the target price ratio to `max_fee` as the
+    /// reserves, with token A valued at `target_price`, drift apart.
+    pub fn imbalance_fee(&self, reserve_a: u64, reserve_b: u64, base_fee: u64, max_fee: u64) -> Result<u64> {
+        let value_a = mul_div(reserve_a as u128, self.resolved().target_price as u128, SCALE, Rounding::Down)?
+            .clamp(1, u64::MAX as u128) as u64;
+        let params = HybridParams { mid_fee: base_fee, out_fee: max_fee.max(base_fee), ..HybridParams::default() };
+        HybridCfmmStrategy::calculate_dynamic_fee(value_a, reserve_b, params)
+    }
+
+    /// Spot price of token A in token B, scaled by 1M and capped at `u64::MAX`
+    pub fn spot_price(reserve_a: u64, reserve_b: u64) -> Result<u64> {
+        require!(reserve_a > 0, AmmError::ZeroReserve);
+        let price = mul_div(reserve_b as u128, SCALE, reserve_a as u128, Rounding::Down)?;
+        Ok(price.min(u64::MAX as u128) as u64)
+    }
+
+    /// Fold `price` sampled at `now` into the moving average
+    ///
+    /// The previous average keeps a weight of 2^(-elapsed / half_time), so
+    /// samples within the same second after the first leave it unchanged.
+    /// Formula: ema = ema * w + price * (1 - w)
+    pub fn record_price(&mut self, price: u64, now: i64, params: HybridParams) -> Result<()> {
+        if self.ema_price == 0 {
+            self.ema_price = price;
+            self.last_sample_time = now;
+            return Ok(());
+        }
+        let elapsed_ms = (now.saturating_sub(self.last_sample_time).max(0) as u128) * 1_000;
+        if elapsed_ms == 0 {
+            return Ok(());
+        }
+        require!(params.ma_half_time > 0, AmmError::InvalidHybridParams);
+        let exponent = mul_div(elapsed_ms, LN_2, params.ma_half_time as u128, Rounding::Down)?;
+        let weight = exp_neg(exponent)?;
+
+        let ema = mul_div(self.ema_price as u128, weight, WAD, Rounding::Down)?
+            + mul_div(price as u128, WAD - weight, WAD, Rounding::Down)?;
+        // A weighted mean of two u64 values fits in u64
+        self.ema_price = ema as u64;
+        self.last_sample_time = now;
+        Ok(())
+    }
+
+    /// Move gamma towards `update_gamma` of the average price, by at most `MAX_GAMMA_STEP`
+    ///
+    /// Fails within `GAMMA_CRANK_INTERVAL_SLOTS` of the previous crank or
+    /// before any price sample. Returns whether gamma changed.
+    pub fn crank(&mut self, current_slot: u64, params: HybridParams) -> Result<bool> {
+        require!(
+            self.ema_price > 0
+                && current_slot >= self.last_crank_slot.saturating_add(GAMMA_CRANK_INTERVAL_SLOTS),
+            AmmError::CrankTooEarly
+        );
+        let state = self.resolved();
+        let target = HybridCfmmStrategy::update_gamma(state.ema_price, state.target_price, state.gamma, params)?;
+        let gamma = if target > state.gamma {
+            target.min(state.gamma + MAX_GAMMA_STEP)
+        } else {
+            target.max(state.gamma.saturating_sub(MAX_GAMMA_STEP))
+        };
+
+        *self = Self {
+            gamma,
+            last_crank_slot: current_slot,
+            ..state
+        };
+        Ok(gamma != state.gamma)
+    }
+}
+
 impl HybridCfmmStrategy {
     /// Calculate the invariant for the hybrid CFMM
     /// 
@@ -56,42 +203,29 @@ impl HybridCfmmStrategy {
         y: u64,
         params: HybridParams,
     ) -> Result<u128> {
-        require!(x > 0 && y > 0, AmmError::InsufficientLiquidity);
-        
-        let x_u128 = x as u128;
-        let y_u128 = y as u128;
-        let gamma_u128 = params.gamma as u128;
-        let scale = 1_000_000u128;
-        
+        require!(x > 0 && y > 0, AmmError::ZeroReserve);
+        params.validate()?;
+
+        let x = U256::from(x);
+        let y = U256::from(y);
+        let gamma = U256::from(params.gamma);
+        let scale = U256::from(SCALE);
+
         // Constant product component: x * y
-        let cp_component = x_u128
-            .checked_mul(y_u128)
-            .ok_or(AmmError::Overflow)?;
-        
-        // Stable swap component: (x + y)^2 / 4
-        let sum = x_u128.checked_add(y_u128).ok_or(AmmError::Overflow)?;
-        let stable_component = sum
-            .checked_mul(sum)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(4)
-            .ok_or(AmmError::Overflow)?;
-        
+        let cp_component = x * y;
+
+        // Stable swap component: (x + y)^2 / 4, whose square overflows u128
+        let sum = x + y;
+        let stable_component = sum * sum / 4;
+
         // Weighted combination
-        let cp_weighted = cp_component
-            .checked_mul(gamma_u128)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(scale)
-            .ok_or(AmmError::Overflow)?;
-        
-        let stable_weighted = stable_component
-            .checked_mul(scale.checked_sub(gamma_u128).ok_or(AmmError::Overflow)?)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(scale)
-            .ok_or(AmmError::Overflow)?;
-        
-        let invariant = cp_weighted.checked_add(stable_weighted).ok_or(AmmError::Overflow)?;
-        
-        Ok(invariant)
+        let cp_weighted = cp_component * gamma / scale;
+        let stable_weighted = stable_component * (scale - gamma) / scale;
+        let invariant = cp_weighted + stable_weighted;
+
+        // Both components are at most (x + y)^2 / 4 < 2^128, and so is their weighted mean
+        require!(invariant <= U256::from(u128::MAX), AmmError::MathOverflow);
+        Ok(invariant.as_u128())
     }
     
     /// Calculate the price based on current reserves and parameters
@@ -102,19 +236,20 @@ impl HybridCfmmStrategy {
         y: u64,
         params: HybridParams,
     ) -> Result<u64> {
-        require!(x > 0 && y > 0, AmmError::InsufficientLiquidity);
+        require!(x > 0 && y > 0, AmmError::ZeroReserve);
+        params.validate()?;
         
         let x_u128 = x as u128;
         let y_u128 = y as u128;
         let gamma_u128 = params.gamma as u128;
-        let scale = 1_000_000u128;
+        let scale = SCALE;
         
         // Constant product price: y / x
         let cp_price = y_u128
             .checked_mul(scale)
-            .ok_or(AmmError::Overflow)?
+            .ok_or(AmmError::MathOverflow)?
             .checked_div(x_u128)
-            .ok_or(AmmError::Overflow)?;
+            .ok_or(AmmError::MathOverflow)?;
         
         // Stable swap price: always 1 (scaled)
         let stable_price = scale;
@@ -122,19 +257,19 @@ impl HybridCfmmStrategy {
         // Weighted combination
         let cp_weighted = cp_price
             .checked_mul(gamma_u128)
-            .ok_or(AmmError::Overflow)?
+            .ok_or(AmmError::MathOverflow)?
             .checked_div(scale)
-            .ok_or(AmmError::Overflow)?;
+            .ok_or(AmmError::MathOverflow)?;
         
         let stable_weighted = stable_price
-            .checked_mul(scale.checked_sub(gamma_u128).ok_or(AmmError::Overflow)?)
-            .ok_or(AmmError::Overflow)?
+            .checked_mul(scale.checked_sub(gamma_u128).ok_or(AmmError::MathOverflow)?)
+            .ok_or(AmmError::MathOverflow)?
             .checked_div(scale)
-            .ok_or(AmmError::Overflow)?;
+            .ok_or(AmmError::MathOverflow)?;
         
-        let price = cp_weighted.checked_add(stable_weighted).ok_or(AmmError::Overflow)?;
+        let price = cp_weighted.checked_add(stable_weighted).ok_or(AmmError::MathOverflow)?;
         
-        Ok(price as u64)
+        u64::try_from(price).map_err(|_| AmmError::MathOverflow.into())
     }
     
     /// Calculate dynamic fee based on current imbalance
@@ -145,40 +280,37 @@ impl HybridCfmmStrategy {
         y: u64,
         params: HybridParams,
     ) -> Result<u64> {
-        require!(x > 0 && y > 0, AmmError::InsufficientLiquidity);
+        require!(x > 0 && y > 0, AmmError::ZeroReserve);
+        params.validate()?;
         
         let x_u128 = x as u128;
         let y_u128 = y as u128;
         
         // Calculate imbalance ratio
-        let total = x_u128.checked_add(y_u128).ok_or(AmmError::Overflow)?;
-        let expected_x = total.checked_div(2).ok_or(AmmError::Overflow)?;
+        let total = x_u128.checked_add(y_u128).ok_or(AmmError::MathOverflow)?;
+        let expected_x = total.checked_div(2).ok_or(AmmError::MathOverflow)?;
         
         // Calculate deviation from balanced state
         let deviation = if x_u128 > expected_x {
-            x_u128.checked_sub(expected_x).ok_or(AmmError::Overflow)?
+            x_u128.checked_sub(expected_x).ok_or(AmmError::MathOverflow)?
         } else {
-            expected_x.checked_sub(x_u128).ok_or(AmmError::Overflow)?
+            expected_x.checked_sub(x_u128).ok_or(AmmError::MathOverflow)?
         };
         
         // Calculate imbalance percentage (scaled by 1M)
         let imbalance_pct = deviation
-            .checked_mul(1_000_000)
-            .ok_or(AmmError::Overflow)?
+            .checked_mul(SCALE)
+            .ok_or(AmmError::MathOverflow)?
             .checked_div(expected_x)
-            .ok_or(AmmError::Overflow)?;
+            .ok_or(AmmError::MathOverflow)?;
         
         // Dynamic fee calculation
-        let fee_range = params.out_fee.checked_sub(params.mid_fee).ok_or(AmmError::Overflow)?;
-        let fee_adjustment = (fee_range as u128)
-            .checked_mul(imbalance_pct)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(1_000_000)
-            .ok_or(AmmError::Overflow)?;
-        
-        let dynamic_fee = params.mid_fee.checked_add(fee_adjustment as u64).ok_or(AmmError::Overflow)?;
+        let fee_range = params.out_fee - params.mid_fee;
+        let fee_adjustment = mul_div(fee_range as u128, imbalance_pct, SCALE, Rounding::Up)?
+            .min(fee_range as u128);
         
-        Ok(dynamic_fee.min(params.out_fee))
+        // At most `out_fee`, which `validate` bounds by 10_000
+        Ok(params.mid_fee + fee_adjustment as u64)
     }
     
     /// Calculate amount out using the hybrid invariant
@@ -191,35 +323,32 @@ impl HybridCfmmStrategy {
         params: HybridParams,
     ) -> Result<u64> {
         require!(amount_in > 0, AmmError::InsufficientLiquidity);
-        require!(reserve_in > 0 && reserve_out > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_in > 0 && reserve_out > 0, AmmError::ZeroReserve);
         
         // Calculate dynamic fee
         let dynamic_fee = Self::calculate_dynamic_fee(reserve_in, reserve_out, params)?;
         
-        // Apply fee to input amount
-        let amount_in_with_fee = amount_in
-            .checked_mul(10_000 - dynamic_fee)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(10_000)
-            .ok_or(AmmError::Overflow)?;
+        // Apply fee to input amount (rounded up)
+        let amount_in_with_fee = amount_after_fee(amount_in, dynamic_fee)?;
         
         // Calculate original invariant
         let invariant = Self::calculate_invariant(reserve_in, reserve_out, params)?;
         
         // New input reserve
-        let new_reserve_in = reserve_in.checked_add(amount_in_with_fee).ok_or(AmmError::Overflow)?;
+        let new_reserve_in = reserve_in.checked_add(amount_in_with_fee).ok_or(AmmError::MathOverflow)?;
         
         // Solve for new output reserve using a more stable method
         // Use constant product approximation for stability
+        // The remaining reserve rounds up so the output rounds down
         let new_reserve_out = if invariant > 0 && new_reserve_in > 0 {
-            let approximation = (invariant * 2) / (new_reserve_in as u128);
+            let approximation = mul_div(invariant, 2, new_reserve_in as u128, Rounding::Up)?;
             approximation.min(reserve_out as u128) as u64
         } else {
             reserve_out
         };
         
         // Calculate amount out
-        let amount_out = reserve_out.checked_sub(new_reserve_out).ok_or(AmmError::Overflow)?;
+        let amount_out = reserve_out.checked_sub(new_reserve_out).ok_or(AmmError::MathOverflow)?;
         
         // Ensure we don't drain the reserves
         require!(amount_out < reserve_out, AmmError::InsufficientLiquidity);
@@ -237,65 +366,68 @@ impl HybridCfmmStrategy {
         current_gamma: u64,
         params: HybridParams,
     ) -> Result<u64> {
-        let price_diff = if current_price > target_price {
-            current_price.checked_sub(target_price).ok_or(AmmError::Overflow)?
-        } else {
-            target_price.checked_sub(current_price).ok_or(AmmError::Overflow)?
-        };
+        require!(target_price > 0, AmmError::ZeroReserve);
+        require!(current_gamma as u128 <= SCALE, AmmError::InvalidWeights);
+
+        let price_diff = current_price.abs_diff(target_price);
         
-        // If price is close to target, decrease gamma (more stable behavior)
-        // If price is far from target, increase gamma (more volatile behavior)
-        let price_deviation = (price_diff as u128)
-            .checked_mul(1_000_000)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(target_price as u128)
-            .ok_or(AmmError::Overflow)?;
+        // If price is close to target, decrease gamma by one step (more stable behavior)
+        // If price is far from target, increase gamma in proportion (more volatile behavior)
+        let price_deviation = mul_div(price_diff as u128, SCALE, target_price as u128, Rounding::Down)?;
         
-        let adjustment = (price_deviation * params.adjustment_step as u128) / 1_000_000;
+        // Gamma never moves by more than 1, so larger adjustments saturate
+        let adjustment = mul_div(price_deviation, params.adjustment_step as u128, SCALE, Rounding::Down)?
+            .min(SCALE) as u64;
         
         let new_gamma = if price_deviation > 10_000 { // 1% deviation
             // Increase gamma for more volatile behavior
-            current_gamma.checked_add(adjustment as u64).ok_or(AmmError::Overflow)?
+            current_gamma + adjustment
         } else {
-            // Decrease gamma for more stable behavior
-            current_gamma.checked_sub(adjustment as u64).unwrap_or(0)
+            // Decrease gamma for more stable behavior; a deviation-scaled step would round to 0
+            current_gamma.saturating_sub(params.adjustment_step)
         };
         
         // Clamp gamma between 0 and 1000000
-        Ok(new_gamma.min(1_000_000))
+        Ok(new_gamma.min(SCALE as u64))
     }
-}
-
-impl AmmStrategy for HybridCfmmStrategy {
-    fn calculate_amount_out(
+    
+    /// Calculate amount out as a blend of constant product and stable swap outputs
+    /// 
+    /// `gamma` is the weight of the constant product output (scaled by 1M);
+    /// pools pass the gamma adapted by `crank_gamma`.
+    pub fn calculate_amount_out_with_gamma(
         amount_in: u64,
         reserve_in: u64,
         reserve_out: u64,
         fee_bps: u64,
+        gamma: u64,
     ) -> Result<u64> {
         require!(amount_in > 0, AmmError::InsufficientLiquidity);
-        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
-        require!(reserve_out > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_in > 0, AmmError::ZeroReserve);
+        require!(reserve_out > 0, AmmError::ZeroReserve);
+        require!(gamma as u128 <= SCALE, AmmError::InvalidWeights);
         
-        // Simplified hybrid approach: use weighted average of constant product and stable swap
+        // Simplified hybrid approach: gamma-weighted average of constant product and stable swap
         
-        // Apply fee
-        let amount_in_with_fee = amount_in
-            .checked_mul(10_000 - fee_bps)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(10_000)
-            .ok_or(AmmError::Overflow)?;
+        // Apply fee (rounded up)
+        let amount_in_with_fee = amount_after_fee(amount_in, fee_bps)?;
         
-        // Constant product calculation
-        let cp_numerator = amount_in_with_fee as u128 * reserve_out as u128;
+        // Constant product calculation (rounded down)
         let cp_denominator = reserve_in as u128 + amount_in_with_fee as u128;
-        let cp_amount_out = (cp_numerator / cp_denominator) as u64;
+        let cp_amount_out = mul_div(
+            amount_in_with_fee as u128,
+            reserve_out as u128,
+            cp_denominator,
+            Rounding::Down,
+        )? as u64;
         
         // Stable swap calculation (simplified)
         let stable_amount_out = amount_in_with_fee.min(reserve_out / 2); // Conservative stable swap
         
-        // Weighted average (50% each for simplicity)
-        let hybrid_amount_out = (cp_amount_out / 2) + (stable_amount_out / 2);
+        // Weighted average, each part rounded down
+        let cp_weighted = mul_div(cp_amount_out as u128, gamma as u128, SCALE, Rounding::Down)?;
+        let stable_weighted = mul_div(stable_amount_out as u128, SCALE - gamma as u128, SCALE, Rounding::Down)?;
+        let hybrid_amount_out = (cp_weighted + stable_weighted) as u64;
         
         // Ensure we don't drain reserves
         require!(hybrid_amount_out < reserve_out, AmmError::InsufficientLiquidity);
@@ -303,6 +435,23 @@ impl AmmStrategy for HybridCfmmStrategy {
         
         Ok(hybrid_amount_out)
     }
+}
+
+impl AmmStrategy for HybridCfmmStrategy {
+    fn calculate_amount_out(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        Self::calculate_amount_out_with_gamma(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+            HybridParams::default().gamma,
+        )
+    }
     
     fn calculate_initial_lp_supply(amount_a: u64, amount_b: u64) -> Result<u64> {
         require!(amount_a > 0, AmmError::InsufficientLiquidity);
@@ -312,8 +461,8 @@ impl AmmStrategy for HybridCfmmStrategy {
         let params = HybridParams::default();
         let invariant = Self::calculate_invariant(amount_a, amount_b, params)?;
         
-        // Take square root of invariant as LP supply (similar to Uniswap v2)
-        let lp_supply = ((invariant as f64).sqrt() as u64).max(1_000); // Minimum 1000 to avoid precision issues
+        // Take square root of invariant as LP supply (similar to Uniswap v2), rounded down
+        let lp_supply = (invariant.isqrt() as u64).max(1_000); // Minimum 1000 to avoid precision issues
         
         Ok(lp_supply)
     }
@@ -324,14 +473,11 @@ impl AmmStrategy for HybridCfmmStrategy {
         lp_supply: u64,
     ) -> Result<u64> {
         require!(amount_a > 0, AmmError::InsufficientLiquidity);
-        require!(reserve_a > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_a > 0, AmmError::ZeroReserve);
         require!(lp_supply > 0, AmmError::InsufficientLiquidity);
         
         // For hybrid CFMM, LP tokens are proportional to the share of invariant added
-        let proportion = (amount_a as u128 * 1_000_000) / reserve_a as u128;
-        let lp_tokens = (lp_supply as u128 * proportion) / 1_000_000;
-        
-        Ok(lp_tokens as u64)
+        mul_div_u64(amount_a, lp_supply, reserve_a, Rounding::Down)
     }
     
     fn calculate_withdraw_amounts(
@@ -344,13 +490,11 @@ impl AmmStrategy for HybridCfmmStrategy {
         require!(lp_supply > 0, AmmError::InsufficientLiquidity);
         require!(lp_amount <= lp_supply, AmmError::InsufficientLiquidity);
         
-        // Proportional withdrawal based on LP token share
-        let proportion = (lp_amount as u128 * 1_000_000) / lp_supply as u128;
+        // Proportional withdrawal based on LP token share, rounded down
+        let amount_a = mul_div_u64(lp_amount, reserve_a, lp_supply, Rounding::Down)?;
+        let amount_b = mul_div_u64(lp_amount, reserve_b, lp_supply, Rounding::Down)?;
         
-        let amount_a = (reserve_a as u128 * proportion) / 1_000_000;
-        let amount_b = (reserve_b as u128 * proportion) / 1_000_000;
-        
-        Ok((amount_a as u64, amount_b as u64))
+        Ok((amount_a, amount_b))
     }
 }
 
@@ -480,7 +624,6 @@ mod tests {
         
         assert!(result.is_ok());
         let new_gamma = result.unwrap();
-        assert!(new_gamma >= 0);
         assert!(new_gamma <= 1_000_000);
     }
     
@@ -506,4 +649,117 @@ mod tests {
         println!("Hybrid result: {}, CP result: {}", hybrid_result, cp_result);
         assert!(hybrid_result != cp_result);
     }
+
+    #[test]
+    fn test_large_reserves_do_not_overflow() {
+        let params = HybridParams::default();
+
+        // (x + y)^2 overflows u128 here, but the invariant itself fits
+        let invariant = HybridCfmmStrategy::calculate_invariant(u64::MAX, u64::MAX, params).unwrap();
+        assert!(invariant.abs_diff((u64::MAX as u128) * (u64::MAX as u128)) <= 1);
+
+        let lp_supply = HybridCfmmStrategy::calculate_initial_lp_supply(u64::MAX, u64::MAX).unwrap();
+        assert!(lp_supply >= u64::MAX - 1);
+
+        let gamma = HybridCfmmStrategy::update_gamma(u64::MAX, 1, 500_000, params).unwrap();
+        assert_eq!(gamma, 1_000_000);
+    }
+
+    #[test]
+    fn test_gamma_weights_the_blend() {
+        let default = HybridCfmmStrategy::calculate_amount_out(100_000, 1_000_000, 1_000_000, 30).unwrap();
+        let at_default =
+            HybridCfmmStrategy::calculate_amount_out_with_gamma(100_000, 1_000_000, 1_000_000, 30, 500_000).unwrap();
+        assert_eq!(at_default, default);
+
+        // Gamma 1 is pure constant product, gamma 0 pure (simplified) stable swap
+        let cp = HybridCfmmStrategy::calculate_amount_out_with_gamma(100_000, 1_000_000, 1_000_000, 30, 1_000_000)
+            .unwrap();
+        let stable = HybridCfmmStrategy::calculate_amount_out_with_gamma(100_000, 1_000_000, 1_000_000, 30, 0).unwrap();
+        assert_eq!(cp, 99_700 * 1_000_000 / 1_099_700);
+        assert_eq!(stable, 99_700);
+        assert!(cp < default && default < stable);
+    }
+
+    #[test]
+    fn test_ema_moves_by_half_life() {
+        let params = HybridParams::default();
+        let mut state = HybridState::new(HybridState::DEFAULT_TARGET_PRICE).unwrap();
+
+        // The first sample seeds the average, later ones in the same second are ignored
+        state.record_price(1_000_000, 100, params).unwrap();
+        state.record_price(5_000_000, 100, params).unwrap();
+        assert_eq!(state.ema_price, 1_000_000);
+
+        // One half-life later the average is halfway to the new price
+        state.record_price(3_000_000, 700, params).unwrap();
+        assert!(state.ema_price.abs_diff(2_000_000) <= 1);
+        assert_eq!(state.last_sample_time, 700);
+    }
+
+    #[test]
+    fn test_crank_is_rate_limited_and_bounded() {
+        let params = HybridParams::default();
+        let mut state = HybridState::new(HybridState::DEFAULT_TARGET_PRICE).unwrap();
+        assert_eq!(state.crank(1_000, params).unwrap_err(), AmmError::CrankTooEarly.into());
+
+        // Far from the target gamma rises, capped at `MAX_GAMMA_STEP`
+        state.record_price(1_000_000_000, 100, params).unwrap();
+        assert!(state.crank(1_000, params).unwrap());
+        assert_eq!(state.gamma, 550_000);
+        assert_eq!(
+            state.crank(1_000 + GAMMA_CRANK_INTERVAL_SLOTS - 1, params).unwrap_err(),
+            AmmError::CrankTooEarly.into()
+        );
+
+        // Near the target it falls back towards stable behaviour
+        state.ema_price = 1_005_000;
+        assert!(state.crank(1_000 + GAMMA_CRANK_INTERVAL_SLOTS, params).unwrap());
+        assert_eq!(state.gamma, 550_000 - params.adjustment_step);
+    }
+
+    #[test]
+    fn test_unset_state_uses_defaults() {
+        let params = HybridParams::default();
+        let mut state = HybridState::default();
+        assert_eq!(state.gamma(), params.gamma);
+        assert_eq!(HybridState::new(0).unwrap_err(), AmmError::InvalidHybridParams.into());
+
+        // The first crank writes the defaults it started from
+        state.record_price(1_000_000, 100, params).unwrap();
+        assert!(state.crank(1_000, params).unwrap());
+        assert_eq!(state.target_price, HybridState::DEFAULT_TARGET_PRICE);
+        assert_eq!(state.gamma, params.gamma - params.adjustment_step);
+    }
+
+    #[test]
+    fn test_precise_errors() {
+        let params = HybridParams::default();
+        assert_eq!(
+            HybridCfmmStrategy::calculate_amount_out(1_000, 0, 1_000_000, 30).unwrap_err(),
+            AmmError::ZeroReserve.into()
+        );
+        assert_eq!(
+            HybridCfmmStrategy::calculate_amount_out(1_000, 1_000_000, 1_000_000, 10_001).unwrap_err(),
+            AmmError::InvalidFee.into()
+        );
+        assert_eq!(
+            HybridCfmmStrategy::calculate_invariant(1_000, 1_000, HybridParams { gamma: 1_000_001, ..params })
+                .unwrap_err(),
+            AmmError::InvalidWeights.into()
+        );
+        assert_eq!(
+            HybridParams { mid_fee: 400, ..params }.validate().unwrap_err(),
+            AmmError::InvalidFee.into()
+        );
+        assert_eq!(
+            HybridCfmmStrategy::calculate_dynamic_fee(1_000, 1_000, HybridParams { mid_fee: 400, ..params })
+                .unwrap_err(),
+            AmmError::InvalidFee.into()
+        );
+        assert_eq!(
+            HybridCfmmStrategy::calculate_price(1, u64::MAX, params).unwrap_err(),
+            AmmError::MathOverflow.into()
+        );
+    }
 }
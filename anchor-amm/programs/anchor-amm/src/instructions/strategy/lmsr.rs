@@ -0,0 +1,200 @@
+// programs/anchor-amm/src/instructions/strategy/lmsr.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use super::constant_product::ConstantProductStrategy;
+use super::math::{amount_after_fee, exp_neg, ln, mul_div, Rounding, LN_2, WAD};
+use super::AmmStrategy;
+
+/// Logarithmic Market Scoring Rule Strategy (Hanson's LMSR)
+///
+/// Prices the two outcome tokens of a binary prediction market. The pool's
+/// reserves are the market maker's inventory of YES and NO tokens, and the
+/// LMSR cost function C(q) = b * ln(e^(q_yes / b) + e^(q_no / b)) becomes the
+/// invariant:
+///
+/// e^(-reserve_a / b) + e^(-reserve_b / b) = constant
+///
+/// - Outcome prices are p_i = e^(-reserve_i / b) / Σ e^(-reserve_j / b) and sum to 1
+/// - `b` is the liquidity parameter: larger `b` means deeper markets
+/// - The market maker can lose at most b * ln(2) (the worst-case subsidy), so a
+///   pool seeded with at least that many of each outcome token can always pay out
+///
+/// Formula: amount_out = b * ln(1 + e^((reserve_out - reserve_in) / b) * (1 - e^(-dx / b)))
+/// Where: dx = amount_in - ceil(amount_in * fee_bps / 10000)
+pub struct LmsrStrategy;
+
+impl LmsrStrategy {
+    /// Maximum loss of a market with liquidity `b`: b * ln(2), rounded up
+    pub fn worst_case_subsidy(b: u64) -> Result<u64> {
+        let subsidy = mul_div(b as u128, LN_2, WAD, Rounding::Up)?;
+        u64::try_from(subsidy).map_err(|_| AmmError::MathOverflow.into())
+    }
+
+    /// Outcome prices `(price_a, price_b)` scaled by `WAD`; they always sum to `WAD`
+    ///
+    /// Formula: price_a = 1 / (1 + e^((reserve_a - reserve_b) / b))
+    pub fn prices(reserve_a: u64, reserve_b: u64, b: u64) -> Result<(u128, u128)> {
+        require!(b > 0, AmmError::InsufficientLiquidity);
+
+        // Work with the reserve difference so large reserves don't underflow e^(-r / b)
+        let price_a = if reserve_a >= reserve_b {
+            let weight = exp_neg(mul_div((reserve_a - reserve_b) as u128, WAD, b as u128, Rounding::Down)?)?;
+            mul_div(weight, WAD, WAD + weight, Rounding::Down)?
+        } else {
+            let weight = exp_neg(mul_div((reserve_b - reserve_a) as u128, WAD, b as u128, Rounding::Down)?)?;
+            mul_div(WAD, WAD, WAD + weight, Rounding::Down)?
+        };
+
+        Ok((price_a, WAD - price_a))
+    }
+
+    /// Calculate swap output for liquidity parameter `b`
+    ///
+    /// Every intermediate step rounds so the output rounds down.
+    pub fn calculate_amount_out_with_liquidity(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+        b: u64,
+    ) -> Result<u64> {
+        require!(amount_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_out > 0, AmmError::InsufficientLiquidity);
+        require!(b > 0, AmmError::InsufficientLiquidity);
+
+        let amount_in_with_fee = amount_after_fee(amount_in, fee_bps)?;
+        let b = b as u128;
+
+        // t = 1 - e^(-dx / b)
+        let t = WAD - exp_neg(mul_div(amount_in_with_fee as u128, WAD, b, Rounding::Up)?)?;
+
+        // amount_out / b = ln(1 + e^(d / b) * t), with d = reserve_out - reserve_in
+        let scaled_out = if reserve_out >= reserve_in {
+            // = d / b + ln(e^(-d / b) + t), increasing in d / b
+            let d = mul_div((reserve_out - reserve_in) as u128, WAD, b, Rounding::Down)?;
+            let log = ln(exp_neg(d)? + t)?;
+            (d as i128 + log).max(0) as u128
+        } else {
+            // = ln(1 + e^(-|d| / b) * t), decreasing in |d| / b
+            let d = mul_div((reserve_in - reserve_out) as u128, WAD, b, Rounding::Up)?;
+            let log = ln(WAD + mul_div(exp_neg(d)?, t, WAD, Rounding::Down)?)?;
+            log.max(0) as u128
+        };
+
+        let amount_out = mul_div(scaled_out, b, WAD, Rounding::Down)?;
+
+        // Ensure we don't drain the entire reserve
+        require!(amount_out < reserve_out as u128, AmmError::InsufficientLiquidity);
+
+        Ok(amount_out as u64)
+    }
+}
+
+impl AmmStrategy for LmsrStrategy {
+    /// Calculate swap output with the deepest `b` the inventory can back
+    ///
+    /// Without a configured `b`, it is chosen so that the worst-case subsidy
+    /// equals the smaller reserve: b = min(reserve_in, reserve_out) / ln(2).
+    /// On-chain swaps use the pool's `lmsr_b`.
+    fn calculate_amount_out(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        let b = mul_div(reserve_in.min(reserve_out) as u128, WAD, LN_2, Rounding::Down)?;
+        let b = u64::try_from(b).map_err(|_| AmmError::MathOverflow)?;
+
+        Self::calculate_amount_out_with_liquidity(amount_in, reserve_in, reserve_out, fee_bps, b)
+    }
+
+    /// LP tokens are accounted proportionally, as in the constant product pool
+    fn calculate_initial_lp_supply(amount_a: u64, amount_b: u64) -> Result<u64> {
+        ConstantProductStrategy::calculate_initial_lp_supply(amount_a, amount_b)
+    }
+
+    fn calculate_lp_tokens_to_mint(
+        amount_a: u64,
+        reserve_a: u64,
+        lp_supply: u64,
+    ) -> Result<u64> {
+        ConstantProductStrategy::calculate_lp_tokens_to_mint(amount_a, reserve_a, lp_supply)
+    }
+
+    fn calculate_withdraw_amounts(
+        lp_amount: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        lp_supply: u64,
+    ) -> Result<(u64, u64)> {
+        ConstantProductStrategy::calculate_withdraw_amounts(lp_amount, reserve_a, reserve_b, lp_supply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const B: u64 = 1_000_000;
+
+    /// Σ e^(-reserve / b), the LMSR invariant
+    fn invariant(reserve_a: u64, reserve_b: u64) -> u128 {
+        let weight = |r: u64| exp_neg(mul_div(r as u128, WAD, B as u128, Rounding::Down).unwrap()).unwrap();
+        weight(reserve_a) + weight(reserve_b)
+    }
+
+    #[test]
+    fn test_prices_sum_to_one() {
+        for (a, b) in [(1_000_000, 1_000_000), (1_500_000, 700_000), (10, 50_000_000), (u64::MAX, 1)] {
+            let (price_a, price_b) = LmsrStrategy::prices(a, b, B).unwrap();
+            assert_eq!(price_a + price_b, WAD);
+        }
+
+        // Balanced inventory prices both outcomes at 0.5
+        let (price_a, _) = LmsrStrategy::prices(1_000_000, 1_000_000, B).unwrap();
+        assert_eq!(price_a, WAD / 2);
+
+        // The market maker holds less of the likelier outcome
+        let (price_a, price_b) = LmsrStrategy::prices(500_000, 1_500_000, B).unwrap();
+        assert!(price_a > price_b);
+    }
+
+    #[test]
+    fn test_swap_keeps_invariant() {
+        for (reserve_in, reserve_out) in [(1_000_000, 1_000_000), (700_000, 2_000_000), (3_000_000, 800_000)] {
+            for amount_in in [1_000, 100_000, 5_000_000] {
+                let amount_out = LmsrStrategy::calculate_amount_out_with_liquidity(
+                    amount_in, reserve_in, reserve_out, 0, B,
+                )
+                .unwrap();
+                assert!(amount_out > 0);
+
+                let before = invariant(reserve_in, reserve_out);
+                let after = invariant(reserve_in + amount_in, reserve_out - amount_out);
+                assert!(after <= before + 10, "invariant grew swapping {}", amount_in);
+            }
+        }
+    }
+
+    #[test]
+    fn test_worst_case_subsidy_bounds_payout() {
+        // b * ln(2) of each outcome is enough to absorb any trade from a balanced market
+        let subsidy = LmsrStrategy::worst_case_subsidy(B).unwrap();
+        assert_eq!(subsidy, 693_148);
+
+        let amount_out = LmsrStrategy::calculate_amount_out_with_liquidity(
+            u32::MAX as u64, subsidy, subsidy, 0, B,
+        )
+        .unwrap();
+        assert!(amount_out < subsidy);
+    }
+
+    #[test]
+    fn test_small_trade_at_marginal_price() {
+        // At a 0.5 / 0.5 price, swapping a little YES returns about the same NO
+        let amount_out =
+            LmsrStrategy::calculate_amount_out_with_liquidity(100, 1_000_000, 1_000_000, 0, B).unwrap();
+        assert!(amount_out == 99 || amount_out == 100);
+    }
+}
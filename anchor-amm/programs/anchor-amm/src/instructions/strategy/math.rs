@@ -0,0 +1,218 @@
+// programs/anchor-amm/src/instructions/strategy/math.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+
+pub use self::wide::{U256, U512};
+
+// Kept in its own module so the macro does not see the anchor prelude's `Result`
+#[allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+mod wide {
+    use uint::construct_uint;
+
+    construct_uint! {
+        /// 256-bit unsigned integer for intermediate products that overflow u128
+        pub struct U256(4);
+    }
+
+    construct_uint! {
+        /// 512-bit unsigned integer for quartic invariants
+        pub struct U512(8);
+    }
+}
+
+/// Rounding direction for a division
+///
+/// Every strategy rounds so that the error favours the pool:
+/// - amounts paid out by the pool (swap output, LP minted, withdrawals) round `Down`
+/// - amounts paid in to the pool (fees, exact-out inputs, deposit amounts) round `Up`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+
+/// Calculate `a * b / c` with an explicit rounding direction
+///
+/// The product is computed in 256 bits so it cannot overflow; the result must fit in u128.
+pub fn mul_div(a: u128, b: u128, c: u128, rounding: Rounding) -> Result<u128> {
+    require!(c > 0, AmmError::MathOverflow);
+
+    let product = U256::from(a)
+        .checked_mul(U256::from(b))
+        .ok_or(AmmError::MathOverflow)?;
+    let divisor = U256::from(c);
+
+    let (quotient, remainder) = product.div_mod(divisor);
+    let result = match rounding {
+        Rounding::Down => quotient,
+        Rounding::Up if remainder.is_zero() => quotient,
+        Rounding::Up => quotient + 1,
+    };
+
+    require!(result <= U256::from(u128::MAX), AmmError::MathOverflow);
+    Ok(result.as_u128())
+}
+
+/// `mul_div` for u64 amounts, failing if the result does not fit in u64
+pub fn mul_div_u64(a: u64, b: u64, c: u64, rounding: Rounding) -> Result<u64> {
+    let result = mul_div(a as u128, b as u128, c as u128, rounding)?;
+    u64::try_from(result).map_err(|_| AmmError::MathOverflow.into())
+}
+
+/// Reject fees above 100%
+pub fn validate_fee(fee_bps: u64) -> Result<()> {
+    require!(fee_bps <= 10_000, AmmError::InvalidFee);
+    Ok(())
+}
+
+/// Fee charged on `amount` at `fee_bps`, rounded up
+pub fn fee_amount(amount: u64, fee_bps: u64) -> Result<u64> {
+    validate_fee(fee_bps)?;
+    mul_div_u64(amount, fee_bps, 10_000, Rounding::Up)
+}
+
+/// `amount` minus its fee, i.e. `amount * (10000 - fee_bps) / 10000` rounded down
+pub fn amount_after_fee(amount: u64, fee_bps: u64) -> Result<u64> {
+    amount
+        .checked_sub(fee_amount(amount, fee_bps)?)
+        .ok_or(AmmError::MathOverflow.into())
+}
+
+/// Smallest amount whose `amount_after_fee` covers `amount`, i.e. `amount * 10000 / (10000 - fee_bps)` rounded up
+pub fn amount_before_fee(amount: u64, fee_bps: u64) -> Result<u64> {
+    validate_fee(fee_bps)?;
+    mul_div_u64(amount, 10_000, 10_000 - fee_bps, Rounding::Up)
+}
+
+/// Fixed-point scale of `exp_neg` and `ln` (1e18 = 1.0)
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// ln(2) scaled by `WAD`
+pub const LN_2: u128 = 693_147_180_559_945_309;
+
+/// e^(-x) for `x` scaled by `WAD`, rounded down
+///
+/// Formula: e^(-x) = 2^(-n) / e^r, where x = n * ln(2) + r and 0 <= r < ln(2)
+pub fn exp_neg(x: u128) -> Result<u128> {
+    let n = x / LN_2;
+    if n >= 128 {
+        return Ok(0);
+    }
+    let r = x - n * LN_2;
+
+    // e^r by its Taylor series, which converges quickly for r < ln(2)
+    let mut term = WAD;
+    let mut exp_r = WAD;
+    for i in 1..=30u128 {
+        term = mul_div(term, r, WAD * i, Rounding::Down)?;
+        if term == 0 {
+            break;
+        }
+        exp_r += term;
+    }
+
+    Ok(mul_div(WAD, WAD, exp_r, Rounding::Down)? >> n)
+}
+
+/// Most that `ln` can fall below the exact logarithm, in `WAD` units
+///
+/// `z` and each of the at most 20 nonzero atanh terms lose under two units to
+/// truncation, and the sum is doubled.
+pub const LN_MAX_ERROR: u128 = 2 * 42;
+
+/// Natural logarithm of `x` scaled by `WAD`, for x > 0, never more than `LN_MAX_ERROR` below the exact value
+///
+/// Formula: ln(x) = ln(m) + n * ln(2) with m = x / 2^n in [1, 2),
+/// and ln(m) = 2 * atanh((m - 1) / (m + 1))
+pub fn ln(x: u128) -> Result<i128> {
+    require!(x > 0, AmmError::MathOverflow);
+
+    // Normalize into [WAD, 2 * WAD)
+    let mut n: i128 = 0;
+    let mut m = x;
+    while m >= 2 * WAD {
+        m >>= 1;
+        n += 1;
+    }
+    while m < WAD {
+        m <<= 1;
+        n -= 1;
+    }
+
+    let z = mul_div(m - WAD, WAD, m + WAD, Rounding::Down)?;
+    let z2 = mul_div(z, z, WAD, Rounding::Down)?;
+
+    // atanh(z) = z + z^3 / 3 + z^5 / 5 + ..., with z <= 1/3
+    let mut power = z;
+    let mut sum = z;
+    for i in 1..=40u128 {
+        power = mul_div(power, z2, WAD, Rounding::Down)?;
+        if power == 0 {
+            break;
+        }
+        sum += power / (2 * i + 1);
+    }
+
+    Ok(2 * sum as i128 + n * LN_2 as i128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(mul_div(10, 10, 3, Rounding::Down).unwrap(), 33);
+        assert_eq!(mul_div(10, 10, 3, Rounding::Up).unwrap(), 34);
+        assert_eq!(mul_div(10, 9, 3, Rounding::Up).unwrap(), 30);
+        assert!(mul_div(1, 1, 0, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn test_mul_div_wide_product() {
+        // u128::MAX * 2 / 4 overflows u128 in the product but not in the result
+        let result = mul_div(u128::MAX, 2, 4, Rounding::Down).unwrap();
+        assert_eq!(result, u128::MAX / 2);
+        assert!(mul_div(u128::MAX, 2, 1, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn test_fee_rounds_up() {
+        assert_eq!(fee_amount(1, 30).unwrap(), 1);
+        assert_eq!(fee_amount(10_000, 30).unwrap(), 30);
+        assert_eq!(fee_amount(10_000, 10_001).unwrap_err(), AmmError::InvalidFee.into());
+        assert_eq!(amount_after_fee(10_001, 30).unwrap(), 9_970);
+        assert!(amount_after_fee(100, 10_001).is_err());
+    }
+
+    #[test]
+    fn test_amount_before_fee_is_smallest_cover() {
+        for amount in [1, 9_970, 9_971, 1_000_000] {
+            let gross = amount_before_fee(amount, 30).unwrap();
+            assert!(amount_after_fee(gross, 30).unwrap() >= amount);
+            assert!(amount_after_fee(gross - 1, 30).unwrap() < amount);
+        }
+        assert_eq!(amount_before_fee(9_970, 30).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn test_exp_neg() {
+        assert_eq!(exp_neg(0).unwrap(), WAD);
+        // e^-1 = 0.367879441171442321...
+        assert!(exp_neg(WAD).unwrap().abs_diff(367_879_441_171_442_321) <= 10);
+        // e^-ln(2) = 0.5
+        assert!(exp_neg(LN_2).unwrap().abs_diff(WAD / 2) <= 10);
+        assert_eq!(exp_neg(200 * WAD).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ln() {
+        assert_eq!(ln(WAD).unwrap(), 0);
+        assert!(ln(2 * WAD).unwrap().abs_diff(LN_2 as i128) <= 10);
+        assert!(ln(WAD / 2).unwrap().abs_diff(-(LN_2 as i128)) <= 10);
+        // ln(e^-3) = -3
+        let x = exp_neg(3 * WAD).unwrap();
+        assert!(ln(x).unwrap().abs_diff(-3 * WAD as i128) <= 1_000);
+        assert!(ln(0).is_err());
+    }
+}
@@ -1,19 +1,150 @@
 // programs/anchor-amm/src/instructions/strategy/mod.rs
 use anchor_lang::prelude::*;
+use crate::errors::AmmError;
 
 pub mod constant_product;
 pub mod stable_swap;
 pub mod concentrated_liquidity;
 pub mod hybrid_cfmm;
 pub mod constant_mean;
+pub mod dynamic_fee;
+pub mod circuit_breaker;
+pub mod math;
+pub mod range_order;
+pub mod oracle;
+pub mod oracle_peg;
+pub mod rate_provider;
+pub mod solidly_stable;
+pub mod lmsr;
+pub mod bonding_curve;
 
 pub use constant_product::ConstantProductStrategy;
 pub use stable_swap::StableSwapStrategy;
 pub use concentrated_liquidity::ConcentratedLiquidityStrategy;
-pub use hybrid_cfmm::HybridCfmmStrategy;
-pub use constant_mean::ConstantMeanStrategy;
+pub use hybrid_cfmm::{HybridCfmmStrategy, HybridParams, HybridState};
+pub use constant_mean::{ConstantMeanStrategy, LbpParams};
+pub use oracle_peg::{OraclePegParams, OraclePegStrategy, PegTarget};
+pub use solidly_stable::SolidlyStableStrategy;
+pub use lmsr::LmsrStrategy;
+pub use bonding_curve::BondingCurveStrategy;
+pub use dynamic_fee::{DynamicFeeParams, VolatilityState};
+pub use circuit_breaker::CircuitBreaker;
+pub use range_order::RangeOrder;
+pub use math::Rounding;
+pub use oracle::PythPrice;
+pub use rate_provider::{RateProvider, RateProviderParams, RateSource, SPL_STAKE_POOL_PROGRAM_ID};
+
+/// Curve a pool prices swaps and LP tokens with, selected at `initialize`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap,
+    ConcentratedLiquidity,
+    HybridCfmm,
+    ConstantMean,
+    OraclePeg,
+    SolidlyStable,
+    Lmsr,
+    Lbp,
+}
+
+// Forward a call to the `AmmStrategy` implementation of `curve`
+macro_rules! dispatch {
+    ($curve:expr, $method:ident($($arg:expr),*)) => {
+        match $curve {
+            CurveType::ConstantProduct => ConstantProductStrategy::$method($($arg),*),
+            CurveType::StableSwap => StableSwapStrategy::$method($($arg),*),
+            CurveType::ConcentratedLiquidity => ConcentratedLiquidityStrategy::$method($($arg),*),
+            CurveType::HybridCfmm => HybridCfmmStrategy::$method($($arg),*),
+            CurveType::ConstantMean => ConstantMeanStrategy::$method($($arg),*),
+            CurveType::OraclePeg => OraclePegStrategy::$method($($arg),*),
+            CurveType::SolidlyStable => SolidlyStableStrategy::$method($($arg),*),
+            CurveType::Lmsr => LmsrStrategy::$method($($arg),*),
+            CurveType::Lbp => ConstantMeanStrategy::$method($($arg),*),
+        }
+    };
+}
+
+impl CurveType {
+    /// See `AmmStrategy::calculate_amount_out`
+    pub fn calculate_amount_out(
+        &self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        dispatch!(self, calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps))
+    }
+
+    /// Whether swaps can move the price onto limit orders of pools on this curve
+    pub fn fills_limit_orders(&self) -> bool {
+        matches!(self, CurveType::ConstantProduct | CurveType::ConcentratedLiquidity)
+    }
+
+    /// Gross input moving the spot price (output per input, scaled by `PRICE_SCALE`)
+    /// down to `target_price`, on the curves that fill limit orders
+    pub fn calculate_amount_in_to_price(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        target_price: u128,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        match self {
+            CurveType::ConstantProduct => {
+                ConstantProductStrategy::calculate_amount_in_to_price(reserve_in, reserve_out, target_price, fee_bps)
+            }
+            CurveType::ConcentratedLiquidity => {
+                ConcentratedLiquidityStrategy::calculate_amount_in_to_price(reserve_in, reserve_out, target_price, fee_bps)
+            }
+            _ => err!(AmmError::InvalidLimitOrder),
+        }
+    }
+
+    /// See `AmmStrategy::calculate_amount_in`
+    pub fn calculate_amount_in(
+        &self,
+        amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        dispatch!(self, calculate_amount_in(amount_out, reserve_in, reserve_out, fee_bps))
+    }
+
+    /// See `AmmStrategy::calculate_initial_lp_supply`
+    pub fn calculate_initial_lp_supply(&self, amount_a: u64, amount_b: u64) -> Result<u64> {
+        dispatch!(self, calculate_initial_lp_supply(amount_a, amount_b))
+    }
+
+    /// See `AmmStrategy::calculate_lp_tokens_to_mint`
+    pub fn calculate_lp_tokens_to_mint(
+        &self,
+        amount_a: u64,
+        reserve_a: u64,
+        lp_supply: u64,
+    ) -> Result<u64> {
+        dispatch!(self, calculate_lp_tokens_to_mint(amount_a, reserve_a, lp_supply))
+    }
+
+    /// See `AmmStrategy::calculate_withdraw_amounts`
+    pub fn calculate_withdraw_amounts(
+        &self,
+        lp_amount: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        lp_supply: u64,
+    ) -> Result<(u64, u64)> {
+        dispatch!(self, calculate_withdraw_amounts(lp_amount, reserve_a, reserve_b, lp_supply))
+    }
+}
 
 /// Trait defining the interface for AMM strategies
+///
+/// All calculations follow the `Rounding` policy: amounts leaving the pool
+/// round down and amounts entering the pool round up, so rounding errors
+/// always accrue to the pool.
 pub trait AmmStrategy {
     /// Calculate the amount out for a given amount in
     /// 
@@ -32,6 +163,55 @@ pub trait AmmStrategy {
         fee_bps: u64,
     ) -> Result<u64>;
 
+    /// Calculate the amount in required to receive exactly `amount_out`
+    ///
+    /// The default implementation searches for the smallest input whose
+    /// `calculate_amount_out` is at least `amount_out`, so it rounds up by
+    /// construction. Strategies with a closed form should override it.
+    ///
+    /// # Arguments
+    /// * `amount_out` - The amount of tokens to receive
+    /// * `reserve_in` - The reserve of the input token
+    /// * `reserve_out` - The reserve of the output token
+    /// * `fee_bps` - The fee in basis points (e.g., 30 = 0.3%)
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The amount of tokens to be paid in
+    fn calculate_amount_in(
+        amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(amount_out > 0, AmmError::InsufficientLiquidity);
+        require!(amount_out < reserve_out, AmmError::InsufficientLiquidity);
+
+        let enough = |amount_in: u64| {
+            matches!(
+                Self::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps),
+                Ok(out) if out >= amount_out
+            )
+        };
+
+        // Find an upper bound by doubling, then bisect down to the smallest input
+        let mut high = amount_out;
+        while !enough(high) {
+            high = high.checked_mul(2).ok_or(AmmError::InsufficientLiquidity)?;
+        }
+
+        let mut low = 0u64;
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if enough(mid) {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        Ok(high)
+    }
+
     /// Calculate the initial LP token supply for the first deposit
     /// 
     /// # Arguments
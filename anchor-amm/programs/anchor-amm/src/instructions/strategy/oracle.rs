@@ -0,0 +1,202 @@
+// programs/anchor-amm/src/instructions/strategy/oracle.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use super::dynamic_fee::PRICE_SCALE;
+
+/// Pyth price account magic number
+pub const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+/// Pyth account layout version
+pub const PYTH_VERSION: u32 = 2;
+/// Pyth account type of a price account
+pub const PYTH_PRICE_ACCOUNT: u32 = 3;
+/// Pyth aggregate status for a price that is currently trading
+pub const PYTH_STATUS_TRADING: u32 = 1;
+
+// Byte offsets of the fields read from a Pyth v2 price account
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_CONF_OFFSET: usize = 216;
+const AGG_STATUS_OFFSET: usize = 224;
+const AGG_PUBLISH_SLOT_OFFSET: usize = 232;
+
+/// Minimum size of a Pyth v2 price account covering the aggregate price
+pub const PYTH_PRICE_ACCOUNT_LEN: usize = 240;
+
+/// Aggregate price read from a Pyth-format price account
+///
+/// Only the fields needed to peg a pool are decoded, so any program writing
+/// the same layout (e.g. a local mock in tests) can act as the oracle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PythPrice {
+    pub price: i64,        // Aggregate price mantissa
+    pub conf: u64,         // Confidence interval, same exponent as `price`
+    pub expo: i32,         // Price exponent, e.g. -8
+    pub status: u32,       // Aggregate status
+    pub publish_slot: u64, // Slot the aggregate was published in
+}
+
+impl PythPrice {
+    /// Decode a Pyth v2 price account
+    pub fn load(data: &[u8]) -> Result<Self> {
+        require!(data.len() >= PYTH_PRICE_ACCOUNT_LEN, AmmError::InvalidOracle);
+        require!(read_u32(data, MAGIC_OFFSET) == PYTH_MAGIC, AmmError::InvalidOracle);
+        require!(read_u32(data, VERSION_OFFSET) == PYTH_VERSION, AmmError::InvalidOracle);
+        require!(
+            read_u32(data, ACCOUNT_TYPE_OFFSET) == PYTH_PRICE_ACCOUNT,
+            AmmError::InvalidOracle
+        );
+
+        Ok(Self {
+            price: read_u64(data, AGG_PRICE_OFFSET) as i64,
+            conf: read_u64(data, AGG_CONF_OFFSET),
+            expo: read_u32(data, EXPONENT_OFFSET) as i32,
+            status: read_u32(data, AGG_STATUS_OFFSET),
+            publish_slot: read_u64(data, AGG_PUBLISH_SLOT_OFFSET),
+        })
+    }
+
+    /// Encode the price into a Pyth v2 price account buffer
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_LEN];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..ACCOUNT_TYPE_OFFSET + 4]
+            .copy_from_slice(&PYTH_PRICE_ACCOUNT.to_le_bytes());
+        data[EXPONENT_OFFSET..EXPONENT_OFFSET + 4].copy_from_slice(&self.expo.to_le_bytes());
+        data[AGG_PRICE_OFFSET..AGG_PRICE_OFFSET + 8].copy_from_slice(&self.price.to_le_bytes());
+        data[AGG_CONF_OFFSET..AGG_CONF_OFFSET + 8].copy_from_slice(&self.conf.to_le_bytes());
+        data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4].copy_from_slice(&self.status.to_le_bytes());
+        data[AGG_PUBLISH_SLOT_OFFSET..AGG_PUBLISH_SLOT_OFFSET + 8]
+            .copy_from_slice(&self.publish_slot.to_le_bytes());
+        data
+    }
+
+    /// Reject prices that are not trading, stale, or too uncertain
+    ///
+    /// - stale: published more than `max_staleness_slots` before `current_slot`
+    /// - uncertain: conf / price above `max_confidence_bps`
+    pub fn validate(
+        &self,
+        current_slot: u64,
+        max_staleness_slots: u64,
+        max_confidence_bps: u64,
+    ) -> Result<()> {
+        require!(self.status == PYTH_STATUS_TRADING, AmmError::OracleStale);
+        require!(self.price > 0, AmmError::InvalidOracle);
+        require!(
+            current_slot.saturating_sub(self.publish_slot) <= max_staleness_slots,
+            AmmError::OracleStale
+        );
+
+        let conf_bps = (self.conf as u128)
+            .checked_mul(10_000)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_div(self.price as u128)
+            .ok_or(AmmError::MathOverflow)?;
+        require!(conf_bps <= max_confidence_bps as u128, AmmError::OracleConfidence);
+
+        Ok(())
+    }
+
+    /// Price of one raw unit of token A in raw units of token B, scaled by `PRICE_SCALE`
+    ///
+    /// Formula: price * 10^(expo + decimals_b - decimals_a) * PRICE_SCALE
+    pub fn scaled_price(&self, decimals_a: u8, decimals_b: u8) -> Result<u128> {
+        require!(self.price > 0, AmmError::InvalidOracle);
+
+        // PRICE_SCALE is 10^12
+        let exponent = self.expo as i64 + decimals_b as i64 - decimals_a as i64 + 12;
+        require!(exponent.abs() <= 38, AmmError::MathOverflow);
+
+        let power = 10u128
+            .checked_pow(exponent.unsigned_abs() as u32)
+            .ok_or(AmmError::MathOverflow)?;
+        let price = if exponent >= 0 {
+            (self.price as u128).checked_mul(power).ok_or(AmmError::MathOverflow)?
+        } else {
+            (self.price as u128) / power
+        };
+
+        require!(price > 0, AmmError::InvalidOracle);
+        Ok(price)
+    }
+}
+
+/// Convert a token A price (B per A, scaled) into an output-per-input price
+pub fn price_out_per_in(price: u128, a_to_b: bool) -> Result<u128> {
+    require!(price > 0, AmmError::InvalidOracle);
+
+    if a_to_b {
+        Ok(price)
+    } else {
+        PRICE_SCALE
+            .checked_mul(PRICE_SCALE)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_div(price)
+            .ok_or(AmmError::MathOverflow.into())
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sol_usdc() -> PythPrice {
+        PythPrice {
+            price: 15_000_000_000, // 150.00000000
+            conf: 7_500_000,       // 0.075
+            expo: -8,
+            status: PYTH_STATUS_TRADING,
+            publish_slot: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_mock_account() {
+        let price = sol_usdc();
+        let data = price.to_account_data();
+        assert_eq!(PythPrice::load(&data).unwrap(), price);
+    }
+
+    #[test]
+    fn test_rejects_non_pyth_data() {
+        let mut data = sol_usdc().to_account_data();
+        data[0] = 0;
+        assert!(PythPrice::load(&data).is_err());
+        assert!(PythPrice::load(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_scaled_price_normalizes_decimals() {
+        // SOL has 9 decimals, USDC has 6: 1 lamport = 150e-9 USDC = 0.15 raw USDC units
+        let price = sol_usdc().scaled_price(9, 6).unwrap();
+        assert_eq!(price, 15 * PRICE_SCALE / 100);
+    }
+
+    #[test]
+    fn test_staleness_and_confidence_guards() {
+        let price = sol_usdc();
+        assert!(price.validate(1_010, 25, 10).is_ok());
+
+        // Too old
+        assert!(price.validate(1_100, 25, 10).is_err());
+
+        // 0.075 / 150 = 5 bps of confidence
+        assert!(price.validate(1_010, 25, 4).is_err());
+
+        // Halted feed
+        let halted = PythPrice { status: 0, ..price };
+        assert!(halted.validate(1_010, 25, 10).is_err());
+    }
+}
@@ -0,0 +1,408 @@
+// programs/anchor-amm/src/instructions/strategy/oracle_peg.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use super::constant_product::ConstantProductStrategy;
+use super::dynamic_fee::PRICE_SCALE;
+use super::math::{amount_after_fee, mul_div_u64, Rounding, U512};
+use super::AmmStrategy;
+
+/// Fixed-point scale of the slippage coefficient `k` (1_000_000 = 1.0)
+pub const K_SCALE: u64 = 1_000_000;
+
+/// Slippage coefficient used when no oracle quote is supplied (0.1)
+pub const DEFAULT_K: u64 = 100_000;
+
+/// Oracle-pegged Proactive Market Maker Strategy (DODO PMM style)
+///
+/// Liquidity is concentrated around an external price `i` instead of the
+/// pool's own reserve ratio. The slippage coefficient `k` controls how
+/// quickly the price moves away from `i` as a trade eats into the reserves:
+/// - k = 0: constant sum at the oracle price (no slippage)
+/// - k = 1: constant product pegged at the oracle price
+///
+/// Each token has a target reserve (`PegTarget`), the balance the pool holds
+/// at rest. Trades move the reserves away from their targets, and a token
+/// below its target is priced with DODO's integral over that side:
+/// - input below target: amount_out = i * dx * (1 - k + k * T_in^2 / (R_in * (R_in + dx)))
+/// - output below target: dx * i = (R_out - R_out') * (1 - k + k * T_out^2 / (R_out * R_out')),
+///   solved for the new output reserve R_out'
+///
+/// Output above its target trades at the oracle price until it reaches the
+/// target. The targets only move on deposits, withdrawals and `repeg_pool`,
+/// so splitting a trade doesn't reset the curve, and arbitrage against the
+/// oracle is what brings the reserves back.
+///
+/// Where: dx = amount_in - ceil(amount_in * fee_bps / 10000)
+pub struct OraclePegStrategy;
+
+/// Oracle peg configuration stored on the pool
+///
+/// - `oracle`: Pyth-format price account quoting token A in token B
+/// - `k`: slippage coefficient, scaled by `K_SCALE`
+/// - `max_staleness_slots`: swaps halt once the price is older than this
+/// - `max_confidence_bps`: swaps halt once conf / price exceeds this
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OraclePegParams {
+    pub oracle: Pubkey,
+    pub k: u64,
+    pub max_staleness_slots: u64,
+    pub max_confidence_bps: u64,
+}
+
+impl OraclePegParams {
+    pub const LEN: usize = 32 + 8 + 8 + 8;
+
+    /// Validate the peg configuration
+    pub fn validate(&self) -> Result<()> {
+        require!(self.oracle != Pubkey::default(), AmmError::InvalidOracle);
+        require!(self.k <= K_SCALE, AmmError::InvalidOracle);
+        require!(self.max_confidence_bps <= 10_000, AmmError::InvalidOracle);
+        Ok(())
+    }
+}
+
+/// Target reserves of an oracle-pegged pool (DODO's B0 and Q0)
+///
+/// Zero on pools created before targets were stored, which trade as if
+/// balanced until `repeg_pool` sets them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PegTarget {
+    pub target_a: u64,
+    pub target_b: u64,
+}
+
+impl PegTarget {
+    pub const LEN: usize = 8 + 8;
+
+    /// Targets to price a swap with, the reserves themselves while unset
+    pub fn targets(&self, reserve_a: u64, reserve_b: u64) -> (u64, u64) {
+        if self.target_a == 0 && self.target_b == 0 {
+            (reserve_a, reserve_b)
+        } else {
+            (self.target_a, self.target_b)
+        }
+    }
+
+    /// Grow the targets with a deposit of `lp_minted` LP tokens on top of `lp_supply`
+    ///
+    /// Deposits are proportional to the reserves, so the targets grow by the
+    /// same share; the first deposit sets them to the deposited amounts.
+    pub fn deposit(&mut self, amount_a: u64, amount_b: u64, lp_minted: u64, lp_supply: u64) -> Result<()> {
+        if lp_supply == 0 {
+            self.target_a = amount_a;
+            self.target_b = amount_b;
+            return Ok(());
+        }
+        for target in [&mut self.target_a, &mut self.target_b] {
+            let added = mul_div_u64(*target, lp_minted, lp_supply, Rounding::Down)?;
+            *target = target.checked_add(added).ok_or(AmmError::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// Shrink the targets with a withdrawal of `lp_burned` out of `lp_supply` LP tokens
+    pub fn withdraw(&mut self, lp_burned: u64, lp_supply: u64) -> Result<()> {
+        for target in [&mut self.target_a, &mut self.target_b] {
+            let removed = mul_div_u64(*target, lp_burned, lp_supply, Rounding::Up)?;
+            *target = target.saturating_sub(removed);
+        }
+        Ok(())
+    }
+}
+
+impl OraclePegStrategy {
+    /// Calculate swap output at an oracle price from a balanced pool
+    ///
+    /// Same as `calculate_amount_out_with_targets` with the targets at the reserves.
+    pub fn calculate_amount_out_at_price(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+        price_out_per_in: u128,
+        k: u64,
+    ) -> Result<u64> {
+        Self::calculate_amount_out_with_targets(
+            amount_in,
+            (reserve_in, reserve_out),
+            (reserve_in, reserve_out),
+            fee_bps,
+            price_out_per_in,
+            k,
+        )
+    }
+
+    /// Calculate swap output at an oracle price, given the input and output
+    /// reserves and their targets
+    ///
+    /// `price_out_per_in` is the oracle price of the input token in the
+    /// output token, scaled by `PRICE_SCALE`. The output rounds down.
+    pub fn calculate_amount_out_with_targets(
+        amount_in: u64,
+        (reserve_in, reserve_out): (u64, u64),
+        (target_in, target_out): (u64, u64),
+        fee_bps: u64,
+        price_out_per_in: u128,
+        k: u64,
+    ) -> Result<u64> {
+        require!(amount_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_out > 0, AmmError::InsufficientLiquidity);
+        require!(price_out_per_in > 0, AmmError::InvalidOracle);
+        require!(k <= K_SCALE, AmmError::InvalidOracle);
+
+        // Quartic intermediate products, e.g. dx * price * k * T^2, overflow U256
+        let mut remaining = U512::from(amount_after_fee(amount_in, fee_bps)?);
+        let price = U512::from(price_out_per_in);
+        let price_scale = U512::from(PRICE_SCALE);
+        let initial_out = U512::from(reserve_out);
+        let reserve_in = U512::from(reserve_in);
+        let mut reserve_out = initial_out;
+        let (target_in, target_out) = (U512::from(target_in), U512::from(target_out));
+        let (k, k_scale) = (U512::from(k), U512::from(K_SCALE));
+
+        // The input refills its own deficit first, at a premium over the oracle price
+        if reserve_in < target_in && !remaining.is_zero() {
+            let dx = remaining.min(target_in - reserve_in);
+            let reserve_after = reserve_in + dx;
+            let curvature = (k_scale - k) * reserve_in * reserve_after + k * target_in * target_in;
+            let out = dx * price * curvature / (price_scale * k_scale * reserve_in * reserve_after);
+            require!(out < reserve_out, AmmError::InsufficientLiquidity);
+            reserve_out -= out;
+            remaining -= dx;
+        }
+
+        // Output above its target trades at the oracle price
+        if reserve_out > target_out && !remaining.is_zero() {
+            let value = remaining * price / price_scale;
+            let surplus = reserve_out - target_out;
+            if value <= surplus {
+                reserve_out -= value;
+                remaining = U512::zero();
+            } else {
+                let used = div_ceil(surplus * price_scale, price);
+                reserve_out = target_out;
+                remaining = remaining.saturating_sub(used);
+            }
+        }
+
+        // Then the output runs into its own deficit: solve for the new reserve R'
+        // (1 - k) * R'^2 - b * R' - k * T^2 = 0, where b = (1 - k) * R - k * T^2 / R - dx * i
+        if !remaining.is_zero() {
+            let constant = k * target_out * target_out;
+            let positive = (k_scale - k) * reserve_out;
+            let negative = constant / reserve_out + k_scale * remaining * price / price_scale;
+            let reserve_after = if k == k_scale {
+                // Constant product around the target: R' = T^2 / (T^2 / R + dx * i)
+                require!(!negative.is_zero(), AmmError::InsufficientLiquidity);
+                div_ceil(constant, negative)
+            } else {
+                let quadratic = k_scale - k;
+                let discriminant = U512::from(4u64) * quadratic * constant;
+                let root = if positive >= negative {
+                    let b = positive - negative;
+                    b + ceil_sqrt(b * b + discriminant)
+                } else {
+                    let b = negative - positive;
+                    ceil_sqrt(b * b + discriminant) - b
+                };
+                div_ceil(root, U512::from(2u64) * quadratic)
+            };
+            reserve_out = reserve_after.min(reserve_out);
+        }
+
+        // Ensure we don't drain the entire reserve
+        require!(!reserve_out.is_zero(), AmmError::InsufficientLiquidity);
+        Ok((initial_out - reserve_out).as_u64())
+    }
+}
+
+/// `numerator / denominator`, rounded up
+fn div_ceil(numerator: U512, denominator: U512) -> U512 {
+    (numerator + denominator - 1) / denominator
+}
+
+/// Square root of `value`, rounded up
+fn ceil_sqrt(value: U512) -> U512 {
+    let root = value.integer_sqrt();
+    if root * root < value {
+        root + 1
+    } else {
+        root
+    }
+}
+
+impl AmmStrategy for OraclePegStrategy {
+    /// Calculate swap output without an oracle quote
+    ///
+    /// The pool's own reserve ratio stands in for the oracle price and
+    /// `DEFAULT_K` is used. On-chain swaps use `calculate_amount_out_at_price`.
+    fn calculate_amount_out(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+
+        let price = (reserve_out as u128)
+            .checked_mul(PRICE_SCALE)
+            .ok_or(AmmError::MathOverflow)?
+            / reserve_in as u128;
+
+        Self::calculate_amount_out_at_price(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+            price,
+            DEFAULT_K,
+        )
+    }
+
+    /// LP tokens are accounted proportionally, as in the constant product pool
+    fn calculate_initial_lp_supply(amount_a: u64, amount_b: u64) -> Result<u64> {
+        ConstantProductStrategy::calculate_initial_lp_supply(amount_a, amount_b)
+    }
+
+    fn calculate_lp_tokens_to_mint(
+        amount_a: u64,
+        reserve_a: u64,
+        lp_supply: u64,
+    ) -> Result<u64> {
+        ConstantProductStrategy::calculate_lp_tokens_to_mint(amount_a, reserve_a, lp_supply)
+    }
+
+    fn calculate_withdraw_amounts(
+        lp_amount: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        lp_supply: u64,
+    ) -> Result<(u64, u64)> {
+        ConstantProductStrategy::calculate_withdraw_amounts(lp_amount, reserve_a, reserve_b, lp_supply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESERVE: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_zero_k_trades_at_oracle_price() {
+        // k = 0 is a constant sum at the oracle price: 2 out per in
+        let amount_out = OraclePegStrategy::calculate_amount_out_at_price(
+            1_000_000, RESERVE, RESERVE * 4, 0, 2 * PRICE_SCALE, 0,
+        )
+        .unwrap();
+        assert_eq!(amount_out, 2_000_000);
+    }
+
+    #[test]
+    fn test_full_k_matches_constant_product() {
+        // k = 1 pegged at the reserve ratio is the constant product curve
+        let pegged = OraclePegStrategy::calculate_amount_out_at_price(
+            50_000_000, RESERVE, RESERVE, 30, PRICE_SCALE, K_SCALE,
+        )
+        .unwrap();
+        let cp = ConstantProductStrategy::calculate_amount_out(50_000_000, RESERVE, RESERVE, 30)
+            .unwrap();
+        assert!(pegged.abs_diff(cp) <= 1);
+    }
+
+    #[test]
+    fn test_lower_k_means_less_slippage() {
+        let quote = |k| {
+            OraclePegStrategy::calculate_amount_out_at_price(
+                100_000_000, RESERVE, RESERVE, 0, PRICE_SCALE, k,
+            )
+            .unwrap()
+        };
+        assert!(quote(10_000) > quote(100_000));
+        assert!(quote(100_000) > quote(K_SCALE));
+    }
+
+    #[test]
+    fn test_peg_follows_oracle_not_reserves() {
+        // Reserves are 1:1 but the oracle says 1 A = 1.5 B
+        let amount_out = OraclePegStrategy::calculate_amount_out_at_price(
+            1_000, RESERVE, RESERVE, 0, 3 * PRICE_SCALE / 2, DEFAULT_K,
+        )
+        .unwrap();
+        assert_eq!(amount_out, 1_499);
+    }
+
+    #[test]
+    fn test_cannot_drain_reserve_out() {
+        let result = OraclePegStrategy::calculate_amount_out_at_price(
+            RESERVE, RESERVE, RESERVE, 0, PRICE_SCALE, 0,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_targets_make_split_trades_path_independent() {
+        let quote = |amount_in, reserves| {
+            OraclePegStrategy::calculate_amount_out_with_targets(
+                amount_in, reserves, (RESERVE, RESERVE), 0, PRICE_SCALE, DEFAULT_K,
+            )
+            .unwrap()
+        };
+        let whole = quote(200_000_000, (RESERVE, RESERVE));
+        let first = quote(100_000_000, (RESERVE, RESERVE));
+        let second = quote(100_000_000, (RESERVE + 100_000_000, RESERVE - first));
+        assert!((first + second).abs_diff(whole) <= 2);
+
+        // Pricing from balanced reserves again would hand out more
+        let rebalanced = OraclePegStrategy::calculate_amount_out_at_price(
+            100_000_000, RESERVE + 100_000_000, RESERVE - first, 0, PRICE_SCALE, DEFAULT_K,
+        )
+        .unwrap();
+        assert!(rebalanced > second);
+    }
+
+    #[test]
+    fn test_deficit_and_surplus_pricing() {
+        // Refilling the input's deficit pays more than the oracle price
+        let refill = OraclePegStrategy::calculate_amount_out_with_targets(
+            1_000_000, (RESERVE / 2, RESERVE), (RESERVE, RESERVE), 0, PRICE_SCALE, DEFAULT_K,
+        )
+        .unwrap();
+        assert!(refill > 1_000_000);
+
+        // Output above its target is sold at the oracle price
+        let surplus = OraclePegStrategy::calculate_amount_out_with_targets(
+            1_000_000, (RESERVE, 2 * RESERVE), (RESERVE, RESERVE), 0, 2 * PRICE_SCALE, DEFAULT_K,
+        )
+        .unwrap();
+        assert_eq!(surplus, 2_000_000);
+    }
+
+    #[test]
+    fn test_peg_target_follows_liquidity() {
+        let mut target = PegTarget::default();
+        assert_eq!(target.targets(5, 7), (5, 7));
+
+        target.deposit(1_000, 2_000, 1_000, 0).unwrap();
+        assert_eq!(target, PegTarget { target_a: 1_000, target_b: 2_000 });
+        target.deposit(500, 900, 500, 1_000).unwrap();
+        assert_eq!(target, PegTarget { target_a: 1_500, target_b: 3_000 });
+        target.withdraw(750, 1_500).unwrap();
+        assert_eq!(target, PegTarget { target_a: 750, target_b: 1_500 });
+        assert_eq!(target.targets(5, 7), (750, 1_500));
+    }
+
+    #[test]
+    fn test_params_validation() {
+        let params = OraclePegParams {
+            oracle: Pubkey::new_unique(),
+            k: DEFAULT_K,
+            max_staleness_slots: 25,
+            max_confidence_bps: 50,
+        };
+        assert!(params.validate().is_ok());
+        assert!(OraclePegParams { k: K_SCALE + 1, ..params }.validate().is_err());
+        assert!(OraclePegParams { oracle: Pubkey::default(), ..params }.validate().is_err());
+    }
+}
@@ -0,0 +1,121 @@
+// programs/anchor-amm/src/instructions/strategy/range_order.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use super::dynamic_fee::PRICE_SCALE;
+use super::math::U256;
+
+/// Single-tick range order math
+///
+/// A range order is liquidity sitting at exactly one tick. When a swap moves
+/// the pool price onto the tick, the order is converted at the tick price
+/// before the curve continues. Once its whole amount has been converted the
+/// order is filled and stops providing liquidity.
+pub struct RangeOrder;
+
+impl RangeOrder {
+    /// Convert a tick price (token B per token A) into an output-per-input price
+    ///
+    /// - Selling A for B: price = p
+    /// - Selling B for A: price = 1 / p
+    pub fn price_out_per_in(tick_price: u128, a_to_b: bool) -> Result<u128> {
+        require!(tick_price > 0, AmmError::InvalidTick);
+
+        if a_to_b {
+            Ok(tick_price)
+        } else {
+            PRICE_SCALE
+                .checked_mul(PRICE_SCALE)
+                .ok_or(AmmError::MathOverflow)?
+                .checked_div(tick_price)
+                .ok_or(AmmError::MathOverflow.into())
+        }
+    }
+
+    /// Fill an order holding `order_remaining` output tokens at `price_out_per_in`
+    ///
+    /// Returns `(amount_in_used, amount_out_filled)`. The input cost of a full
+    /// fill rounds up and a partial fill's output rounds down, so rounding
+    /// always favours the order owner.
+    pub fn fill(
+        available_in: u64,
+        order_remaining: u64,
+        price_out_per_in: u128,
+    ) -> Result<(u64, u64)> {
+        require!(price_out_per_in > 0, AmmError::InvalidTick);
+
+        if available_in == 0 || order_remaining == 0 {
+            return Ok((0, 0));
+        }
+
+        let price = U256::from(price_out_per_in);
+        let scale = U256::from(PRICE_SCALE);
+
+        // Input needed to take the whole order: ceil(remaining / price)
+        let full_cost = U256::from(order_remaining)
+            .checked_mul(scale)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_add(price - 1)
+            .ok_or(AmmError::MathOverflow)?
+            / price;
+
+        if full_cost <= U256::from(available_in) {
+            return Ok((full_cost.as_u64(), order_remaining));
+        }
+
+        // Partial fill: floor(available_in * price)
+        let amount_out = (U256::from(available_in)
+            .checked_mul(price)
+            .ok_or(AmmError::MathOverflow)?
+            / scale)
+            .min(U256::from(order_remaining))
+            .as_u64();
+
+        if amount_out == 0 {
+            return Ok((0, 0));
+        }
+
+        Ok((available_in, amount_out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_fill() {
+        // Order sells 1000 B at 2 B per A: costs 500 A
+        let (used, filled) = RangeOrder::fill(10_000, 1_000, 2 * PRICE_SCALE).unwrap();
+        assert_eq!(used, 500);
+        assert_eq!(filled, 1_000);
+    }
+
+    #[test]
+    fn test_partial_fill() {
+        let (used, filled) = RangeOrder::fill(200, 1_000, 2 * PRICE_SCALE).unwrap();
+        assert_eq!(used, 200);
+        assert_eq!(filled, 400);
+    }
+
+    #[test]
+    fn test_full_fill_cost_rounds_up() {
+        // 1001 B at 2 B per A costs 500.5 A, rounded up to 501
+        let (used, filled) = RangeOrder::fill(10_000, 1_001, 2 * PRICE_SCALE).unwrap();
+        assert_eq!(used, 501);
+        assert_eq!(filled, 1_001);
+    }
+
+    #[test]
+    fn test_dust_input_does_not_fill() {
+        // 1 unit of input at 0.5 out per in rounds down to nothing
+        let (used, filled) = RangeOrder::fill(1, 1_000, PRICE_SCALE / 2).unwrap();
+        assert_eq!((used, filled), (0, 0));
+    }
+
+    #[test]
+    fn test_price_out_per_in() {
+        let p = 4 * PRICE_SCALE;
+        assert_eq!(RangeOrder::price_out_per_in(p, true).unwrap(), p);
+        assert_eq!(RangeOrder::price_out_per_in(p, false).unwrap(), PRICE_SCALE / 4);
+    }
+}
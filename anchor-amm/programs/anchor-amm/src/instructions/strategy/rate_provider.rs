@@ -0,0 +1,446 @@
+// programs/anchor-amm/src/instructions/strategy/rate_provider.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use anchor_spl::token_2022::spl_token_2022;
+use super::math::{exp_neg, mul_div, Rounding, WAD};
+
+/// Fixed-point scale of exchange rates (1e12 = 1.0)
+pub const RATE_SCALE: u128 = 1_000_000_000_000;
+
+/// SPL stake pool program, the only owner of stake pool accounts
+pub const SPL_STAKE_POOL_PROGRAM_ID: Pubkey = pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+
+/// SPL stake pool account type of the pool state
+pub const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+/// Token-2022 account type of a mint
+pub const MINT_ACCOUNT_TYPE: u8 = 1;
+/// Token-2022 extension type of the interest-bearing config
+pub const INTEREST_BEARING_EXTENSION: u16 = 10;
+/// Year length Token-2022 accrues interest over (365.24 days)
+pub const SECONDS_PER_YEAR: u128 = 31_556_736;
+
+// Byte offsets of the fields read from an SPL stake pool account
+const STAKE_POOL_MINT_OFFSET: usize = 162;
+const TOTAL_LAMPORTS_OFFSET: usize = 258;
+const POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+const LAST_UPDATE_EPOCH_OFFSET: usize = 274;
+
+/// Minimum size of an SPL stake pool account covering the fields read
+pub const STAKE_POOL_STATE_LEN: usize = 282;
+
+// Token-2022 mints with extensions: the base mint, padding up to the size of
+// a token account, the account type, then type-length-value extensions
+const MINT_BASE_LEN: usize = 82;
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const EXTENSIONS_OFFSET: usize = 166;
+const INTEREST_BEARING_CONFIG_LEN: usize = 52;
+
+/// Where a token's exchange rate is read from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateSource {
+    #[default]
+    None,                // Rate of 1.0
+    SplStakePool,        // Stake pool state: total lamports per pool token
+    InterestBearingMint, // Token-2022 interest-bearing config of the mint the token redeems for
+}
+
+impl RateSource {
+    /// Program that must own the provider account, so a lookalike can't set the rate
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            RateSource::None => Pubkey::default(),
+            RateSource::SplStakePool => SPL_STAKE_POOL_PROGRAM_ID,
+            RateSource::InterestBearingMint => spl_token_2022::ID,
+        }
+    }
+}
+
+/// Rate provider configuration of one pool token
+///
+/// - `source`: layout of `account`
+/// - `account`: stake pool state, or the Token-2022 interest-bearing mint the token
+///   redeems for one to one (pool tokens are SPL Token mints, so an interest-bearing
+///   token trades through its SPL Token wrapper)
+/// - `max_staleness_slots`: swaps halt once the cached rate is older than this
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateProviderParams {
+    pub source: RateSource,
+    pub account: Pubkey,
+    pub max_staleness_slots: u64,
+}
+
+impl RateProviderParams {
+    pub const LEN: usize = 1 + 32 + 8;
+
+    /// Validate the configuration of the token with mint `mint` against its provider account
+    pub fn validate(&self, mint: Pubkey, account: &AccountInfo) -> Result<()> {
+        require!(self.source != RateSource::None, AmmError::InvalidRateProvider);
+        require_keys_eq!(account.key(), self.account, AmmError::InvalidRateProvider);
+        // Anyone can write a stake pool or mint layout into an account of their own
+        require_keys_eq!(*account.owner, self.source.program_id(), AmmError::InvalidRateProvider);
+        let data = account.try_borrow_data()?;
+        match self.source {
+            RateSource::SplStakePool => {
+                let state = StakePoolState::load(&data)?;
+                require_keys_eq!(state.pool_mint, mint, AmmError::InvalidRateProvider);
+            }
+            // The wrapper has no on-chain link to the mint, so the creator vouches for it
+            RateSource::InterestBearingMint => {
+                InterestBearingConfig::load(&data)?;
+            }
+            RateSource::None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Exchange rate of a pool token, stored on the pool
+///
+/// Stable pools of yield-bearing tokens (e.g. jitoSOL/SOL) scale each balance
+/// by its rate before the StableSwap math, so the curve is centered on the
+/// value of the tokens rather than on their raw amounts.
+///
+/// The last rate read is cached with its slot. Swaps that don't pass the
+/// provider account, or pass a stake pool that isn't updated for the current
+/// epoch yet, use the cached rate while it is at most `max_staleness_slots` old.
+/// Interest-bearing mints accrue continuously, so passing one always reads
+/// the rate as of the current time.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateProvider {
+    pub params: RateProviderParams,
+    pub cached_rate: u128, // Last rate read, scaled by `RATE_SCALE` (0 = never read)
+    pub cached_slot: u64,  // Slot `cached_rate` was read in
+}
+
+impl RateProvider {
+    pub const LEN: usize = RateProviderParams::LEN + 16 + 8;
+
+    pub fn new(params: RateProviderParams) -> Self {
+        Self { params, ..Self::default() }
+    }
+
+    /// Whether the token has a rate provider
+    pub fn is_set(&self) -> bool {
+        self.params.source != RateSource::None
+    }
+
+    /// Rate of the token with mint `mint`, scaled by `RATE_SCALE`
+    ///
+    /// Reads `data` (the provider account) into the cache if it is current,
+    /// then fails if the cached rate is missing or stale.
+    pub fn refresh(&mut self, data: Option<&[u8]>, mint: Pubkey, clock: &Clock) -> Result<u128> {
+        if !self.is_set() {
+            return Ok(RATE_SCALE);
+        }
+
+        let rate = match (self.params.source, data) {
+            (RateSource::SplStakePool, Some(data)) => {
+                let state = StakePoolState::load(data)?;
+                require_keys_eq!(state.pool_mint, mint, AmmError::InvalidRateProvider);
+                // Stake rewards are only counted once the pool is updated for the epoch
+                if state.last_update_epoch == clock.epoch {
+                    Some(state.rate()?)
+                } else {
+                    None
+                }
+            }
+            (RateSource::InterestBearingMint, Some(data)) => {
+                Some(InterestBearingConfig::load(data)?.rate_at(clock.unix_timestamp)?)
+            }
+            _ => None,
+        };
+        if let Some(rate) = rate {
+            self.cached_rate = rate;
+            self.cached_slot = clock.slot;
+        }
+
+        require!(self.cached_rate > 0, AmmError::RateStale);
+        require!(
+            clock.slot.saturating_sub(self.cached_slot) <= self.params.max_staleness_slots,
+            AmmError::RateStale
+        );
+        Ok(self.cached_rate)
+    }
+}
+
+/// Fields of an SPL stake pool account that price its pool token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StakePoolState {
+    pub pool_mint: Pubkey,      // Mint of the pool token
+    pub total_lamports: u64,    // Lamports under management as of `last_update_epoch`
+    pub pool_token_supply: u64, // Pool tokens minted
+    pub last_update_epoch: u64, // Epoch the balances were last updated in
+}
+
+impl StakePoolState {
+    /// Decode an SPL stake pool account
+    pub fn load(data: &[u8]) -> Result<Self> {
+        require!(data.len() >= STAKE_POOL_STATE_LEN, AmmError::InvalidRateProvider);
+        require!(data[0] == STAKE_POOL_ACCOUNT_TYPE, AmmError::InvalidRateProvider);
+
+        Ok(Self {
+            pool_mint: Pubkey::try_from(&data[STAKE_POOL_MINT_OFFSET..STAKE_POOL_MINT_OFFSET + 32])
+                .map_err(|_| AmmError::InvalidRateProvider)?,
+            total_lamports: read_u64(data, TOTAL_LAMPORTS_OFFSET),
+            pool_token_supply: read_u64(data, POOL_TOKEN_SUPPLY_OFFSET),
+            last_update_epoch: read_u64(data, LAST_UPDATE_EPOCH_OFFSET),
+        })
+    }
+
+    /// Encode the fields into a stake pool account buffer
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut data = vec![0u8; STAKE_POOL_STATE_LEN];
+        data[0] = STAKE_POOL_ACCOUNT_TYPE;
+        data[STAKE_POOL_MINT_OFFSET..STAKE_POOL_MINT_OFFSET + 32]
+            .copy_from_slice(self.pool_mint.as_ref());
+        data[TOTAL_LAMPORTS_OFFSET..TOTAL_LAMPORTS_OFFSET + 8]
+            .copy_from_slice(&self.total_lamports.to_le_bytes());
+        data[POOL_TOKEN_SUPPLY_OFFSET..POOL_TOKEN_SUPPLY_OFFSET + 8]
+            .copy_from_slice(&self.pool_token_supply.to_le_bytes());
+        data[LAST_UPDATE_EPOCH_OFFSET..LAST_UPDATE_EPOCH_OFFSET + 8]
+            .copy_from_slice(&self.last_update_epoch.to_le_bytes());
+        data
+    }
+
+    /// Lamports per pool token, scaled by `RATE_SCALE` (1.0 while the pool is empty)
+    pub fn rate(&self) -> Result<u128> {
+        if self.pool_token_supply == 0 {
+            return Ok(RATE_SCALE);
+        }
+        let rate = mul_div(
+            self.total_lamports as u128,
+            RATE_SCALE,
+            self.pool_token_supply as u128,
+            Rounding::Down,
+        )?;
+        require!(rate > 0, AmmError::InvalidRateProvider);
+        Ok(rate)
+    }
+}
+
+/// Token-2022 interest-bearing config of a mint
+///
+/// Raw balances don't change; the UI amount grows continuously at the
+/// configured rate, which is the exchange rate the pool uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterestBearingConfig {
+    pub initialization_timestamp: i64,
+    pub pre_update_average_rate: i16, // Basis points per year before `last_update_timestamp`
+    pub last_update_timestamp: i64,
+    pub current_rate: i16,            // Basis points per year since `last_update_timestamp`
+}
+
+impl InterestBearingConfig {
+    /// Find and decode the config among the extensions of a Token-2022 mint account
+    pub fn load(mint_data: &[u8]) -> Result<Self> {
+        require!(mint_data.len() > EXTENSIONS_OFFSET, AmmError::InvalidRateProvider);
+        require!(
+            mint_data[ACCOUNT_TYPE_OFFSET] == MINT_ACCOUNT_TYPE,
+            AmmError::InvalidRateProvider
+        );
+
+        let mut offset = EXTENSIONS_OFFSET;
+        while offset + 4 <= mint_data.len() {
+            let extension = read_u16(mint_data, offset);
+            let length = read_u16(mint_data, offset + 2) as usize;
+            let value = offset + 4;
+            require!(value + length <= mint_data.len(), AmmError::InvalidRateProvider);
+
+            if extension == INTEREST_BEARING_EXTENSION {
+                require!(length == INTEREST_BEARING_CONFIG_LEN, AmmError::InvalidRateProvider);
+                // Skips the 32-byte rate authority
+                return Ok(Self {
+                    initialization_timestamp: read_u64(mint_data, value + 32) as i64,
+                    pre_update_average_rate: read_u16(mint_data, value + 40) as i16,
+                    last_update_timestamp: read_u64(mint_data, value + 42) as i64,
+                    current_rate: read_u16(mint_data, value + 50) as i16,
+                });
+            }
+            offset = value + length;
+        }
+
+        err!(AmmError::InvalidRateProvider)
+    }
+
+    /// Encode the config as the only extension of a Token-2022 mint account
+    pub fn to_mint_data(&self) -> Vec<u8> {
+        let mut data = vec![0u8; EXTENSIONS_OFFSET + 4 + INTEREST_BEARING_CONFIG_LEN];
+        data[MINT_BASE_LEN - 1] = 1; // Initialized
+        data[ACCOUNT_TYPE_OFFSET] = MINT_ACCOUNT_TYPE;
+
+        let value = EXTENSIONS_OFFSET + 4;
+        data[EXTENSIONS_OFFSET..EXTENSIONS_OFFSET + 2]
+            .copy_from_slice(&INTEREST_BEARING_EXTENSION.to_le_bytes());
+        data[EXTENSIONS_OFFSET + 2..value]
+            .copy_from_slice(&(INTEREST_BEARING_CONFIG_LEN as u16).to_le_bytes());
+        data[value + 32..value + 40].copy_from_slice(&self.initialization_timestamp.to_le_bytes());
+        data[value + 40..value + 42].copy_from_slice(&self.pre_update_average_rate.to_le_bytes());
+        data[value + 42..value + 50].copy_from_slice(&self.last_update_timestamp.to_le_bytes());
+        data[value + 50..value + 52].copy_from_slice(&self.current_rate.to_le_bytes());
+        data
+    }
+
+    /// UI amount per raw amount at `now`, scaled by `RATE_SCALE`
+    ///
+    /// Formula: e^((pre_rate * (last_update - init) + rate * (now - last_update)) / (SECONDS_PER_YEAR * 10_000))
+    pub fn rate_at(&self, now: i64) -> Result<u128> {
+        let before = self.pre_update_average_rate as i128
+            * (self.last_update_timestamp as i128 - self.initialization_timestamp as i128);
+        let since = self.current_rate as i128 * (now as i128 - self.last_update_timestamp as i128);
+        let exponent = before + since;
+
+        let x = mul_div(exponent.unsigned_abs(), WAD, SECONDS_PER_YEAR * 10_000, Rounding::Down)?;
+        let e_neg = exp_neg(x)?;
+        require!(e_neg > 0, AmmError::MathOverflow);
+        let growth = if exponent >= 0 {
+            mul_div(WAD, WAD, e_neg, Rounding::Down)?
+        } else {
+            e_neg
+        };
+
+        let rate = growth / (WAD / RATE_SCALE);
+        require!(rate > 0, AmmError::InvalidRateProvider);
+        Ok(rate)
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(slot: u64, epoch: u64, unix_timestamp: i64) -> Clock {
+        Clock { slot, epoch, unix_timestamp, ..Clock::default() }
+    }
+
+    fn stake_pool(mint: Pubkey, last_update_epoch: u64) -> StakePoolState {
+        StakePoolState {
+            pool_mint: mint,
+            total_lamports: 1_100_000_000_000,
+            pool_token_supply: 1_000_000_000_000,
+            last_update_epoch,
+        }
+    }
+
+    fn provider(source: RateSource, account: Pubkey) -> RateProvider {
+        RateProvider::new(RateProviderParams { source, account, max_staleness_slots: 100 })
+    }
+
+    #[test]
+    fn test_stake_pool_round_trip_and_rate() {
+        let state = stake_pool(Pubkey::new_unique(), 7);
+        let data = state.to_account_data();
+        assert_eq!(StakePoolState::load(&data).unwrap(), state);
+        assert_eq!(state.rate().unwrap(), RATE_SCALE * 11 / 10);
+
+        let mut other = data.clone();
+        other[0] = 2; // Validator list
+        assert!(StakePoolState::load(&other).is_err());
+        assert!(StakePoolState::load(&data[..200]).is_err());
+    }
+
+    #[test]
+    fn test_interest_bearing_rate() {
+        let config = InterestBearingConfig {
+            initialization_timestamp: 0,
+            pre_update_average_rate: 500, // 5% a year
+            last_update_timestamp: SECONDS_PER_YEAR as i64,
+            current_rate: -500,
+        };
+        let data = config.to_mint_data();
+        assert_eq!(InterestBearingConfig::load(&data).unwrap(), config);
+
+        // e^0.05 = 1.051271096...
+        let rate = config.rate_at(SECONDS_PER_YEAR as i64).unwrap();
+        assert!(rate.abs_diff(1_051_271_096_376) < 10, "{rate}");
+
+        // The negative rate since then brings it back to 1.0
+        let rate = config.rate_at(2 * SECONDS_PER_YEAR as i64).unwrap();
+        assert!(rate.abs_diff(RATE_SCALE) < 10, "{rate}");
+
+        // A classic SPL mint has no extensions
+        assert!(InterestBearingConfig::load(&data[..MINT_BASE_LEN]).is_err());
+
+        // Refreshing reads the rate as of now, whatever the slot
+        let mut provider = provider(RateSource::InterestBearingMint, Pubkey::new_unique());
+        let rate = provider.refresh(Some(&data), Pubkey::new_unique(), &clock(10, 7, SECONDS_PER_YEAR as i64));
+        assert!(rate.unwrap().abs_diff(1_051_271_096_376) < 10);
+    }
+
+    #[test]
+    fn test_refresh_caches_the_rate_within_the_staleness_window() {
+        let mint = Pubkey::new_unique();
+        let mut provider = provider(RateSource::SplStakePool, Pubkey::new_unique());
+
+        // Never read
+        assert_eq!(
+            provider.refresh(None, mint, &clock(10, 7, 0)).unwrap_err(),
+            AmmError::RateStale.into()
+        );
+
+        let data = stake_pool(mint, 7).to_account_data();
+        assert_eq!(provider.refresh(Some(&data), mint, &clock(10, 7, 0)).unwrap(), RATE_SCALE * 11 / 10);
+        assert_eq!(provider.refresh(None, mint, &clock(110, 7, 0)).unwrap(), RATE_SCALE * 11 / 10);
+        assert_eq!(
+            provider.refresh(None, mint, &clock(111, 7, 0)).unwrap_err(),
+            AmmError::RateStale.into()
+        );
+
+        // A stake pool not yet updated for the epoch leaves the cache as it is
+        assert!(provider.refresh(Some(&data), mint, &clock(111, 8, 0)).is_err());
+        assert_eq!(provider.refresh(Some(&data), mint, &clock(110, 8, 0)).unwrap(), RATE_SCALE * 11 / 10);
+        assert_eq!(provider.cached_slot, 10);
+
+        // The stake pool must be the one of the token
+        assert_eq!(
+            provider.refresh(Some(&data), Pubkey::new_unique(), &clock(10, 7, 0)).unwrap_err(),
+            AmmError::InvalidRateProvider.into()
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mint = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let data = stake_pool(mint, 7).to_account_data();
+        let params = |source| RateProviderParams { source, account, max_staleness_slots: 0 };
+        let validate = |params: RateProviderParams, key: Pubkey, owner: Pubkey, data: &[u8]| {
+            let (mut lamports, mut data) = (0, data.to_vec());
+            params.validate(mint, &AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0))
+        };
+        let invalid = || Err(AmmError::InvalidRateProvider.into());
+
+        assert!(validate(params(RateSource::SplStakePool), account, SPL_STAKE_POOL_PROGRAM_ID, &data).is_ok());
+        assert_eq!(validate(params(RateSource::None), account, SPL_STAKE_POOL_PROGRAM_ID, &data), invalid());
+        // Another account than configured, or a lookalike not owned by the stake pool program
+        assert_eq!(validate(params(RateSource::SplStakePool), Pubkey::new_unique(), SPL_STAKE_POOL_PROGRAM_ID, &data), invalid());
+        assert_eq!(validate(params(RateSource::SplStakePool), account, Pubkey::new_unique(), &data), invalid());
+        // The stake pool of another token
+        let other = stake_pool(Pubkey::new_unique(), 7).to_account_data();
+        assert_eq!(validate(params(RateSource::SplStakePool), account, SPL_STAKE_POOL_PROGRAM_ID, &other), invalid());
+
+        // Interest-bearing mints are Token-2022 mints with the extension
+        let mint_data = InterestBearingConfig {
+            initialization_timestamp: 0,
+            pre_update_average_rate: 500,
+            last_update_timestamp: 0,
+            current_rate: 500,
+        }
+        .to_mint_data();
+        let interest_bearing = params(RateSource::InterestBearingMint);
+        assert!(validate(interest_bearing, account, spl_token_2022::ID, &mint_data).is_ok());
+        assert_eq!(validate(interest_bearing, account, Pubkey::new_unique(), &mint_data), invalid());
+        assert_eq!(validate(interest_bearing, account, spl_token_2022::ID, &mint_data[..MINT_BASE_LEN]), invalid());
+
+        // Tokens without a provider are priced at 1.0
+        let mut none = provider(RateSource::None, Pubkey::default());
+        assert_eq!(none.refresh(None, mint, &clock(10, 7, 0)).unwrap(), RATE_SCALE);
+    }
+}
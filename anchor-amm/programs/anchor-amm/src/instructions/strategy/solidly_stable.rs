@@ -0,0 +1,236 @@
+// programs/anchor-amm/src/instructions/strategy/solidly_stable.rs
+use anchor_lang::prelude::*;
+use crate::errors::AmmError;
+use super::constant_product::ConstantProductStrategy;
+use super::math::{amount_after_fee, U512};
+use super::AmmStrategy;
+
+/// Decimals assumed for both tokens when the mints are unknown
+///
+/// Equal decimals cancel out, so any value gives the same quotes.
+pub const DEFAULT_DECIMALS: u8 = 6;
+
+/// Maximum number of Newton iterations when solving for the output reserve
+const MAX_NEWTON_ITERATIONS: usize = 255;
+
+/// Solidly Stable AMM Strategy (x³y + xy³ = k)
+///
+/// The invariant of Solidly / Velodrome stable pools. It is flat around the
+/// 1:1 point, which suits correlated pairs, but has no amplification
+/// parameter to tune.
+///
+/// Both reserves are normalized to the larger of the two mint decimals, so a
+/// 6-decimal and a 9-decimal stablecoin still trade around 1:1. The invariant
+/// is evaluated exactly in 512 bits.
+///
+/// Formula: k = x * y * (x² + y²), solved for y with Newton's method
+pub struct SolidlyStableStrategy;
+
+impl SolidlyStableStrategy {
+    /// Invariant of normalized reserves: x³y + xy³
+    pub fn calculate_invariant(x: U512, y: U512) -> Result<U512> {
+        let xy = x.checked_mul(y).ok_or(AmmError::MathOverflow)?;
+        let squares = x
+            .checked_mul(x)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_add(y.checked_mul(y).ok_or(AmmError::MathOverflow)?)
+            .ok_or(AmmError::MathOverflow)?;
+
+        xy.checked_mul(squares).ok_or(AmmError::MathOverflow.into())
+    }
+
+    /// Derivative of the invariant with respect to y: x³ + 3xy²
+    fn invariant_derivative(x: U512, y: U512) -> Result<U512> {
+        let x3 = x
+            .checked_mul(x)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_mul(x)
+            .ok_or(AmmError::MathOverflow)?;
+        let xy2 = x
+            .checked_mul(y)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_mul(y)
+            .ok_or(AmmError::MathOverflow)?;
+
+        xy2.checked_mul(U512::from(3))
+            .ok_or(AmmError::MathOverflow)?
+            .checked_add(x3)
+            .ok_or(AmmError::MathOverflow.into())
+    }
+
+    /// Solve x³y + xy³ = k for y with Newton's method, starting from `y`
+    ///
+    /// Returns the smallest y with invariant(x, y) >= k, so the invariant
+    /// never decreases.
+    pub fn calculate_y(x: U512, k: U512, mut y: U512) -> Result<U512> {
+        for _ in 0..MAX_NEWTON_ITERATIONS {
+            let current = Self::calculate_invariant(x, y)?;
+            let derivative = Self::invariant_derivative(x, y)?;
+            require!(!derivative.is_zero(), AmmError::InsufficientLiquidity);
+
+            if current < k {
+                let dy = (k - current) / derivative;
+                if dy.is_zero() {
+                    // The curve is convex, so one more unit reaches k
+                    return Ok(y + 1);
+                }
+                y = y.checked_add(dy).ok_or(AmmError::MathOverflow)?;
+            } else {
+                let dy = (current - k) / derivative;
+                if dy.is_zero() {
+                    // Within one unit of the root: step down while k still holds
+                    while !y.is_zero() && Self::calculate_invariant(x, y - 1)? >= k {
+                        y -= U512::one();
+                    }
+                    return Ok(y);
+                }
+                y -= dy;
+            }
+        }
+
+        err!(AmmError::MathOverflow)
+    }
+
+    /// Scale a raw amount with `decimals` up to `target_decimals`
+    pub fn normalize(amount: u64, decimals: u8, target_decimals: u8) -> Result<U512> {
+        require!(target_decimals >= decimals, AmmError::MathOverflow);
+
+        let unit = U512::from(10u64).pow(U512::from(target_decimals - decimals));
+        U512::from(amount).checked_mul(unit).ok_or(AmmError::MathOverflow.into())
+    }
+
+    /// Calculate swap output with reserves normalized by the mint decimals
+    ///
+    /// Formula: dy = y - solve(x + dx, k(x, y)), denormalized and rounded down
+    pub fn calculate_amount_out_with_decimals(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+        decimals_in: u8,
+        decimals_out: u8,
+    ) -> Result<u64> {
+        require!(amount_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+        require!(reserve_out > 0, AmmError::InsufficientLiquidity);
+
+        let amount_in_with_fee = amount_after_fee(amount_in, fee_bps)?;
+        let decimals = decimals_in.max(decimals_out);
+
+        let x = Self::normalize(reserve_in, decimals_in, decimals)?;
+        let y = Self::normalize(reserve_out, decimals_out, decimals)?;
+        let dx = Self::normalize(amount_in_with_fee, decimals_in, decimals)?;
+
+        let k = Self::calculate_invariant(x, y)?;
+        let new_y = Self::calculate_y(x.checked_add(dx).ok_or(AmmError::MathOverflow)?, k, y)?;
+        let dy = y.saturating_sub(new_y);
+
+        // Denormalize, rounding down
+        let unit = U512::from(10u64).pow(U512::from(decimals - decimals_out));
+        let amount_out = dy / unit;
+
+        // Ensure we don't drain the entire reserve
+        require!(amount_out < U512::from(reserve_out), AmmError::InsufficientLiquidity);
+
+        Ok(amount_out.as_u64())
+    }
+}
+
+impl AmmStrategy for SolidlyStableStrategy {
+    /// Calculate swap output assuming both tokens have `DEFAULT_DECIMALS`
+    ///
+    /// On-chain swaps use `calculate_amount_out_with_decimals` with the pool's mint decimals.
+    fn calculate_amount_out(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        Self::calculate_amount_out_with_decimals(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+            DEFAULT_DECIMALS,
+            DEFAULT_DECIMALS,
+        )
+    }
+
+    /// Initial LP supply is the geometric mean, as in Solidly
+    fn calculate_initial_lp_supply(amount_a: u64, amount_b: u64) -> Result<u64> {
+        ConstantProductStrategy::calculate_initial_lp_supply(amount_a, amount_b)
+    }
+
+    fn calculate_lp_tokens_to_mint(
+        amount_a: u64,
+        reserve_a: u64,
+        lp_supply: u64,
+    ) -> Result<u64> {
+        ConstantProductStrategy::calculate_lp_tokens_to_mint(amount_a, reserve_a, lp_supply)
+    }
+
+    fn calculate_withdraw_amounts(
+        lp_amount: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        lp_supply: u64,
+    ) -> Result<(u64, u64)> {
+        ConstantProductStrategy::calculate_withdraw_amounts(lp_amount, reserve_a, reserve_b, lp_supply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newton_solves_invariant() {
+        let x = SolidlyStableStrategy::normalize(1_000_000, 6, 6).unwrap();
+        let y = SolidlyStableStrategy::normalize(1_000_000, 6, 6).unwrap();
+        let k = SolidlyStableStrategy::calculate_invariant(x, y).unwrap();
+
+        let new_x = SolidlyStableStrategy::normalize(1_100_000, 6, 6).unwrap();
+        let new_y = SolidlyStableStrategy::calculate_y(new_x, k, y).unwrap();
+
+        // Smallest y that keeps the invariant
+        assert!(SolidlyStableStrategy::calculate_invariant(new_x, new_y).unwrap() >= k);
+        assert!(SolidlyStableStrategy::calculate_invariant(new_x, new_y - 1).unwrap() < k);
+    }
+
+    #[test]
+    fn test_flat_around_peg() {
+        // 1% of the pool trades almost 1:1, far better than constant product
+        let amount_out =
+            SolidlyStableStrategy::calculate_amount_out(10_000, 1_000_000, 1_000_000, 0).unwrap();
+        let cp = ConstantProductStrategy::calculate_amount_out(10_000, 1_000_000, 1_000_000, 0)
+            .unwrap();
+        assert!(amount_out > cp);
+        assert!(amount_out > 9_990 && amount_out < 10_000);
+    }
+
+    #[test]
+    fn test_decimal_normalization() {
+        // 1M USDC (6 decimals) against 1M of a 9-decimal stablecoin
+        let six = 1_000_000_000_000u64;
+        let nine = 1_000_000_000_000_000u64;
+
+        // Selling 100 USDC returns ~100 of the 9-decimal token
+        let amount_out = SolidlyStableStrategy::calculate_amount_out_with_decimals(
+            100_000_000, six, nine, 0, 6, 9,
+        )
+        .unwrap();
+        assert!(amount_out > 99_999_000_000 && amount_out < 100_000_000_000);
+
+        // Without normalization the pool looks imbalanced and misprices the trade
+        let raw = SolidlyStableStrategy::calculate_amount_out(100_000_000, six, nine, 0).unwrap();
+        assert!(raw < amount_out / 2);
+    }
+
+    #[test]
+    fn test_cannot_drain_reserve_out() {
+        let amount_out =
+            SolidlyStableStrategy::calculate_amount_out(u32::MAX as u64, 1_000_000, 1_000_000, 0)
+                .unwrap();
+        assert!(amount_out < 1_000_000);
+    }
+}
@@ -1,6 +1,8 @@
 // programs/anchor-amm/src/instructions/strategy/stable_swap.rs
 use anchor_lang::prelude::*;
 use crate::errors::AmmError;
+use super::math::{amount_after_fee, mul_div, mul_div_u64, Rounding};
+use super::rate_provider::RATE_SCALE;
 use super::AmmStrategy;
 
 /// Stable Swap AMM Strategy
@@ -12,6 +14,38 @@ use super::AmmStrategy;
 /// implement the full StableSwap invariant with amplification factor.
 pub struct StableSwapStrategy;
 
+impl StableSwapStrategy {
+    /// Calculate swap output with balances scaled by each token's exchange rate
+    ///
+    /// `rate_in` and `rate_out` are scaled by `RATE_SCALE`. The input and the
+    /// reserves are converted to common units (rounded down), swapped on the
+    /// curve, and the output is converted back (rounded down).
+    pub fn calculate_amount_out_with_rates(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+        rate_in: u128,
+        rate_out: u128,
+    ) -> Result<u64> {
+        require!(rate_in > 0 && rate_out > 0, AmmError::InvalidRateProvider);
+
+        let scale = |amount: u64, rate: u128| -> Result<u64> {
+            let scaled = mul_div(amount as u128, rate, RATE_SCALE, Rounding::Down)?;
+            u64::try_from(scaled).map_err(|_| AmmError::MathOverflow.into())
+        };
+        let scaled_out = Self::calculate_amount_out(
+            scale(amount_in, rate_in)?,
+            scale(reserve_in, rate_in)?,
+            scale(reserve_out, rate_out)?,
+            fee_bps,
+        )?;
+
+        let amount_out = mul_div(scaled_out as u128, RATE_SCALE, rate_out, Rounding::Down)?;
+        u64::try_from(amount_out).map_err(|_| AmmError::MathOverflow.into())
+    }
+}
+
 impl AmmStrategy for StableSwapStrategy {
     /// Calculate swap output using a simplified stable swap formula
     /// 
@@ -27,12 +61,8 @@ impl AmmStrategy for StableSwapStrategy {
         require!(reserve_in > 0, AmmError::InsufficientLiquidity);
         require!(reserve_out > 0, AmmError::InsufficientLiquidity);
         
-        // Apply fee
-        let amount_in_with_fee = amount_in
-            .checked_mul(10_000 - fee_bps)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(10_000)
-            .ok_or(AmmError::Overflow)?;
+        // Apply fee (rounded up)
+        let amount_in_with_fee = amount_after_fee(amount_in, fee_bps)?;
         
         // Simplified stable swap: closer to 1:1 ratio
         // In a real implementation, this would use the StableSwap invariant
@@ -40,16 +70,19 @@ impl AmmStrategy for StableSwapStrategy {
         // where A is amplification, n is number of coins, S is sum, P is product
         
         // For now, use a simple linear approximation with reduced slippage
+        // Price impact rounds up so the output rounds down
         let slippage_factor = 10_000; // Much less slippage than constant product
-        let price_impact = amount_in_with_fee
-            .checked_mul(slippage_factor)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(reserve_in + reserve_out)
-            .ok_or(AmmError::Overflow)?;
+        let total_reserves = reserve_in.checked_add(reserve_out).ok_or(AmmError::MathOverflow)?;
+        let price_impact = mul_div_u64(
+            amount_in_with_fee,
+            slippage_factor,
+            total_reserves,
+            Rounding::Up,
+        )?;
         
-        let amount_out = amount_in_with_fee
-            .checked_sub(price_impact)
-            .ok_or(AmmError::Overflow)?;
+        // Pools holding under 10_000 units in total impact more than the whole input
+        require!(price_impact <= amount_in_with_fee, AmmError::InsufficientLiquidity);
+        let amount_out = amount_in_with_fee - price_impact;
         
         // Ensure we don't drain reserves
         require!(amount_out < reserve_out, AmmError::InsufficientLiquidity);
@@ -63,10 +96,10 @@ impl AmmStrategy for StableSwapStrategy {
         require!(amount_b > 0, AmmError::InsufficientLiquidity);
         
         // For stablecoins, LP supply is closer to the sum rather than geometric mean
-        Ok(amount_a.checked_add(amount_b).ok_or(AmmError::Overflow)?)
+        Ok(amount_a.checked_add(amount_b).ok_or(AmmError::MathOverflow)?)
     }
 
-    /// Standard proportional LP calculation
+    /// Standard proportional LP calculation, rounded down
     fn calculate_lp_tokens_to_mint(
         amount_a: u64,
         reserve_a: u64,
@@ -76,16 +109,10 @@ impl AmmStrategy for StableSwapStrategy {
         require!(reserve_a > 0, AmmError::InsufficientLiquidity);
         require!(lp_supply > 0, AmmError::InsufficientLiquidity);
         
-        let lp_tokens = (amount_a as u128)
-            .checked_mul(lp_supply as u128)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(reserve_a as u128)
-            .ok_or(AmmError::Overflow)?;
-        
-        Ok(lp_tokens as u64)
+        mul_div_u64(amount_a, lp_supply, reserve_a, Rounding::Down)
     }
 
-    /// Standard proportional withdraw
+    /// Standard proportional withdraw, rounded down
     fn calculate_withdraw_amounts(
         lp_amount: u64,
         reserve_a: u64,
@@ -96,17 +123,8 @@ impl AmmStrategy for StableSwapStrategy {
         require!(lp_supply > 0, AmmError::InsufficientLiquidity);
         require!(lp_amount <= lp_supply, AmmError::InsufficientLiquidity);
         
-        let amount_a = (lp_amount as u128)
-            .checked_mul(reserve_a as u128)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(lp_supply as u128)
-            .ok_or(AmmError::Overflow)? as u64;
-        
-        let amount_b = (lp_amount as u128)
-            .checked_mul(reserve_b as u128)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(lp_supply as u128)
-            .ok_or(AmmError::Overflow)? as u64;
+        let amount_a = mul_div_u64(lp_amount, reserve_a, lp_supply, Rounding::Down)?;
+        let amount_b = mul_div_u64(lp_amount, reserve_b, lp_supply, Rounding::Down)?;
         
         Ok((amount_a, amount_b))
     }
@@ -144,4 +162,42 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), 200_000_000); // Sum, not geometric mean
     }
+
+    #[test]
+    fn test_dust_pool_rejects_swap() {
+        // 2 units in against 12 units of reserves would pay out less than nothing
+        assert_eq!(
+            StableSwapStrategy::calculate_amount_out(2, 1, 11, 0).unwrap_err(),
+            AmmError::InsufficientLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn test_rates_scale_balances() {
+        // 1 token in is worth 1.1 tokens out, and the reserves are balanced in value
+        let amount_out = StableSwapStrategy::calculate_amount_out_with_rates(
+            1_000_000,
+            100_000_000,
+            110_000_000,
+            0,
+            RATE_SCALE * 11 / 10,
+            RATE_SCALE,
+        )
+        .unwrap();
+        assert!(amount_out > 1_090_000 && amount_out < 1_100_000, "{amount_out}");
+
+        // Rates of 1.0 leave the curve unchanged
+        assert_eq!(
+            StableSwapStrategy::calculate_amount_out_with_rates(
+                1_000_000,
+                100_000_000,
+                100_000_000,
+                30,
+                RATE_SCALE,
+                RATE_SCALE,
+            )
+            .unwrap(),
+            StableSwapStrategy::calculate_amount_out(1_000_000, 100_000_000, 100_000_000, 30).unwrap()
+        );
+    }
 }
@@ -1,90 +1,370 @@
 // programs/amm/src/instructions/swap.rs
 use crate::*;
-use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
     #[account(mut)]
     pub pool: Account<'info, Pool>,
 
-    #[account(mut)]
+    /// Vault of the input token, which sets the swap direction
+    #[account(
+        mut,
+        constraint = vault_in.key() == pool.vault_a || vault_in.key() == pool.vault_b @ AmmError::InvalidVault,
+    )]
     pub vault_in: Account<'info, TokenAccount>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        address = if vault_in.key() == pool.vault_a { pool.vault_b } else { pool.vault_a } @ AmmError::InvalidVault,
+    )]
     pub vault_out: Account<'info, TokenAccount>,
 
+    /// Left out to pay native SOL from `user`
     #[account(mut)]
-    pub user_in: Account<'info, TokenAccount>,
+    pub user_in: Option<Account<'info, TokenAccount>>,
 
+    /// Left out to receive native SOL to `user`
     #[account(mut)]
-    pub user_out: Account<'info, TokenAccount>,
+    pub user_out: Option<Account<'info, TokenAccount>>,
 
+    #[account(mut)]
     pub user: Signer<'info>,
 
+    /// CHECK: Pyth-format price account, parsed by `PythPrice::load`; only used by oracle-pegged pools
+    #[account(address = pool.oracle_peg.oracle @ AmmError::InvalidOracle)]
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Rate provider of token A, parsed by `RateProvider::refresh`; only used by stable pools with rate providers
+    #[account(
+        address = pool.rate_a.params.account @ AmmError::InvalidRateProvider,
+        owner = pool.rate_a.params.source.program_id() @ AmmError::InvalidRateProvider,
+    )]
+    pub rate_provider_a: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Rate provider of token B, parsed by `RateProvider::refresh`; only used by stable pools with rate providers
+    #[account(
+        address = pool.rate_b.params.account @ AmmError::InvalidRateProvider,
+        owner = pool.rate_b.params.source.program_id() @ AmmError::InvalidRateProvider,
+    )]
+    pub rate_provider_b: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Program registered with `initialize`, called by `invoke_hook`; only used by hooked pools
+    #[account(address = pool.hook.program @ AmmError::InvalidHook)]
+    pub hook_program: Option<UncheckedAccount<'info>>,
+
+    /// Allowlist of the pool; only used by permissioned pools
+    #[account(seeds = [b"allowlist", pool.key().as_ref()], bump = allowlist.bump)]
+    pub allowlist: Option<Account<'info, Allowlist>>,
+
+    /// Entry of `user` in a `Members` allowlist
+    #[account(
+        seeds = [b"allowlist_entry", pool.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump,
+    )]
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
+
     pub token_program: Program<'info, Token>,
+
+    /// Temporary WSOL account of the pool, only passed when a user side is native SOL
+    #[account(
+        init,
+        payer = user,
+        seeds = [WSOL_SEED, pool.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = pool,
+    )]
+    pub wsol_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(address = NATIVE_MINT @ AmmError::InvalidNativeSol)]
+    pub native_mint: Option<Account<'info, Mint>>,
+
+    pub system_program: Option<Program<'info, System>>,
+
+    /// Fee tier of the pool, holding the referral caps; only needed with a referrer
+    #[account(address = pool.amm_config @ AmmError::InvalidReferral)]
+    pub amm_config: Option<Box<Account<'info, AmmConfig>>>,
+
+    /// Token account of the input token paid the referral share of the fee
+    #[account(mut, constraint = referrer.mint == vault_in.mint @ AmmError::InvalidReferral)]
+    pub referrer: Option<Account<'info, TokenAccount>>,
+
+    /// Range orders of the pool; required while it has open orders
+    #[account(mut, seeds = [b"order_book", pool.key().as_ref()], bump = order_book.bump)]
+    pub order_book: Option<Box<Account<'info, OrderBook>>>,
 }
 
-pub fn handler(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
+/// Every range order the price move crosses is filled from the pool's `OrderBook`
+/// at its tick price, paying the pool fee like the curve, and the rest of the
+/// input goes through the curve.
+///
+/// Remaining accounts are passed on to the hook of hooked pools.
+pub(crate) fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+    proof: Vec<[u8; 32]>,
+    referral_fee_bps: u64,
+) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
+    require!(!pool.paused, AmmError::PoolPaused);
+
+    // Permissioned pools only serve the wallets on their allowlist
+    pool.check_allowed(
+        ctx.accounts.allowlist.as_deref(),
+        ctx.accounts.allowlist_entry.as_deref(),
+        &ctx.accounts.user.key(),
+        &proof,
+    )?;
+
+    // The temporary WSOL account is created by this instruction and must be closed by it
+    require!(
+        ctx.accounts.wsol_account.is_some()
+            == (ctx.accounts.user_in.is_none() || ctx.accounts.user_out.is_none()),
+        AmmError::InvalidNativeSol
+    );
+    let clock = Clock::get()?;
+    let current_slot = clock.slot;
 
-    let (reserve_in, reserve_out) = if ctx.accounts.vault_in.mint == pool.token_a {
+    // Spot price the circuit breaker measures this swap's move from
+    let price_before = pool.breaker_price(current_slot)?;
+
+    // Hybrid pools average the price gamma adapts to
+    pool.record_hybrid_price(clock.unix_timestamp)?;
+
+    let a_to_b = ctx.accounts.vault_in.key() == pool.vault_a;
+    let (mut reserve_in, mut reserve_out) = if a_to_b {
         (pool.reserve_a, pool.reserve_b)
     } else {
         (pool.reserve_b, pool.reserve_a)
     };
 
-    // Use strategy to calculate swap output
-    let amount_out = ConstantProductStrategy::calculate_amount_out(
-        amount_in,
-        reserve_in,
-        reserve_out,
-        pool.fee,
-    )?;
+    // Static pool fee, or base fee plus volatility component in dynamic mode
+    let mut fee = pool.swap_fee(current_slot)?;
+
+    // The hook can veto the swap or set the fee within the pool's bounds
+    let hook_accounts = ctx.remaining_accounts;
+    let (amount_a, amount_b) = if a_to_b { (amount_in, 0) } else { (0, amount_in) };
+    let trade = HookTrade { amount_a, amount_b, lp_amount: 0, fee, a_to_b };
+    if let Some(hook_fee) = invoke_hook(
+        pool,
+        ctx.accounts.hook_program.as_ref(),
+        &ctx.accounts.user,
+        hook_accounts,
+        HookPoint::BeforeSwap,
+        trade,
+    )? {
+        fee = hook_fee;
+    }
+
+    // Oracle-pegged pools quote around the feed price and halt while it is unreliable
+    let oracle_price = if pool.curve == CurveType::OraclePeg {
+        let oracle = ctx.accounts.oracle.as_ref().ok_or(AmmError::InvalidOracle)?;
+        Some(pool.oracle_price(&oracle.try_borrow_data()?, current_slot, a_to_b)?)
+    } else {
+        None
+    };
+
+    // Stable pools of yield-bearing tokens trade at their exchange rates, cached between reads
+    let data_a = ctx.accounts.rate_provider_a.as_ref().map(|account| account.try_borrow_data()).transpose()?;
+    let data_b = ctx.accounts.rate_provider_b.as_ref().map(|account| account.try_borrow_data()).transpose()?;
+    let rates = pool.refresh_rates(data_a.as_deref().map(|data| &**data), data_b.as_deref().map(|data| &**data), &clock)?;
+
+    // The book is what keeps swaps from moving the price past orders without filling them
+    require!(
+        pool.open_orders == 0 || ctx.accounts.order_book.is_some(),
+        AmmError::InvalidLimitOrder
+    );
+
+    let mut remaining_in = amount_in;
+    let mut amount_out: u64 = 0;
+    let mut lp_fee: u64 = 0; // Fees charged on the input, through the curve or into orders
+
+    if let Some(order_book) = ctx.accounts.order_book.as_deref_mut() {
+        while remaining_in > 0 {
+            let Some(index) = order_book.next_fillable(a_to_b) else {
+                break;
+            };
+            let order = &mut order_book.orders[index];
+            let tick_price = ConcentratedLiquidityStrategy::tick_to_price(order.tick)?;
+            let price = RangeOrder::price_out_per_in(tick_price, a_to_b)?;
+
+            // Move the curve onto the order's tick
+            let to_tick = pool.curve.calculate_amount_in_to_price(reserve_in, reserve_out, price, fee)?;
+            if to_tick >= remaining_in {
+                break;
+            }
+            if to_tick > 0 {
+                let curve_out = pool.curve.calculate_amount_out(to_tick, reserve_in, reserve_out, fee)?;
+                lp_fee = lp_fee.checked_add(strategy::math::fee_amount(to_tick, fee)?).ok_or(AmmError::MathOverflow)?;
+                reserve_in = reserve_in.checked_add(to_tick).ok_or(AmmError::MathOverflow)?;
+                reserve_out = reserve_out.checked_sub(curve_out).ok_or(AmmError::MathOverflow)?;
+                remaining_in -= to_tick;
+                amount_out = amount_out.checked_add(curve_out).ok_or(AmmError::MathOverflow)?;
+            }
+
+            // Convert the order at its tick price, out of the input left after the pool fee
+            let available = strategy::math::amount_after_fee(remaining_in, fee)?;
+            let (used, filled) = RangeOrder::fill(available, order.remaining, price)?;
+            // Dust that converts to nothing goes through the curve
+            if filled == 0 {
+                break;
+            }
+            let paid = if used == available {
+                remaining_in
+            } else {
+                strategy::math::amount_before_fee(used, fee)?
+            };
+            let order_fee = paid.checked_sub(used).ok_or(AmmError::MathOverflow)?;
+            lp_fee = lp_fee.checked_add(order_fee).ok_or(AmmError::MathOverflow)?;
+            reserve_in = reserve_in.checked_add(order_fee).ok_or(AmmError::MathOverflow)?;
+            remaining_in = remaining_in.checked_sub(paid).ok_or(AmmError::MathOverflow)?;
+            amount_out = amount_out.checked_add(filled).ok_or(AmmError::MathOverflow)?;
+
+            order.remaining -= filled;
+            order.proceeds = order.proceeds.checked_add(used).ok_or(AmmError::MathOverflow)?;
+        }
+    }
+
+    // Use the pool's curve to calculate swap output for the rest of the input
+    if remaining_in > 0 {
+        let curve_out = pool.calculate_amount_out(
+            remaining_in,
+            reserve_in,
+            reserve_out,
+            fee,
+            a_to_b,
+            SwapInputs { oracle_price, now: clock.unix_timestamp, rates },
+        )?;
+        lp_fee = lp_fee.checked_add(strategy::math::fee_amount(remaining_in, fee)?).ok_or(AmmError::MathOverflow)?;
+        reserve_in = reserve_in.checked_add(remaining_in).ok_or(AmmError::MathOverflow)?;
+        reserve_out = reserve_out.checked_sub(curve_out).ok_or(AmmError::MathOverflow)?;
+        amount_out = amount_out.checked_add(curve_out).ok_or(AmmError::MathOverflow)?;
+    }
 
     require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
 
-    // Transfer tokens in
-    let cpi_accounts = token::Transfer {
-        from: ctx.accounts.user_in.to_account_info(),
-        to: ctx.accounts.vault_in.to_account_info(),
-        authority: ctx.accounts.user.to_account_info(),
+    // The referrer's share of the fee leaves the pool instead of accruing to LPs
+    let swap_fee = lp_fee;
+    let referral_fee = match &ctx.accounts.referrer {
+        Some(referrer) => {
+            let amm_config = ctx.accounts.amm_config.as_ref().ok_or(AmmError::InvalidReferral)?;
+            amm_config.referral_amount(&referrer.owner, swap_fee, referral_fee_bps)?
+        }
+        None => {
+            require!(referral_fee_bps == 0, AmmError::InvalidReferral);
+            0
+        }
     };
-    token::transfer(
-        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-        amount_in,
-    )?;
+    reserve_in = reserve_in.checked_sub(referral_fee).ok_or(AmmError::MathOverflow)?;
+    lp_fee -= referral_fee;
 
-    // Transfer tokens out
     let seeds = &[
         b"pool",
         pool.token_a.as_ref(),
         pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
         &[pool.bump],
     ];
     let signer_seeds = &[&seeds[..]];
-
-    let cpi_accounts = token::Transfer {
-        from: ctx.accounts.vault_out.to_account_info(),
-        to: ctx.accounts.user_out.to_account_info(),
-        authority: pool.to_account_info(),
+    let native_sol = NativeSol {
+        pool: pool.to_account_info(),
+        user: ctx.accounts.user.to_account_info(),
+        wsol_account: ctx.accounts.wsol_account.as_ref().map(|account| account.to_account_info()),
+        system_program: ctx.accounts.system_program.as_ref().map(|program| program.to_account_info()),
+        token_program: ctx.accounts.token_program.to_account_info(),
+        signer_seeds,
     };
-    token::transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            cpi_accounts,
-            signer_seeds,
-        ),
-        amount_out,
-    )?;
 
-    // Update reserves
-    if ctx.accounts.vault_in.mint == pool.token_a {
-        pool.reserve_a += amount_in;
-        pool.reserve_b -= amount_out;
+    // Transfer tokens in, wrapping native SOL
+    match &ctx.accounts.user_in {
+        Some(user_in) => {
+            let cpi_accounts = token::Transfer {
+                from: user_in.to_account_info(),
+                to: ctx.accounts.vault_in.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+                amount_in,
+            )?;
+        }
+        None => native_sol.wrap(&ctx.accounts.vault_in, amount_in)?,
+    }
+
+    // Pay the referral out of the input
+    if let Some(referrer) = ctx.accounts.referrer.as_ref().filter(|_| referral_fee > 0) {
+        let cpi_accounts = token::Transfer {
+            from: ctx.accounts.vault_in.to_account_info(),
+            to: referrer.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            referral_fee,
+        )?;
+    }
+
+    // Transfer tokens out, unwrapping native SOL
+    match &ctx.accounts.user_out {
+        Some(user_out) => {
+            let cpi_accounts = token::Transfer {
+                from: ctx.accounts.vault_out.to_account_info(),
+                to: user_out.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds,
+                ),
+                amount_out,
+            )?;
+        }
+        None => native_sol.unwrap(&ctx.accounts.vault_out, amount_out)?,
+    }
+
+    // Update reserves (order fills are escrowed outside of them)
+    if a_to_b {
+        pool.reserve_a = reserve_in;
+        pool.reserve_b = reserve_out;
     } else {
-        pool.reserve_b += amount_in;
-        pool.reserve_a -= amount_out;
+        pool.reserve_b = reserve_in;
+        pool.reserve_a = reserve_out;
     }
 
+    pool.check_price_move(price_before)?;
+    pool.record_price_move(current_slot)?;
+    let lp_supply = pool.lp_supply;
+    pool.stats
+        .record_swap(amount_in, amount_out, lp_fee, a_to_b, lp_supply, clock.unix_timestamp)?;
+
+    emit!(SwapEvent {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        a_to_b,
+        amount_in,
+        amount_out,
+        fee: swap_fee,
+        referrer: ctx.accounts.referrer.as_ref().map(|referrer| referrer.key()),
+        referral_fee,
+    });
+
+    let (amount_a, amount_b) = if a_to_b { (amount_in, amount_out) } else { (amount_out, amount_in) };
+    invoke_hook(
+        pool,
+        ctx.accounts.hook_program.as_ref(),
+        &ctx.accounts.user,
+        hook_accounts,
+        HookPoint::AfterSwap,
+        HookTrade { amount_a, amount_b, fee, ..trade },
+    )?;
+
     Ok(())
 }
@@ -0,0 +1,114 @@
+// programs/amm/src/instructions/swap_launch.rs
+use crate::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct SwapLaunch<'info> {
+    #[account(mut, has_one = token_vault, has_one = quote_vault)]
+    pub launch: Account<'info, Launch>,
+
+    #[account(mut)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = launch.token_mint)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = launch.quote_mint)]
+    pub user_quote: Account<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Buy launched tokens with quote (`buy`) or sell them back for quote
+pub(crate) fn handler(ctx: Context<SwapLaunch>, amount_in: u64, min_amount_out: u64, buy: bool) -> Result<()> {
+    let launch = &mut ctx.accounts.launch;
+    require!(!launch.graduated, AmmError::LaunchGraduated);
+
+    let amount_out = if buy {
+        BondingCurveStrategy::calculate_amount_out_with_virtual(
+            amount_in,
+            launch.real_quote_reserve,
+            launch.real_token_reserve,
+            launch.virtual_quote_reserve,
+            launch.virtual_token_reserve,
+            launch.fee,
+        )?
+    } else {
+        BondingCurveStrategy::calculate_amount_out_with_virtual(
+            amount_in,
+            launch.real_token_reserve,
+            launch.real_quote_reserve,
+            launch.virtual_token_reserve,
+            launch.virtual_quote_reserve,
+            launch.fee,
+        )?
+    };
+    require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+
+    let (user_in, vault_in, vault_out, user_out) = if buy {
+        (
+            &ctx.accounts.user_quote,
+            &ctx.accounts.quote_vault,
+            &ctx.accounts.token_vault,
+            &ctx.accounts.user_token,
+        )
+    } else {
+        (
+            &ctx.accounts.user_token,
+            &ctx.accounts.token_vault,
+            &ctx.accounts.quote_vault,
+            &ctx.accounts.user_quote,
+        )
+    };
+
+    // Transfer tokens in
+    let cpi_accounts = token::Transfer {
+        from: user_in.to_account_info(),
+        to: vault_in.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+        amount_in,
+    )?;
+
+    // Transfer tokens out
+    let seeds = &[b"launch", launch.token_mint.as_ref(), &[launch.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = token::Transfer {
+        from: vault_out.to_account_info(),
+        to: user_out.to_account_info(),
+        authority: launch.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        amount_out,
+    )?;
+
+    // Fees stay on the curve, as in the pools
+    if buy {
+        launch.real_quote_reserve = launch.real_quote_reserve.checked_add(amount_in).ok_or(AmmError::MathOverflow)?;
+        launch.real_token_reserve = launch.real_token_reserve.checked_sub(amount_out).ok_or(AmmError::MathOverflow)?;
+
+        // Keep enough tokens on the curve to pair with the raised quote at graduation
+        require!(
+            launch.real_token_reserve >= launch.graduation_token_amount()?,
+            AmmError::LaunchSoldOut
+        );
+    } else {
+        launch.real_token_reserve = launch.real_token_reserve.checked_add(amount_in).ok_or(AmmError::MathOverflow)?;
+        launch.real_quote_reserve = launch.real_quote_reserve.checked_sub(amount_out).ok_or(AmmError::MathOverflow)?;
+    }
+
+    Ok(())
+}
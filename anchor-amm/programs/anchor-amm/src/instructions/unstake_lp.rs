@@ -0,0 +1,69 @@
+// programs/amm/src/instructions/unstake_lp.rs
+use crate::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct UnstakeLp<'info> {
+    #[account(mut, has_one = staked_vault)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        has_one = farm,
+        has_one = owner,
+        seeds = [b"farm_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, FarmPosition>,
+
+    #[account(mut)]
+    pub staked_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = farm.lp_mint @ AmmError::InvalidLpMint)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, token::mint = farm.lp_mint)]
+    pub user_lp: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    /// Token program of the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub(crate) fn handler(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+    let position = &mut ctx.accounts.position;
+    require!(amount > 0 && amount <= position.amount, AmmError::InsufficientStake);
+
+    farm.update(Clock::get()?.unix_timestamp)?;
+    let new_amount = position.amount - amount;
+    position.settle(farm, new_amount)?;
+    farm.total_staked = farm.total_staked.checked_sub(amount).ok_or(AmmError::MathOverflow)?;
+
+    let seeds = &[
+        b"farm",
+        farm.pool.as_ref(),
+        farm.reward_mint.as_ref(),
+        &[farm.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = token_interface::TransferChecked {
+        from: ctx.accounts.staked_vault.to_account_info(),
+        mint: ctx.accounts.lp_mint.to_account_info(),
+        to: ctx.accounts.user_lp.to_account_info(),
+        authority: farm.to_account_info(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.lp_mint.decimals,
+    )?;
+
+    Ok(())
+}
@@ -1,106 +1,220 @@
 // programs/amm/src/instructions/withdraw.rs
 use crate::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{self, TokenInterface};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(mut)]
     pub pool: Account<'info, Pool>,
 
-    #[account(mut)]
+    #[account(mut, address = pool.vault_a @ AmmError::InvalidVault)]
     pub vault_a: Account<'info, TokenAccount>,
 
-    #[account(mut)]
+    #[account(mut, address = pool.vault_b @ AmmError::InvalidVault)]
     pub vault_b: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub lp_mint: Account<'info, Mint>,
+    /// SPL Token LP mint of older pools, Token-2022 otherwise
+    #[account(mut, address = pool.lp_mint @ AmmError::InvalidLpMint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, token_interface::Mint>,
 
-    #[account(mut)]
-    pub user_lp: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = lp_mint, token::token_program = lp_token_program)]
+    pub user_lp: InterfaceAccount<'info, token_interface::TokenAccount>,
 
+    /// Left out to receive native SOL to `user`
     #[account(mut)]
-    pub user_a: Account<'info, TokenAccount>,
+    pub user_a: Option<Account<'info, TokenAccount>>,
 
+    /// Left out to receive native SOL to `user`
     #[account(mut)]
-    pub user_b: Account<'info, TokenAccount>,
+    pub user_b: Option<Account<'info, TokenAccount>>,
 
+    #[account(mut)]
     pub user: Signer<'info>,
 
+    /// CHECK: Program registered with `initialize`, called by `invoke_hook`; left out to skip the hook
+    #[account(address = pool.hook.program @ AmmError::InvalidHook)]
+    pub hook_program: Option<UncheckedAccount<'info>>,
+
+    /// Allowlist of the pool; only used by permissioned pools
+    #[account(seeds = [b"allowlist", pool.key().as_ref()], bump = allowlist.bump)]
+    pub allowlist: Option<Account<'info, Allowlist>>,
+
+    /// Entry of `user` in a `Members` allowlist
+    #[account(
+        seeds = [b"allowlist_entry", pool.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump,
+    )]
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
+
+    /// Prediction market of an `Lmsr` pool
+    pub market: Option<Account<'info, PredictionMarket>>,
+
     pub token_program: Program<'info, Token>,
+    pub lp_token_program: Interface<'info, TokenInterface>,
+
+    /// Temporary WSOL account of the pool, only passed when a user side is native SOL
+    #[account(
+        init,
+        payer = user,
+        seeds = [WSOL_SEED, pool.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = pool,
+    )]
+    pub wsol_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(address = NATIVE_MINT @ AmmError::InvalidNativeSol)]
+    pub native_mint: Option<Account<'info, Mint>>,
+
+    pub system_program: Option<Program<'info, System>>,
 }
 
-pub fn handler(ctx: Context<Withdraw>, lp_amount: u64) -> Result<()> {
+/// Hooks are only called when `hook_program` is passed, and their response is
+/// ignored, so they can't lock liquidity in the pool.
+///
+/// Remaining accounts are passed on to the hook.
+pub(crate) fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
+    lp_amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
-    
+
+    // The creator withdraws what is left of an LBP sale once it has ended
+    if pool.curve == CurveType::Lbp {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= pool.lbp.end_time, AmmError::SaleNotEnded);
+    }
+
+    // Permissioned pools only serve the wallets on their allowlist
+    pool.check_allowed(
+        ctx.accounts.allowlist.as_deref(),
+        ctx.accounts.allowlist_entry.as_deref(),
+        &ctx.accounts.user.key(),
+        &proof,
+    )?;
+
+    // The temporary WSOL account is created by this instruction and must be closed by it
+    require!(
+        ctx.accounts.wsol_account.is_some()
+            == (ctx.accounts.user_a.is_none() || ctx.accounts.user_b.is_none()),
+        AmmError::InvalidNativeSol
+    );
+
     // Use strategy to calculate withdraw amounts
-    let (out_a, out_b) = ConstantProductStrategy::calculate_withdraw_amounts(
+    let (out_a, out_b) = pool.curve.calculate_withdraw_amounts(
         lp_amount,
         pool.reserve_a,
         pool.reserve_b,
         pool.lp_supply,
     )?;
 
+    // An open market keeps the subsidy that bounds the market maker's loss; LPs leave once it resolves
+    if pool.curve == CurveType::Lmsr {
+        let market = ctx.accounts.market.as_ref().ok_or(AmmError::InvalidMarket)?;
+        market.check_outcome_mints(&pool.token_a, &pool.token_b)?;
+        if market.outcome == MarketOutcome::Unresolved {
+            let subsidy = LmsrStrategy::worst_case_subsidy(pool.lmsr_b)?;
+            let remaining_a = pool.reserve_a.checked_sub(out_a).ok_or(AmmError::MathOverflow)?;
+            let remaining_b = pool.reserve_b.checked_sub(out_b).ok_or(AmmError::MathOverflow)?;
+            require!(remaining_a.min(remaining_b) >= subsidy, AmmError::InsufficientLiquidity);
+        }
+    }
+
+    // The LP opts in to the hook by passing it, so it can never keep them from withdrawing
+    let trade = HookTrade { amount_a: out_a, amount_b: out_b, lp_amount, ..HookTrade::default() };
+    if ctx.accounts.hook_program.is_some() {
+        invoke_hook(
+            pool,
+            ctx.accounts.hook_program.as_ref(),
+            &ctx.accounts.user,
+            ctx.remaining_accounts,
+            HookPoint::BeforeWithdraw,
+            trade,
+        )?;
+    }
+
     // Burn LP tokens
     let seeds = &[
         b"pool",
         pool.token_a.as_ref(),
         pool.token_b.as_ref(),
+        &pool.tier_seed()[..],
         &[pool.bump],
     ];
     let signer_seeds = &[&seeds[..]];
 
-    let cpi_accounts = token::Burn {
+    let cpi_accounts = token_interface::Burn {
         mint: ctx.accounts.lp_mint.to_account_info(),
         from: ctx.accounts.user_lp.to_account_info(),
         authority: ctx.accounts.user.to_account_info(),
     };
-    token::burn(
-        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+    token_interface::burn(
+        CpiContext::new(ctx.accounts.lp_token_program.to_account_info(), cpi_accounts),
         lp_amount,
     )?;
 
-    // Transfer tokens out
-    let cpi_accounts = token::Transfer {
-        from: ctx.accounts.vault_a.to_account_info(),
-        to: ctx.accounts.user_a.to_account_info(),
-        authority: pool.to_account_info(),
+    // Transfer tokens out, unwrapping native SOL
+    let native_sol = NativeSol {
+        pool: pool.to_account_info(),
+        user: ctx.accounts.user.to_account_info(),
+        wsol_account: ctx.accounts.wsol_account.as_ref().map(|account| account.to_account_info()),
+        system_program: ctx.accounts.system_program.as_ref().map(|program| program.to_account_info()),
+        token_program: ctx.accounts.token_program.to_account_info(),
+        signer_seeds,
     };
-    token::transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            cpi_accounts,
-            signer_seeds,
-        ),
-        out_a,
-    )?;
-
-    let cpi_accounts = token::Transfer {
-        from: ctx.accounts.vault_b.to_account_info(),
-        to: ctx.accounts.user_b.to_account_info(),
-        authority: pool.to_account_info(),
-    };
-    token::transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            cpi_accounts,
-            signer_seeds,
-        ),
-        out_b,
-    )?;
-
+    for (user_account, vault, amount) in [
+        (&ctx.accounts.user_a, &ctx.accounts.vault_a, out_a),
+        (&ctx.accounts.user_b, &ctx.accounts.vault_b, out_b),
+    ] {
+        match user_account {
+            Some(user_account) => {
+                let cpi_accounts = token::Transfer {
+                    from: vault.to_account_info(),
+                    to: user_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                };
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        cpi_accounts,
+                        signer_seeds,
+                    ),
+                    amount,
+                )?;
+            }
+            None => native_sol.unwrap(vault, amount)?,
+        }
+    }
+
+    if pool.curve == CurveType::OraclePeg {
+        let lp_supply = pool.lp_supply;
+        pool.peg_target.withdraw(lp_amount, lp_supply)?;
+    }
     pool.reserve_a = pool
         .reserve_a
         .checked_sub(out_a)
-        .ok_or(AmmError::Overflow)?;
+        .ok_or(AmmError::MathOverflow)?;
     pool.reserve_b = pool
         .reserve_b
         .checked_sub(out_b)
-        .ok_or(AmmError::Overflow)?;
+        .ok_or(AmmError::MathOverflow)?;
     pool.lp_supply = pool
         .lp_supply
         .checked_sub(lp_amount)
-        .ok_or(AmmError::Overflow)?;
+        .ok_or(AmmError::MathOverflow)?;
+
+    if ctx.accounts.hook_program.is_some() {
+        invoke_hook(
+            pool,
+            ctx.accounts.hook_program.as_ref(),
+            &ctx.accounts.user,
+            ctx.remaining_accounts,
+            HookPoint::AfterWithdraw,
+            trade,
+        )?;
+    }
 
     Ok(())
 }
@@ -1,13 +1,26 @@
 // programs/amm/src/lib.rs
+// The IDL instructions `#[program]` generates beside the program module, out of
+// reach of its attributes, still call the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
 use anchor_lang::prelude::*;
 
 pub mod errors;
+pub mod events;
+pub mod hooks;
 pub mod instructions;
+pub mod lp_token;
+pub mod registry;
 pub mod state;
+pub mod wsol;
 
 use errors::*;
+use events::*;
+use hooks::*;
 use instructions::*;
+use lp_token::*;
+use registry::*;
 use state::*;
+use wsol::*;
 
 declare_id!("7Jfk3eEeHhc93ndPv3g5GUMkZ353142Z1EfeJ8SxaVKm");
 
@@ -15,26 +28,338 @@ declare_id!("7Jfk3eEeHhc93ndPv3g5GUMkZ353142Z1EfeJ8SxaVKm");
 pub mod amm {
     use super::*;
 
-    /// Initialize a new liquidity pool with two tokens.
+    /// Initialize a new liquidity pool with two tokens in the fee tier `amm_config`.
+    /// The token A mint must sort before token B, so each pair has a single pool per tier.
+    ///
+    /// Passing `dynamic_fee` enables the volatility-driven fee mode, with the tier fee as the base fee.
+    /// `oracle_peg` must be set exactly when `curve` is `OraclePeg`, `lmsr_b` exactly when it is `Lmsr`,
+    /// and `lbp` exactly when it is `Lbp`. `hook` registers a program called before and after every
+    /// swap and deposit, which can veto the trade or set the swap fee within its bounds.
+    /// `StableSwap` pools can read the exchange rate of yield-bearing tokens from `rate_a` and `rate_b`.
+    /// `HybridCfmm` pools adapt their gamma around `hybrid_target_price` (token B per token A,
+    /// scaled by 1M, default 1.0); it is rejected for other curves.
+    /// The LP mint is a Token-2022 mint named `lp_name` (`lp_symbol`), with the mean of the token decimals.
+    /// Passing `allowlist_params` (with the `allowlist` account) makes the pool permissioned from the start.
+    /// Tokens of launches that have not graduated are rejected.
+    /// `Lmsr` pools take the open `market` whose YES and NO mints are the two tokens.
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         ctx: Context<Initialize>,
-        fee: u64, // Basis points (e.g. 30 = 0.3 %)
+        dynamic_fee: Option<DynamicFeeParams>,
+        curve: CurveType,
+        oracle_peg: Option<OraclePegParams>,
+        lmsr_b: Option<u64>,
+        lbp: Option<LbpParams>,
+        hook: Option<HookParams>,
+        rate_a: Option<RateProviderParams>,
+        rate_b: Option<RateProviderParams>,
+        hybrid_target_price: Option<u64>,
+        lp_name: String,
+        lp_symbol: String,
+        allowlist_params: Option<AllowlistParams>,
     ) -> Result<()> {
-        instructions::initialize::handler(ctx, fee)
+        instructions::initialize::handler(
+            ctx,
+            dynamic_fee,
+            curve,
+            oracle_peg,
+            lmsr_b,
+            lbp,
+            hook,
+            rate_a,
+            rate_b,
+            hybrid_target_price,
+            lp_name,
+            lp_symbol,
+            allowlist_params,
+        )
     }
 
     /// Deposit two tokens at the current ratio and receive LP tokens.
-    pub fn deposit(ctx: Context<Deposit>, max_a: u64, max_b: u64) -> Result<()> {
-        instructions::deposit::handler(ctx, max_a, max_b)
+    ///
+    /// Only the creator can provide liquidity to an LBP pool.
+    /// On hooked pools, the remaining accounts are passed on to the hook.
+    /// On permissioned pools, `proof` shows the user is on a merkle allowlist (empty otherwise).
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+        max_a: u64,
+        max_b: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::deposit::handler(ctx, max_a, max_b, proof)
     }
 
     /// Burn LP tokens and withdraw your share of the two reserves.
-    pub fn withdraw(ctx: Context<Withdraw>, lp_amount: u64) -> Result<()> {
-        instructions::withdraw::handler(ctx, lp_amount)
+    ///
+    /// LBP liquidity is locked until the end of the weight schedule.
+    /// LMSR pools pass their `market` and keep `b * ln(2)` of each outcome until it resolves.
+    /// Hooked pools call their hook only when `hook_program` is passed, with the remaining
+    /// accounts, and ignore its response, so a hook can't lock liquidity in the pool.
+    /// On permissioned pools, `proof` shows the user is on a merkle allowlist (empty otherwise).
+    pub fn withdraw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
+        lp_amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::withdraw::handler(ctx, lp_amount, proof)
+    }
+
+    /// Swap token A for token B (or vice-versa) along the pool's curve.
+    ///
+    /// Oracle-pegged pools need their price account in `oracle` and halt while it is stale.
+    /// Stable pools with rate providers refresh their cached rates from `rate_provider_a/b`
+    /// and halt once a cached rate is stale.
+    /// Pools with open range orders need their `order_book`; every order the price crosses is
+    /// filled at its tick price, paying the pool fee. Hooked pools pass their remaining accounts on to the hook.
+    /// On permissioned pools, `proof` shows the user is on a merkle allowlist (empty otherwise).
+    /// Integrators listed in the pool's `amm_config` can pass a `referrer` token account of the
+    /// input token with it to take `referral_fee_bps` of the swap fee, up to the tier's cap for them.
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        proof: Vec<[u8; 32]>,
+        referral_fee_bps: u64,
+    ) -> Result<()> {
+        instructions::swap::handler(ctx, amount_in, min_amount_out, proof, referral_fee_bps)
+    }
+
+    /// Close an empty pool, its vaults and its LP mint, returning the rent to the pool creator.
+    ///
+    /// The creator can call this, and so can the program's upgrade authority to clean up
+    /// abandoned pools. LP mints of older pools have no close authority and stay open.
+    /// The pool's order book and allowlist are closed too, its registry entry is
+    /// tombstoned and it is taken off the `MintPools` lists of its tokens.
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool::handler(ctx)
+    }
+
+    /// Place a single-tick range order that fills when a swap moves the price across `tick`.
+    ///
+    /// `tick` must be a multiple of the pool's tick spacing. Constant product and concentrated
+    /// liquidity pools take orders.
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        tick: i32,
+        amount: u64,
+        side: OrderSide,
+    ) -> Result<()> {
+        instructions::place_limit_order::handler(ctx, tick, amount, side)
+    }
+
+    /// Withdraw the proceeds of a fully filled range order and close it.
+    pub fn claim_filled_order(ctx: Context<ClaimFilledOrder>) -> Result<()> {
+        instructions::claim_filled_order::handler(ctx)
+    }
+
+    /// Cancel a range order, returning the unfilled amount and any partial proceeds.
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        instructions::cancel_limit_order::handler(ctx)
+    }
+
+    /// Create a reward farm for a pool's LP tokens, funded with `reward_rate * (end_time - start_time)`.
+    ///
+    /// Only the pool creator or the upgrade authority (with `program_data`) can create farms,
+    /// and `start_time` can't lie in the past.
+    pub fn create_farm(
+        ctx: Context<CreateFarm>,
+        reward_rate: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        instructions::create_farm::handler(ctx, reward_rate, start_time, end_time)
+    }
+
+    /// Stake LP tokens in a farm.
+    pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        instructions::stake_lp::handler(ctx, amount)
+    }
+
+    /// Unstake LP tokens from a farm, settling rewards accrued so far.
+    pub fn unstake_lp(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+        instructions::unstake_lp::handler(ctx, amount)
+    }
+
+    /// Claim accrued farm rewards.
+    pub fn harvest(ctx: Context<Harvest>) -> Result<()> {
+        instructions::harvest::handler(ctx)
+    }
+
+    /// Return the farm rewards never emitted, because nothing was staked, to the farm authority.
+    ///
+    /// Only callable after `end_time`; staking stops then.
+    pub fn reclaim_farm_rewards(ctx: Context<ReclaimFarmRewards>) -> Result<()> {
+        instructions::reclaim_farm_rewards::handler(ctx)
+    }
+
+    /// Create a binary prediction market with YES/NO outcome mints backed 1:1 by `collateral_mint`.
+    ///
+    /// The outcome tokens are traded in a pool initialized with the `Lmsr` curve and the market.
+    pub fn create_market(ctx: Context<CreateMarket>, market_id: u64, resolver: Pubkey) -> Result<()> {
+        instructions::create_market::handler(ctx, market_id, resolver)
+    }
+
+    /// Deposit collateral for an equal amount of YES and NO tokens.
+    pub fn mint_outcome_tokens(ctx: Context<MintOutcomeTokens>, amount: u64) -> Result<()> {
+        instructions::mint_outcome_tokens::handler(ctx, amount)
+    }
+
+    /// Redeem outcome tokens for collateral: complete sets before resolution, winning tokens after.
+    pub fn redeem_outcome_tokens(ctx: Context<RedeemOutcomeTokens>, amount: u64) -> Result<()> {
+        instructions::redeem_outcome_tokens::handler(ctx, amount)
+    }
+
+    /// Resolve a market; only its resolver can call this, once.
+    pub fn resolve_market(ctx: Context<ResolveMarket>, outcome: MarketOutcome) -> Result<()> {
+        instructions::resolve_market::handler(ctx, outcome)
+    }
+
+    /// Launch a new token on a bonding curve priced against `quote_mint` through virtual reserves.
+    ///
+    /// The whole `total_supply` is minted to the curve and the mint authority is revoked.
+    /// `graduation_market_cap` must be reachable before buys run into the graduation liquidity.
+    pub fn create_launch(
+        ctx: Context<CreateLaunch>,
+        decimals: u8,
+        total_supply: u64,
+        virtual_token_reserve: u64,
+        virtual_quote_reserve: u64,
+        fee: u64, // Basis points
+        graduation_market_cap: u64,
+    ) -> Result<()> {
+        instructions::create_launch::handler(
+            ctx,
+            decimals,
+            total_supply,
+            virtual_token_reserve,
+            virtual_quote_reserve,
+            fee,
+            graduation_market_cap,
+        )
+    }
+
+    /// Buy (`buy = true`) or sell launched tokens on the bonding curve.
+    ///
+    /// Buys must leave enough tokens to pair with the raised quote at graduation.
+    pub fn swap_launch(
+        ctx: Context<SwapLaunch>,
+        amount_in: u64,
+        min_amount_out: u64,
+        buy: bool,
+    ) -> Result<()> {
+        instructions::swap_launch::handler(ctx, amount_in, min_amount_out, buy)
+    }
+
+    /// Migrate a launch past its market cap threshold into a constant product pool with locked LP.
+    ///
+    /// The pool is created in the fee tier chosen at `create_launch`, with its mints sorted like `initialize`'s.
+    pub fn graduate(ctx: Context<Graduate>) -> Result<()> {
+        instructions::graduate::handler(ctx)
+    }
+
+    /// Pause or resume swaps on an LBP pool; only its creator can call this.
+    pub fn set_pool_paused(ctx: Context<SetPoolPaused>, paused: bool) -> Result<()> {
+        instructions::set_pool_paused::handler(ctx, paused)
+    }
+
+    /// Reset the target reserves of an oracle-pegged pool to its current reserves;
+    /// only its creator or the program's upgrade authority (with `program_data`) can call this.
+    pub fn repeg_pool(ctx: Context<RepegPool>) -> Result<()> {
+        instructions::repeg_pool::handler(ctx)
+    }
+
+    /// Create a fee tier; only the program's upgrade authority can call this.
+    ///
+    /// Pools are created per (token pair, tier), so a pair can have one pool per tier.
+    /// The metadata URI of their LP tokens is `lp_uri_base` followed by the pool address
+    /// (no URI when empty).
+    pub fn create_amm_config(
+        ctx: Context<CreateAmmConfig>,
+        index: u16,
+        trade_fee_bps: u64,
+        tick_spacing: u16,
+        lp_uri_base: String,
+    ) -> Result<()> {
+        instructions::create_amm_config::handler(ctx, index, trade_fee_bps, tick_spacing, lp_uri_base)
+    }
+
+    /// Change the LP metadata URI base of a fee tier for pools created afterwards;
+    /// only the program's upgrade authority can call this.
+    pub fn set_lp_uri_base(ctx: Context<SetLpUriBase>, lp_uri_base: String) -> Result<()> {
+        instructions::set_lp_uri_base::handler(ctx, lp_uri_base)
+    }
+
+    /// Bound how far swaps can move a pool's spot price per slot and per trade (0 = no bound),
+    /// or override the bounds during an incident; only the program's upgrade authority can call this.
+    pub fn set_circuit_breaker(
+        ctx: Context<SetCircuitBreaker>,
+        max_slot_move_bps: u64,
+        max_trade_move_bps: u64,
+        overridden: bool,
+    ) -> Result<()> {
+        instructions::set_circuit_breaker::handler(ctx, max_slot_move_bps, max_trade_move_bps, overridden)
+    }
+
+    /// Make an empty pool permissioned, so only wallets on its allowlist can trade or provide liquidity;
+    /// only the pool creator can call this. New pools should pass `allowlist_params` to `initialize`
+    /// instead, as any deposit makes the pool non-empty.
+    ///
+    /// `merkle_root` is only used by `MerkleRoot` lists; `Members` lists start empty.
+    pub fn create_allowlist(
+        ctx: Context<CreateAllowlist>,
+        kind: AllowlistKind,
+        merkle_root: [u8; 32],
+    ) -> Result<()> {
+        instructions::create_allowlist::handler(ctx, kind, merkle_root)
+    }
+
+    /// Replace the root of a `MerkleRoot` allowlist; only the pool creator can call this.
+    pub fn set_allowlist_root(ctx: Context<SetAllowlistRoot>, merkle_root: [u8; 32]) -> Result<()> {
+        instructions::set_allowlist_root::handler(ctx, merkle_root)
+    }
+
+    /// Add `member` to a `Members` allowlist; only the pool creator can call this.
+    pub fn add_allowlist_member(ctx: Context<AddAllowlistMember>, member: Pubkey) -> Result<()> {
+        instructions::add_allowlist_member::handler(ctx, member)
+    }
+
+    /// Remove a member from a `Members` allowlist, returning the entry rent to the pool creator.
+    pub fn remove_allowlist_member(ctx: Context<RemoveAllowlistMember>) -> Result<()> {
+        instructions::remove_allowlist_member::handler(ctx)
+    }
+
+    /// Move a hybrid pool's gamma towards its moving average price, at most once per
+    /// `GAMMA_CRANK_INTERVAL_SLOTS`.
+    ///
+    /// Anyone can call this. A crank that moves gamma pays the keeper `GAMMA_CRANK_BOUNTY_LAMPORTS`
+    /// out of lamports sent to the pool beyond its rent, while there are enough.
+    pub fn crank_gamma(ctx: Context<CrankGamma>) -> Result<()> {
+        instructions::crank_gamma::handler(ctx)
+    }
+
+    /// Set the referrers of a fee tier and their caps, each at most `max_referral_fee_bps`,
+    /// in bps of the swap fee; only the program's upgrade authority can call this.
+    pub fn set_referral_fees(
+        ctx: Context<SetReferralFees>,
+        max_referral_fee_bps: u64,
+        referrer_caps: Vec<ReferrerCap>,
+    ) -> Result<()> {
+        instructions::set_referral_fees::handler(ctx, max_referral_fee_bps, referrer_caps)
+    }
+
+    /// Grow a fee tier created by an older program version to the current `AmmConfig` layout.
+    ///
+    /// Anyone can call this; the payer covers the rent of the added space.
+    pub fn migrate_amm_config(ctx: Context<MigrateAmmConfig>) -> Result<()> {
+        instructions::migrate_amm_config::handler(ctx)
     }
 
-    /// Swap token A for token B (or vice-versa) using the constant-product curve x*y=k.
-    pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
-        instructions::swap::handler(ctx, amount_in, min_amount_out)
+    /// Grow a pool created by an older program version to the current `Pool` layout.
+    ///
+    /// Only the program's upgrade authority can call this, paying the rent of the added space.
+    /// Pools from before fee tiers also record the vaults and LP mint passed in, join the
+    /// tier `amm_config` charging their fee and get the authority as their creator.
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::migrate_pool::handler(ctx)
     }
 }
@@ -0,0 +1,147 @@
+// programs/amm/src/lp_token.rs
+//! LP token mints
+//!
+//! `initialize` and `graduate` create the LP mint under Token-2022, with a
+//! metadata pointer to itself and the token metadata stored on the mint, so
+//! wallets can name the pool's LP tokens. The URI is the fee tier's
+//! `lp_uri_base` followed by the pool address. Its decimals are derived from
+//! the two underlying tokens. Pools created before keep their SPL Token LP mint,
+//! so the LP instructions take either token program.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+use anchor_spl::token_interface::{self, TokenMetadataInitialize};
+
+use crate::errors::AmmError;
+
+/// Longest LP token name
+pub const MAX_LP_NAME_LEN: usize = 32;
+
+/// Longest LP token symbol; wallets usually show at most 10 characters
+pub const MAX_LP_SYMBOL_LEN: usize = 10;
+
+/// Longest `AmmConfig::lp_uri_base`
+pub const MAX_LP_URI_BASE_LEN: usize = 128;
+
+/// Characters of each mint address that label it in LP names
+const MINT_LABEL_LEN: usize = 4;
+
+/// Decimals of a pool's LP mint: the mean of the token decimals, rounded down
+///
+/// Initial LP supplies are close to `sqrt(amount_a * amount_b)`, whose unit is
+/// 10^-((decimals_a + decimals_b) / 2) of a whole token.
+pub fn lp_decimals(decimals_a: u8, decimals_b: u8) -> u8 {
+    ((decimals_a as u16 + decimals_b as u16) / 2) as u8
+}
+
+/// Name, symbol and URI of a pool's LP token
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LpMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl LpMetadata {
+    /// Metadata of `pool`'s LP token, with no URI when `uri_base` is empty
+    pub fn new(name: String, symbol: String, uri_base: &str, pool: &Pubkey) -> Result<Self> {
+        require!(!name.is_empty() && name.len() <= MAX_LP_NAME_LEN, AmmError::InvalidLpMetadata);
+        require!(!symbol.is_empty() && symbol.len() <= MAX_LP_SYMBOL_LEN, AmmError::InvalidLpMetadata);
+        let uri = if uri_base.is_empty() { String::new() } else { format!("{uri_base}{pool}") };
+        Ok(Self { name, symbol, uri })
+    }
+
+    /// "A-B LP" name and symbol, with each token labelled by the start of its mint address
+    pub fn pair_label(token_a: &Pubkey, token_b: &Pubkey) -> (String, String) {
+        let label = |mint: &Pubkey| mint.to_string()[..MINT_LABEL_LEN].to_string();
+        let (a, b) = (label(token_a), label(token_b));
+        (format!("{a}-{b} LP"), format!("{a}{b}LP"))
+    }
+}
+
+/// Accounts that write the metadata of a new LP mint
+pub struct InitLpMetadata<'a, 'info> {
+    pub lp_mint: AccountInfo<'info>,
+    pub pool: AccountInfo<'info>, // Mint and update authority
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub signer_seeds: &'a [&'a [&'a [u8]]], // Pool PDA seeds
+}
+
+impl<'a, 'info> InitLpMetadata<'a, 'info> {
+    /// Fund the space the metadata takes on the mint, then initialize it
+    pub fn initialize(self, metadata: LpMetadata) -> Result<()> {
+        let state = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey(self.pool.key()),
+            mint: self.lp_mint.key(),
+            name: metadata.name.clone(),
+            symbol: metadata.symbol.clone(),
+            uri: metadata.uri.clone(),
+            additional_metadata: vec![],
+        };
+        let len = self.lp_mint.data_len() + state.tlv_size_of()?;
+        let top_up = Rent::get()?.minimum_balance(len).saturating_sub(self.lp_mint.lamports());
+        if top_up > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: self.payer,
+                to: self.lp_mint.clone(),
+            };
+            system_program::transfer(CpiContext::new(self.system_program, cpi_accounts), top_up)?;
+        }
+
+        let cpi_accounts = TokenMetadataInitialize {
+            program_id: self.token_program.clone(),
+            metadata: self.lp_mint.clone(),
+            update_authority: self.pool.clone(),
+            mint_authority: self.pool,
+            mint: self.lp_mint,
+        };
+        token_interface::token_metadata_initialize(
+            CpiContext::new_with_signer(self.token_program, cpi_accounts, self.signer_seeds),
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lp_decimals_average_the_tokens() {
+        assert_eq!(lp_decimals(6, 6), 6);
+        assert_eq!(lp_decimals(6, 9), 7);
+        assert_eq!(lp_decimals(0, 9), 4);
+        assert_eq!(lp_decimals(u8::MAX, u8::MAX), u8::MAX);
+    }
+
+    #[test]
+    fn test_lp_metadata_names_the_pair() {
+        let pool = Pubkey::new_unique();
+        let token_a = Pubkey::new_unique();
+        let token_b = Pubkey::new_unique();
+        let (name, symbol) = LpMetadata::pair_label(&token_a, &token_b);
+        let metadata = LpMetadata::new(name, symbol, "https://lp.example/", &pool).unwrap();
+
+        let (a, b) = (&token_a.to_string()[..4], &token_b.to_string()[..4]);
+        assert_eq!(metadata.name, format!("{a}-{b} LP"));
+        assert_eq!(metadata.symbol, format!("{a}{b}LP"));
+        assert_eq!(metadata.uri, format!("https://lp.example/{pool}"));
+    }
+
+    #[test]
+    fn test_lp_metadata_bounds() {
+        let pool = Pubkey::new_unique();
+        let metadata = |name: &str, symbol: &str| LpMetadata::new(name.into(), symbol.into(), "", &pool);
+
+        assert_eq!(metadata("USDC-SOL LP", "USDCSOLLP").unwrap().uri, "");
+        assert_eq!(metadata("", "LP").unwrap_err(), AmmError::InvalidLpMetadata.into());
+        assert_eq!(metadata("LP", "").unwrap_err(), AmmError::InvalidLpMetadata.into());
+        assert_eq!(metadata(&"A".repeat(MAX_LP_NAME_LEN + 1), "LP").unwrap_err(), AmmError::InvalidLpMetadata.into());
+        assert_eq!(metadata("LP", &"A".repeat(MAX_LP_SYMBOL_LEN + 1)).unwrap_err(), AmmError::InvalidLpMetadata.into());
+    }
+}
@@ -0,0 +1,154 @@
+// programs/amm/src/registry.rs
+//! On-chain pool discovery
+//!
+//! `initialize` and `graduate` append every new pool to the `PoolRegistry`
+//! pages and to the `MintPools` list of each of its two tokens, so routers can
+//! enumerate pools, or find those containing a token, by reading PDAs.
+//! `close_pool` tombstones the registry entry and takes the pool off both lists.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::state::{MintPools, PoolRegistry, RegistryEntry, RegistryPage};
+
+/// Accounts that record a new pool
+pub struct RegisterPool<'a, 'info> {
+    pub registry: &'a mut PoolRegistry,
+    pub page: &'a mut RegistryPage,
+    pub page_bump: u8,
+    pub mint_pools_a: AccountInfo<'info>,
+    pub mint_pools_a_bump: u8,
+    pub mint_pools_b: AccountInfo<'info>,
+    pub mint_pools_b_bump: u8,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'a, 'info> RegisterPool<'a, 'info> {
+    /// Append `entry` to the registry and to the mint lookups of both its tokens
+    pub fn register(self, entry: RegistryEntry) -> Result<()> {
+        self.page.bump = self.page_bump;
+        self.registry.push(self.page, entry)?;
+
+        append_mint_pool(
+            &self.mint_pools_a,
+            &entry.token_a,
+            self.mint_pools_a_bump,
+            &entry.pool,
+            &self.payer,
+            &self.system_program,
+        )?;
+        // A pool of one token twice is listed once
+        if entry.token_b != entry.token_a {
+            append_mint_pool(
+                &self.mint_pools_b,
+                &entry.token_b,
+                self.mint_pools_b_bump,
+                &entry.pool,
+                &self.payer,
+                &self.system_program,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Append `pool` to the `MintPools` account of `mint`, creating it on first use
+pub fn append_mint_pool<'info>(
+    mint_pools: &AccountInfo<'info>,
+    mint: &Pubkey,
+    bump: u8,
+    pool: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if mint_pools.data_len() == 0 {
+        create_mint_pools(mint_pools, mint, bump, payer, system_program)?;
+    } else {
+        require_keys_eq!(*mint_pools.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    }
+
+    let len = mint_pools.data_len() + 32;
+    let top_up = Rent::get()?.minimum_balance(len).saturating_sub(mint_pools.lamports());
+    if top_up > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: payer.clone(),
+            to: mint_pools.clone(),
+        };
+        system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), top_up)?;
+    }
+    mint_pools.resize(len)?;
+
+    MintPools::append(&mut mint_pools.try_borrow_mut_data()?, pool)
+}
+
+/// Take `pool` off the `MintPools` account of its token, refunding the freed rent to `destination`
+///
+/// Accounts never created, or not listing the pool, are left as they are.
+pub fn remove_mint_pool(mint_pools: &AccountInfo, pool: &Pubkey, destination: &AccountInfo) -> Result<()> {
+    if mint_pools.data_len() == 0 {
+        return Ok(());
+    }
+    require_keys_eq!(*mint_pools.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    if !MintPools::remove(&mut mint_pools.try_borrow_mut_data()?, pool)? {
+        return Ok(());
+    }
+
+    let len = mint_pools.data_len() - 32;
+    mint_pools.resize(len)?;
+    let refund = mint_pools.lamports().saturating_sub(Rent::get()?.minimum_balance(len));
+    mint_pools.sub_lamports(refund)?;
+    destination.add_lamports(refund)?;
+    Ok(())
+}
+
+/// Create an empty `MintPools` account at its PDA, even if someone already funded the address
+fn create_mint_pools<'info>(
+    mint_pools: &AccountInfo<'info>,
+    mint: &Pubkey,
+    bump: u8,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let seeds = &[b"mint_pools", mint.as_ref(), &[bump]];
+    let signer_seeds = &[&seeds[..]];
+    let space = MintPools::space(0);
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if mint_pools.lamports() == 0 {
+        let cpi_accounts = system_program::CreateAccount {
+            from: payer.clone(),
+            to: mint_pools.clone(),
+        };
+        system_program::create_account(
+            CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds),
+            rent,
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        let top_up = rent.saturating_sub(mint_pools.lamports());
+        if top_up > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: payer.clone(),
+                to: mint_pools.clone(),
+            };
+            system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), top_up)?;
+        }
+        let cpi_accounts = system_program::Allocate {
+            account_to_allocate: mint_pools.clone(),
+        };
+        system_program::allocate(
+            CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds),
+            space as u64,
+        )?;
+        let cpi_accounts = system_program::Assign {
+            account_to_assign: mint_pools.clone(),
+        };
+        system_program::assign(
+            CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds),
+            &crate::ID,
+        )?;
+    }
+
+    MintPools::write_header(&mut mint_pools.try_borrow_mut_data()?, mint, bump)
+}
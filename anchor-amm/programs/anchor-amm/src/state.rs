@@ -1,5 +1,73 @@
 // programs/amm/src/state.rs
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::errors::AmmError;
+use crate::hooks::HookParams;
+use crate::lp_token::MAX_LP_URI_BASE_LEN;
+use crate::instructions::strategy::math::{mul_div, mul_div_u64};
+use crate::instructions::strategy::oracle::price_out_per_in;
+use crate::instructions::strategy::{
+    AmmStrategy, BondingCurveStrategy, CircuitBreaker, ConstantMeanStrategy, CurveType, DynamicFeeParams, HybridCfmmStrategy,
+    HybridParams, HybridState, LbpParams, LmsrStrategy, OraclePegParams,
+    OraclePegStrategy, PegTarget, PythPrice, RateProvider, Rounding, SolidlyStableStrategy, StableSwapStrategy, VolatilityState,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeMode {
+    Static,  // Always charge `Pool.fee`
+    Dynamic, // `Pool.fee` plus a volatility-driven variable fee
+}
+
+/// Admin-defined fee tier; each token pair can have one pool per tier
+#[account]
+pub struct AmmConfig {
+    pub index: u16,         // Tier index, part of the config and pool PDA seeds
+    pub trade_fee_bps: u64, // Swap fee of pools in this tier
+    pub tick_spacing: u16,  // Ticks that range orders can be placed on
+    pub bump: u8,           // PDA bump
+    pub max_referral_fee_bps: u64, // Bound on every referrer's cap, in bps of the swap fee
+    pub referrer_caps: [ReferrerCap; MAX_REFERRER_CAPS], // The only referrers paid, each with its own cap
+    pub lp_uri_base: String, // Prefix of the LP metadata URIs, followed by the pool address; empty for none
+}
+
+/// Referrers a fee tier can pay
+pub const MAX_REFERRER_CAPS: usize = 8;
+
+/// Referral cap of one referrer, identified by the owner of its token accounts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReferrerCap {
+    pub referrer: Pubkey,  // Default pubkey in unused slots
+    pub max_fee_bps: u64,  // Cap on the referral share of the swap fee, in bps of that fee
+}
+
+impl ReferrerCap {
+    pub const LEN: usize = 32 + 8;
+}
+
+impl AmmConfig {
+    pub const LEN: usize = 8 + 2 + 8 + 2 + 1 + 8 + ReferrerCap::LEN * MAX_REFERRER_CAPS + 4 + MAX_LP_URI_BASE_LEN;
+
+    /// Highest referral share `referrer` can take, in bps of the swap fee
+    ///
+    /// Referrers not listed in `referrer_caps` get nothing, so swappers can't
+    /// refer themselves to claw back part of the fee.
+    pub fn referral_cap(&self, referrer: &Pubkey) -> u64 {
+        self.referrer_caps
+            .iter()
+            .find(|cap| cap.referrer == *referrer && *referrer != Pubkey::default())
+            .map_or(0, |cap| cap.max_fee_bps.min(self.max_referral_fee_bps))
+    }
+
+    /// Part of `fee` paid to a referrer taking `referral_fee_bps` of it
+    pub fn referral_amount(&self, referrer: &Pubkey, fee: u64, referral_fee_bps: u64) -> Result<u64> {
+        require!(
+            referral_fee_bps <= self.referral_cap(referrer),
+            AmmError::ReferralFeeTooHigh
+        );
+        mul_div_u64(fee, referral_fee_bps, 10_000, Rounding::Down)
+    }
+}
 
 #[account]
 pub struct Pool {
@@ -8,10 +76,1208 @@ pub struct Pool {
     pub reserve_a: u64,     // Raw amount of token A
     pub reserve_b: u64,     // Raw amount of token B
     pub lp_supply: u64,     // Total LP tokens minted
-    pub fee: u64,           // Swap fee in basis points
+    pub fee: u64,           // Swap fee in basis points (base fee in dynamic mode)
     pub bump: u8,           // PDA bump
+    pub fee_mode: FeeMode,  // Static or volatility-driven fee
+    pub dynamic_fee: DynamicFeeParams, // Dynamic fee configuration
+    pub volatility: VolatilityState,   // Volatility accumulator
+    pub creator: Pubkey,    // Payer of `initialize`, receives the rent on `close_pool`
+    pub open_orders: u64,   // Limit orders whose escrow sits in the vaults
+    pub curve: CurveType,   // Curve used for swaps and LP accounting
+    pub decimals_a: u8,     // Decimals of token A
+    pub decimals_b: u8,     // Decimals of token B
+    pub oracle_peg: OraclePegParams, // Oracle configuration of `CurveType::OraclePeg` pools
+    pub lmsr_b: u64,        // Liquidity parameter of `CurveType::Lmsr` pools
+    pub lbp: LbpParams,     // Weight schedule of `CurveType::Lbp` pools
+    pub paused: bool,       // Swaps halted by the creator (LBP pools only)
+    pub amm_config: Pubkey, // Fee tier the pool was created in
+    pub fee_tier: u16,      // Index of `amm_config`, part of the PDA seeds
+    pub tick_spacing: u16,  // Tick spacing of `amm_config`
+    pub vault_a: Pubkey,    // Token account holding reserve A
+    pub vault_b: Pubkey,    // Token account holding reserve B
+    pub hook: HookParams,   // Trade hook registered at `initialize`
+    pub circuit_breaker: CircuitBreaker, // Price move bounds set by the program authority
+    pub stats: PoolStats,   // Cumulative volume and fees, added by `migrate_pool` to older pools
+    pub rate_a: RateProvider, // Exchange rate of token A in `CurveType::StableSwap` pools
+    pub rate_b: RateProvider, // Exchange rate of token B in `CurveType::StableSwap` pools
+    pub permissioned: bool, // Only wallets on the pool's `Allowlist` can trade or provide liquidity
+    pub hybrid: HybridState, // Adaptive gamma of `CurveType::HybridCfmm` pools
+    pub lp_mint: Pubkey,    // LP mint, set by `migrate_pool` on pools from before it was recorded
+    pub peg_target: PegTarget, // Target reserves of `CurveType::OraclePeg` pools
+    pub legacy_seeds: bool, // PDA of `[b"pool", token_a, token_b]`, from before fee tiers; set by `migrate_pool`
+}
+
+/// Swap inputs that come from outside the pool account
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SwapInputs {
+    pub oracle_price: Option<u128>, // Validated output-per-input price of oracle-pegged pools
+    pub now: i64,                   // Current unix timestamp, for LBP weights
+    pub rates: Option<(u128, u128)>, // Token A and B exchange rates of stable pools with rate providers
 }
 
 impl Pool {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1
+        + 1
+        + DynamicFeeParams::LEN
+        + VolatilityState::LEN
+        + 32 + 8
+        + 1 + 1 + 1
+        + OraclePegParams::LEN
+        + 8
+        + LbpParams::LEN
+        + 1
+        + 32 + 2 + 2
+        + 32 + 32
+        + HookParams::LEN
+        + CircuitBreaker::LEN
+        + PoolStats::LEN
+        + RateProvider::LEN * 2
+        + 1
+        + HybridState::LEN
+        + 32
+        + PegTarget::LEN
+        + 1;
+
+    /// Fee tier seed of the pool PDA, empty for pools created before fee tiers
+    ///
+    /// An empty seed adds nothing to the address hash, so the pool signs with
+    /// `[b"pool", token_a, token_b, tier_seed, bump]` in either layout.
+    pub fn tier_seed(&self) -> Vec<u8> {
+        if self.legacy_seeds {
+            Vec::new()
+        } else {
+            self.fee_tier.to_le_bytes().to_vec()
+        }
+    }
+
+    /// Fee in basis points to charge on a swap at `current_slot`
+    ///
+    /// In dynamic mode this also rolls the volatility references forward, and
+    /// hybrid pools start from their imbalance fee rather than `fee`.
+    pub fn swap_fee(&mut self, current_slot: u64) -> Result<u64> {
+        match self.fee_mode {
+            FeeMode::Static => Ok(self.fee),
+            FeeMode::Dynamic => {
+                let price = VolatilityState::spot_price(self.reserve_a, self.reserve_b)?;
+                self.volatility
+                    .update_references(current_slot, price, &self.dynamic_fee)?;
+                self.volatility
+                    .update_accumulator(current_slot, price, &self.dynamic_fee)?;
+                let base_fee = if self.curve == CurveType::HybridCfmm {
+                    self.hybrid
+                        .imbalance_fee(self.reserve_a, self.reserve_b, self.fee, self.dynamic_fee.max_fee_bps)?
+                } else {
+                    self.fee
+                };
+                self.volatility.effective_fee(base_fee, &self.dynamic_fee)
+            }
+        }
+    }
+
+    /// Oracle price of the swap input token in the output token, scaled by `PRICE_SCALE`
+    ///
+    /// Fails if the feed is stale or its confidence interval is too wide.
+    pub fn oracle_price(&self, oracle_data: &[u8], current_slot: u64, a_to_b: bool) -> Result<u128> {
+        let feed = PythPrice::load(oracle_data)?;
+        feed.validate(
+            current_slot,
+            self.oracle_peg.max_staleness_slots,
+            self.oracle_peg.max_confidence_bps,
+        )?;
+        price_out_per_in(feed.scaled_price(self.decimals_a, self.decimals_b)?, a_to_b)
+    }
+
+    /// Exchange rates of token A and B, scaled by `RATE_SCALE`; `None` without rate providers
+    ///
+    /// `data_a` and `data_b` are the provider accounts, refreshing the cached rates when passed.
+    pub fn refresh_rates(
+        &mut self,
+        data_a: Option<&[u8]>,
+        data_b: Option<&[u8]>,
+        clock: &Clock,
+    ) -> Result<Option<(u128, u128)>> {
+        if !self.rate_a.is_set() && !self.rate_b.is_set() {
+            return Ok(None);
+        }
+        let rate_a = self.rate_a.refresh(data_a, self.token_a, clock)?;
+        let rate_b = self.rate_b.refresh(data_b, self.token_b, clock)?;
+        Ok(Some((rate_a, rate_b)))
+    }
+
+    /// Sample the spot price before a swap into the moving average of hybrid pools
+    pub fn record_hybrid_price(&mut self, now: i64) -> Result<()> {
+        if self.curve != CurveType::HybridCfmm || self.reserve_a == 0 {
+            return Ok(());
+        }
+        let price = HybridState::spot_price(self.reserve_a, self.reserve_b)?;
+        self.hybrid.record_price(price, now, HybridParams::default())
+    }
+
+    /// Swap output on the pool's curve, with the pool-specific inputs some curves need
+    pub fn calculate_amount_out(
+        &self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+        a_to_b: bool,
+        inputs: SwapInputs,
+    ) -> Result<u64> {
+        match self.curve {
+            CurveType::OraclePeg => {
+                let (target_a, target_b) = self.peg_target.targets(self.reserve_a, self.reserve_b);
+                let targets = if a_to_b { (target_a, target_b) } else { (target_b, target_a) };
+                OraclePegStrategy::calculate_amount_out_with_targets(
+                    amount_in,
+                    (reserve_in, reserve_out),
+                    targets,
+                    fee_bps,
+                    inputs.oracle_price.ok_or(AmmError::InvalidOracle)?,
+                    self.oracle_peg.k,
+                )
+            }
+            CurveType::StableSwap => match inputs.rates {
+                Some((rate_a, rate_b)) => {
+                    let (rate_in, rate_out) = if a_to_b { (rate_a, rate_b) } else { (rate_b, rate_a) };
+                    StableSwapStrategy::calculate_amount_out_with_rates(
+                        amount_in,
+                        reserve_in,
+                        reserve_out,
+                        fee_bps,
+                        rate_in,
+                        rate_out,
+                    )
+                }
+                None => StableSwapStrategy::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps),
+            },
+            CurveType::SolidlyStable => {
+                let (decimals_in, decimals_out) = if a_to_b {
+                    (self.decimals_a, self.decimals_b)
+                } else {
+                    (self.decimals_b, self.decimals_a)
+                };
+                SolidlyStableStrategy::calculate_amount_out_with_decimals(
+                    amount_in,
+                    reserve_in,
+                    reserve_out,
+                    fee_bps,
+                    decimals_in,
+                    decimals_out,
+                )
+            }
+            CurveType::HybridCfmm => HybridCfmmStrategy::calculate_amount_out_with_gamma(
+                amount_in,
+                reserve_in,
+                reserve_out,
+                fee_bps,
+                self.hybrid.gamma(),
+            ),
+            CurveType::Lmsr => LmsrStrategy::calculate_amount_out_with_liquidity(
+                amount_in,
+                reserve_in,
+                reserve_out,
+                fee_bps,
+                self.lmsr_b,
+            ),
+            CurveType::Lbp => {
+                let (weight_a, weight_b) = self.lbp.weights_at(inputs.now)?;
+                let (weight_in, weight_out) = if a_to_b {
+                    (weight_a, weight_b)
+                } else {
+                    (weight_b, weight_a)
+                };
+                ConstantMeanStrategy::calculate_amount_out_weighted(
+                    amount_in,
+                    reserve_in,
+                    reserve_out,
+                    weight_in,
+                    weight_out,
+                    fee_bps,
+                )
+            }
+            curve => curve.calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps),
+        }
+    }
+
+    /// Spot price before a swap, taken as the circuit breaker reference on the first swap of a slot
+    ///
+    /// `None` while the breaker is off or overridden.
+    pub fn breaker_price(&mut self, current_slot: u64) -> Result<Option<u128>> {
+        if !self.circuit_breaker.is_active() {
+            return Ok(None);
+        }
+        let price = VolatilityState::spot_price(self.reserve_a, self.reserve_b)?;
+        self.circuit_breaker.update_reference(current_slot, price);
+        Ok(Some(price))
+    }
+
+    /// Fail if the swap moved the spot price from `price_before` past the circuit breaker bounds
+    pub fn check_price_move(&self, price_before: Option<u128>) -> Result<()> {
+        match price_before {
+            Some(price_before) => {
+                let price_after = VolatilityState::spot_price(self.reserve_a, self.reserve_b)?;
+                self.circuit_breaker.check(price_before, price_after)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Fail unless `user` may trade on the pool
+    ///
+    /// Permissioned pools need their `allowlist`, plus the user's `entry` or a
+    /// merkle `proof` depending on its kind.
+    pub fn check_allowed(
+        &self,
+        allowlist: Option<&Allowlist>,
+        entry: Option<&AllowlistEntry>,
+        user: &Pubkey,
+        proof: &[[u8; 32]],
+    ) -> Result<()> {
+        if !self.permissioned {
+            return Ok(());
+        }
+        allowlist.ok_or(AmmError::NotAllowlisted)?.verify(user, entry, proof)
+    }
+
+    /// Record the post-trade price in the volatility accumulator
+    pub fn record_price_move(&mut self, current_slot: u64) -> Result<()> {
+        if self.fee_mode == FeeMode::Dynamic {
+            let price = VolatilityState::spot_price(self.reserve_a, self.reserve_b)?;
+            self.volatility
+                .update_accumulator(current_slot, price, &self.dynamic_fee)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fixed-point scale of `PoolStats::fee_growth_global_a/b` (Q64.64)
+pub const FEE_GROWTH_SCALE: u128 = 1 << 64;
+
+/// Cumulative trading statistics, updated by every swap
+///
+/// Volumes count each token both ways: swapped in and paid out. LP fees are
+/// the swap fees charged on the input token, which stay in the reserves.
+///
+/// `fee_growth_global_a/b` is the LP fee earned per LP token since the pool
+/// was created, wrapping on overflow. An LP that records it when depositing
+/// has earned `lp_amount * (growth_now - growth_then) / FEE_GROWTH_SCALE`
+/// since (see `fees_earned`).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub volume_a: u128,
+    pub volume_b: u128,
+    pub lp_fees_a: u128,
+    pub lp_fees_b: u128,
+    pub swap_count: u64,
+    pub last_trade_timestamp: i64,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+impl PoolStats {
+    pub const LEN: usize = 16 * 4 + 8 + 8 + 16 * 2;
+
+    /// Record a swap of `amount_in` for `amount_out` that charged `lp_fee` on the input
+    pub fn record_swap(
+        &mut self,
+        amount_in: u64,
+        amount_out: u64,
+        lp_fee: u64,
+        a_to_b: bool,
+        lp_supply: u64,
+        now: i64,
+    ) -> Result<()> {
+        let (volume_in, volume_out, lp_fees, fee_growth) = if a_to_b {
+            (&mut self.volume_a, &mut self.volume_b, &mut self.lp_fees_a, &mut self.fee_growth_global_a)
+        } else {
+            (&mut self.volume_b, &mut self.volume_a, &mut self.lp_fees_b, &mut self.fee_growth_global_b)
+        };
+
+        *volume_in = volume_in.checked_add(amount_in as u128).ok_or(AmmError::MathOverflow)?;
+        *volume_out = volume_out.checked_add(amount_out as u128).ok_or(AmmError::MathOverflow)?;
+        *lp_fees = lp_fees.checked_add(lp_fee as u128).ok_or(AmmError::MathOverflow)?;
+        if lp_supply > 0 {
+            // Fits: lp_fee < 2^64
+            let growth = (lp_fee as u128 * FEE_GROWTH_SCALE) / lp_supply as u128;
+            *fee_growth = fee_growth.wrapping_add(growth);
+        }
+
+        self.swap_count = self.swap_count.checked_add(1).ok_or(AmmError::MathOverflow)?;
+        self.last_trade_timestamp = now;
+        Ok(())
+    }
+
+    /// Fees earned by `lp_amount` LP tokens while the fee growth went from `growth_then` to `growth_now`
+    pub fn fees_earned(lp_amount: u64, growth_then: u128, growth_now: u128) -> Result<u64> {
+        let growth = growth_now.wrapping_sub(growth_then);
+        let fees = mul_div(lp_amount as u128, growth, FEE_GROWTH_SCALE, Rounding::Down)?;
+        u64::try_from(fees).map_err(|_| AmmError::MathOverflow.into())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderSide {
+    Ask, // Sells token A for token B, placed above the current price
+    Bid, // Sells token B for token A, placed below the current price
+}
+
+/// Range order of `owner`; its fill state is kept in the pool's `OrderBook`
+#[account]
+pub struct LimitOrder {
+    pub pool: Pubkey,       // Pool the order rests on
+    pub owner: Pubkey,      // Order owner
+    pub tick: i32,          // Tick the order is placed at
+    pub side: OrderSide,    // Ask or bid
+    pub amount: u64,        // Amount originally deposited
+    pub bump: u8,           // PDA bump
+}
+
+impl LimitOrder {
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 1 + 8 + 1;
+}
+
+/// Orders listed per `OrderBook`
+pub const ORDER_BOOK_CAPACITY: usize = 64;
+
+/// Every open range order of a pool, at PDA `[b"order_book", pool]`
+///
+/// `swap` fills the orders its price move crosses from the book itself, so
+/// no order can be skipped by leaving its account out. Filled orders stay
+/// listed with their proceeds until they are claimed.
+#[account]
+pub struct OrderBook {
+    pub pool: Pubkey,           // Pool the orders rest on
+    pub bump: u8,               // PDA bump
+    pub orders: Vec<BookOrder>, // Open orders in placement order
+}
+
+impl OrderBook {
+    pub const LEN: usize = 8 + 32 + 1 + 4 + ORDER_BOOK_CAPACITY * BookOrder::LEN;
+
+    pub fn push(&mut self, order: BookOrder) -> Result<()> {
+        require!(self.orders.len() < ORDER_BOOK_CAPACITY, AmmError::OrderBookFull);
+        self.orders.push(order);
+        Ok(())
+    }
+
+    /// Take the order of the `LimitOrder` account `order` off the book
+    pub fn remove(&mut self, order: &Pubkey) -> Result<BookOrder> {
+        let index = self
+            .orders
+            .iter()
+            .position(|listed| listed.order == *order)
+            .ok_or(AmmError::InvalidLimitOrder)?;
+        Ok(self.orders.remove(index))
+    }
+
+    /// Index of the first order a swap reaches: the unfilled order of the side it sells
+    /// into at the nearest tick, placed first among orders at that tick
+    pub fn next_fillable(&self, a_to_b: bool) -> Option<usize> {
+        // Selling A pushes the price down onto bids, selling B pushes it up onto asks
+        let side = if a_to_b { OrderSide::Bid } else { OrderSide::Ask };
+        self.orders
+            .iter()
+            .enumerate()
+            .filter(|(_, order)| order.side == side && order.remaining > 0)
+            .min_by_key(|(index, order)| {
+                let distance = if a_to_b { -(order.tick as i64) } else { order.tick as i64 };
+                (distance, *index)
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+/// Fill state of a range order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookOrder {
+    pub order: Pubkey,    // `LimitOrder` account of the order
+    pub tick: i32,        // Tick the order is placed at
+    pub side: OrderSide,  // Ask or bid
+    pub remaining: u64,   // Amount still providing liquidity
+    pub proceeds: u64,    // Converted amount owed to the owner
+}
+
+impl BookOrder {
+    pub const LEN: usize = 32 + 4 + 1 + 8 + 8;
+}
+
+/// Pools listed per `RegistryPage`
+pub const REGISTRY_PAGE_CAPACITY: usize = 32;
+
+/// Index of every pool the program creates, for discovery without `getProgramAccounts`
+///
+/// Pools are appended to `RegistryPage` accounts (PDA `[b"registry_page", index]`),
+/// each holding up to `REGISTRY_PAGE_CAPACITY` pools. Page `i + 1` is created
+/// once page `i` is full, so walking pages `0..page_count` lists every pool.
+#[account]
+pub struct PoolRegistry {
+    pub pool_count: u64, // Pools registered
+    pub page_count: u32, // Pages created
+    pub bump: u8,        // PDA bump
+}
+
+impl PoolRegistry {
+    pub const LEN: usize = 8 + 8 + 4 + 1;
+
+    /// Index of the page the next pool is appended to
+    pub fn next_page(&self) -> u32 {
+        (self.pool_count / REGISTRY_PAGE_CAPACITY as u64) as u32
+    }
+
+    /// Append `entry` to `page`, which must be the page at `next_page`
+    pub fn push(&mut self, page: &mut RegistryPage, entry: RegistryEntry) -> Result<()> {
+        let index = self.next_page();
+        require!(page.entries.len() < REGISTRY_PAGE_CAPACITY, AmmError::InvalidRegistryPage);
+        if page.entries.is_empty() {
+            page.index = index;
+        }
+        require!(page.index == index, AmmError::InvalidRegistryPage);
+
+        page.entries.push(entry);
+        self.pool_count = self.pool_count.checked_add(1).ok_or(AmmError::MathOverflow)?;
+        self.page_count = self.page_count.max(index + 1);
+        Ok(())
+    }
+}
+
+/// A pool as listed in the registry
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub pool: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub curve: CurveType,
+    pub fee_tier: u16,
+}
+
+impl RegistryEntry {
+    pub const LEN: usize = 32 * 3 + 1 + 2;
+}
+
+/// Page of the pool registry, at PDA `[b"registry_page", index as u32 LE]`
+///
+/// `close_pool` leaves a closed pool's entry in place with the default pool
+/// address, so no entry moves to another page.
+#[account]
+pub struct RegistryPage {
+    pub index: u32,                   // Position of the page, part of its PDA seeds
+    pub bump: u8,                     // PDA bump
+    pub entries: Vec<RegistryEntry>,  // Pools in creation order
+}
+
+impl RegistryPage {
+    pub const LEN: usize = 8 + 4 + 1 + 4 + REGISTRY_PAGE_CAPACITY * RegistryEntry::LEN;
+
+    /// Tombstone the entry of `pool`, which must be on the page
+    pub fn remove(&mut self, pool: &Pubkey) -> Result<()> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.pool == *pool)
+            .ok_or(AmmError::InvalidRegistryPage)?;
+        entry.pool = Pubkey::default();
+        Ok(())
+    }
+}
+
+/// Every pool containing `mint`, at PDA `[b"mint_pools", mint]`
+///
+/// The list grows by one key per pool. The program appends to the raw account
+/// data (see `registry::append_mint_pool`) rather than deserializing it, so the
+/// size of the list is not bound by the heap.
+#[account]
+pub struct MintPools {
+    pub mint: Pubkey,       // Token the pools contain
+    pub bump: u8,           // PDA bump
+    pub pools: Vec<Pubkey>, // Pools in creation order
+}
+
+impl MintPools {
+    /// Size without any pool
+    pub const HEADER_LEN: usize = 8 + 32 + 1 + 4;
+    const COUNT_OFFSET: usize = 8 + 32 + 1;
+
+    /// Account size holding `count` pools
+    pub fn space(count: usize) -> usize {
+        Self::HEADER_LEN + count * 32
+    }
+
+    /// Write an empty list of `mint` into `data`
+    pub fn write_header(data: &mut [u8], mint: &Pubkey, bump: u8) -> Result<()> {
+        require!(data.len() >= Self::HEADER_LEN, AmmError::InvalidRegistryPage);
+        data[..8].copy_from_slice(Self::DISCRIMINATOR);
+        data[8..40].copy_from_slice(mint.as_ref());
+        data[40] = bump;
+        data[Self::COUNT_OFFSET..Self::HEADER_LEN].copy_from_slice(&0u32.to_le_bytes());
+        Ok(())
+    }
+
+    /// Append `pool` to the list in `data`, already grown by 32 bytes
+    pub fn append(data: &mut [u8], pool: &Pubkey) -> Result<()> {
+        require!(data.starts_with(Self::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+        require!(data.len() >= Self::HEADER_LEN, AmmError::InvalidRegistryPage);
+
+        let count_bytes = &data[Self::COUNT_OFFSET..Self::HEADER_LEN];
+        let count = u32::from_le_bytes(count_bytes.try_into().unwrap());
+        let offset = Self::space(count as usize);
+        require!(data.len() == offset + 32, AmmError::InvalidRegistryPage);
+
+        data[offset..].copy_from_slice(pool.as_ref());
+        data[Self::COUNT_OFFSET..Self::HEADER_LEN].copy_from_slice(&(count + 1).to_le_bytes());
+        Ok(())
+    }
+
+    /// Move the last pool of the list in `data` over `pool`, returning whether it was listed
+    ///
+    /// The caller shrinks the account by 32 bytes when it was.
+    pub fn remove(data: &mut [u8], pool: &Pubkey) -> Result<bool> {
+        require!(data.starts_with(Self::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+        require!(data.len() >= Self::HEADER_LEN, AmmError::InvalidRegistryPage);
+
+        let count_bytes = &data[Self::COUNT_OFFSET..Self::HEADER_LEN];
+        let count = u32::from_le_bytes(count_bytes.try_into().unwrap());
+        require!(data.len() == Self::space(count as usize), AmmError::InvalidRegistryPage);
+
+        let Some(index) = (0..count as usize).find(|index| data[Self::space(*index)..][..32] == pool.to_bytes()) else {
+            return Ok(false);
+        };
+        let last = Self::space(count as usize - 1);
+        data.copy_within(last..last + 32, Self::space(index));
+        data[Self::COUNT_OFFSET..Self::HEADER_LEN].copy_from_slice(&(count - 1).to_le_bytes());
+        Ok(true)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllowlistKind {
+    MerkleRoot, // Members prove their inclusion in `merkle_root` with each trade
+    Members,    // Each member has an `AllowlistEntry` account
+}
+
+/// Allowlist a pool is created with by `initialize`, making it permissioned
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllowlistParams {
+    pub kind: AllowlistKind,
+    pub merkle_root: [u8; 32], // Ignored for `Members` lists
+}
+
+/// Wallets allowed to trade on a permissioned pool, managed by the pool creator
+#[account]
+pub struct Allowlist {
+    pub pool: Pubkey,          // Pool the list applies to
+    pub kind: AllowlistKind,   // Merkle root or per-member entries
+    pub merkle_root: [u8; 32], // Root over `leaf(member)` of every member (`MerkleRoot` lists only)
+    pub bump: u8,              // PDA bump
+}
+
+impl Allowlist {
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 1;
+
+    /// Fail unless `member` is on the list, shown by its `entry` or a merkle `proof`
+    pub fn verify(&self, member: &Pubkey, entry: Option<&AllowlistEntry>, proof: &[[u8; 32]]) -> Result<()> {
+        match self.kind {
+            AllowlistKind::MerkleRoot => {
+                let root = proof.iter().fold(Self::leaf(member), |node, sibling| Self::parent(&node, sibling));
+                require!(root == self.merkle_root, AmmError::NotAllowlisted);
+            }
+            AllowlistKind::Members => {
+                let entry = entry.ok_or(AmmError::NotAllowlisted)?;
+                require_keys_eq!(entry.pool, self.pool, AmmError::NotAllowlisted);
+                require_keys_eq!(entry.member, *member, AmmError::NotAllowlisted);
+            }
+        }
+        Ok(())
+    }
+
+    /// Merkle leaf of a member
+    ///
+    /// Formula: sha256(0x00 || member)
+    pub fn leaf(member: &Pubkey) -> [u8; 32] {
+        hashv(&[&[0], member.as_ref()]).to_bytes()
+    }
+
+    /// Merkle node over two children, in either order
+    ///
+    /// Formula: sha256(0x01 || min(a, b) || max(a, b))
+    pub fn parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[&[1], left, right]).to_bytes()
+    }
+}
+
+/// Membership of a wallet in a `Members` allowlist
+#[account]
+pub struct AllowlistEntry {
+    pub pool: Pubkey,   // Pool the list applies to
+    pub member: Pubkey, // Allowed wallet
+    pub bump: u8,       // PDA bump
+}
+
+impl AllowlistEntry {
+    pub const LEN: usize = 8 + 32 + 32 + 1;
+}
+
+/// Fixed-point scale for `Farm.acc_reward_per_share`
+pub const REWARD_SCALE: u128 = 1_000_000_000_000;
+
+#[account]
+pub struct Farm {
+    pub pool: Pubkey,               // Pool whose LP tokens are staked
+    pub lp_mint: Pubkey,            // LP mint of the pool
+    pub reward_mint: Pubkey,        // Mint of the reward token
+    pub staked_vault: Pubkey,       // Token account holding staked LP tokens
+    pub reward_vault: Pubkey,       // Token account holding undistributed rewards
+    pub authority: Pubkey,          // Farm creator
+    pub reward_rate: u64,           // Reward tokens emitted per second
+    pub start_time: i64,            // Emission start (unix timestamp)
+    pub end_time: i64,              // Emission end (unix timestamp)
+    pub total_staked: u64,          // Total LP tokens staked
+    pub acc_reward_per_share: u128, // Accumulated rewards per staked LP token, scaled by REWARD_SCALE
+    pub last_update_time: i64,      // Last time the index was updated
+    pub bump: u8,                   // PDA bump
+    pub idle_rewards: u64,          // Rewards emitted while nothing was staked, left for the authority
+}
+
+impl Farm {
+    pub const LEN: usize = 8 + 32 * 6 + 8 + 8 + 8 + 8 + 16 + 8 + 1 + 8;
+
+    /// Accrue rewards emitted since `last_update_time` into the index
+    ///
+    /// Formula: acc += (min(now, end) - last_update) * reward_rate / total_staked
+    ///
+    /// Rewards emitted while nothing is staked go to `idle_rewards` instead, so
+    /// later stakers don't earn them and the authority reclaims them after `end_time`.
+    pub fn update(&mut self, now: i64) -> Result<()> {
+        let until = now.min(self.end_time);
+        if until <= self.last_update_time {
+            return Ok(());
+        }
+
+        let elapsed = (until - self.last_update_time) as u128;
+        let emitted = elapsed
+            .checked_mul(self.reward_rate as u128)
+            .ok_or(AmmError::MathOverflow)?;
+        if self.total_staked == 0 {
+            let emitted = u64::try_from(emitted).map_err(|_| AmmError::MathOverflow)?;
+            self.idle_rewards = self.idle_rewards.checked_add(emitted).ok_or(AmmError::MathOverflow)?;
+        } else {
+            let increment = emitted
+                .checked_mul(REWARD_SCALE)
+                .ok_or(AmmError::MathOverflow)?
+                .checked_div(self.total_staked as u128)
+                .ok_or(AmmError::MathOverflow)?;
+            self.acc_reward_per_share = self
+                .acc_reward_per_share
+                .checked_add(increment)
+                .ok_or(AmmError::MathOverflow)?;
+        }
+
+        self.last_update_time = until;
+        Ok(())
+    }
+
+    /// Rewards of the budget not emitted to stakers
+    ///
+    /// Formula: (end - last_update) * reward_rate + idle_rewards
+    pub fn unemitted_rewards(&self) -> Result<u64> {
+        let remaining = self.end_time.saturating_sub(self.last_update_time).max(0) as u64;
+        remaining
+            .checked_mul(self.reward_rate)
+            .and_then(|remaining| remaining.checked_add(self.idle_rewards))
+            .ok_or(AmmError::MathOverflow.into())
+    }
+
+    /// Rewards accrued by `amount` staked LP tokens since `reward_debt` was recorded
+    pub fn pending_rewards(&self, amount: u64, reward_debt: u128) -> Result<u64> {
+        let accrued = self.reward_debt(amount)?;
+        let pending = accrued
+            .checked_sub(reward_debt)
+            .ok_or(AmmError::MathOverflow)?
+            .checked_div(REWARD_SCALE)
+            .ok_or(AmmError::MathOverflow)?;
+
+        Ok(pending as u64)
+    }
+
+    /// Index snapshot for `amount` staked LP tokens
+    pub fn reward_debt(&self, amount: u64) -> Result<u128> {
+        (amount as u128)
+            .checked_mul(self.acc_reward_per_share)
+            .ok_or(AmmError::MathOverflow.into())
+    }
+}
+
+#[account]
+pub struct FarmPosition {
+    pub farm: Pubkey,          // Farm the position belongs to
+    pub owner: Pubkey,         // Position owner
+    pub amount: u64,           // LP tokens staked
+    pub reward_debt: u128,     // amount * acc_reward_per_share at the last settlement
+    pub rewards_owed: u64,     // Settled rewards not yet harvested
+    pub bump: u8,              // PDA bump
+}
+
+impl FarmPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 16 + 8 + 1;
+
+    /// Move pending rewards into `rewards_owed` and apply a stake change
+    pub fn settle(&mut self, farm: &Farm, new_amount: u64) -> Result<()> {
+        let pending = farm.pending_rewards(self.amount, self.reward_debt)?;
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_add(pending)
+            .ok_or(AmmError::MathOverflow)?;
+        self.amount = new_amount;
+        self.reward_debt = farm.reward_debt(new_amount)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketOutcome {
+    Unresolved, // Trading; complete sets can be minted and merged
+    Yes,        // YES tokens redeem 1:1 for collateral
+    No,         // NO tokens redeem 1:1 for collateral
+}
+
+#[account]
+pub struct PredictionMarket {
+    pub creator: Pubkey,         // Market creator
+    pub resolver: Pubkey,        // Authority allowed to resolve the market
+    pub collateral_mint: Pubkey, // Mint backing the outcome tokens 1:1
+    pub collateral_vault: Pubkey, // Token account holding the collateral
+    pub yes_mint: Pubkey,        // Mint of the YES outcome token
+    pub no_mint: Pubkey,         // Mint of the NO outcome token
+    pub market_id: u64,          // Creator-chosen id, part of the PDA seeds
+    pub outcome: MarketOutcome,  // Resolution state
+    pub bump: u8,                // PDA bump
+}
+
+impl PredictionMarket {
+    pub const LEN: usize = 8 + 32 * 6 + 8 + 1 + 1;
+
+    /// Check that `token_a` and `token_b` are the YES and NO mints of the market, in either order
+    ///
+    /// Pool mints are sorted, and the LMSR curve treats both outcomes alike.
+    pub fn check_outcome_mints(&self, token_a: &Pubkey, token_b: &Pubkey) -> Result<()> {
+        require!(
+            (self.yes_mint, self.no_mint) == (*token_a, *token_b)
+                || (self.no_mint, self.yes_mint) == (*token_a, *token_b),
+            AmmError::InvalidMarket
+        );
+        Ok(())
+    }
+}
+
+#[account]
+pub struct Launch {
+    pub creator: Pubkey,             // Launch creator
+    pub token_mint: Pubkey,          // Newly minted token sold on the curve
+    pub quote_mint: Pubkey,          // Token the curve is priced in
+    pub token_vault: Pubkey,         // Token account holding unsold tokens
+    pub quote_vault: Pubkey,         // Token account holding the raised quote
+    pub total_supply: u64,           // Fixed supply of the launched token
+    pub real_token_reserve: u64,     // Tokens left on the curve
+    pub real_quote_reserve: u64,     // Quote raised by the curve
+    pub virtual_token_reserve: u64,  // Virtual token reserve shaping the price
+    pub virtual_quote_reserve: u64,  // Virtual quote reserve shaping the price
+    pub fee: u64,                    // Trade fee on the curve in basis points
+    pub graduation_market_cap: u64,  // Market cap (in quote) at which the launch can graduate
+    pub graduated: bool,             // Migrated into a constant product pool
+    pub bump: u8,                    // PDA bump
+    pub amm_config: Pubkey,          // Fee tier of the pool the launch graduates into
+}
+
+impl Launch {
+    pub const LEN: usize = 8 + 32 * 5 + 8 * 8 + 1 + 1 + 32;
+
+    /// Token and quote reserves including the virtual part
+    pub fn effective_reserves(&self) -> Result<(u64, u64)> {
+        let token = self
+            .real_token_reserve
+            .checked_add(self.virtual_token_reserve)
+            .ok_or(AmmError::MathOverflow)?;
+        let quote = self
+            .real_quote_reserve
+            .checked_add(self.virtual_quote_reserve)
+            .ok_or(AmmError::MathOverflow)?;
+        Ok((token, quote))
+    }
+
+    /// Current market cap in quote units
+    pub fn market_cap(&self) -> Result<u64> {
+        let (token, quote) = self.effective_reserves()?;
+        BondingCurveStrategy::market_cap(token, quote, self.total_supply)
+    }
+
+    /// Tokens paired with all raised quote at the curve price when the launch graduates
+    pub fn graduation_token_amount(&self) -> Result<u64> {
+        let (token, quote) = self.effective_reserves()?;
+        BondingCurveStrategy::tokens_at_price(self.real_quote_reserve, token, quote)
+    }
+
+    /// Reject a token whose launch at `account` has not graduated yet
+    ///
+    /// `account` is the launch PDA of the token, which is empty for tokens never launched.
+    pub fn check_not_launching(account: &AccountInfo) -> Result<()> {
+        if account.owner != &crate::ID || account.data_is_empty() {
+            return Ok(());
+        }
+        let launch = Launch::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require!(launch.graduated, AmmError::LaunchInProgress);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_stats() {
+        let mut stats = PoolStats::default();
+        stats.record_swap(1_000, 990, 3, true, 1_000, 100).unwrap();
+        stats.record_swap(500, 505, 2, false, 1_000, 200).unwrap();
+
+        assert_eq!((stats.volume_a, stats.volume_b), (1_000 + 505, 990 + 500));
+        assert_eq!((stats.lp_fees_a, stats.lp_fees_b), (3, 2));
+        assert_eq!(stats.swap_count, 2);
+        assert_eq!(stats.last_trade_timestamp, 200);
+
+        // 3 fee over 1_000 LP tokens: 100 LP tokens earned 0.3
+        assert_eq!(stats.fee_growth_global_a, 3 * FEE_GROWTH_SCALE / 1_000);
+        assert_eq!(PoolStats::fees_earned(100, 0, stats.fee_growth_global_a).unwrap(), 0);
+        assert_eq!(PoolStats::fees_earned(1_000, 0, stats.fee_growth_global_a).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_fees_earned_across_wraparound() {
+        let then = u128::MAX - FEE_GROWTH_SCALE + 1;
+        let now = then.wrapping_add(5 * FEE_GROWTH_SCALE);
+        assert_eq!(PoolStats::fees_earned(10, then, now).unwrap(), 50);
+    }
+
+    #[test]
+    fn test_hybrid_dynamic_fee_rises_with_imbalance() {
+        // No volatility surcharge, so the fee is the imbalance fee alone
+        let dynamic_fee = DynamicFeeParams { max_fee_bps: 300, variable_fee_control: 0, ..DynamicFeeParams::default() };
+        let mut pool = Pool {
+            reserve_a: 1_000_000,
+            reserve_b: 1_000_000,
+            fee: 30,
+            fee_mode: FeeMode::Dynamic,
+            curve: CurveType::HybridCfmm,
+            dynamic_fee,
+            ..Pool::try_from_slice(&[0u8; Pool::LEN - 8]).unwrap()
+        };
+        assert_eq!(pool.swap_fee(1).unwrap(), 30);
+
+        pool.reserve_a = 2_000_000;
+        let imbalanced = pool.swap_fee(2).unwrap();
+        assert!(imbalanced > 30 && imbalanced <= 300);
+
+        // Balanced again at a target price of 2 B per A
+        pool.hybrid = HybridState::new(2_000_000).unwrap();
+        pool.reserve_b = 4_000_000;
+        assert_eq!(pool.swap_fee(3).unwrap(), 30);
+
+        // Static pools keep their fee
+        pool.fee_mode = FeeMode::Static;
+        pool.reserve_b = 1_000_000;
+        assert_eq!(pool.swap_fee(4).unwrap(), 30);
+    }
+
+    #[test]
+    fn test_zero_extended_pool_has_default_stats() {
+        // What `migrate_pool` does to a pool written before the stats
+        let pool = Pool {
+            reserve_a: 123,
+            stats: PoolStats { swap_count: 7, ..PoolStats::default() },
+            ..Pool::try_from_slice(&[0u8; Pool::LEN - 8]).unwrap()
+        };
+        let mut data = Vec::new();
+        pool.try_serialize(&mut data).unwrap();
+        // Drop everything from the stats on
+        data.truncate(Pool::LEN - PoolStats::LEN - RateProvider::LEN * 2 - 1 - HybridState::LEN - 32 - PegTarget::LEN);
+        data.resize(Pool::LEN, 0);
+
+        let migrated = Pool::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.reserve_a, 123);
+        assert_eq!(migrated.stats, PoolStats::default());
+        assert_eq!(migrated.hybrid, HybridState::default());
+        assert_eq!(migrated.lp_mint, Pubkey::default());
+    }
+
+    #[test]
+    fn test_referral_caps() {
+        let partner = Pubkey::new_unique();
+        let mut config = AmmConfig {
+            index: 0,
+            trade_fee_bps: 30,
+            tick_spacing: 10,
+            bump: 255,
+            max_referral_fee_bps: 5_000,
+            referrer_caps: [ReferrerCap::default(); MAX_REFERRER_CAPS],
+            lp_uri_base: String::new(),
+        };
+        config.referrer_caps[3] = ReferrerCap { referrer: partner, max_fee_bps: 5_000 };
+
+        // Up to half of a 3_000 fee for the partner
+        assert_eq!(config.referral_amount(&partner, 3_000, 5_000).unwrap(), 1_500);
+        assert_eq!(config.referral_amount(&partner, 3_000, 5_001).unwrap_err(), AmmError::ReferralFeeTooHigh.into());
+
+        // Anyone else, such as a swapper referring themselves, gets nothing
+        let other = Pubkey::new_unique();
+        assert_eq!(config.referral_amount(&other, 3_000, 0).unwrap(), 0);
+        assert_eq!(config.referral_amount(&other, 3_000, 1).unwrap_err(), AmmError::ReferralFeeTooHigh.into());
+
+        // Empty slots don't list the default pubkey
+        assert_eq!(config.referral_cap(&Pubkey::default()), 0);
+
+        // The tier bound applies on top of the partner's cap
+        config.max_referral_fee_bps = 1_000;
+        assert_eq!(config.referral_cap(&partner), 1_000);
+    }
+
+    #[test]
+    fn test_zero_extended_amm_config_has_no_referrals() {
+        // What `migrate_amm_config` does to a fee tier written before referrals
+        let mut data = Vec::new();
+        AmmConfig::deserialize(&mut &[0u8; AmmConfig::LEN - 8][..]).unwrap().try_serialize(&mut data).unwrap();
+        data[8] = 2;
+        data.truncate(8 + 2 + 8 + 2 + 1);
+        data.resize(AmmConfig::LEN, 0);
+
+        let migrated = AmmConfig::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.index, 2);
+        assert_eq!(migrated.referral_cap(&Pubkey::new_unique()), 0);
+        assert_eq!(migrated.lp_uri_base, "");
+    }
+
+    #[test]
+    fn test_allowlist_merkle_proofs() {
+        let members = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves = members.map(|member| Allowlist::leaf(&member));
+        let left = Allowlist::parent(&leaves[0], &leaves[1]);
+        let allowlist = Allowlist {
+            pool: Pubkey::new_unique(),
+            kind: AllowlistKind::MerkleRoot,
+            merkle_root: Allowlist::parent(&left, &leaves[2]),
+            bump: 255,
+        };
+
+        assert!(allowlist.verify(&members[0], None, &[leaves[1], leaves[2]]).is_ok());
+        assert!(allowlist.verify(&members[1], None, &[leaves[0], leaves[2]]).is_ok());
+        assert!(allowlist.verify(&members[2], None, &[left]).is_ok());
+
+        let outsider = Pubkey::new_unique();
+        assert_eq!(
+            allowlist.verify(&outsider, None, &[leaves[1], leaves[2]]).unwrap_err(),
+            AmmError::NotAllowlisted.into()
+        );
+        // An inner node is not a member
+        assert!(allowlist.verify(&members[0], None, &[leaves[2]]).is_err());
+    }
+
+    #[test]
+    fn test_allowlist_entries() {
+        let pool = Pubkey::new_unique();
+        let member = Pubkey::new_unique();
+        let allowlist = Allowlist { pool, kind: AllowlistKind::Members, merkle_root: [0; 32], bump: 255 };
+        let entry = AllowlistEntry { pool, member, bump: 255 };
+
+        assert!(allowlist.verify(&member, Some(&entry), &[]).is_ok());
+        assert!(allowlist.verify(&member, None, &[]).is_err());
+        assert!(allowlist.verify(&Pubkey::new_unique(), Some(&entry), &[]).is_err());
+        let other_pool = AllowlistEntry { pool: Pubkey::new_unique(), ..entry };
+        assert!(allowlist.verify(&member, Some(&other_pool), &[]).is_err());
+    }
+
+    fn entry() -> RegistryEntry {
+        RegistryEntry {
+            pool: Pubkey::new_unique(),
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            curve: CurveType::ConstantProduct,
+            fee_tier: 0,
+        }
+    }
+
+    #[test]
+    fn test_registry_fills_pages_in_order() {
+        let mut registry = PoolRegistry { pool_count: 0, page_count: 0, bump: 255 };
+        let mut first = RegistryPage { index: 0, bump: 255, entries: vec![] };
+        for _ in 0..REGISTRY_PAGE_CAPACITY {
+            assert_eq!(registry.next_page(), 0);
+            registry.push(&mut first, entry()).unwrap();
+        }
+        assert_eq!(registry.page_count, 1);
+        assert_eq!(registry.next_page(), 1);
+        assert_eq!(
+            registry.push(&mut first, entry()).unwrap_err(),
+            AmmError::InvalidRegistryPage.into()
+        );
+
+        let mut second = RegistryPage { index: 0, bump: 255, entries: vec![] };
+        registry.push(&mut second, entry()).unwrap();
+        assert_eq!(second.index, 1);
+        assert_eq!(registry.pool_count, REGISTRY_PAGE_CAPACITY as u64 + 1);
+        assert_eq!(registry.page_count, 2);
+
+        // A full page fits the account
+        let mut data = Vec::new();
+        first.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), RegistryPage::LEN);
+
+        // Closed pools leave a tombstone in their place
+        let closed = second.entries[0].pool;
+        second.remove(&closed).unwrap();
+        assert_eq!(second.entries[0].pool, Pubkey::default());
+        assert_eq!(second.remove(&closed).unwrap_err(), AmmError::InvalidRegistryPage.into());
+    }
+
+    #[test]
+    fn test_mint_pools_raw_append_deserializes() {
+        let mint = Pubkey::new_unique();
+        let pools = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let mut data = vec![0u8; MintPools::space(0)];
+        MintPools::write_header(&mut data, &mint, 254).unwrap();
+        for pool in &pools {
+            data.resize(data.len() + 32, 0);
+            MintPools::append(&mut data, pool).unwrap();
+        }
+
+        let list = MintPools::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(list.mint, mint);
+        assert_eq!(list.bump, 254);
+        assert_eq!(list.pools, pools);
+
+        // The account must have been grown by exactly one key
+        data.resize(data.len() + 64, 0);
+        assert!(MintPools::append(&mut data, &mint).is_err());
+    }
+
+    #[test]
+    fn test_mint_pools_raw_remove_moves_last_pool() {
+        let mint = Pubkey::new_unique();
+        let pools = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut data = vec![0u8; MintPools::space(0)];
+        MintPools::write_header(&mut data, &mint, 254).unwrap();
+        for pool in &pools {
+            data.resize(data.len() + 32, 0);
+            MintPools::append(&mut data, pool).unwrap();
+        }
+
+        assert!(!MintPools::remove(&mut data, &Pubkey::new_unique()).unwrap());
+        assert!(MintPools::remove(&mut data, &pools[0]).unwrap());
+        data.truncate(MintPools::space(2));
+        assert_eq!(MintPools::try_deserialize(&mut &data[..]).unwrap().pools, [pools[2], pools[1]]);
+
+        assert!(MintPools::remove(&mut data, &pools[1]).unwrap());
+        data.truncate(MintPools::space(1));
+        assert_eq!(MintPools::try_deserialize(&mut &data[..]).unwrap().pools, [pools[2]]);
+    }
+
+    fn farm() -> Farm {
+        Farm {
+            pool: Pubkey::default(),
+            lp_mint: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            staked_vault: Pubkey::default(),
+            reward_vault: Pubkey::default(),
+            authority: Pubkey::default(),
+            reward_rate: 100,
+            start_time: 1_000,
+            end_time: 2_000,
+            total_staked: 0,
+            acc_reward_per_share: 0,
+            last_update_time: 1_000,
+            bump: 0,
+            idle_rewards: 0,
+        }
+    }
+
+    fn position() -> FarmPosition {
+        FarmPosition {
+            farm: Pubkey::default(),
+            owner: Pubkey::default(),
+            amount: 0,
+            reward_debt: 0,
+            rewards_owed: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_no_emission_before_start() {
+        let mut farm = farm();
+        farm.total_staked = 1_000;
+        farm.update(500).unwrap();
+        assert_eq!(farm.acc_reward_per_share, 0);
+        assert_eq!(farm.last_update_time, 1_000);
+    }
+
+    #[test]
+    fn test_rewards_split_by_stake() {
+        let mut farm = farm();
+        let mut alice = position();
+        let mut bob = position();
+
+        // Alice stakes 100 at start, Bob stakes 300 after 100s
+        farm.update(1_000).unwrap();
+        alice.settle(&farm, 100).unwrap();
+        farm.total_staked = 100;
+
+        farm.update(1_100).unwrap();
+        bob.settle(&farm, 300).unwrap();
+        farm.total_staked = 400;
+
+        // Another 100s emits 10_000 more, split 1:3
+        farm.update(1_200).unwrap();
+        alice.settle(&farm, alice.amount).unwrap();
+        bob.settle(&farm, bob.amount).unwrap();
+
+        assert_eq!(alice.rewards_owed, 10_000 + 2_500);
+        assert_eq!(bob.rewards_owed, 7_500);
+    }
+
+    #[test]
+    fn test_emission_stops_at_end() {
+        let mut farm = farm();
+        let mut alice = position();
+
+        alice.settle(&farm, 1_000).unwrap();
+        farm.total_staked = 1_000;
+
+        farm.update(5_000).unwrap();
+        assert_eq!(farm.last_update_time, 2_000);
+
+        alice.settle(&farm, 0).unwrap();
+        assert_eq!(alice.rewards_owed, 100_000);
+        assert_eq!(farm.unemitted_rewards().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_idle_rewards_left_for_authority() {
+        let mut farm = farm();
+        let mut alice = position();
+
+        // Nobody stakes for the first 300s, which Alice doesn't earn
+        farm.update(1_300).unwrap();
+        assert_eq!(farm.last_update_time, 1_300);
+        assert_eq!(farm.idle_rewards, 30_000);
+        alice.settle(&farm, 100).unwrap();
+        farm.total_staked = 100;
+
+        farm.update(1_400).unwrap();
+        alice.settle(&farm, 0).unwrap();
+        farm.total_staked = 0;
+        assert_eq!(alice.rewards_owed, 10_000);
+
+        // After Alice leaves, the rest of the budget is left for the authority too
+        farm.update(5_000).unwrap();
+        assert_eq!(farm.last_update_time, 2_000);
+        assert_eq!(farm.unemitted_rewards().unwrap(), 90_000);
+    }
 }
@@ -0,0 +1,95 @@
+// programs/amm/src/wsol.rs
+//! Native SOL wrapping for pool instructions
+//!
+//! `swap`, `deposit` and `withdraw` accept native SOL on a user side whose
+//! mint is `NATIVE_MINT`: the user token account is left out and the SOL moves
+//! from or to the `user` system account. The instruction creates the pool's
+//! temporary WSOL account (PDA `[b"wsol", pool]`, authority the pool), moves
+//! the SOL through it and closes it again, returning its rent to the user.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, spl_token, TokenAccount};
+
+use crate::errors::AmmError;
+
+pub use spl_token::native_mint::ID as NATIVE_MINT;
+
+/// Seed prefix of the pool's temporary WSOL account
+pub const WSOL_SEED: &[u8] = b"wsol";
+
+/// Accounts that move native SOL through the pool's temporary WSOL account
+pub struct NativeSol<'a, 'info> {
+    pub pool: AccountInfo<'info>,
+    pub user: AccountInfo<'info>,
+    pub wsol_account: Option<AccountInfo<'info>>,
+    pub system_program: Option<AccountInfo<'info>>,
+    pub token_program: AccountInfo<'info>,
+    pub signer_seeds: &'a [&'a [&'a [u8]]], // Pool PDA seeds
+}
+
+impl<'a, 'info> NativeSol<'a, 'info> {
+    /// Wrap `amount` lamports of the user and transfer them into `vault`
+    pub fn wrap(&self, vault: &Account<'info, TokenAccount>, amount: u64) -> Result<()> {
+        require_keys_eq!(vault.mint, NATIVE_MINT, AmmError::InvalidNativeSol);
+        let wsol_account = self.wsol_account()?;
+        let system_program = self.system_program.clone().ok_or(AmmError::InvalidNativeSol)?;
+
+        let cpi_accounts = system_program::Transfer {
+            from: self.user.clone(),
+            to: wsol_account.clone(),
+        };
+        system_program::transfer(CpiContext::new(system_program, cpi_accounts), amount)?;
+
+        let cpi_accounts = token::SyncNative {
+            account: wsol_account.clone(),
+        };
+        token::sync_native(CpiContext::new(self.token_program.clone(), cpi_accounts))?;
+
+        let cpi_accounts = token::Transfer {
+            from: wsol_account,
+            to: vault.to_account_info(),
+            authority: self.pool.clone(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(self.token_program.clone(), cpi_accounts, self.signer_seeds),
+            amount,
+        )?;
+
+        self.close()
+    }
+
+    /// Transfer `amount` out of `vault` and unwrap it to the user as lamports
+    pub fn unwrap(&self, vault: &Account<'info, TokenAccount>, amount: u64) -> Result<()> {
+        require_keys_eq!(vault.mint, NATIVE_MINT, AmmError::InvalidNativeSol);
+
+        let cpi_accounts = token::Transfer {
+            from: vault.to_account_info(),
+            to: self.wsol_account()?,
+            authority: self.pool.clone(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(self.token_program.clone(), cpi_accounts, self.signer_seeds),
+            amount,
+        )?;
+
+        self.close()
+    }
+
+    /// Close the WSOL account, paying its lamports (unwrapped SOL and rent) to the user
+    fn close(&self) -> Result<()> {
+        let cpi_accounts = token::CloseAccount {
+            account: self.wsol_account()?,
+            destination: self.user.clone(),
+            authority: self.pool.clone(),
+        };
+        token::close_account(CpiContext::new_with_signer(
+            self.token_program.clone(),
+            cpi_accounts,
+            self.signer_seeds,
+        ))
+    }
+
+    fn wsol_account(&self) -> Result<AccountInfo<'info>> {
+        Ok(self.wsol_account.clone().ok_or(AmmError::InvalidNativeSol)?)
+    }
+}
@@ -0,0 +1,104 @@
+// programs/anchor-amm/tests/allowlist_test.rs
+//! Swaps on permissioned pools, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm::state::{Allowlist, AllowlistKind, AllowlistParams, Pool};
+use anchor_amm_test_support::{
+    amm_config, execute, find, initialize_accounts, install_stubs, pool, pool_accounts, pool_address, program_error,
+    swap_accounts, token_account, TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::InstructionData;
+
+#[test]
+fn test_permissioned_pool_checks_allowlist() {
+    install_stubs();
+
+    let user = Pubkey::new_unique();
+    let mut pool = Pool { permissioned: true, ..pool(CurveType::ConstantProduct) };
+    let pool_key = pool_address(&mut pool);
+
+    // A merkle allowlist of `user` and one other wallet
+    let other = Allowlist::leaf(&Pubkey::new_unique());
+    let (allowlist_key, bump) =
+        Pubkey::find_program_address(&[b"allowlist", pool_key.as_ref()], &anchor_amm::ID);
+    let allowlist = Allowlist {
+        pool: pool_key,
+        kind: AllowlistKind::MerkleRoot,
+        merkle_root: Allowlist::parent(&Allowlist::leaf(&user), &other),
+        bump,
+    };
+
+    let user_in = Pubkey::new_unique();
+    let user_out = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        token_account(user_in, pool.token_a, user, 1_000_000),
+        token_account(user_out, pool.token_b, user, 0),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        TestAccount::anchor(allowlist_key, anchor_amm::ID, &allowlist, Allowlist::LEN),
+    ]);
+    let swap = |allowlist: Option<Pubkey>, proof: Vec<[u8; 32]>| {
+        let metas = anchor_amm::accounts::Swap { allowlist, ..swap_accounts(pool_key, &pool, user, user_in, user_out) }
+            .to_account_metas(None);
+        let data = anchor_amm::instruction::Swap { amount_in: 1_000_000, min_amount_out: 0, proof, referral_fee_bps: 0 }
+            .data();
+        (metas, data)
+    };
+
+    let not_allowlisted = program_error(AmmError::NotAllowlisted);
+    let (metas, data) = swap(None, vec![]);
+    assert_eq!(execute(&mut accounts, &metas, &data).unwrap_err(), not_allowlisted);
+    let (metas, data) = swap(Some(allowlist_key), vec![]);
+    assert_eq!(execute(&mut accounts, &metas, &data).unwrap_err(), not_allowlisted);
+    let (metas, data) = swap(Some(allowlist_key), vec![other]);
+    execute(&mut accounts, &metas, &data).unwrap();
+
+    assert!(find(&accounts, user_out).token_amount() > 0);
+}
+
+#[test]
+fn test_initialize_creates_allowlist() {
+    install_stubs();
+
+    let (mut accounts, mut metas) = initialize_accounts(&amm_config(0), Pubkey::new_unique());
+    let (allowlist_key, _) = Pubkey::find_program_address(&[b"allowlist", metas.pool.as_ref()], &anchor_amm::ID);
+    accounts.push(TestAccount { lamports: 0, ..TestAccount::new(allowlist_key, system_program::ID, vec![]) });
+    let params = AllowlistParams { kind: AllowlistKind::MerkleRoot, merkle_root: [7; 32] };
+    let initialize = |allowlist_params: Option<AllowlistParams>| {
+        anchor_amm::instruction::Initialize {
+            dynamic_fee: None,
+            curve: CurveType::ConstantProduct,
+            oracle_peg: None,
+            lmsr_b: None,
+            lbp: None,
+            hook: None,
+            rate_a: None,
+            rate_b: None,
+            hybrid_target_price: None,
+            lp_name: "A-B LP".into(),
+            lp_symbol: "ABLP".into(),
+            allowlist_params,
+        }
+        .data()
+    };
+
+    // The allowlist account and its parameters come together
+    let without_account = metas.to_account_metas(None);
+    assert_eq!(
+        execute(&mut accounts, &without_account, &initialize(Some(params))).unwrap_err(),
+        program_error(AmmError::InvalidAllowlist)
+    );
+
+    metas.allowlist = Some(allowlist_key);
+    let with_account = metas.to_account_metas(None);
+    execute(&mut accounts, &with_account, &initialize(Some(params))).unwrap();
+
+    let pool = Pool::try_deserialize(&mut &find(&accounts, metas.pool).data[..]).unwrap();
+    assert!(pool.permissioned);
+    let allowlist = Allowlist::try_deserialize(&mut &find(&accounts, allowlist_key).data[..]).unwrap();
+    assert_eq!(allowlist.pool, metas.pool);
+    assert_eq!(allowlist.kind, AllowlistKind::MerkleRoot);
+    assert_eq!(allowlist.merkle_root, [7; 32]);
+}
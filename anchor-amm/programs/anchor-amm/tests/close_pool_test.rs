@@ -0,0 +1,241 @@
+// programs/anchor-amm/tests/close_pool_test.rs
+//! Closing empty pools, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm::state::{Allowlist, AllowlistKind, MintPools, OrderBook, Pool, RegistryEntry, RegistryPage};
+use anchor_amm_test_support::{
+    execute, find, install_stubs, mint_account, pool, pool_accounts, pool_address, program_data_key, program_error,
+    token_account, TestAccount, ADMIN,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+};
+use anchor_spl::token_interface::spl_pod::optional_keys::OptionalNonZeroPubkey;
+
+/// An empty Token-2022 LP mint that `pool_key` can close, like `initialize` creates
+fn closable_lp_mint(key: Pubkey, pool_key: Pubkey) -> TestAccount {
+    let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::MintCloseAuthority,
+    ])
+    .unwrap();
+    let mut data = vec![0u8; len];
+    let mut state = StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data).unwrap();
+    state.init_extension::<MintCloseAuthority>(true).unwrap().close_authority =
+        OptionalNonZeroPubkey::try_from(Some(pool_key)).unwrap();
+    state.base = spl_token_2022::state::Mint {
+        mint_authority: COption::Some(pool_key),
+        supply: 0,
+        decimals: 7,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    TestAccount::new(key, spl_token_2022::ID, data)
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &anchor_amm::ID)
+}
+
+/// A rent-exempt `MintPools` list of `mint`
+fn mint_pools(mint: Pubkey, pools: &[Pubkey]) -> TestAccount {
+    let (key, bump) = pda(&[b"mint_pools", mint.as_ref()]);
+    let mut data = vec![0u8; MintPools::space(0)];
+    MintPools::write_header(&mut data, &mint, bump).unwrap();
+    for pool in pools {
+        data.resize(data.len() + 32, 0);
+        MintPools::append(&mut data, pool).unwrap();
+    }
+    let lamports = Rent::default().minimum_balance(data.len());
+    TestAccount { lamports, ..TestAccount::new(key, anchor_amm::ID, data) }
+}
+
+fn registry_entry(pool: Pubkey, token_a: Pubkey, token_b: Pubkey) -> RegistryEntry {
+    RegistryEntry { pool, token_a, token_b, curve: CurveType::ConstantProduct, fee_tier: 0 }
+}
+
+struct Setup {
+    accounts: Vec<TestAccount>,
+    pool_key: Pubkey,
+    pool: Pool,
+    creator_a: Pubkey,
+    creator_b: Pubkey,
+    other_pool: Pubkey,
+    registry_page: Pubkey,
+    order_book: Option<Pubkey>,
+    allowlist: Option<Pubkey>,
+}
+
+/// An emptied pool with 5 units of token A left behind
+///
+/// The pool is listed on registry page 0 after `other_pool`, and on the
+/// `MintPools` list of token A with it. Token B's list was never created.
+fn setup(lp_mint: fn(Pubkey, Pubkey) -> TestAccount) -> Setup {
+    install_stubs();
+
+    let mut pool = Pool { reserve_a: 0, reserve_b: 0, lp_supply: 0, ..pool(CurveType::ConstantProduct) };
+    let pool_key = pool_address(&mut pool);
+    let other_pool = Pubkey::new_unique();
+    let (registry_page, page_bump) = pda(&[b"registry_page", &0u32.to_le_bytes()]);
+    let page = RegistryPage {
+        index: 0,
+        bump: page_bump,
+        entries: vec![
+            registry_entry(other_pool, pool.token_a, Pubkey::new_unique()),
+            registry_entry(pool_key, pool.token_a, pool.token_b),
+        ],
+    };
+    let creator_a = Pubkey::new_unique();
+    let creator_b = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts[1] = token_account(pool.vault_a, pool.token_a, pool_key, 5);
+    accounts.extend([
+        lp_mint(pool.lp_mint, pool_key),
+        token_account(creator_a, pool.token_a, pool.creator, 0),
+        token_account(creator_b, pool.token_b, pool.creator, 0),
+        TestAccount::new(pool.creator, Pubkey::default(), vec![]),
+        TestAccount::new(ADMIN, Pubkey::default(), vec![]),
+        TestAccount::program(spl_token_2022::ID),
+        TestAccount::anchor(registry_page, anchor_amm::ID, &page, RegistryPage::LEN),
+        mint_pools(pool.token_a, &[pool_key, other_pool]),
+        TestAccount::new(pda(&[b"mint_pools", pool.token_b.as_ref()]).0, Pubkey::default(), vec![]),
+    ]);
+    Setup {
+        accounts,
+        pool_key,
+        pool,
+        creator_a,
+        creator_b,
+        other_pool,
+        registry_page,
+        order_book: None,
+        allowlist: None,
+    }
+}
+
+impl Setup {
+    fn close(&mut self, authority: Pubkey, as_admin: bool) -> std::result::Result<(), ProgramError> {
+        let lp_token_program = find(&self.accounts, self.pool.lp_mint).owner;
+        let metas = anchor_amm::accounts::ClosePool {
+            pool: self.pool_key,
+            vault_a: self.pool.vault_a,
+            vault_b: self.pool.vault_b,
+            lp_mint: self.pool.lp_mint,
+            creator_a: self.creator_a,
+            creator_b: self.creator_b,
+            registry_page: Some(self.registry_page),
+            mint_pools_a: pda(&[b"mint_pools", self.pool.token_a.as_ref()]).0,
+            mint_pools_b: pda(&[b"mint_pools", self.pool.token_b.as_ref()]).0,
+            order_book: self.order_book,
+            allowlist: self.allowlist,
+            creator: self.pool.creator,
+            authority,
+            program: anchor_amm::ID,
+            program_data: as_admin.then(program_data_key),
+            token_program: spl_token::ID,
+            lp_token_program,
+        }
+        .to_account_metas(None);
+        execute(&mut self.accounts, &metas, &anchor_amm::instruction::ClosePool {}.data())
+    }
+}
+
+#[test]
+fn test_creator_closes_pool_and_lp_mint() {
+    let mut setup = setup(closable_lp_mint);
+    let rent = [setup.pool_key, setup.pool.vault_a, setup.pool.vault_b, setup.pool.lp_mint]
+        .map(|key| find(&setup.accounts, key).lamports)
+        .iter()
+        .sum::<u64>();
+    let balance = find(&setup.accounts, setup.pool.creator).lamports;
+    // The rent of the key taken off token A's list
+    let list_rent = Rent::default().minimum_balance(MintPools::space(2))
+        - Rent::default().minimum_balance(MintPools::space(1));
+
+    setup.close(setup.pool.creator, false).unwrap();
+
+    assert_eq!(find(&setup.accounts, setup.creator_a).token_amount(), 5);
+    assert_eq!(find(&setup.accounts, setup.pool.lp_mint).lamports, 0);
+    assert_eq!(find(&setup.accounts, setup.pool.creator).lamports, balance + rent + list_rent);
+}
+
+#[test]
+fn test_closed_pool_leaves_discovery() {
+    let mut setup = setup(closable_lp_mint);
+    setup.close(setup.pool.creator, false).unwrap();
+
+    // The entry is tombstoned in place, and the other pool keeps its own
+    let page = find(&setup.accounts, setup.registry_page);
+    let page = RegistryPage::try_deserialize(&mut &page.data[..]).unwrap();
+    assert_eq!(page.entries.len(), 2);
+    assert_eq!(page.entries[0].pool, setup.other_pool);
+    assert_eq!(page.entries[1].pool, Pubkey::default());
+
+    let list = find(&setup.accounts, pda(&[b"mint_pools", setup.pool.token_a.as_ref()]).0);
+    assert_eq!(list.data.len(), MintPools::space(1));
+    assert_eq!(MintPools::try_deserialize(&mut &list.data[..]).unwrap().pools, [setup.other_pool]);
+}
+
+#[test]
+fn test_order_book_and_allowlist_close_with_the_pool() {
+    let mut setup = setup(closable_lp_mint);
+    setup.pool.permissioned = true;
+    setup.accounts[0] = TestAccount::anchor(setup.pool_key, anchor_amm::ID, &setup.pool, Pool::LEN);
+    let (order_book, book_bump) = pda(&[b"order_book", setup.pool_key.as_ref()]);
+    let (allowlist, list_bump) = pda(&[b"allowlist", setup.pool_key.as_ref()]);
+    let book = OrderBook { pool: setup.pool_key, bump: book_bump, orders: vec![] };
+    let list = Allowlist { pool: setup.pool_key, kind: AllowlistKind::Members, merkle_root: [0; 32], bump: list_bump };
+    setup.accounts.extend([
+        TestAccount::anchor(order_book, anchor_amm::ID, &book, OrderBook::LEN),
+        TestAccount::anchor(allowlist, anchor_amm::ID, &list, Allowlist::LEN),
+    ]);
+    setup.order_book = Some(order_book);
+
+    // A permissioned pool can't be closed without its allowlist
+    assert_eq!(setup.close(setup.pool.creator, false).unwrap_err(), program_error(AmmError::InvalidAllowlist));
+
+    setup.allowlist = Some(allowlist);
+    let rent = find(&setup.accounts, order_book).lamports + find(&setup.accounts, allowlist).lamports;
+    let balance = find(&setup.accounts, setup.pool.creator).lamports;
+    setup.close(setup.pool.creator, false).unwrap();
+
+    assert_eq!(find(&setup.accounts, order_book).lamports, 0);
+    assert_eq!(find(&setup.accounts, allowlist).lamports, 0);
+    assert!(find(&setup.accounts, setup.pool.creator).lamports > balance + rent);
+}
+
+#[test]
+fn test_upgrade_authority_closes_abandoned_pool() {
+    let stranger = Pubkey::new_unique();
+    let mut setup = setup(closable_lp_mint);
+    setup.accounts.push(TestAccount::new(stranger, Pubkey::default(), vec![]));
+
+    let unauthorized = program_error(AmmError::Unauthorized);
+    assert_eq!(setup.close(stranger, false).unwrap_err(), unauthorized);
+    assert_eq!(setup.close(stranger, true).unwrap_err(), unauthorized);
+
+    // The rent still goes to the creator
+    let balance = find(&setup.accounts, setup.pool.creator).lamports;
+    setup.close(ADMIN, true).unwrap();
+    assert_eq!(find(&setup.accounts, setup.pool_key).lamports, 0);
+    assert!(find(&setup.accounts, setup.pool.creator).lamports > balance);
+}
+
+#[test]
+fn test_older_lp_mint_stays_open() {
+    // SPL Token mints have no close authority
+    let mut setup = setup(|key, pool_key| mint_account(key, pool_key, 0));
+    let rent = find(&setup.accounts, setup.pool.lp_mint).lamports;
+
+    setup.close(setup.pool.creator, false).unwrap();
+
+    assert_eq!(find(&setup.accounts, setup.pool_key).lamports, 0);
+    assert_eq!(find(&setup.accounts, setup.pool.lp_mint).lamports, rent);
+}
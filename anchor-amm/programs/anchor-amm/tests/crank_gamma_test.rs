@@ -0,0 +1,57 @@
+// programs/anchor-amm/tests/crank_gamma_test.rs
+//! `crank_gamma` executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::hybrid_cfmm::{
+    GAMMA_CRANK_BOUNTY_LAMPORTS, GAMMA_CRANK_INTERVAL_SLOTS, MAX_GAMMA_STEP,
+};
+use anchor_amm::instructions::strategy::{CurveType, HybridState};
+use anchor_amm::state::Pool;
+use anchor_amm_test_support::{execute, install_stubs, pool, program_error, TestAccount, SLOT, UNIX_TIMESTAMP};
+use anchor_lang::prelude::*;
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData};
+
+#[test]
+fn test_crank_gamma_pays_keeper_bounty() {
+    install_stubs();
+
+    // The average price sits 1000x above the target, so gamma rises by a full step
+    let pool = Pool {
+        hybrid: HybridState {
+            ema_price: 1_000_000_000,
+            last_sample_time: UNIX_TIMESTAMP - 60,
+            ..HybridState::new(HybridState::DEFAULT_TARGET_PRICE).unwrap()
+        },
+        ..pool(CurveType::HybridCfmm)
+    };
+    let pool_key = Pubkey::new_unique();
+    let rent = Rent::default().minimum_balance(Pool::LEN);
+    let keeper = Pubkey::new_unique();
+    let mut accounts = vec![
+        TestAccount { lamports: rent + 15_000, ..TestAccount::anchor(pool_key, anchor_amm::ID, &pool, Pool::LEN) },
+        TestAccount { lamports: 0, ..TestAccount::new(keeper, Pubkey::default(), vec![]) },
+        TestAccount::program(anchor_amm::ID),
+    ];
+    let metas = anchor_amm::accounts::CrankGamma { pool: pool_key, keeper }.to_account_metas(None);
+    let data = anchor_amm::instruction::CrankGamma {}.data();
+    execute(&mut accounts, &metas, &data).unwrap();
+
+    let hybrid = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap().hybrid;
+    assert_eq!(hybrid.gamma, pool.hybrid.gamma + MAX_GAMMA_STEP);
+    assert_eq!(hybrid.last_crank_slot, SLOT);
+    assert_eq!(accounts[0].lamports, rent + 15_000 - GAMMA_CRANK_BOUNTY_LAMPORTS);
+    assert_eq!(accounts[1].lamports, GAMMA_CRANK_BOUNTY_LAMPORTS);
+
+    // Rate limited until the interval has passed
+    assert_eq!(execute(&mut accounts, &metas, &data).unwrap_err(), program_error(AmmError::CrankTooEarly));
+
+    // Once the pool can no longer fund the bounty, cranks still move gamma for free
+    let mut state = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+    state.hybrid.last_crank_slot = SLOT - GAMMA_CRANK_INTERVAL_SLOTS;
+    accounts[0].data.clear();
+    state.try_serialize(&mut accounts[0].data).unwrap();
+    execute(&mut accounts, &metas, &data).unwrap();
+
+    let hybrid = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap().hybrid;
+    assert_eq!(hybrid.gamma, pool.hybrid.gamma + 2 * MAX_GAMMA_STEP);
+    assert_eq!(accounts[1].lamports, GAMMA_CRANK_BOUNTY_LAMPORTS);
+}
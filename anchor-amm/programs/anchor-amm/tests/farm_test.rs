@@ -0,0 +1,218 @@
+// programs/anchor-amm/tests/farm_test.rs
+//! Farm creation and reward reclaims, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm::state::{Farm, FarmPosition};
+use anchor_amm_test_support::{
+    execute, find, install_stubs, mint_account, pool, pool_accounts, program_data_key, program_error, token_account,
+    TestAccount, ADMIN, UNIX_TIMESTAMP,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token;
+
+const REWARD_RATE: u64 = 10;
+
+/// Create a farm of a new reward mint on a pool as `authority`, or as the pool creator
+fn create_farm(authority: Option<Pubkey>, as_admin: bool, start_time: i64) -> std::result::Result<(), ProgramError> {
+    install_stubs();
+
+    let pool_key = Pubkey::new_unique();
+    let pool = pool(CurveType::ConstantProduct);
+    let authority = authority.unwrap_or(pool.creator);
+    let reward_mint = Pubkey::new_unique();
+    let farm = Pubkey::find_program_address(&[b"farm", pool_key.as_ref(), reward_mint.as_ref()], &anchor_amm::ID).0;
+    let staked_vault = Pubkey::new_unique();
+    let reward_vault = Pubkey::new_unique();
+    let authority_reward = Pubkey::new_unique();
+
+    let uncreated = |key| TestAccount { lamports: 0, ..TestAccount::new(key, system_program::ID, vec![]) };
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        mint_account(pool.lp_mint, pool_key, pool.lp_supply),
+        mint_account(reward_mint, Pubkey::new_unique(), 1_000_000),
+        token_account(authority_reward, reward_mint, authority, 1_000_000),
+        TestAccount::new(authority, system_program::ID, vec![]),
+        TestAccount::program(system_program::ID),
+        uncreated(farm),
+        uncreated(staked_vault),
+        uncreated(reward_vault),
+    ]);
+    let metas = anchor_amm::accounts::CreateFarm {
+        pool: pool_key,
+        farm,
+        lp_mint: pool.lp_mint,
+        reward_mint,
+        staked_vault,
+        reward_vault,
+        authority_reward,
+        authority,
+        program: anchor_amm::ID,
+        program_data: as_admin.then(program_data_key),
+        system_program: system_program::ID,
+        token_program: spl_token::ID,
+        lp_token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    let data = anchor_amm::instruction::CreateFarm { reward_rate: REWARD_RATE, start_time, end_time: start_time + 1_000 }
+        .data();
+    execute(&mut accounts, &metas, &data)?;
+
+    assert_eq!(find(&accounts, reward_vault).token_amount(), REWARD_RATE * 1_000);
+    Ok(())
+}
+
+#[test]
+fn test_create_farm_checks_authority_and_start() {
+    create_farm(None, false, UNIX_TIMESTAMP).unwrap();
+    create_farm(Some(ADMIN), true, UNIX_TIMESTAMP + 100).unwrap();
+
+    let unauthorized = program_error(AmmError::Unauthorized);
+    assert_eq!(create_farm(Some(Pubkey::new_unique()), false, UNIX_TIMESTAMP).unwrap_err(), unauthorized);
+    assert_eq!(create_farm(Some(Pubkey::new_unique()), true, UNIX_TIMESTAMP).unwrap_err(), unauthorized);
+
+    // A start in the past would fund rewards nobody could earn
+    assert_eq!(
+        create_farm(None, false, UNIX_TIMESTAMP - 1).unwrap_err(),
+        program_error(AmmError::InvalidFarmSchedule)
+    );
+}
+
+struct Setup {
+    accounts: Vec<TestAccount>,
+    farm_key: Pubkey,
+    farm: Farm,
+    authority_reward: Pubkey,
+}
+
+/// A farm of 10 rewards per second over `[start_time, start_time + 1_000)` with nothing staked,
+/// whose stakers earned the rewards up to `last_update_time` and harvested none
+fn setup(start_time: i64, last_update_time: i64) -> Setup {
+    install_stubs();
+
+    let pool_key = Pubkey::new_unique();
+    let reward_mint = Pubkey::new_unique();
+    let (farm_key, bump) =
+        Pubkey::find_program_address(&[b"farm", pool_key.as_ref(), reward_mint.as_ref()], &anchor_amm::ID);
+    let farm = Farm {
+        pool: pool_key,
+        lp_mint: Pubkey::new_unique(),
+        reward_mint,
+        staked_vault: Pubkey::new_unique(),
+        reward_vault: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        reward_rate: REWARD_RATE,
+        start_time,
+        end_time: start_time + 1_000,
+        total_staked: 0,
+        acc_reward_per_share: 0,
+        last_update_time,
+        bump,
+        idle_rewards: 0,
+    };
+    let authority_reward = Pubkey::new_unique();
+    let accounts = vec![
+        TestAccount::anchor(farm_key, anchor_amm::ID, &farm, Farm::LEN),
+        token_account(farm.reward_vault, reward_mint, farm_key, REWARD_RATE * 1_000),
+        token_account(farm.staked_vault, farm.lp_mint, farm_key, 0),
+        token_account(authority_reward, reward_mint, farm.authority, 0),
+        mint_account(farm.lp_mint, pool_key, 1_000),
+        TestAccount::new(farm.authority, system_program::ID, vec![]),
+        TestAccount::program(anchor_amm::ID),
+        TestAccount::program(system_program::ID),
+        TestAccount::program(spl_token::ID),
+    ];
+    Setup { accounts, farm_key, farm, authority_reward }
+}
+
+impl Setup {
+    fn reclaim(&mut self) -> std::result::Result<(), ProgramError> {
+        let metas = anchor_amm::accounts::ReclaimFarmRewards {
+            farm: self.farm_key,
+            reward_vault: self.farm.reward_vault,
+            authority_reward: self.authority_reward,
+            authority: self.farm.authority,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None);
+        execute(&mut self.accounts, &metas, &anchor_amm::instruction::ReclaimFarmRewards {}.data())
+    }
+
+    /// Stake `amount` LP tokens from a new wallet, returning its position and LP account
+    fn stake(&mut self, amount: u64) -> (std::result::Result<(), ProgramError>, Pubkey, Pubkey) {
+        let owner = Pubkey::new_unique();
+        let user_lp = Pubkey::new_unique();
+        let position =
+            Pubkey::find_program_address(&[b"farm_position", self.farm_key.as_ref(), owner.as_ref()], &anchor_amm::ID)
+                .0;
+        self.accounts.extend([
+            token_account(user_lp, self.farm.lp_mint, owner, amount),
+            TestAccount::new(owner, system_program::ID, vec![]),
+            TestAccount { lamports: 0, ..TestAccount::new(position, system_program::ID, vec![]) },
+        ]);
+        let metas = anchor_amm::accounts::StakeLp {
+            farm: self.farm_key,
+            position,
+            staked_vault: self.farm.staked_vault,
+            lp_mint: self.farm.lp_mint,
+            user_lp,
+            owner,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None);
+        let data = anchor_amm::instruction::StakeLp { amount }.data();
+        (execute(&mut self.accounts, &metas, &data), position, user_lp)
+    }
+}
+
+#[test]
+fn test_reclaim_returns_unemitted_rewards() {
+    // Stakers earned the first 400s, then everyone left
+    let mut setup = setup(0, 400);
+    setup.reclaim().unwrap();
+    assert_eq!(find(&setup.accounts, setup.authority_reward).token_amount(), REWARD_RATE * 600);
+    assert_eq!(find(&setup.accounts, setup.farm.reward_vault).token_amount(), REWARD_RATE * 400);
+
+    // Nothing is left for a second reclaim
+    setup.reclaim().unwrap();
+    assert_eq!(find(&setup.accounts, setup.authority_reward).token_amount(), REWARD_RATE * 600);
+    let farm = Farm::try_deserialize(&mut &setup.accounts[0].data[..]).unwrap();
+    assert_eq!(farm.last_update_time, farm.end_time);
+}
+
+#[test]
+fn test_reclaim_waits_for_end() {
+    let mut setup = setup(UNIX_TIMESTAMP - 100, UNIX_TIMESTAMP - 100);
+    assert_eq!(setup.reclaim().unwrap_err(), program_error(AmmError::FarmNotEnded));
+    assert_eq!(find(&setup.accounts, setup.authority_reward).token_amount(), 0);
+}
+
+#[test]
+fn test_stake_stops_at_end() {
+    // Staking after the end would collect the rewards left for the authority
+    let mut setup = setup(0, 400);
+    let (result, _, user_lp) = setup.stake(100);
+    assert_eq!(result.unwrap_err(), program_error(AmmError::FarmEnded));
+    assert_eq!(find(&setup.accounts, user_lp).token_amount(), 100);
+}
+
+#[test]
+fn test_late_stake_does_not_earn_idle_rewards() {
+    // Nothing was staked for the first 300s of the farm
+    let mut setup = setup(UNIX_TIMESTAMP - 300, UNIX_TIMESTAMP - 300);
+    let (result, position, _) = setup.stake(100);
+    result.unwrap();
+
+    let farm = Farm::try_deserialize(&mut &setup.accounts[0].data[..]).unwrap();
+    assert_eq!(farm.idle_rewards, REWARD_RATE * 300);
+    assert_eq!(farm.last_update_time, UNIX_TIMESTAMP);
+    assert_eq!(farm.acc_reward_per_share, 0);
+    assert_eq!(farm.total_staked, 100);
+
+    // The staker owes nothing yet and the authority keeps those 300s
+    let position = FarmPosition::try_deserialize(&mut &find(&setup.accounts, position).data[..]).unwrap();
+    assert_eq!(farm.pending_rewards(position.amount, position.reward_debt).unwrap(), 0);
+    assert_eq!(farm.unemitted_rewards().unwrap(), REWARD_RATE * 1_000);
+}
@@ -0,0 +1,58 @@
+// programs/anchor-amm/tests/fee_tier_test.rs
+//! Pools per pair and fee tier, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm::state::Pool;
+use anchor_amm_test_support::{amm_config, execute, find, initialize_accounts, install_stubs, program_error, TestAccount};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::InstructionData;
+
+#[test]
+fn test_pair_has_one_pool_per_tier() {
+    install_stubs();
+
+    let (mut accounts, metas) = initialize_accounts(&amm_config(3), Pubkey::new_unique());
+    let data = anchor_amm::instruction::Initialize {
+        dynamic_fee: None,
+        curve: CurveType::ConstantProduct,
+        oracle_peg: None,
+        lmsr_b: None,
+        lbp: None,
+        hook: None,
+        rate_a: None,
+        rate_b: None,
+        hybrid_target_price: None,
+        lp_name: "A-B LP".into(),
+        lp_symbol: "ABLP".into(),
+        allowlist_params: None,
+    }
+    .data();
+
+    // The same pair the other way round would be a second pool in the tier
+    let (pool_key, token_a, token_b) = (metas.pool, metas.token_a, metas.token_b);
+    let in_order = metas.to_account_metas(None);
+    assert!(token_a < token_b);
+    let seeds: &[&[u8]] = &[b"pool", token_b.as_ref(), token_a.as_ref(), &3u16.to_le_bytes()];
+    let reversed_pool = Pubkey::find_program_address(seeds, &anchor_amm::ID).0;
+    accounts.push(TestAccount { lamports: 0, ..TestAccount::new(reversed_pool, system_program::ID, vec![]) });
+    let reversed = anchor_amm::accounts::Initialize {
+        pool: reversed_pool,
+        token_a: token_b,
+        token_b: token_a,
+        mint_pools_a: metas.mint_pools_b,
+        mint_pools_b: metas.mint_pools_a,
+        launch_a: metas.launch_b,
+        launch_b: metas.launch_a,
+        ..metas
+    };
+    assert_eq!(
+        execute(&mut accounts, &reversed.to_account_metas(None), &data).unwrap_err(),
+        program_error(AmmError::InvalidTokenOrder)
+    );
+
+    execute(&mut accounts, &in_order, &data).unwrap();
+    let pool = Pool::try_deserialize(&mut &find(&accounts, pool_key).data[..]).unwrap();
+    assert_eq!((pool.token_a, pool.token_b, pool.fee_tier), (token_a, token_b, 3));
+    assert!(!pool.legacy_seeds);
+}
@@ -0,0 +1,120 @@
+// programs/anchor-amm/tests/launch_test.rs
+//! Launch curves and their graduation pools, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm::state::{Launch, Pool};
+use anchor_amm_test_support::{
+    amm_config, execute, find, initialize_accounts, install_stubs, program_error, token_account, TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token;
+
+const TOTAL_SUPPLY: u64 = 1_000_000_000;
+
+/// A launch of `token_mint` starting at 1 quote per 2 tokens, sold out at 1 quote per token
+fn launch(token_mint: Pubkey, quote_mint: Pubkey) -> (Pubkey, Launch) {
+    let (key, bump) = Pubkey::find_program_address(&[b"launch", token_mint.as_ref()], &anchor_amm::ID);
+    let launch = Launch {
+        creator: Pubkey::new_unique(),
+        token_mint,
+        quote_mint,
+        token_vault: Pubkey::new_unique(),
+        quote_vault: Pubkey::new_unique(),
+        total_supply: TOTAL_SUPPLY,
+        real_token_reserve: TOTAL_SUPPLY,
+        real_quote_reserve: 0,
+        virtual_token_reserve: TOTAL_SUPPLY,
+        virtual_quote_reserve: TOTAL_SUPPLY,
+        fee: 0,
+        graduation_market_cap: TOTAL_SUPPLY * 9 / 10,
+        graduated: false,
+        bump,
+        amm_config: Pubkey::new_unique(),
+    };
+    (key, launch)
+}
+
+#[test]
+fn test_initialize_rejects_launching_token() {
+    install_stubs();
+
+    // Graduation creates this pool, so initialize can't take it first
+    let (mut accounts, metas) = initialize_accounts(&amm_config(0), Pubkey::new_unique());
+    let (launch_key, mut launch) = launch(metas.token_a, metas.token_b);
+    assert_eq!(launch_key, metas.launch_a);
+    let data = anchor_amm::instruction::Initialize {
+        dynamic_fee: None,
+        curve: CurveType::ConstantProduct,
+        oracle_peg: None,
+        lmsr_b: None,
+        lbp: None,
+        hook: None,
+        rate_a: None,
+        rate_b: None,
+        hybrid_target_price: None,
+        lp_name: "A-B LP".into(),
+        lp_symbol: "ABLP".into(),
+        allowlist_params: None,
+    }
+    .data();
+
+    let launch_account = accounts.iter_mut().find(|account| account.key == launch_key).unwrap();
+    *launch_account = TestAccount::anchor(launch_key, anchor_amm::ID, &launch, Launch::LEN);
+    assert_eq!(
+        execute(&mut accounts, &metas.to_account_metas(None), &data).unwrap_err(),
+        program_error(AmmError::LaunchInProgress)
+    );
+
+    // Once graduated, the token can be paired in other fee tiers
+    launch.graduated = true;
+    let launch_account = accounts.iter_mut().find(|account| account.key == launch_key).unwrap();
+    *launch_account = TestAccount::anchor(launch_key, anchor_amm::ID, &launch, Launch::LEN);
+    execute(&mut accounts, &metas.to_account_metas(None), &data).unwrap();
+    assert!(Pool::try_deserialize(&mut &find(&accounts, metas.pool).data[..]).is_ok());
+}
+
+#[test]
+fn test_launch_buys_keep_graduation_liquidity() {
+    install_stubs();
+
+    let user = Pubkey::new_unique();
+    let (launch_key, launch) = launch(Pubkey::new_unique(), Pubkey::new_unique());
+    let user_token = Pubkey::new_unique();
+    let user_quote = Pubkey::new_unique();
+    let mut accounts = vec![
+        TestAccount::anchor(launch_key, anchor_amm::ID, &launch, Launch::LEN),
+        token_account(launch.token_vault, launch.token_mint, launch_key, TOTAL_SUPPLY),
+        token_account(launch.quote_vault, launch.quote_mint, launch_key, 0),
+        token_account(user_token, launch.token_mint, user, 0),
+        token_account(user_quote, launch.quote_mint, user, TOTAL_SUPPLY),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        TestAccount::program(anchor_amm::ID),
+        TestAccount::program(spl_token::ID),
+    ];
+    let metas = anchor_amm::accounts::SwapLaunch {
+        launch: launch_key,
+        token_vault: launch.token_vault,
+        quote_vault: launch.quote_vault,
+        user_token,
+        user_quote,
+        user,
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    let buy = |amount_in| anchor_amm::instruction::SwapLaunch { amount_in, min_amount_out: 0, buy: true }.data();
+
+    // The curve sells out after about 414M of quote, at a price of 1
+    execute(&mut accounts, &metas, &buy(300_000_000)).unwrap();
+    assert_eq!(
+        execute(&mut accounts, &metas, &buy(200_000_000)).unwrap_err(),
+        program_error(AmmError::LaunchSoldOut)
+    );
+    execute(&mut accounts, &metas, &buy(100_000_000)).unwrap();
+
+    let launch = Launch::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+    assert_eq!(launch.real_quote_reserve, 400_000_000);
+    assert!(launch.real_token_reserve >= launch.graduation_token_amount().unwrap());
+    assert!(launch.market_cap().unwrap() >= launch.graduation_market_cap);
+    assert_eq!(find(&accounts, user_token).token_amount(), TOTAL_SUPPLY - launch.real_token_reserve);
+}
@@ -0,0 +1,69 @@
+// programs/anchor-amm/tests/lp_mint_test.rs
+//! LP mint checks of withdrawals, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm_test_support::{
+    execute, find, install_stubs, mint_account, pool, pool_accounts, pool_address, program_error, token_account,
+    TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token;
+
+#[test]
+fn test_withdraw_burns_the_pool_lp_mint() {
+    install_stubs();
+
+    let user = Pubkey::new_unique();
+    let mut pool = pool(CurveType::ConstantProduct);
+    let pool_key = pool_address(&mut pool);
+    let lp_amount = pool.lp_supply / 10;
+
+    // Anyone can create a mint with the pool as its authority
+    let fake_lp_mint = Pubkey::new_unique();
+    let fake_user_lp = Pubkey::new_unique();
+    let user_lp = Pubkey::new_unique();
+    let user_a = Pubkey::new_unique();
+    let user_b = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        mint_account(pool.lp_mint, pool_key, pool.lp_supply),
+        mint_account(fake_lp_mint, pool_key, pool.lp_supply),
+        token_account(user_lp, pool.lp_mint, user, lp_amount),
+        token_account(fake_user_lp, fake_lp_mint, user, pool.lp_supply),
+        token_account(user_a, pool.token_a, user, 0),
+        token_account(user_b, pool.token_b, user, 0),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+    ]);
+    let withdraw = |lp_mint: Pubkey, user_lp: Pubkey| {
+        anchor_amm::accounts::Withdraw {
+            pool: pool_key,
+            vault_a: pool.vault_a,
+            vault_b: pool.vault_b,
+            lp_mint,
+            user_lp,
+            user_a: Some(user_a),
+            user_b: Some(user_b),
+            user,
+            hook_program: None,
+            allowlist: None,
+            allowlist_entry: None,
+            market: None,
+            token_program: spl_token::ID,
+            lp_token_program: spl_token::ID,
+            wsol_account: None,
+            native_mint: None,
+            system_program: None,
+        }
+        .to_account_metas(None)
+    };
+    let data = anchor_amm::instruction::Withdraw { lp_amount, proof: vec![] }.data();
+
+    let metas = withdraw(fake_lp_mint, fake_user_lp);
+    assert_eq!(execute(&mut accounts, &metas, &data).unwrap_err(), program_error(AmmError::InvalidLpMint));
+
+    let metas = withdraw(pool.lp_mint, user_lp);
+    execute(&mut accounts, &metas, &data).unwrap();
+    assert_eq!(find(&accounts, user_lp).token_amount(), 0);
+    assert_eq!(find(&accounts, user_a).token_amount(), pool.reserve_a / 10);
+}
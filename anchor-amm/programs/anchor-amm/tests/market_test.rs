@@ -0,0 +1,333 @@
+// programs/anchor-amm/tests/market_test.rs
+//! Prediction markets and their LMSR pools, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm::state::{MarketOutcome, Pool, PredictionMarket};
+use anchor_amm_test_support::{
+    amm_config, execute, find, initialize_accounts, install_stubs, mint_account, pool, pool_accounts,
+    pool_address, program_error, token_account, TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token;
+
+const MARKET_ID: u64 = 1;
+
+/// A market created through `create_market`, with a user holding 1_000 collateral
+struct Setup {
+    accounts: Vec<TestAccount>,
+    market_key: Pubkey,
+    market: PredictionMarket,
+    user: Pubkey,
+    user_collateral: Pubkey,
+    user_yes: Pubkey,
+    user_no: Pubkey,
+}
+
+fn setup() -> Setup {
+    install_stubs();
+
+    let creator = Pubkey::new_unique();
+    let resolver = Pubkey::new_unique();
+    let collateral_mint = Pubkey::new_unique();
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &anchor_amm::ID).0;
+    let market_key = pda(&[b"market", creator.as_ref(), &MARKET_ID.to_le_bytes()]);
+    let collateral_vault = pda(&[b"collateral", market_key.as_ref()]);
+    let yes_mint = pda(&[b"yes_mint", market_key.as_ref()]);
+    let no_mint = pda(&[b"no_mint", market_key.as_ref()]);
+
+    let uncreated = |key| TestAccount { lamports: 0, ..TestAccount::new(key, system_program::ID, vec![]) };
+    let mut accounts = vec![
+        uncreated(market_key),
+        uncreated(collateral_vault),
+        uncreated(yes_mint),
+        uncreated(no_mint),
+        mint_account(collateral_mint, Pubkey::new_unique(), 1_000),
+        TestAccount::new(creator, system_program::ID, vec![]),
+        TestAccount::new(resolver, system_program::ID, vec![]),
+        TestAccount::program(anchor_amm::ID),
+        TestAccount::program(system_program::ID),
+        TestAccount::program(spl_token::ID),
+    ];
+    let metas = anchor_amm::accounts::CreateMarket {
+        market: market_key,
+        collateral_mint,
+        collateral_vault,
+        yes_mint,
+        no_mint,
+        creator,
+        system_program: system_program::ID,
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    let data = anchor_amm::instruction::CreateMarket { market_id: MARKET_ID, resolver }.data();
+    execute(&mut accounts, &metas, &data).unwrap();
+
+    let market = PredictionMarket::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+    assert_eq!(market.creator, creator);
+    assert_eq!(market.resolver, resolver);
+    assert_eq!((market.yes_mint, market.no_mint), (yes_mint, no_mint));
+    assert_eq!(market.collateral_vault, collateral_vault);
+    assert_eq!(market.outcome, MarketOutcome::Unresolved);
+    assert_eq!(find(&accounts, yes_mint).mint_supply(), 0);
+
+    let user = Pubkey::new_unique();
+    let user_collateral = Pubkey::new_unique();
+    let user_yes = Pubkey::new_unique();
+    let user_no = Pubkey::new_unique();
+    accounts.extend([
+        token_account(user_collateral, collateral_mint, user, 1_000),
+        token_account(user_yes, yes_mint, user, 0),
+        token_account(user_no, no_mint, user, 0),
+        TestAccount::new(user, system_program::ID, vec![]),
+    ]);
+    Setup { accounts, market_key, market, user, user_collateral, user_yes, user_no }
+}
+
+impl Setup {
+    fn mint(&mut self, amount: u64) -> std::result::Result<(), ProgramError> {
+        let metas = anchor_amm::accounts::MintOutcomeTokens {
+            market: self.market_key,
+            collateral_vault: self.market.collateral_vault,
+            yes_mint: self.market.yes_mint,
+            no_mint: self.market.no_mint,
+            user_collateral: self.user_collateral,
+            user_yes: self.user_yes,
+            user_no: self.user_no,
+            user: self.user,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None);
+        execute(&mut self.accounts, &metas, &anchor_amm::instruction::MintOutcomeTokens { amount }.data())
+    }
+
+    fn redeem(&mut self, amount: u64) -> std::result::Result<(), ProgramError> {
+        let metas = anchor_amm::accounts::RedeemOutcomeTokens {
+            market: self.market_key,
+            collateral_vault: self.market.collateral_vault,
+            yes_mint: self.market.yes_mint,
+            no_mint: self.market.no_mint,
+            user_collateral: self.user_collateral,
+            user_yes: self.user_yes,
+            user_no: self.user_no,
+            user: self.user,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None);
+        execute(&mut self.accounts, &metas, &anchor_amm::instruction::RedeemOutcomeTokens { amount }.data())
+    }
+
+    fn resolve(&mut self, resolver: Pubkey, outcome: MarketOutcome) -> std::result::Result<(), ProgramError> {
+        let metas = anchor_amm::accounts::ResolveMarket { market: self.market_key, resolver }.to_account_metas(None);
+        execute(&mut self.accounts, &metas, &anchor_amm::instruction::ResolveMarket { outcome }.data())
+    }
+
+    /// Collateral, YES and NO balances of the user
+    fn balances(&self) -> (u64, u64, u64) {
+        (
+            find(&self.accounts, self.user_collateral).token_amount(),
+            find(&self.accounts, self.user_yes).token_amount(),
+            find(&self.accounts, self.user_no).token_amount(),
+        )
+    }
+}
+
+#[test]
+fn test_redeem_merges_complete_sets_before_resolution() {
+    let mut setup = setup();
+    setup.mint(400).unwrap();
+    assert_eq!(setup.balances(), (600, 400, 400));
+    assert_eq!(find(&setup.accounts, setup.market.collateral_vault).token_amount(), 400);
+
+    setup.redeem(150).unwrap();
+    assert_eq!(setup.balances(), (750, 250, 250));
+
+    // A set needs both outcomes
+    let no = setup.user_no;
+    let account = setup.accounts.iter_mut().find(|account| account.key == no).unwrap();
+    *account = token_account(no, setup.market.no_mint, setup.user, 100);
+    assert!(setup.redeem(150).is_err());
+    assert_eq!(setup.balances(), (750, 250, 100));
+}
+
+#[test]
+fn test_resolve_checks_resolver_and_outcome() {
+    let mut setup = setup();
+    let resolver = setup.market.resolver;
+
+    assert_eq!(
+        setup.resolve(setup.user, MarketOutcome::Yes).unwrap_err(),
+        program_error(anchor_lang::error::ErrorCode::ConstraintHasOne)
+    );
+    assert_eq!(
+        setup.resolve(resolver, MarketOutcome::Unresolved).unwrap_err(),
+        program_error(AmmError::InvalidOutcome)
+    );
+    setup.resolve(resolver, MarketOutcome::No).unwrap();
+    assert_eq!(
+        setup.resolve(resolver, MarketOutcome::Yes).unwrap_err(),
+        program_error(AmmError::MarketResolved)
+    );
+
+    let market = PredictionMarket::try_deserialize(&mut &setup.accounts[0].data[..]).unwrap();
+    assert_eq!(market.outcome, MarketOutcome::No);
+}
+
+#[test]
+fn test_redeem_pays_winning_tokens_after_resolution() {
+    let mut setup = setup();
+    setup.mint(400).unwrap();
+    setup.resolve(setup.market.resolver, MarketOutcome::Yes).unwrap();
+
+    // Only YES is burned, losing NO tokens are worth nothing
+    setup.redeem(300).unwrap();
+    assert_eq!(setup.balances(), (900, 100, 400));
+    assert_eq!(find(&setup.accounts, setup.market.collateral_vault).token_amount(), 100);
+
+    assert!(setup.redeem(200).is_err());
+    assert_eq!(setup.mint(100).unwrap_err(), program_error(AmmError::MarketResolved));
+    assert_eq!(setup.balances(), (900, 100, 400));
+}
+
+/// A market over `yes_mint` and `no_mint`
+fn market(yes_mint: Pubkey, no_mint: Pubkey, outcome: MarketOutcome) -> PredictionMarket {
+    PredictionMarket {
+        creator: Pubkey::new_unique(),
+        resolver: Pubkey::new_unique(),
+        collateral_mint: Pubkey::new_unique(),
+        collateral_vault: Pubkey::new_unique(),
+        yes_mint,
+        no_mint,
+        market_id: MARKET_ID,
+        outcome,
+        bump: 255,
+    }
+}
+
+#[test]
+fn test_lmsr_pool_trades_market_outcomes() {
+    // `mints` maps the pool's token A and B to the market's YES and NO mints
+    let initialize = |curve: CurveType, market: Option<PredictionMarket>, mints: fn(Pubkey, Pubkey) -> (Pubkey, Pubkey)| {
+        install_stubs();
+
+        let (mut accounts, mut metas) = initialize_accounts(&amm_config(0), Pubkey::new_unique());
+        if let Some(market) = market {
+            let (yes_mint, no_mint) = mints(metas.token_a, metas.token_b);
+            let market = PredictionMarket { yes_mint, no_mint, ..market };
+            let market_key = Pubkey::new_unique();
+            accounts.push(TestAccount::anchor(market_key, anchor_amm::ID, &market, PredictionMarket::LEN));
+            metas.market = Some(market_key);
+        }
+        let data = anchor_amm::instruction::Initialize {
+            dynamic_fee: None,
+            curve,
+            oracle_peg: None,
+            lmsr_b: (curve == CurveType::Lmsr).then_some(1_000_000),
+            lbp: None,
+            hook: None,
+            rate_a: None,
+            rate_b: None,
+            hybrid_target_price: None,
+            lp_name: "YES-NO LP".into(),
+            lp_symbol: "YNLP".into(),
+            allowlist_params: None,
+        }
+        .data();
+        execute(&mut accounts, &metas.to_account_metas(None), &data)?;
+        assert!(Pool::try_deserialize(&mut &find(&accounts, metas.pool).data[..]).is_ok());
+        Ok::<_, ProgramError>(())
+    };
+    let open = market(Pubkey::default(), Pubkey::default(), MarketOutcome::Unresolved);
+    let resolved = market(Pubkey::default(), Pubkey::default(), MarketOutcome::Yes);
+
+    let (same, swapped) = (|a, b| (a, b), |a, b| (b, a));
+
+    // Pool mints are sorted, so NO can come first
+    initialize(CurveType::Lmsr, Some(open.clone()), same).unwrap();
+    initialize(CurveType::Lmsr, Some(open.clone()), swapped).unwrap();
+
+    let invalid_market = program_error(AmmError::InvalidMarket);
+    assert_eq!(initialize(CurveType::Lmsr, None, same).unwrap_err(), invalid_market);
+    assert_eq!(
+        initialize(CurveType::Lmsr, Some(open.clone()), |a, _| (a, Pubkey::new_unique())).unwrap_err(),
+        invalid_market
+    );
+    assert_eq!(initialize(CurveType::ConstantProduct, Some(open), same).unwrap_err(), invalid_market);
+    assert_eq!(
+        initialize(CurveType::Lmsr, Some(resolved), same).unwrap_err(),
+        program_error(AmmError::MarketResolved)
+    );
+}
+
+#[test]
+fn test_lmsr_withdraw_keeps_subsidy_until_resolution() {
+    install_stubs();
+
+    // b = 1M needs a subsidy of 693_148 of each outcome
+    let user = Pubkey::new_unique();
+    let mut pool = Pool {
+        reserve_a: 1_000_000,
+        reserve_b: 1_000_000,
+        lp_supply: 1_000,
+        lmsr_b: 1_000_000,
+        ..pool(CurveType::Lmsr)
+    };
+    let pool_key = pool_address(&mut pool);
+    let market_key = Pubkey::new_unique();
+    let mut market = market(pool.token_a, pool.token_b, MarketOutcome::Unresolved);
+    let user_lp = Pubkey::new_unique();
+    let user_a = Pubkey::new_unique();
+    let user_b = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        TestAccount::anchor(market_key, anchor_amm::ID, &market, PredictionMarket::LEN),
+        mint_account(pool.lp_mint, pool_key, pool.lp_supply),
+        token_account(user_lp, pool.lp_mint, user, pool.lp_supply),
+        token_account(user_a, pool.token_a, user, 0),
+        token_account(user_b, pool.token_b, user, 0),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+    ]);
+    let withdraw = |market: Option<Pubkey>| {
+        anchor_amm::accounts::Withdraw {
+            pool: pool_key,
+            vault_a: pool.vault_a,
+            vault_b: pool.vault_b,
+            lp_mint: pool.lp_mint,
+            user_lp,
+            user_a: Some(user_a),
+            user_b: Some(user_b),
+            user,
+            hook_program: None,
+            allowlist: None,
+            allowlist_entry: None,
+            market,
+            token_program: spl_token::ID,
+            lp_token_program: spl_token::ID,
+            wsol_account: None,
+            native_mint: None,
+            system_program: None,
+        }
+        .to_account_metas(None)
+    };
+    let data = |lp_amount| anchor_amm::instruction::Withdraw { lp_amount, proof: vec![] }.data();
+
+    assert_eq!(
+        execute(&mut accounts, &withdraw(None), &data(200)).unwrap_err(),
+        program_error(AmmError::InvalidMarket)
+    );
+    execute(&mut accounts, &withdraw(Some(market_key)), &data(200)).unwrap();
+    assert_eq!(
+        execute(&mut accounts, &withdraw(Some(market_key)), &data(200)).unwrap_err(),
+        program_error(AmmError::InsufficientLiquidity)
+    );
+    assert_eq!(find(&accounts, user_a).token_amount(), 200_000);
+
+    // Once resolved, the subsidy is no longer at risk
+    market.outcome = MarketOutcome::No;
+    let account = accounts.iter_mut().find(|account| account.key == market_key).unwrap();
+    *account = TestAccount::anchor(market_key, anchor_amm::ID, &market, PredictionMarket::LEN);
+    execute(&mut accounts, &withdraw(Some(market_key)), &data(800)).unwrap();
+    assert_eq!(find(&accounts, user_a).token_amount(), 1_000_000);
+    assert_eq!(find(&accounts, user_b).token_amount(), 1_000_000);
+}
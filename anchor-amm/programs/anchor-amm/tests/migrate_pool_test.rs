@@ -0,0 +1,145 @@
+// programs/anchor-amm/tests/migrate_pool_test.rs
+//! Migration of pools from before fee tiers, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm::state::{AmmConfig, FeeMode, Pool};
+use anchor_amm_test_support::{
+    amm_config, amm_program_accounts, execute, find, install_stubs, mint_account, program_data_key, program_error,
+    swap_accounts, token_account, TestAccount, ADMIN,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::{Discriminator, InstructionData};
+use anchor_spl::token::spl_token;
+
+const RESERVE: u64 = 1_000_000_000;
+const LP_SUPPLY: u64 = 1_000_000_000;
+const FEE: u64 = 30;
+
+/// A pool as the first program version wrote it: 105 bytes at `[b"pool", token_a, token_b]`
+fn baseline_pool(token_a: Pubkey, token_b: Pubkey) -> (Pubkey, TestAccount) {
+    let (key, bump) = Pubkey::find_program_address(&[b"pool", token_a.as_ref(), token_b.as_ref()], &anchor_amm::ID);
+    let mut data = Pool::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(token_a.as_ref());
+    data.extend_from_slice(token_b.as_ref());
+    for value in [RESERVE, RESERVE, LP_SUPPLY, FEE] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.push(bump);
+    assert_eq!(data.len(), 105);
+    let lamports = Rent::default().minimum_balance(data.len());
+    (key, TestAccount { lamports, ..TestAccount::new(key, anchor_amm::ID, data) })
+}
+
+#[test]
+fn test_migrated_baseline_pool_trades_and_withdraws() {
+    install_stubs();
+
+    // Mints were not sorted before fee tiers
+    let token_b = Pubkey::new_unique();
+    let token_a = Pubkey::new_unique();
+    let (pool_key, pool_account) = baseline_pool(token_a, token_b);
+    let (vault_a, vault_b, lp_mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let config_key = Pubkey::find_program_address(&[b"amm_config", &0u16.to_le_bytes()], &anchor_amm::ID).0;
+    let user = Pubkey::new_unique();
+    let (user_lp, user_a, user_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let fake_vault = Pubkey::new_unique();
+    let mut accounts = vec![
+        pool_account,
+        token_account(vault_a, token_a, pool_key, RESERVE),
+        token_account(vault_b, token_b, pool_key, RESERVE),
+        // Anyone can create an account of the pool, but not fund it with the reserve
+        token_account(fake_vault, token_a, pool_key, 0),
+        mint_account(lp_mint, pool_key, LP_SUPPLY),
+        mint_account(token_a, Pubkey::new_unique(), RESERVE),
+        mint_account(token_b, Pubkey::new_unique(), RESERVE),
+        TestAccount::anchor(config_key, anchor_amm::ID, &amm_config(0), AmmConfig::LEN),
+        TestAccount::new(ADMIN, system_program::ID, vec![]),
+        TestAccount::new(user, system_program::ID, vec![]),
+        token_account(user_lp, lp_mint, user, LP_SUPPLY / 10),
+        token_account(user_a, token_a, user, RESERVE),
+        token_account(user_b, token_b, user, 0),
+        TestAccount::program(system_program::ID),
+        TestAccount::program(spl_token::ID),
+    ];
+    accounts.extend(amm_program_accounts());
+
+    let migrate = |vault_a: Pubkey, admin: Pubkey| {
+        anchor_amm::accounts::MigratePool {
+            pool: pool_key,
+            vault_a,
+            vault_b,
+            lp_mint,
+            token_a,
+            token_b,
+            amm_config: config_key,
+            admin,
+            program: anchor_amm::ID,
+            program_data: program_data_key(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None)
+    };
+    let data = anchor_amm::instruction::MigratePool {}.data();
+    assert_eq!(
+        execute(&mut accounts, &migrate(vault_a, user), &data).unwrap_err(),
+        program_error(AmmError::Unauthorized)
+    );
+    assert_eq!(
+        execute(&mut accounts, &migrate(fake_vault, ADMIN), &data).unwrap_err(),
+        program_error(AmmError::InvalidVault)
+    );
+    execute(&mut accounts, &migrate(vault_a, ADMIN), &data).unwrap();
+
+    let pool = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+    assert_eq!(accounts[0].data.len(), Pool::LEN);
+    assert_eq!((pool.token_a, pool.token_b), (token_a, token_b));
+    assert_eq!((pool.reserve_a, pool.reserve_b, pool.lp_supply, pool.fee), (RESERVE, RESERVE, LP_SUPPLY, FEE));
+    assert_eq!((pool.vault_a, pool.vault_b, pool.lp_mint), (vault_a, vault_b, lp_mint));
+    assert_eq!((pool.amm_config, pool.fee_tier, pool.creator), (config_key, 0, ADMIN));
+    assert_eq!((pool.curve, pool.fee_mode), (CurveType::ConstantProduct, FeeMode::Static));
+    assert!(pool.legacy_seeds);
+
+    // The pool signs with its original seeds: swaps pay out of its vault...
+    let metas = swap_accounts(pool_key, &pool, user, user_a, user_b).to_account_metas(None);
+    let data = anchor_amm::instruction::Swap { amount_in: 1_000_000, min_amount_out: 0, proof: vec![], referral_fee_bps: 0 }
+        .data();
+    execute(&mut accounts, &metas, &data).unwrap();
+    let swapped_out = find(&accounts, user_b).token_amount();
+    assert!(swapped_out > 0);
+
+    // ...and LPs burn their SPL Token LP and withdraw their share
+    let metas = anchor_amm::accounts::Withdraw {
+        pool: pool_key,
+        vault_a,
+        vault_b,
+        lp_mint,
+        user_lp,
+        user_a: Some(user_a),
+        user_b: Some(user_b),
+        user,
+        hook_program: None,
+        allowlist: None,
+        allowlist_entry: None,
+        market: None,
+        token_program: spl_token::ID,
+        lp_token_program: spl_token::ID,
+        wsol_account: None,
+        native_mint: None,
+        system_program: None,
+    }
+    .to_account_metas(None);
+    let data = anchor_amm::instruction::Withdraw { lp_amount: LP_SUPPLY / 10, proof: vec![] }.data();
+    execute(&mut accounts, &metas, &data).unwrap();
+
+    let pool = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+    assert_eq!(pool.lp_supply, LP_SUPPLY - LP_SUPPLY / 10);
+    assert_eq!(find(&accounts, user_lp).token_amount(), 0);
+    assert_eq!(find(&accounts, user_b).token_amount(), swapped_out + (RESERVE - swapped_out) / 10);
+    assert_eq!(find(&accounts, vault_b).token_amount(), pool.reserve_b);
+
+    // Migrating again leaves the pool as it is
+    let data = anchor_amm::instruction::MigratePool {}.data();
+    execute(&mut accounts, &migrate(vault_a, ADMIN), &data).unwrap();
+    assert_eq!(Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap().vault_a, vault_a);
+}
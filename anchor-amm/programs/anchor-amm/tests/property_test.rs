@@ -0,0 +1,249 @@
+// programs/anchor-amm/tests/property_test.rs
+//! Property-based and differential tests of the five core strategies
+//!
+//! The properties hold for any input the strategy accepts; inputs it rejects
+//! with an error are skipped. The differential tests compare every swap with
+//! the exact rational formulas in `reference`.
+mod reference;
+
+use anchor_amm::instructions::strategy::math::WAD;
+use anchor_amm::instructions::strategy::{
+    AmmStrategy, ConcentratedLiquidityStrategy, ConstantMeanStrategy, ConstantProductStrategy, HybridCfmmStrategy,
+    HybridParams, StableSwapStrategy,
+};
+use anchor_lang::prelude::Result;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use proptest::prelude::*;
+use reference::{after_fee, floor, int};
+
+type Swap = fn(u64, u64, u64, u64) -> Result<u64>;
+
+/// Reserves from dust to well past any real pool
+fn reserve() -> impl Strategy<Value = u64> {
+    prop_oneof![1u64..10_000, 10_000u64..1_000_000_000, 1_000_000_000u64..1 << 48]
+}
+
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![1u64..1_000, 1_000u64..1 << 48]
+}
+
+fn fee() -> impl Strategy<Value = u64> {
+    0u64..=1_000
+}
+
+/// The value the curve conserves never decreases across a swap
+///
+/// A curve blending several others only has to keep one of `invariants`.
+fn check_invariant(
+    swap: impl Fn(u64, u64, u64, u64) -> Result<u64>,
+    invariants: &[fn(u64, u64) -> BigInt],
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u64,
+) -> std::result::Result<(), TestCaseError> {
+    let Ok(amount_out) = swap(amount_in, reserve_in, reserve_out, fee_bps) else {
+        return Ok(());
+    };
+    prop_assert!(amount_out < reserve_out);
+    let kept = invariants
+        .iter()
+        .any(|invariant| invariant(reserve_in + amount_in, reserve_out - amount_out) >= invariant(reserve_in, reserve_out));
+    prop_assert!(kept, "{} in paid {} and every invariant fell", amount_in, amount_out);
+    Ok(())
+}
+
+fn product(x: u64, y: u64) -> BigInt {
+    BigInt::from(x) * BigInt::from(y)
+}
+
+fn sum(x: u64, y: u64) -> BigInt {
+    BigInt::from(x) + BigInt::from(y)
+}
+
+/// x^0.8 * y^0.2, raised to the fifth power
+fn weighted_4_1(x: u64, y: u64) -> BigInt {
+    BigInt::from(x).pow(4) * BigInt::from(y)
+}
+
+/// x^0.2 * y^0.8, raised to the fifth power
+fn weighted_1_4(x: u64, y: u64) -> BigInt {
+    BigInt::from(x) * BigInt::from(y).pow(4)
+}
+
+/// A larger input never pays out less
+fn check_monotonic(
+    swap: impl Fn(u64, u64, u64, u64) -> Result<u64>,
+    amount_in: u64,
+    extra: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u64,
+) -> std::result::Result<(), TestCaseError> {
+    let (Ok(smaller), Ok(larger)) = (
+        swap(amount_in, reserve_in, reserve_out, fee_bps),
+        swap(amount_in + extra, reserve_in, reserve_out, fee_bps),
+    ) else {
+        return Ok(());
+    };
+    prop_assert!(smaller <= larger, "{} in paid {}, {} more paid {}", amount_in, smaller, extra, larger);
+    Ok(())
+}
+
+/// Depositing at the pool ratio and withdrawing right away never returns more than was paid
+fn check_round_trip<S: AmmStrategy>(
+    amount_a: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+) -> std::result::Result<(), TestCaseError> {
+    let Ok(lp_supply) = S::calculate_initial_lp_supply(reserve_a, reserve_b) else {
+        return Ok(());
+    };
+    let Ok(amount_b) = u64::try_from((amount_a as u128 * reserve_b as u128).div_ceil(reserve_a as u128)) else {
+        return Ok(());
+    };
+    let Ok(minted) = S::calculate_lp_tokens_to_mint(amount_a, reserve_a, lp_supply) else {
+        return Ok(());
+    };
+    // Deposits that would overflow the pool are rejected on chain
+    let (Some(reserve_a), Some(reserve_b), Some(lp_supply)) = (
+        reserve_a.checked_add(amount_a),
+        reserve_b.checked_add(amount_b),
+        lp_supply.checked_add(minted),
+    ) else {
+        return Ok(());
+    };
+    if minted == 0 {
+        return Ok(());
+    }
+    let (out_a, out_b) = S::calculate_withdraw_amounts(minted, reserve_a, reserve_b, lp_supply).unwrap();
+    prop_assert!(out_a <= amount_a && out_b <= amount_b, "deposited ({}, {}), withdrew ({}, {})", amount_a, amount_b, out_a, out_b);
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2_000))]
+
+    #[test]
+    fn prop_constant_product(amount_in in amount(), extra in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let swap: Swap = ConstantProductStrategy::calculate_amount_out;
+        check_invariant(swap, &[product], amount_in, reserve_in, reserve_out, fee_bps)?;
+        check_monotonic(swap, amount_in, extra, reserve_in, reserve_out, fee_bps)?;
+        check_round_trip::<ConstantProductStrategy>(amount_in, reserve_in, reserve_out)?;
+    }
+
+    #[test]
+    fn prop_stable_swap(amount_in in amount(), extra in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let swap: Swap = StableSwapStrategy::calculate_amount_out;
+        check_invariant(swap, &[sum], amount_in, reserve_in, reserve_out, fee_bps)?;
+        check_monotonic(swap, amount_in, extra, reserve_in, reserve_out, fee_bps)?;
+        check_round_trip::<StableSwapStrategy>(amount_in, reserve_in, reserve_out)?;
+    }
+
+    #[test]
+    fn prop_concentrated_liquidity(amount_in in amount(), extra in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let swap: Swap = ConcentratedLiquidityStrategy::calculate_amount_out;
+        check_invariant(swap, &[product], amount_in, reserve_in, reserve_out, fee_bps)?;
+        check_monotonic(swap, amount_in, extra, reserve_in, reserve_out, fee_bps)?;
+        check_round_trip::<ConcentratedLiquidityStrategy>(amount_in, reserve_in, reserve_out)?;
+    }
+
+    #[test]
+    fn prop_hybrid_cfmm(amount_in in amount(), extra in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        // The output lies between the constant product and constant sum outputs
+        let swap: Swap = HybridCfmmStrategy::calculate_amount_out;
+        check_invariant(swap, &[product, sum], amount_in, reserve_in, reserve_out, fee_bps)?;
+        check_monotonic(swap, amount_in, extra, reserve_in, reserve_out, fee_bps)?;
+        check_round_trip::<HybridCfmmStrategy>(amount_in, reserve_in, reserve_out)?;
+    }
+
+    #[test]
+    fn prop_constant_mean(amount_in in amount(), extra in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let swap: Swap = ConstantMeanStrategy::calculate_amount_out;
+        check_invariant(swap, &[product], amount_in, reserve_in, reserve_out, fee_bps)?;
+        check_monotonic(swap, amount_in, extra, reserve_in, reserve_out, fee_bps)?;
+        check_round_trip::<ConstantMeanStrategy>(amount_in, reserve_in, reserve_out)?;
+    }
+
+    #[test]
+    fn prop_constant_mean_weighted(amount_in in amount(), extra in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee(), heavy_in in any::<bool>()) {
+        // 4:1 weights conserve x^4 * y, 1:4 weights x * y^4
+        let (weight_in, weight_out, invariant): (u64, u64, fn(u64, u64) -> BigInt) = if heavy_in {
+            (800_000, 200_000, weighted_4_1)
+        } else {
+            (200_000, 800_000, weighted_1_4)
+        };
+        let swap = |amount_in, reserve_in, reserve_out, fee_bps| {
+            ConstantMeanStrategy::calculate_amount_out_weighted(amount_in, reserve_in, reserve_out, weight_in, weight_out, fee_bps)
+        };
+        check_invariant(swap, &[invariant], amount_in, reserve_in, reserve_out, fee_bps)?;
+        check_monotonic(swap, amount_in, extra, reserve_in, reserve_out, fee_bps)?;
+    }
+
+    #[test]
+    fn diff_constant_product(amount_in in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let Ok(out) = ConstantProductStrategy::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps) else {
+            return Ok(());
+        };
+        let dx = after_fee(amount_in, fee_bps);
+        prop_assert_eq!(BigInt::from(out), floor(&reference::constant_product_out(&dx, reserve_in, reserve_out)));
+    }
+
+    #[test]
+    fn diff_stable_swap(amount_in in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let Ok(out) = StableSwapStrategy::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps) else {
+            return Ok(());
+        };
+        let dx = after_fee(amount_in, fee_bps);
+        prop_assert_eq!(BigInt::from(out), floor(&reference::stable_swap_out(&dx, reserve_in, reserve_out)));
+    }
+
+    #[test]
+    fn diff_concentrated_liquidity(amount_in in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let Ok(out) = ConcentratedLiquidityStrategy::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps) else {
+            return Ok(());
+        };
+        let dx = after_fee(amount_in, fee_bps);
+        let (exact, liquidity) = reference::concentrated_liquidity_out(&dx, reserve_in, reserve_out);
+        // The strategy prices with L rounded down plus one
+        let pool_liquidity = int(1) + BigRational::from_integer(floor(&liquidity));
+        let lowest = floor(&(dx.clone() * int(reserve_out) / (pool_liquidity + dx)));
+        prop_assert!(BigInt::from(out) <= floor(&exact), "{} above {}", out, exact);
+        prop_assert!(BigInt::from(out) >= lowest, "{} below {}", out, lowest);
+    }
+
+    #[test]
+    fn diff_hybrid_cfmm(amount_in in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee()) {
+        let Ok(out) = HybridCfmmStrategy::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps) else {
+            return Ok(());
+        };
+        let dx = after_fee(amount_in, fee_bps);
+        let exact = reference::hybrid_out(&dx, reserve_in, reserve_out, HybridParams::default().gamma);
+        // The constant product part and both weighted parts each round down
+        let floor_exact = floor(&exact);
+        prop_assert!(BigInt::from(out) <= floor_exact.clone() && BigInt::from(out) + 2 >= floor_exact, "{} vs {}", out, exact);
+    }
+
+    #[test]
+    fn diff_constant_mean(amount_in in amount(), reserve_in in reserve(), reserve_out in reserve(), fee_bps in fee(), heavy_in in any::<bool>()) {
+        // Equal weights are exact; 4:1 weights go through ln and exp
+        let Ok(out) = ConstantMeanStrategy::calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps) else {
+            return Ok(());
+        };
+        let dx = after_fee(amount_in, fee_bps);
+        let exact = reference::constant_mean_out(&dx, reserve_in, reserve_out, 1, 1).unwrap();
+        prop_assert_eq!(BigInt::from(out), floor(&exact));
+
+        let (weight_in, weight_out) = if heavy_in { (800_000, 200_000) } else { (200_000, 800_000) };
+        let Ok(out) = ConstantMeanStrategy::calculate_amount_out_weighted(amount_in, reserve_in, reserve_out, weight_in, weight_out, fee_bps) else {
+            return Ok(());
+        };
+        if let Some(exact) = reference::constant_mean_out(&dx, reserve_in, reserve_out, weight_in, weight_out) {
+            // ln and exp are off by a few hundred WAD units of the power, so of reserve_out
+            let lowest = exact.clone() - int(reserve_out) * int(1_000) / int(WAD as u64) - int(1);
+            prop_assert!(BigInt::from(out) <= floor(&exact), "{} above {}", out, exact);
+            prop_assert!(int(out) >= lowest, "{} below {}", out, exact);
+        }
+    }
+}
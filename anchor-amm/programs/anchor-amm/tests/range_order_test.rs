@@ -0,0 +1,269 @@
+// programs/anchor-amm/tests/range_order_test.rs
+//! Range orders filled by swaps from the pool's order book, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::math::fee_amount;
+use anchor_amm::instructions::strategy::{ConcentratedLiquidityStrategy, CurveType, VolatilityState};
+use anchor_amm::state::{OrderBook, OrderSide, Pool};
+use anchor_amm_test_support::{
+    execute, find, install_stubs, pool, pool_accounts, pool_address, program_error, swap_accounts, token_account,
+    TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token;
+
+const ORDER_AMOUNT: u64 = 1_000_000_000;
+
+/// A pool at a price of 1 (tick 0), with a user holding both tokens
+struct Setup {
+    accounts: Vec<TestAccount>,
+    pool_key: Pubkey,
+    pool: Pool,
+    order_book: Pubkey,
+    user: Pubkey,
+    user_a: Pubkey,
+    user_b: Pubkey,
+}
+
+fn setup() -> Setup {
+    setup_on(CurveType::ConstantProduct)
+}
+
+fn setup_on(curve: CurveType) -> Setup {
+    install_stubs();
+
+    let mut pool = Pool { reserve_b: 1_000_000_000_000, ..pool(curve) };
+    let pool_key = pool_address(&mut pool);
+    let order_book = Pubkey::find_program_address(&[b"order_book", pool_key.as_ref()], &anchor_amm::ID).0;
+    let user = Pubkey::new_unique();
+    let user_a = Pubkey::new_unique();
+    let user_b = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        TestAccount { lamports: 0, ..TestAccount::new(order_book, system_program::ID, vec![]) },
+        token_account(user_a, pool.token_a, user, 100_000_000_000),
+        token_account(user_b, pool.token_b, user, 100_000_000_000),
+        TestAccount::new(user, system_program::ID, vec![]),
+        TestAccount::program(system_program::ID),
+    ]);
+    Setup { accounts, pool_key, pool, order_book, user, user_a, user_b }
+}
+
+impl Setup {
+    fn order_key(&self, tick: i32) -> Pubkey {
+        let seeds: &[&[u8]] = &[b"limit_order", self.pool_key.as_ref(), self.user.as_ref(), &tick.to_le_bytes()];
+        Pubkey::find_program_address(seeds, &anchor_amm::ID).0
+    }
+
+    /// Place an order of `ORDER_AMOUNT` at `tick`, paid from the user's account of the sold token
+    fn place(&mut self, tick: i32, side: OrderSide) -> std::result::Result<(), ProgramError> {
+        let vault = match side {
+            OrderSide::Ask => self.pool.vault_a,
+            OrderSide::Bid => self.pool.vault_b,
+        };
+        self.place_into(tick, side, vault)
+    }
+
+    /// Place an order escrowed in `vault`
+    fn place_into(&mut self, tick: i32, side: OrderSide, vault: Pubkey) -> std::result::Result<(), ProgramError> {
+        let order = self.order_key(tick);
+        if self.accounts.iter().all(|account| account.key != order) {
+            self.accounts.push(TestAccount { lamports: 0, ..TestAccount::new(order, system_program::ID, vec![]) });
+        }
+        let user_source = match side {
+            OrderSide::Ask => self.user_a,
+            OrderSide::Bid => self.user_b,
+        };
+        let metas = anchor_amm::accounts::PlaceLimitOrder {
+            pool: self.pool_key,
+            order,
+            order_book: self.order_book,
+            vault,
+            user_source,
+            owner: self.user,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None);
+        let data = anchor_amm::instruction::PlaceLimitOrder { tick, amount: ORDER_AMOUNT, side }.data();
+        execute(&mut self.accounts, &metas, &data)
+    }
+
+    /// Swap `amount_in` of token A for token B, passing the order book or not
+    fn sell_a(&mut self, amount_in: u64, with_book: bool) -> std::result::Result<(), ProgramError> {
+        let metas = anchor_amm::accounts::Swap {
+            order_book: with_book.then_some(self.order_book),
+            ..swap_accounts(self.pool_key, &self.pool, self.user, self.user_a, self.user_b)
+        }
+        .to_account_metas(None);
+        let data = anchor_amm::instruction::Swap { amount_in, min_amount_out: 0, proof: vec![], referral_fee_bps: 0 }
+            .data();
+        execute(&mut self.accounts, &metas, &data)
+    }
+
+    fn claim(&mut self, tick: i32) -> std::result::Result<(), ProgramError> {
+        let metas = anchor_amm::accounts::ClaimFilledOrder {
+            pool: self.pool_key,
+            order: self.order_key(tick),
+            order_book: self.order_book,
+            vault: self.pool.vault_a,
+            user_destination: self.user_a,
+            owner: self.user,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None);
+        execute(&mut self.accounts, &metas, &anchor_amm::instruction::ClaimFilledOrder {}.data())
+    }
+
+    fn cancel(&mut self, tick: i32) -> std::result::Result<(), ProgramError> {
+        let metas = anchor_amm::accounts::CancelLimitOrder {
+            pool: self.pool_key,
+            order: self.order_key(tick),
+            order_book: self.order_book,
+            vault_a: self.pool.vault_a,
+            vault_b: self.pool.vault_b,
+            user_a: self.user_a,
+            user_b: self.user_b,
+            owner: self.user,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None);
+        execute(&mut self.accounts, &metas, &anchor_amm::instruction::CancelLimitOrder {}.data())
+    }
+
+    fn book(&self) -> OrderBook {
+        OrderBook::try_deserialize(&mut &find(&self.accounts, self.order_book).data[..]).unwrap()
+    }
+
+    fn pool_state(&self) -> Pool {
+        Pool::try_deserialize(&mut &self.accounts[0].data[..]).unwrap()
+    }
+}
+
+#[test]
+fn test_swap_fills_every_crossed_order() {
+    let mut setup = setup();
+    // Placed far side first: the swap still reaches the nearest tick first
+    setup.place(-200, OrderSide::Bid).unwrap();
+    setup.place(-100, OrderSide::Bid).unwrap();
+    setup.place(100, OrderSide::Ask).unwrap();
+    assert_eq!(setup.pool_state().open_orders, 3);
+
+    // The book can't be left out while orders rest on the pool
+    assert_eq!(setup.sell_a(20_000_000_000, false).unwrap_err(), program_error(AmmError::InvalidLimitOrder));
+
+    let amount_in = 20_000_000_000;
+    setup.sell_a(amount_in, true).unwrap();
+    let book = setup.book();
+    let [far, near, ask] = &book.orders[..] else { panic!("three orders") };
+    assert_eq!((far.tick, far.remaining), (-200, 0));
+    assert_eq!((near.tick, near.remaining), (-100, 0));
+    assert_eq!(ask.remaining, ORDER_AMOUNT);
+
+    // Each bid converted at its tick price: more A for the lower one
+    assert!(near.proceeds > ORDER_AMOUNT && far.proceeds > near.proceeds);
+    assert!(far.proceeds < ORDER_AMOUNT * 103 / 100);
+
+    // Order proceeds stay out of the reserves, and the pool fee is charged on the whole input
+    let before = setup.pool.clone();
+    let pool = setup.pool_state();
+    assert_eq!(pool.reserve_a, before.reserve_a + amount_in - near.proceeds - far.proceeds);
+    let fee = fee_amount(amount_in, before.fee).unwrap() as u128;
+    assert!(pool.stats.lp_fees_a.abs_diff(fee) <= 3);
+    assert!(pool.stats.lp_fees_a > fee_amount(amount_in - near.proceeds - far.proceeds, before.fee).unwrap() as u128);
+    // The user placed both bids, so the fills bring their B back on top of the curve output
+    let curve_out = before.reserve_b - pool.reserve_b;
+    assert_eq!(find(&setup.accounts, setup.user_b).token_amount(), 100_000_000_000 + curve_out);
+}
+
+#[test]
+fn test_orders_escrow_in_the_pool_vaults() {
+    let mut setup = setup();
+
+    // Anyone can create an account of the pool, which its reserves don't cover
+    let fake_vault = Pubkey::new_unique();
+    setup.accounts.push(token_account(fake_vault, setup.pool.token_b, setup.pool_key, 0));
+    let invalid_vault = program_error(AmmError::InvalidVault);
+    assert_eq!(setup.place_into(-100, OrderSide::Bid, fake_vault).unwrap_err(), invalid_vault);
+    assert_eq!(setup.place_into(-100, OrderSide::Bid, setup.pool.vault_a).unwrap_err(), invalid_vault);
+
+    setup.place(-100, OrderSide::Bid).unwrap();
+    assert_eq!(find(&setup.accounts, setup.pool.vault_b).token_amount(), setup.pool.reserve_b + ORDER_AMOUNT);
+}
+
+#[test]
+fn test_partial_fill_pays_fee_and_cancels() {
+    let mut setup = setup();
+    setup.place(-100, OrderSide::Bid).unwrap();
+
+    // Enough to reach the tick and take part of the order
+    let amount_in = 5_500_000_000;
+    setup.sell_a(amount_in, true).unwrap();
+    let order = setup.book().orders[0];
+    assert!(order.remaining > 0 && order.remaining < ORDER_AMOUNT);
+
+    // The order converted at its tick price of 0.990049, after the pool fee was taken
+    let filled = ORDER_AMOUNT - order.remaining;
+    assert_eq!(filled * 10_000 / order.proceeds, 9_900);
+    let fee = fee_amount(amount_in, setup.pool.fee).unwrap() as u128;
+    assert!(setup.pool_state().stats.lp_fees_a.abs_diff(fee) <= 3);
+
+    assert_eq!(setup.claim(-100).unwrap_err(), program_error(AmmError::OrderNotFilled));
+
+    let (a, b) = (
+        find(&setup.accounts, setup.user_a).token_amount(),
+        find(&setup.accounts, setup.user_b).token_amount(),
+    );
+    setup.cancel(-100).unwrap();
+    assert_eq!(find(&setup.accounts, setup.user_a).token_amount(), a + order.proceeds);
+    assert_eq!(find(&setup.accounts, setup.user_b).token_amount(), b + order.remaining);
+    assert!(setup.book().orders.is_empty());
+    assert_eq!(setup.pool_state().open_orders, 0);
+
+    // With no orders left, swaps no longer need the book
+    setup.sell_a(1_000_000, false).unwrap();
+}
+
+#[test]
+fn test_claim_pays_filled_order() {
+    let mut setup = setup();
+    setup.place(-100, OrderSide::Bid).unwrap();
+    setup.sell_a(10_000_000_000, true).unwrap();
+
+    let order = setup.book().orders[0];
+    assert_eq!(order.remaining, 0);
+    let a = find(&setup.accounts, setup.user_a).token_amount();
+    setup.claim(-100).unwrap();
+    assert_eq!(find(&setup.accounts, setup.user_a).token_amount(), a + order.proceeds);
+    assert!(setup.book().orders.is_empty());
+    assert_eq!(find(&setup.accounts, setup.order_key(-100)).lamports, 0);
+
+    // The order is gone from the book, so it can't be claimed twice
+    assert!(setup.claim(-100).is_err());
+}
+
+#[test]
+fn test_concentrated_liquidity_pool_fills_orders() {
+    let mut setup = setup_on(CurveType::ConcentratedLiquidity);
+    setup.place(-100, OrderSide::Bid).unwrap();
+    let tick_price = ConcentratedLiquidityStrategy::tick_to_price(-100).unwrap();
+    let spot_price = |pool: Pool| VolatilityState::spot_price(pool.reserve_a, pool.reserve_b).unwrap();
+
+    // The concentrated curve is deeper: what reaches the tick on a constant product pool falls short
+    setup.sell_a(5_500_000_000, true).unwrap();
+    assert_eq!(setup.book().orders[0].remaining, ORDER_AMOUNT);
+    assert!(spot_price(setup.pool_state()) > tick_price);
+
+    // The curve stops on the tick, the order converts at its price and the curve takes the rest
+    setup.sell_a(10_000_000_000, true).unwrap();
+    let order = setup.book().orders[0];
+    assert_eq!(order.remaining, 0);
+    assert_eq!(ORDER_AMOUNT * 10_000 / order.proceeds, 9_900);
+    assert!(spot_price(setup.pool_state()) < tick_price);
+
+    let a = find(&setup.accounts, setup.user_a).token_amount();
+    setup.claim(-100).unwrap();
+    assert_eq!(find(&setup.accounts, setup.user_a).token_amount(), a + order.proceeds);
+    assert_eq!(setup.pool_state().open_orders, 0);
+}
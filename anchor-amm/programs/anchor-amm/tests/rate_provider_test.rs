@@ -0,0 +1,172 @@
+// programs/anchor-amm/tests/rate_provider_test.rs
+//! Rate provider checks of initialize and swap, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::rate_provider::{
+    InterestBearingConfig, StakePoolState, SECONDS_PER_YEAR, SPL_STAKE_POOL_PROGRAM_ID,
+};
+use anchor_amm::instructions::strategy::{CurveType, RateProvider, RateProviderParams, RateSource};
+use anchor_amm::state::Pool;
+use anchor_amm_test_support::{
+    amm_config, execute, find, initialize_accounts, install_stubs, pool, pool_accounts, pool_address, program_error,
+    swap_accounts, token_account, TestAccount, EPOCH,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+use anchor_spl::token_2022::spl_token_2022;
+
+/// A stake pool of `mint` whose token is worth 1.1 of the underlying
+fn stake_pool(mint: Pubkey) -> Vec<u8> {
+    StakePoolState {
+        pool_mint: mint,
+        total_lamports: 1_100_000_000_000,
+        pool_token_supply: 1_000_000_000_000,
+        last_update_epoch: EPOCH,
+    }
+    .to_account_data()
+}
+
+fn params(account: Pubkey) -> RateProviderParams {
+    RateProviderParams { source: RateSource::SplStakePool, account, max_staleness_slots: 100 }
+}
+
+#[test]
+fn test_initialize_checks_rate_provider() {
+    install_stubs();
+
+    let (mut accounts, mut metas) = initialize_accounts(&amm_config(0), Pubkey::new_unique());
+    let stake_pool_key = Pubkey::new_unique();
+    let lookalike_key = Pubkey::new_unique();
+    let other_mint_key = Pubkey::new_unique();
+    accounts.extend([
+        TestAccount::new(stake_pool_key, SPL_STAKE_POOL_PROGRAM_ID, stake_pool(metas.token_a)),
+        TestAccount::new(lookalike_key, Pubkey::new_unique(), stake_pool(metas.token_a)),
+        TestAccount::new(other_mint_key, SPL_STAKE_POOL_PROGRAM_ID, stake_pool(Pubkey::new_unique())),
+    ]);
+    let initialize = |rate_a: Option<RateProviderParams>| {
+        anchor_amm::instruction::Initialize {
+            dynamic_fee: None,
+            curve: CurveType::StableSwap,
+            oracle_peg: None,
+            lmsr_b: None,
+            lbp: None,
+            hook: None,
+            rate_a,
+            rate_b: None,
+            hybrid_target_price: None,
+            lp_name: "A-B LP".into(),
+            lp_symbol: "ABLP".into(),
+            allowlist_params: None,
+        }
+        .data()
+    };
+    let invalid = program_error(AmmError::InvalidRateProvider);
+
+    // The stake pool account and its parameters come together
+    let without_account = metas.to_account_metas(None);
+    assert_eq!(execute(&mut accounts, &without_account, &initialize(Some(params(stake_pool_key)))).unwrap_err(), invalid);
+    metas.rate_provider_a = Some(stake_pool_key);
+    let with_account = metas.to_account_metas(None);
+    assert_eq!(execute(&mut accounts, &with_account, &initialize(None)).unwrap_err(), invalid);
+
+    // Accounts the stake pool program doesn't own, or of another token's stake pool
+    for key in [lookalike_key, other_mint_key] {
+        metas.rate_provider_a = Some(key);
+        let metas = metas.to_account_metas(None);
+        assert_eq!(execute(&mut accounts, &metas, &initialize(Some(params(key)))).unwrap_err(), invalid);
+    }
+
+    metas.rate_provider_a = Some(stake_pool_key);
+    let with_account = metas.to_account_metas(None);
+    execute(&mut accounts, &with_account, &initialize(Some(params(stake_pool_key)))).unwrap();
+
+    let pool = Pool::try_deserialize(&mut &find(&accounts, metas.pool).data[..]).unwrap();
+    assert_eq!(pool.rate_a.params, params(stake_pool_key));
+}
+
+#[test]
+fn test_swap_checks_rate_provider_owner() {
+    install_stubs();
+
+    let user = Pubkey::new_unique();
+    let pool = pool(CurveType::StableSwap);
+    let rate_provider = Pubkey::new_unique();
+    let mut pool = Pool { reserve_b: 1_100_000_000_000, rate_a: RateProvider::new(params(rate_provider)), ..pool };
+    let pool_key = pool_address(&mut pool);
+
+    let user_in = Pubkey::new_unique();
+    let user_out = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        token_account(user_in, pool.token_a, user, 1_000_000_000),
+        token_account(user_out, pool.token_b, user, 0),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        // A lookalike at the configured address, owned by another program
+        TestAccount::new(rate_provider, Pubkey::new_unique(), stake_pool(pool.token_a)),
+    ]);
+    let metas = anchor_amm::accounts::Swap {
+        rate_provider_a: Some(rate_provider),
+        ..swap_accounts(pool_key, &pool, user, user_in, user_out)
+    }
+    .to_account_metas(None);
+    let data =
+        anchor_amm::instruction::Swap { amount_in: 1_000_000_000, min_amount_out: 0, proof: vec![], referral_fee_bps: 0 }
+            .data();
+    assert_eq!(
+        execute(&mut accounts, &metas, &data).unwrap_err(),
+        program_error(AmmError::InvalidRateProvider)
+    );
+
+    accounts.last_mut().unwrap().owner = SPL_STAKE_POOL_PROGRAM_ID;
+    execute(&mut accounts, &metas, &data).unwrap();
+    assert!(find(&accounts, user_out).token_amount() > 0);
+}
+
+#[test]
+fn test_swap_reads_interest_bearing_mint() {
+    install_stubs();
+
+    // Token A wraps a mint that has accrued 5% a year since long before the clock
+    let user = Pubkey::new_unique();
+    let rate_provider = Pubkey::new_unique();
+    let config = InterestBearingConfig {
+        initialization_timestamp: -(SECONDS_PER_YEAR as i64),
+        pre_update_average_rate: 0,
+        last_update_timestamp: -(SECONDS_PER_YEAR as i64),
+        current_rate: 500,
+    };
+    let params =
+        RateProviderParams { source: RateSource::InterestBearingMint, account: rate_provider, max_staleness_slots: 0 };
+    let mut pool = Pool { rate_a: RateProvider::new(params), ..pool(CurveType::StableSwap) };
+    let pool_key = pool_address(&mut pool);
+
+    let user_in = Pubkey::new_unique();
+    let user_out = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        token_account(user_in, pool.token_a, user, 1_000_000_000),
+        token_account(user_out, pool.token_b, user, 0),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        // A lookalike at the configured address, not owned by Token-2022
+        TestAccount::new(rate_provider, SPL_STAKE_POOL_PROGRAM_ID, config.to_mint_data()),
+    ]);
+    let metas = anchor_amm::accounts::Swap {
+        rate_provider_a: Some(rate_provider),
+        ..swap_accounts(pool_key, &pool, user, user_in, user_out)
+    }
+    .to_account_metas(None);
+    let data =
+        anchor_amm::instruction::Swap { amount_in: 1_000_000_000, min_amount_out: 0, proof: vec![], referral_fee_bps: 0 }
+            .data();
+    assert_eq!(
+        execute(&mut accounts, &metas, &data).unwrap_err(),
+        program_error(AmmError::InvalidRateProvider)
+    );
+
+    accounts.last_mut().unwrap().owner = spl_token_2022::ID;
+    execute(&mut accounts, &metas, &data).unwrap();
+
+    // Each A is worth at least 1.05 B on a balanced stable pool
+    let pool = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+    assert!(pool.rate_a.cached_rate > 1_050_000_000_000);
+    assert!(find(&accounts, user_out).token_amount() > 1_040_000_000);
+}
@@ -0,0 +1,85 @@
+// programs/anchor-amm/tests/reference/mod.rs
+//! Exact rational reference implementations of the strategies' swap formulas
+//!
+//! Each function evaluates the formula its strategy documents for an input
+//! `dx` already net of fees, with no rounding at all. The on-chain result
+//! rounds in the pool's favour, so it is at most the floor of the reference,
+//! and the differential tests bound how far below it can be.
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+
+pub fn int(value: u64) -> BigRational {
+    BigRational::from_integer(BigInt::from(value))
+}
+
+/// Largest integer at most `value`
+pub fn floor(value: &BigRational) -> BigInt {
+    value.floor().to_integer()
+}
+
+/// Input left once the fee is charged, rounded up as the program charges it
+pub fn after_fee(amount_in: u64, fee_bps: u64) -> BigRational {
+    let fee = (int(amount_in) * int(fee_bps) / int(10_000)).ceil();
+    int(amount_in) - fee
+}
+
+/// x * y = k: dy = dx * y / (x + dx)
+pub fn constant_product_out(dx: &BigRational, reserve_in: u64, reserve_out: u64) -> BigRational {
+    dx * int(reserve_out) / (int(reserve_in) + dx)
+}
+
+/// Simplified stable swap: dy = dx - dx * 10_000 / (x + y), never below 0
+pub fn stable_swap_out(dx: &BigRational, reserve_in: u64, reserve_out: u64) -> BigRational {
+    let impact = dx * int(10_000) / (int(reserve_in) + int(reserve_out));
+    (dx - impact).max(BigRational::zero())
+}
+
+/// Single range from 0.9 to 1.1 times the spot price, on the strategy's
+/// integer price grid: p = floor(y * 10^6 / x), p_lower = floor(0.9 * p) and
+/// p_upper = floor(1.1 * p). Then
+/// L = min(x * p * p_upper / (p_upper - p), y * 10^6 / (p - p_lower)) and
+/// dy = dx * y / (L + dx)
+///
+/// Returns dy and L.
+pub fn concentrated_liquidity_out(dx: &BigRational, reserve_in: u64, reserve_out: u64) -> (BigRational, BigRational) {
+    let scale = int(1_000_000);
+    let price = (int(reserve_out) * &scale / int(reserve_in)).floor();
+    let lower = (&price * int(9) / int(10)).floor();
+    let upper = (&price * int(11) / int(10)).floor();
+
+    // A bound the price sits on holds no liquidity on that side
+    let liquidity_in = (upper > price).then(|| int(reserve_in) * &price * &upper / (&upper - &price));
+    let liquidity_out = (price > lower).then(|| int(reserve_out) * &scale / (&price - &lower));
+    let liquidity = match (liquidity_in, liquidity_out) {
+        (Some(liquidity_in), Some(liquidity_out)) => liquidity_in.min(liquidity_out),
+        (liquidity_in, liquidity_out) => liquidity_in.or(liquidity_out).expect("price of zero"),
+    };
+
+    (dx * int(reserve_out) / (&liquidity + dx), liquidity)
+}
+
+/// gamma * constant product output + (1 - gamma) * min(dx, y / 2), gamma scaled by 10^6
+pub fn hybrid_out(dx: &BigRational, reserve_in: u64, reserve_out: u64, gamma: u64) -> BigRational {
+    let gamma = int(gamma) / int(1_000_000);
+    let stable = dx.clone().min(int(reserve_out) / int(2));
+    &gamma * constant_product_out(dx, reserve_in, reserve_out) + (BigRational::one() - gamma) * stable
+}
+
+/// Balancer out-given-in: dy = y * (1 - (x / (x + dx))^(w_in / w_out))
+///
+/// Only weight ratios with an integer exponent have an exact rational value.
+pub fn constant_mean_out(
+    dx: &BigRational,
+    reserve_in: u64,
+    reserve_out: u64,
+    weight_in: u64,
+    weight_out: u64,
+) -> Option<BigRational> {
+    if !weight_in.is_multiple_of(weight_out) {
+        return None;
+    }
+    let exponent = (weight_in / weight_out) as i32;
+    let ratio = int(reserve_in) / (int(reserve_in) + dx);
+    Some(int(reserve_out) * (BigRational::one() - ratio.pow(exponent)))
+}
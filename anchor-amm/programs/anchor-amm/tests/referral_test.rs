@@ -0,0 +1,83 @@
+// programs/anchor-amm/tests/referral_test.rs
+//! Swaps paying referral fees, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::{AmmStrategy, ConstantProductStrategy, CurveType};
+use anchor_amm::state::{AmmConfig, Pool, ReferrerCap, MAX_REFERRER_CAPS};
+use anchor_amm_test_support::{
+    execute, find, install_stubs, pool, pool_accounts, pool_address, program_error, swap_accounts, token_account,
+    TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::{AccountDeserialize, InstructionData};
+
+#[test]
+fn test_swap_pays_referrer() {
+    install_stubs();
+
+    let user = Pubkey::new_unique();
+    let integrator = Pubkey::new_unique();
+    let mut pool = pool(CurveType::ConstantProduct);
+    let pool_key = pool_address(&mut pool);
+
+    // Only the listed integrator is paid, up to 20 % of the fee
+    let mut referrer_caps = [ReferrerCap::default(); MAX_REFERRER_CAPS];
+    referrer_caps[0] = ReferrerCap { referrer: integrator, max_fee_bps: 2_000 };
+    let config = AmmConfig {
+        index: 0,
+        trade_fee_bps: 30,
+        tick_spacing: 1,
+        bump: 255,
+        max_referral_fee_bps: 5_000,
+        referrer_caps,
+        lp_uri_base: String::new(),
+    };
+
+    let amount_in = 1_000_000;
+    let user_in = Pubkey::new_unique();
+    let user_out = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+    let self_referrer = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        token_account(user_in, pool.token_a, user, amount_in),
+        token_account(user_out, pool.token_b, user, 0),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        TestAccount::anchor(pool.amm_config, anchor_amm::ID, &config, AmmConfig::LEN),
+        token_account(referrer, pool.token_a, integrator, 0),
+        token_account(self_referrer, pool.token_a, user, 0),
+    ]);
+    let mut swap = |amm_config: Option<Pubkey>, referrer: Pubkey, referral_fee_bps: u64| {
+        let metas = anchor_amm::accounts::Swap {
+            amm_config,
+            referrer: Some(referrer),
+            ..swap_accounts(pool_key, &pool, user, user_in, user_out)
+        }
+        .to_account_metas(None);
+        let data = anchor_amm::instruction::Swap { amount_in, min_amount_out: 0, proof: vec![], referral_fee_bps }
+            .data();
+        execute(&mut accounts, &metas, &data)
+    };
+
+    assert_eq!(swap(None, referrer, 2_000).unwrap_err(), program_error(AmmError::InvalidReferral));
+    assert_eq!(
+        swap(Some(pool.amm_config), referrer, 2_001).unwrap_err(),
+        program_error(AmmError::ReferralFeeTooHigh)
+    );
+    // Referring themselves earns the user nothing
+    assert_eq!(
+        swap(Some(pool.amm_config), self_referrer, 1).unwrap_err(),
+        program_error(AmmError::ReferralFeeTooHigh)
+    );
+    swap(Some(pool.amm_config), referrer, 2_000).unwrap();
+
+    // The user gets the curve's output; the referrer takes 20 % of the 3_000 fee from the LPs
+    let amount_out =
+        ConstantProductStrategy::calculate_amount_out(amount_in, pool.reserve_a, pool.reserve_b, pool.fee).unwrap();
+    assert_eq!(find(&accounts, user_out).token_amount(), amount_out);
+    assert_eq!(find(&accounts, referrer).token_amount(), 600);
+    assert_eq!(find(&accounts, pool.vault_a).token_amount(), pool.reserve_a + amount_in - 600);
+
+    let swapped = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+    assert_eq!(swapped.reserve_a, pool.reserve_a + amount_in - 600);
+    assert_eq!(swapped.stats.lp_fees_a, 3_000 - 600);
+}
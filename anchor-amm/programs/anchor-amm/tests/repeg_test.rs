@@ -0,0 +1,54 @@
+// programs/anchor-amm/tests/repeg_test.rs
+//! Resetting the target reserves of oracle-pegged pools, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::{CurveType, PegTarget};
+use anchor_amm::state::Pool;
+use anchor_amm_test_support::{
+    execute, find, install_stubs, pool, pool_accounts, program_data_key, program_error, TestAccount, ADMIN,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+
+/// Repeg a pool holding 900K of token A against a target of 1M, returning its new targets
+fn repeg(curve: CurveType, authority: Option<Pubkey>, as_admin: bool) -> std::result::Result<PegTarget, ProgramError> {
+    install_stubs();
+
+    let pool_key = Pubkey::new_unique();
+    let pool = Pool {
+        reserve_a: 900_000_000_000,
+        peg_target: PegTarget { target_a: 1_000_000_000_000, target_b: 1_000_000_000_000 },
+        ..pool(curve)
+    };
+    let authority = authority.unwrap_or(pool.creator);
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.push(TestAccount::new(authority, Pubkey::default(), vec![]));
+    let metas = anchor_amm::accounts::RepegPool {
+        pool: pool_key,
+        authority,
+        program: anchor_amm::ID,
+        program_data: as_admin.then(program_data_key),
+    }
+    .to_account_metas(None);
+    execute(&mut accounts, &metas, &anchor_amm::instruction::RepegPool {}.data())?;
+    Ok(Pool::try_deserialize(&mut &find(&accounts, pool_key).data[..]).unwrap().peg_target)
+}
+
+#[test]
+fn test_repeg_resets_targets_to_reserves() {
+    for (authority, as_admin) in [(None, false), (Some(ADMIN), true)] {
+        let target = repeg(CurveType::OraclePeg, authority, as_admin).unwrap();
+        let reserve_b = pool(CurveType::OraclePeg).reserve_b;
+        assert_eq!(target, PegTarget { target_a: 900_000_000_000, target_b: reserve_b });
+    }
+}
+
+#[test]
+fn test_repeg_checks_authority_and_curve() {
+    let unauthorized = program_error(AmmError::Unauthorized);
+    assert_eq!(repeg(CurveType::OraclePeg, Some(Pubkey::new_unique()), false).unwrap_err(), unauthorized);
+    assert_eq!(repeg(CurveType::OraclePeg, Some(Pubkey::new_unique()), true).unwrap_err(), unauthorized);
+    assert_eq!(
+        repeg(CurveType::ConstantProduct, None, false).unwrap_err(),
+        program_error(AmmError::InvalidOracle)
+    );
+}
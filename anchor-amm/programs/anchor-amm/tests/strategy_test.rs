@@ -1,7 +1,69 @@
 use anchor_amm::instructions::strategy::{
     AmmStrategy, ConstantProductStrategy, ConcentratedLiquidityStrategy, HybridCfmmStrategy, ConstantMeanStrategy,
+    StableSwapStrategy, OraclePegStrategy, SolidlyStableStrategy, LmsrStrategy,
 };
 
+/// Odd-sized amounts so that every division has a remainder to round
+const ROUNDING_RESERVES: [(u64, u64); 3] = [(1_000_003, 999_983), (7_777_777, 3_333_331), (101, 10_007)];
+const ROUNDING_AMOUNTS: [u64; 5] = [1, 3, 997, 12_345, 99_991];
+
+/// Swap, then check that x * y never decreases
+fn assert_k_never_decreases<S: AmmStrategy>(name: &str) {
+    for (reserve_in, reserve_out) in ROUNDING_RESERVES {
+        for amount_in in ROUNDING_AMOUNTS {
+            let Ok(amount_out) = S::calculate_amount_out(amount_in, reserve_in, reserve_out, 30) else {
+                continue;
+            };
+            let k_before = reserve_in as u128 * reserve_out as u128;
+            let k_after = (reserve_in + amount_in) as u128 * (reserve_out - amount_out) as u128;
+            assert!(
+                k_after >= k_before,
+                "{}: k decreased swapping {} into ({}, {})",
+                name, amount_in, reserve_in, reserve_out,
+            );
+        }
+    }
+}
+
+/// Deposit then immediately withdraw, and check the LP never gets back more than it paid
+fn assert_round_trip_never_profits<S: AmmStrategy>(name: &str) {
+    for (reserve_a, reserve_b) in ROUNDING_RESERVES {
+        let lp_supply = S::calculate_initial_lp_supply(reserve_a, reserve_b).unwrap();
+        for amount_a in ROUNDING_AMOUNTS {
+            let amount_b = (amount_a as u128 * reserve_b as u128).div_ceil(reserve_a as u128) as u64;
+            let Ok(minted) = S::calculate_lp_tokens_to_mint(amount_a, reserve_a, lp_supply) else {
+                continue;
+            };
+            if minted == 0 {
+                continue;
+            }
+            let (out_a, out_b) = S::calculate_withdraw_amounts(
+                minted,
+                reserve_a + amount_a,
+                reserve_b + amount_b,
+                lp_supply + minted,
+            ).unwrap();
+            assert!(
+                out_a <= amount_a && out_b <= amount_b,
+                "{}: deposit of ({}, {}) withdrew ({}, {})",
+                name, amount_a, amount_b, out_a, out_b,
+            );
+        }
+    }
+}
+
+/// The exact-out input must be the smallest input producing the requested output
+fn assert_exact_out_rounds_up<S: AmmStrategy>(name: &str) {
+    let (reserve_in, reserve_out) = (1_000_003, 999_983);
+    for amount_out in [10, 997, 12_345] {
+        let amount_in = S::calculate_amount_in(amount_out, reserve_in, reserve_out, 30).unwrap();
+        let out = S::calculate_amount_out(amount_in, reserve_in, reserve_out, 30).unwrap();
+        assert!(out >= amount_out, "{}: exact-out {} gave {}", name, amount_out, out);
+        let out_less = S::calculate_amount_out(amount_in - 1, reserve_in, reserve_out, 30).unwrap_or(0);
+        assert!(out_less < amount_out, "{}: exact-out {} overcharged", name, amount_out);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -273,16 +335,25 @@ mod tests {
         let cm_result = ConstantMeanStrategy::calculate_amount_out(
             amount_in, reserve_in, reserve_out, fee_bps,
         ).unwrap();
+
+        let solidly_result = SolidlyStableStrategy::calculate_amount_out(
+            amount_in, reserve_in, reserve_out, fee_bps,
+        ).unwrap();
         assert!(cp_result > 0);
         assert!(cl_result > 0);
         assert!(hybrid_result > 0);
 
         assert!(cm_result > 0);
+        assert!(solidly_result > 0);
 
         // All should be less than input due to fees and slippage
         assert!(cp_result < amount_in);
         assert!(cl_result < amount_in);
         assert!(hybrid_result < amount_in);
+        assert!(solidly_result < amount_in);
+
+        // The stable curve is flatter than constant product around 1:1
+        assert!(solidly_result > cp_result);
 
         println!("All strategies comparison test passed!");
         println!("Amount in: {}", amount_in);
@@ -293,6 +364,7 @@ mod tests {
         // They should have different behaviors
         assert!(cp_result != cl_result || cl_result != hybrid_result);
         println!("Constant Mean output: {}", cm_result);
+        println!("Solidly Stable output: {}", solidly_result);
     }
 
     #[test]
@@ -442,5 +514,42 @@ mod tests {
         assert!(cm_supply > 0);
 
         println!("Constant Mean Supply: {}", cm_supply);
+        let solidly_supply = SolidlyStableStrategy::calculate_initial_lp_supply(
+            amount_a, amount_b,
+        ).unwrap();
+
+        assert!(solidly_supply > 0);
+
+        println!("Solidly Stable Supply: {}", solidly_supply);
+    }
+
+    #[test]
+    fn test_rounding_keeps_k_for_product_curves() {
+        // Constant product and balanced constant mean share the x * y = k invariant
+        assert_k_never_decreases::<ConstantProductStrategy>("Constant Product");
+        assert_k_never_decreases::<ConstantMeanStrategy>("Constant Mean");
+    }
+
+    #[test]
+    fn test_rounding_deposit_withdraw_never_profits() {
+        assert_round_trip_never_profits::<ConstantProductStrategy>("Constant Product");
+        assert_round_trip_never_profits::<StableSwapStrategy>("Stable Swap");
+        assert_round_trip_never_profits::<ConcentratedLiquidityStrategy>("Concentrated Liquidity");
+        assert_round_trip_never_profits::<HybridCfmmStrategy>("Hybrid CFMM");
+        assert_round_trip_never_profits::<ConstantMeanStrategy>("Constant Mean");
+        assert_round_trip_never_profits::<OraclePegStrategy>("Oracle Peg");
+        assert_round_trip_never_profits::<SolidlyStableStrategy>("Solidly Stable");
+        assert_round_trip_never_profits::<LmsrStrategy>("LMSR");
+    }
+
+    #[test]
+    fn test_rounding_exact_out_rounds_up() {
+        assert_exact_out_rounds_up::<ConstantProductStrategy>("Constant Product");
+        assert_exact_out_rounds_up::<StableSwapStrategy>("Stable Swap");
+        assert_exact_out_rounds_up::<HybridCfmmStrategy>("Hybrid CFMM");
+        assert_exact_out_rounds_up::<ConstantMeanStrategy>("Constant Mean");
+        assert_exact_out_rounds_up::<OraclePegStrategy>("Oracle Peg");
+        assert_exact_out_rounds_up::<SolidlyStableStrategy>("Solidly Stable");
+        assert_exact_out_rounds_up::<LmsrStrategy>("LMSR");
     }
 }
@@ -0,0 +1,62 @@
+// programs/anchor-amm/tests/vault_test.rs
+//! Vault checks of swaps, executed natively on the `anchor-amm-test-support` harness
+use anchor_amm::errors::AmmError;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm_test_support::{
+    execute, find, install_stubs, pool, pool_accounts, pool_address, program_error, swap_accounts, token_account,
+    TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+
+#[test]
+fn test_swap_only_uses_pool_vaults() {
+    install_stubs();
+
+    let user = Pubkey::new_unique();
+    let mut pool = pool(CurveType::ConstantProduct);
+    let pool_key = pool_address(&mut pool);
+
+    // Token accounts of the right mints that the pool doesn't own
+    let fake_vault_a = Pubkey::new_unique();
+    let fake_vault_b = Pubkey::new_unique();
+    let user_a = Pubkey::new_unique();
+    let user_b = Pubkey::new_unique();
+    let mut accounts = pool_accounts(pool_key, &pool);
+    accounts.extend([
+        token_account(fake_vault_a, pool.token_a, pool_key, 0),
+        token_account(fake_vault_b, pool.token_b, pool_key, 1_000_000_000),
+        token_account(user_a, pool.token_a, user, 1_000_000),
+        token_account(user_b, pool.token_b, user, 1_000_000),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+    ]);
+    let data = anchor_amm::instruction::Swap { amount_in: 1_000_000, min_amount_out: 0, proof: vec![], referral_fee_bps: 0 }
+        .data();
+    let invalid_vault = program_error(AmmError::InvalidVault);
+
+    let metas = anchor_amm::accounts::Swap { vault_in: fake_vault_a, ..swap_accounts(pool_key, &pool, user, user_a, user_b) }
+        .to_account_metas(None);
+    assert_eq!(execute(&mut accounts, &metas, &data).unwrap_err(), invalid_vault);
+
+    let metas = anchor_amm::accounts::Swap { vault_out: fake_vault_b, ..swap_accounts(pool_key, &pool, user, user_a, user_b) }
+        .to_account_metas(None);
+    assert_eq!(execute(&mut accounts, &metas, &data).unwrap_err(), invalid_vault);
+
+    // Both vaults on the same side
+    let metas = anchor_amm::accounts::Swap { vault_out: pool.vault_a, ..swap_accounts(pool_key, &pool, user, user_a, user_b) }
+        .to_account_metas(None);
+    assert_eq!(execute(&mut accounts, &metas, &data).unwrap_err(), invalid_vault);
+
+    // B to A: the input vault sets the direction
+    let metas = anchor_amm::accounts::Swap {
+        vault_in: pool.vault_b,
+        vault_out: pool.vault_a,
+        ..swap_accounts(pool_key, &pool, user, user_b, user_a)
+    }
+    .to_account_metas(None);
+    execute(&mut accounts, &metas, &data).unwrap();
+
+    assert_eq!(find(&accounts, user_b).token_amount(), 0);
+    assert!(find(&accounts, user_a).token_amount() > 1_000_000);
+    assert_eq!(find(&accounts, fake_vault_b).token_amount(), 1_000_000_000);
+}
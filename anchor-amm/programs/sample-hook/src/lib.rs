@@ -0,0 +1,112 @@
+// programs/sample-hook/src/lib.rs
+//! Sample trade hook for anchor-amm pools
+//!
+//! The pool creator configures it per pool with `initialize_config`. It then:
+//! - rejects swaps, deposits and withdrawals of more than `max_amount` of either token
+//! - charges `vip` the `vip_fee_bps` swap fee instead of the pool's
+//!
+//! The AMM passes the `HookConfig` of the pool as the first remaining account
+//! of `swap`, `deposit` and `withdraw`. LPs can withdraw without the hook, so
+//! its withdrawal limit only applies to those who pass it.
+// The IDL instructions `#[program]` generates beside the program module, out of
+// reach of its attributes, still call the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+use anchor_amm::hooks::{HookPayload, HookPoint, HookResponse};
+use anchor_amm::state::Pool;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+declare_id!("6CXqMc55nUmcE5nXEFiF1LUHu3SVGcJDrBUWmTodfeqS");
+
+#[program]
+pub mod sample_hook {
+    use super::*;
+
+    /// Configure the hook for `pool`; only the pool creator can call this.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        max_amount: u64,
+        vip: Pubkey,
+        vip_fee_bps: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.pool = ctx.accounts.pool.key();
+        config.max_amount = max_amount;
+        config.vip = vip;
+        config.vip_fee_bps = vip_fee_bps;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    /// Called by the AMM before and after every trade on the pool.
+    pub fn on_hook(ctx: Context<OnHook>, payload: HookPayload) -> Result<()> {
+        let config = &ctx.accounts.config;
+        match payload.point {
+            HookPoint::BeforeSwap | HookPoint::BeforeDeposit | HookPoint::BeforeWithdraw => {
+                require!(
+                    payload.amount_a.max(payload.amount_b) <= config.max_amount,
+                    HookError::TradeTooLarge
+                );
+                if payload.point == HookPoint::BeforeSwap && ctx.accounts.user.key() == config.vip {
+                    let response = HookResponse { fee_bps: Some(config.vip_fee_bps) };
+                    set_return_data(&response.try_to_vec()?);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Hook configuration of a pool
+#[account]
+pub struct HookConfig {
+    pub pool: Pubkey,      // Pool the configuration applies to
+    pub max_amount: u64,   // Largest swap input, deposit or withdrawal of either token
+    pub vip: Pubkey,       // User charged `vip_fee_bps`
+    pub vip_fee_bps: u64,  // Swap fee of `vip`
+    pub bump: u8,          // PDA bump
+}
+
+impl HookConfig {
+    pub const LEN: usize = 8 + 32 + 8 + 32 + 8 + 1;
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(has_one = creator @ HookError::Unauthorized)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = HookConfig::LEN,
+        seeds = [b"hook_config", pool.key().as_ref()],
+        bump,
+    )]
+    pub config: Account<'info, HookConfig>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OnHook<'info> {
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: Trader the hook is called for; the AMM passes it unsigned, so only its key is used
+    pub user: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"hook_config", pool.key().as_ref()], bump = config.bump)]
+    pub config: Account<'info, HookConfig>,
+}
+
+#[error_code]
+pub enum HookError {
+    #[msg("Trade exceeds the hook's maximum amount")]
+    TradeTooLarge,
+    #[msg("Unauthorized")]
+    Unauthorized,
+}
@@ -0,0 +1,292 @@
+// programs/sample-hook/tests/hook_test.rs
+//! Runs `swap`, `deposit` and `withdraw` on a pool hooked to the sample hook.
+//!
+//! Both programs run natively on the `anchor-amm-test-support` harness, which
+//! routes the AMM's hook CPIs to the sample hook.
+use anchor_amm::errors::AmmError;
+use anchor_amm::hooks::{HookParams, HOOK_DISCRIMINATOR};
+use anchor_amm::instructions::strategy::{AmmStrategy, ConstantProductStrategy, CurveType};
+use anchor_amm::state::Pool;
+use anchor_amm_test_support::{
+    execute, install_stubs, mint_account, pool_address, program_error, register_program, token_account, TestAccount,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use sample_hook::{HookConfig, HookError};
+
+const MAX_AMOUNT: u64 = 10_000_000_000;
+const VIP_FEE_BPS: u64 = 5;
+
+/// A constant product pool with 1M of each token, hooked to the sample hook
+fn pool(min_fee_bps: u64, max_fee_bps: u64) -> Pool {
+    Pool {
+        reserve_b: 1_000_000_000_000,
+        lp_supply: 1_000_000_000_000,
+        decimals_b: 6,
+        hook: HookParams {
+            program: sample_hook::ID,
+            min_fee_bps,
+            max_fee_bps,
+        },
+        ..anchor_amm_test_support::pool(CurveType::ConstantProduct)
+    }
+}
+
+/// Pool, vault, hook config and program accounts shared by every test
+struct Setup {
+    pool_key: Pubkey,
+    pool: Pool,
+    config_key: Pubkey,
+    vip: Pubkey,
+    accounts: Vec<TestAccount>,
+}
+
+fn setup(mut pool: Pool) -> Setup {
+    install_stubs();
+    register_program(sample_hook::ID, sample_hook::entry);
+
+    let pool_key = pool_address(&mut pool);
+    let (config_key, bump) =
+        Pubkey::find_program_address(&[b"hook_config", pool_key.as_ref()], &sample_hook::ID);
+    let vip = Pubkey::new_unique();
+    let config = HookConfig {
+        pool: pool_key,
+        max_amount: MAX_AMOUNT,
+        vip,
+        vip_fee_bps: VIP_FEE_BPS,
+        bump,
+    };
+
+    let accounts = vec![
+        TestAccount::anchor(pool_key, anchor_amm::ID, &pool, Pool::LEN),
+        TestAccount::anchor(config_key, sample_hook::ID, &config, HookConfig::LEN),
+        token_account(pool.vault_a, pool.token_a, pool_key, pool.reserve_a),
+        token_account(pool.vault_b, pool.token_b, pool_key, pool.reserve_b),
+        TestAccount::program(anchor_amm::ID),
+        TestAccount::program(sample_hook::ID),
+        TestAccount::program(spl_token::ID),
+    ];
+    Setup { pool_key, pool, config_key, vip, accounts }
+}
+
+/// Swap `amount_in` of token A as `user` and return the amount of token B received
+fn swap(setup: &mut Setup, user: Pubkey, amount_in: u64, with_hook: bool) -> std::result::Result<u64, ProgramError> {
+    let user_in = Pubkey::new_unique();
+    let user_out = Pubkey::new_unique();
+    let mut metas = anchor_amm::accounts::Swap {
+        pool: setup.pool_key,
+        vault_in: setup.pool.vault_a,
+        vault_out: setup.pool.vault_b,
+        user_in: Some(user_in),
+        user_out: Some(user_out),
+        user,
+        oracle: None,
+        rate_provider_a: None,
+        rate_provider_b: None,
+        hook_program: with_hook.then_some(setup.pool.hook.program),
+        allowlist: None,
+        allowlist_entry: None,
+        token_program: spl_token::ID,
+        wsol_account: None,
+        native_mint: None,
+        system_program: None,
+        amm_config: None,
+        referrer: None,
+        order_book: None,
+    }
+    .to_account_metas(None);
+    metas.push(AccountMeta::new_readonly(setup.config_key, false));
+    // The user's signature, which the AMM must not pass on to the hook
+    metas.push(AccountMeta::new_readonly(user, true));
+
+    setup.accounts.push(token_account(user_in, setup.pool.token_a, user, amount_in));
+    setup.accounts.push(token_account(user_out, setup.pool.token_b, user, 0));
+    setup.accounts.push(TestAccount::new(user, Pubkey::default(), vec![]));
+
+    let data = anchor_amm::instruction::Swap { amount_in, min_amount_out: 0, proof: vec![], referral_fee_bps: 0 }.data();
+    execute(&mut setup.accounts, &metas, &data)?;
+
+    let received = setup.accounts.iter().find(|account| account.key == user_out).unwrap();
+    Ok(received.token_amount())
+}
+
+#[test]
+fn test_discriminator_matches_hook_entry_point() {
+    assert_eq!(sample_hook::instruction::OnHook::DISCRIMINATOR, HOOK_DISCRIMINATOR);
+}
+
+#[test]
+fn test_hook_sets_vip_fee() {
+    // Others pay the pool fee, the VIP pays the hook's
+    let mut setup = setup(pool(0, 100));
+    let reserve = setup.pool.reserve_a;
+    let received = swap(&mut setup, Pubkey::new_unique(), 1_000_000_000, true).unwrap();
+    let expected = ConstantProductStrategy::calculate_amount_out(1_000_000_000, reserve, reserve, 30).unwrap();
+    assert_eq!(received, expected);
+
+    let mut setup = self::setup(pool(0, 100));
+    let vip = setup.vip;
+    let received = swap(&mut setup, vip, 1_000_000_000, true).unwrap();
+    let expected =
+        ConstantProductStrategy::calculate_amount_out(1_000_000_000, reserve, reserve, VIP_FEE_BPS).unwrap();
+    assert_eq!(received, expected);
+}
+
+/// A hook that fails when any account it is called with is signed
+fn signature_probe<'a>(_: &Pubkey, accounts: &'a [AccountInfo<'a>], _: &[u8]) -> ProgramResult {
+    if accounts.iter().any(|account| account.is_signer) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_hook_gets_no_signatures() {
+    let probe = Pubkey::new_unique();
+    let mut pool = pool(0, 100);
+    pool.hook.program = probe;
+    let mut setup = setup(pool);
+    register_program(probe, signature_probe);
+    setup.accounts.push(TestAccount::program(probe));
+
+    assert!(swap(&mut setup, Pubkey::new_unique(), 1_000_000_000, true).unwrap() > 0);
+}
+
+#[test]
+fn test_hook_fee_must_be_within_bounds() {
+    let mut setup = setup(pool(10, 100));
+    let vip = setup.vip;
+    let result = swap(&mut setup, vip, 1_000_000_000, true);
+    assert_eq!(result.unwrap_err(), program_error(AmmError::HookFeeOutOfBounds));
+}
+
+#[test]
+fn test_hook_vetoes_large_swap() {
+    let mut setup = setup(pool(0, 100));
+    let result = swap(&mut setup, Pubkey::new_unique(), MAX_AMOUNT + 1, true);
+    assert_eq!(result.unwrap_err(), program_error(HookError::TradeTooLarge));
+}
+
+#[test]
+fn test_hook_program_is_required() {
+    let mut setup = setup(pool(0, 100));
+    let result = swap(&mut setup, Pubkey::new_unique(), 1_000_000_000, false);
+    assert_eq!(result.unwrap_err(), program_error(AmmError::InvalidHook));
+}
+
+#[test]
+fn test_hook_vetoes_large_deposit() {
+    // Older pools have SPL Token LP mints, newer ones Token-2022
+    for (amount, vetoed, lp_token_program) in [
+        (MAX_AMOUNT, false, spl_token::ID),
+        (MAX_AMOUNT, false, spl_token_2022::ID),
+        (MAX_AMOUNT + 1, true, spl_token_2022::ID),
+    ] {
+        let mut setup = setup(pool(0, 100));
+        let user = Pubkey::new_unique();
+        let lp_mint = setup.pool.lp_mint;
+        let user_a = Pubkey::new_unique();
+        let user_b = Pubkey::new_unique();
+        let user_lp = Pubkey::new_unique();
+
+        let mut metas = anchor_amm::accounts::Deposit {
+            pool: setup.pool_key,
+            vault_a: setup.pool.vault_a,
+            vault_b: setup.pool.vault_b,
+            lp_mint,
+            user_a: Some(user_a),
+            user_b: Some(user_b),
+            user_lp,
+            user,
+            hook_program: Some(sample_hook::ID),
+            allowlist: None,
+            allowlist_entry: None,
+            token_program: spl_token::ID,
+            lp_token_program,
+            wsol_account: None,
+            native_mint: None,
+            system_program: None,
+        }
+        .to_account_metas(None);
+        metas.push(AccountMeta::new_readonly(setup.config_key, false));
+
+        setup.accounts.extend([
+            TestAccount { owner: lp_token_program, ..mint_account(lp_mint, setup.pool_key, setup.pool.lp_supply) },
+            token_account(user_a, setup.pool.token_a, user, amount),
+            token_account(user_b, setup.pool.token_b, user, amount),
+            TestAccount { owner: lp_token_program, ..token_account(user_lp, lp_mint, user, 0) },
+            TestAccount::new(user, Pubkey::default(), vec![]),
+            TestAccount::program(spl_token_2022::ID),
+        ]);
+
+        let data = anchor_amm::instruction::Deposit { max_a: amount, max_b: amount, proof: vec![] }.data();
+        let result = execute(&mut setup.accounts, &metas, &data);
+        if vetoed {
+            assert_eq!(result.unwrap_err(), program_error(HookError::TradeTooLarge));
+        } else {
+            result.unwrap();
+            let lp = setup.accounts.iter().find(|account| account.key == user_lp).unwrap();
+            assert_eq!(lp.token_amount(), amount);
+        }
+    }
+}
+
+#[test]
+fn test_hook_cannot_block_withdraw() {
+    // The hook vetoes a withdrawal this large, but only when the LP passes it
+    let mut setup = setup(pool(0, 100));
+    let user = Pubkey::new_unique();
+    let lp_mint = setup.pool.lp_mint;
+    let user_a = Pubkey::new_unique();
+    let user_b = Pubkey::new_unique();
+    let user_lp = Pubkey::new_unique();
+    let lp_amount = MAX_AMOUNT + 1;
+
+    let withdraw = |with_hook: bool| {
+        let mut metas = anchor_amm::accounts::Withdraw {
+            pool: setup.pool_key,
+            vault_a: setup.pool.vault_a,
+            vault_b: setup.pool.vault_b,
+            lp_mint,
+            user_lp,
+            user_a: Some(user_a),
+            user_b: Some(user_b),
+            user,
+            hook_program: with_hook.then_some(sample_hook::ID),
+            allowlist: None,
+            allowlist_entry: None,
+            market: None,
+            token_program: spl_token::ID,
+            lp_token_program: spl_token::ID,
+            wsol_account: None,
+            native_mint: None,
+            system_program: None,
+        }
+        .to_account_metas(None);
+        metas.push(AccountMeta::new_readonly(setup.config_key, false));
+        metas
+    };
+    let (with_hook, without_hook) = (withdraw(true), withdraw(false));
+
+    setup.accounts.extend([
+        mint_account(lp_mint, setup.pool_key, setup.pool.lp_supply),
+        token_account(user_a, setup.pool.token_a, user, 0),
+        token_account(user_b, setup.pool.token_b, user, 0),
+        token_account(user_lp, lp_mint, user, lp_amount),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+    ]);
+
+    let data = anchor_amm::instruction::Withdraw { lp_amount, proof: vec![] }.data();
+    assert_eq!(
+        execute(&mut setup.accounts, &with_hook, &data).unwrap_err(),
+        program_error(HookError::TradeTooLarge)
+    );
+    execute(&mut setup.accounts, &without_hook, &data).unwrap();
+
+    let received = setup.accounts.iter().find(|account| account.key == user_a).unwrap();
+    assert_eq!(received.token_amount(), lp_amount);
+}
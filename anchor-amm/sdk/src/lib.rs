@@ -0,0 +1,346 @@
+// sdk/src/lib.rs
+//! Off-chain quoting for anchor-amm pools
+//!
+//! `AnchorAmmPool` implements `Amm`, a trait in the shape of the Jupiter
+//! aggregator's `Amm` interface. Quotes reuse the program's own `Pool` methods
+//! and strategies, so they match on-chain execution bit for bit.
+//!
+//! Range orders are not included in quotes; a quote is what the pool's curve
+//! alone pays out. Pools with a trade hook are not quoted, since the hook can
+//! change the fee or reject the swap, and neither are permissioned pools.
+use std::collections::HashMap;
+
+use anchor_amm::state::{Pool, SwapInputs};
+use anchor_amm::wsol::{NATIVE_MINT, WSOL_SEED};
+use anchor_amm::instructions::strategy::math::fee_amount;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::AccountMeta, sysvar};
+use anchor_lang::{system_program, AccountDeserialize, InstructionData};
+use anyhow::{bail, ensure, Context, Result};
+
+/// Raw account data by address, as fetched from an RPC node
+pub type AccountMap = HashMap<Pubkey, Vec<u8>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapMode {
+    ExactIn,
+    ExactOut,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct QuoteParams {
+    pub amount: u64,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub swap_mode: SwapMode,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quote {
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub fee_amount: u64, // Charged on the input, in `fee_mint`
+    pub fee_mint: Pubkey,
+    pub fee_bps: u64,    // Fee rate of this swap (includes the dynamic component)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SwapParams {
+    pub source_mint: Pubkey,
+    pub destination_mint: Pubkey,
+    pub source_token_account: Pubkey,
+    pub destination_token_account: Pubkey,
+    pub token_transfer_authority: Pubkey,
+    pub in_amount: u64,
+    pub out_amount: u64, // Minimum amount out
+}
+
+/// `swap` instruction data and accounts
+#[derive(Clone, Debug)]
+pub struct SwapAndAccountMetas {
+    pub data: Vec<u8>,
+    pub account_metas: Vec<AccountMeta>,
+}
+
+/// Aggregator-facing pool interface, in the shape of Jupiter's `Amm` trait
+pub trait Amm {
+    /// Build from the pool account; call `update` before quoting
+    fn from_keyed_account(key: Pubkey, data: &[u8]) -> Result<Self>
+    where
+        Self: Sized;
+
+    fn label(&self) -> String;
+
+    fn program_id(&self) -> Pubkey;
+
+    fn key(&self) -> Pubkey;
+
+    fn get_reserve_mints(&self) -> Vec<Pubkey>;
+
+    /// Accounts whose latest data `update` needs
+    fn get_accounts_to_update(&self) -> Vec<Pubkey>;
+
+    fn update(&mut self, account_map: &AccountMap) -> Result<()>;
+
+    fn quote(&self, quote_params: &QuoteParams) -> Result<Quote>;
+
+    fn get_swap_and_account_metas(&self, swap_params: &SwapParams) -> Result<SwapAndAccountMetas>;
+}
+
+/// Temporary WSOL account the pool creates when a swap side is native SOL
+pub fn wsol_account(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[WSOL_SEED, pool.as_ref()], &anchor_amm::ID).0
+}
+
+/// `PoolRegistry` of the program, holding the pool and page counts
+pub fn pool_registry() -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_registry"], &anchor_amm::ID).0
+}
+
+/// `RegistryPage` number `index`; pages `0..page_count` list every pool
+pub fn registry_page(index: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"registry_page", &index.to_le_bytes()], &anchor_amm::ID).0
+}
+
+/// `MintPools` listing every pool that contains `mint`
+pub fn mint_pools(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_pools", mint.as_ref()], &anchor_amm::ID).0
+}
+
+/// `OrderBook` holding the range orders of `pool`
+pub fn order_book(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"order_book", pool.as_ref()], &anchor_amm::ID).0
+}
+
+/// Slot, epoch and unix timestamp of the clock sysvar
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClockState {
+    pub slot: u64,
+    pub epoch: u64,
+    pub unix_timestamp: i64,
+}
+
+impl ClockState {
+    /// Parse the clock sysvar account (bincode layout of `Clock`)
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        ensure!(data.len() >= 40, "clock sysvar data too short");
+        let slot = u64::from_le_bytes(data[0..8].try_into()?);
+        let epoch = u64::from_le_bytes(data[16..24].try_into()?);
+        let unix_timestamp = i64::from_le_bytes(data[32..40].try_into()?);
+        Ok(Self { slot, epoch, unix_timestamp })
+    }
+
+    fn clock(&self) -> Clock {
+        Clock {
+            slot: self.slot,
+            epoch: self.epoch,
+            unix_timestamp: self.unix_timestamp,
+            ..Clock::default()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AnchorAmmPool {
+    key: Pubkey,
+    pool: Pool,
+    clock: ClockState,
+    oracle_data: Option<Vec<u8>>,
+    rate_data: [Option<Vec<u8>>; 2], // Rate provider accounts of token A and B
+}
+
+impl AnchorAmmPool {
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
+    /// Whether swapping `input_mint` sells token A
+    fn a_to_b(&self, input_mint: Pubkey, output_mint: Pubkey) -> Result<bool> {
+        if input_mint == self.pool.token_a && output_mint == self.pool.token_b {
+            Ok(true)
+        } else if input_mint == self.pool.token_b && output_mint == self.pool.token_a {
+            Ok(false)
+        } else {
+            bail!("pool {} does not trade {} for {}", self.key, input_mint, output_mint)
+        }
+    }
+}
+
+impl Amm for AnchorAmmPool {
+    fn from_keyed_account(key: Pubkey, data: &[u8]) -> Result<Self> {
+        let pool = Pool::try_deserialize(&mut &data[..])?;
+        Ok(Self {
+            key,
+            pool,
+            clock: ClockState::default(),
+            oracle_data: None,
+            rate_data: [None, None],
+        })
+    }
+
+    fn label(&self) -> String {
+        "Anchor AMM".to_string()
+    }
+
+    fn program_id(&self) -> Pubkey {
+        anchor_amm::ID
+    }
+
+    fn key(&self) -> Pubkey {
+        self.key
+    }
+
+    fn get_reserve_mints(&self) -> Vec<Pubkey> {
+        vec![self.pool.token_a, self.pool.token_b]
+    }
+
+    fn get_accounts_to_update(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![self.key, sysvar::clock::ID];
+        if self.pool.curve == CurveType::OraclePeg {
+            accounts.push(self.pool.oracle_peg.oracle);
+        }
+        for rate in [&self.pool.rate_a, &self.pool.rate_b] {
+            if rate.is_set() {
+                accounts.push(rate.params.account);
+            }
+        }
+        accounts
+    }
+
+    fn update(&mut self, account_map: &AccountMap) -> Result<()> {
+        let data = account_map.get(&self.key).context("missing pool account")?;
+        self.pool = Pool::try_deserialize(&mut &data[..])?;
+
+        let clock = account_map.get(&sysvar::clock::ID).context("missing clock sysvar")?;
+        self.clock = ClockState::parse(clock)?;
+
+        self.oracle_data = if self.pool.curve == CurveType::OraclePeg {
+            let oracle = account_map
+                .get(&self.pool.oracle_peg.oracle)
+                .context("missing oracle account")?;
+            Some(oracle.clone())
+        } else {
+            None
+        };
+
+        for (rate, data) in [&self.pool.rate_a, &self.pool.rate_b].into_iter().zip(&mut self.rate_data) {
+            *data = if rate.is_set() {
+                let account = account_map
+                    .get(&rate.params.account)
+                    .context("missing rate provider account")?;
+                Some(account.clone())
+            } else {
+                None
+            };
+        }
+        Ok(())
+    }
+
+    /// Quote an exact-in swap the way the `swap` instruction executes it
+    fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
+        ensure!(quote_params.swap_mode == SwapMode::ExactIn, "only exact-in swaps are supported");
+        ensure!(!self.pool.paused, "pool is paused");
+        ensure!(!self.pool.hook.is_set(), "pool has a trade hook");
+        ensure!(!self.pool.permissioned, "pool is permissioned");
+
+        let a_to_b = self.a_to_b(quote_params.input_mint, quote_params.output_mint)?;
+        let (reserve_in, reserve_out) = if a_to_b {
+            (self.pool.reserve_a, self.pool.reserve_b)
+        } else {
+            (self.pool.reserve_b, self.pool.reserve_a)
+        };
+
+        // `breaker_price`, `record_hybrid_price` and `swap_fee` roll the pool state forward, as the swap would
+        let mut pool = self.pool.clone();
+        let price_before = pool.breaker_price(self.clock.slot)?;
+        pool.record_hybrid_price(self.clock.unix_timestamp)?;
+        let fee = pool.swap_fee(self.clock.slot)?;
+
+        let oracle_price = match &self.oracle_data {
+            Some(data) => Some(pool.oracle_price(data, self.clock.slot, a_to_b)?),
+            None => None,
+        };
+
+        let [data_a, data_b] = &self.rate_data;
+        let rates = pool.refresh_rates(data_a.as_deref(), data_b.as_deref(), &self.clock.clock())?;
+
+        let out_amount = pool.calculate_amount_out(
+            quote_params.amount,
+            reserve_in,
+            reserve_out,
+            fee,
+            a_to_b,
+            SwapInputs { oracle_price, now: self.clock.unix_timestamp, rates },
+        )?;
+
+        // Swaps the circuit breaker would reject can't be quoted
+        let (reserve_in, reserve_out) = (
+            reserve_in.checked_add(quote_params.amount).context("reserve overflow")?,
+            reserve_out.checked_sub(out_amount).context("reserve underflow")?,
+        );
+        (pool.reserve_a, pool.reserve_b) = if a_to_b {
+            (reserve_in, reserve_out)
+        } else {
+            (reserve_out, reserve_in)
+        };
+        pool.check_price_move(price_before)?;
+
+        Ok(Quote {
+            in_amount: quote_params.amount,
+            out_amount,
+            fee_amount: fee_amount(quote_params.amount, fee)?,
+            fee_mint: quote_params.input_mint,
+            fee_bps: fee,
+        })
+    }
+
+    fn get_swap_and_account_metas(&self, swap_params: &SwapParams) -> Result<SwapAndAccountMetas> {
+        let a_to_b = self.a_to_b(swap_params.source_mint, swap_params.destination_mint)?;
+        let (vault_in, vault_out) = if a_to_b {
+            (self.pool.vault_a, self.pool.vault_b)
+        } else {
+            (self.pool.vault_b, self.pool.vault_a)
+        };
+
+        // Passing the wallet itself as a native SOL side wraps or unwraps SOL in the swap
+        let user = swap_params.token_transfer_authority;
+        let is_native = |mint: Pubkey, account: Pubkey| mint == NATIVE_MINT && account == user;
+        let native_in = is_native(swap_params.source_mint, swap_params.source_token_account);
+        let native_out = is_native(swap_params.destination_mint, swap_params.destination_token_account);
+        let native = native_in || native_out;
+
+        let accounts = anchor_amm::accounts::Swap {
+            pool: self.key,
+            vault_in,
+            vault_out,
+            user_in: (!native_in).then_some(swap_params.source_token_account),
+            user_out: (!native_out).then_some(swap_params.destination_token_account),
+            user,
+            oracle: (self.pool.curve == CurveType::OraclePeg).then_some(self.pool.oracle_peg.oracle),
+            rate_provider_a: self.pool.rate_a.is_set().then_some(self.pool.rate_a.params.account),
+            rate_provider_b: self.pool.rate_b.is_set().then_some(self.pool.rate_b.params.account),
+            hook_program: self.pool.hook.is_set().then_some(self.pool.hook.program),
+            allowlist: None,
+            allowlist_entry: None,
+            token_program: anchor_spl::token::ID,
+            wsol_account: native.then(|| wsol_account(&self.key)),
+            native_mint: native.then_some(NATIVE_MINT),
+            system_program: native.then_some(system_program::ID),
+            amm_config: None,
+            referrer: None,
+            order_book: (self.pool.open_orders > 0).then(|| order_book(&self.key)),
+        };
+
+        Ok(SwapAndAccountMetas {
+            data: anchor_amm::instruction::Swap {
+                amount_in: swap_params.in_amount,
+                min_amount_out: swap_params.out_amount,
+                proof: vec![],
+                referral_fee_bps: 0,
+            }
+            .data(),
+            account_metas: accounts.to_account_metas(None),
+        })
+    }
+}
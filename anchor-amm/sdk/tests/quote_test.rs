@@ -0,0 +1,453 @@
+// sdk/tests/quote_test.rs
+//! Compares SDK quotes with the `swap` instruction executed by the program.
+//!
+//! The program runs natively on the `anchor-amm-test-support` harness.
+use anchor_amm::hooks::HookParams;
+use anchor_amm::instructions::strategy::oracle::PYTH_STATUS_TRADING;
+use anchor_amm::instructions::strategy::rate_provider::{StakePoolState, SPL_STAKE_POOL_PROGRAM_ID};
+use anchor_amm::instructions::strategy::{
+    CircuitBreaker, CurveType, DynamicFeeParams, HybridState, LbpParams, OraclePegParams, PythPrice, RateProvider,
+    RateProviderParams, RateSource, VolatilityState,
+};
+use anchor_amm::state::{FeeMode, Pool};
+use anchor_amm::wsol::NATIVE_MINT;
+use anchor_amm_sdk::{
+    wsol_account, AccountMap, Amm, AnchorAmmPool, QuoteParams, SwapAndAccountMetas, SwapMode, SwapParams,
+};
+use anchor_amm_test_support::{
+    clock_data, execute, install_stubs, pool, pool_address, token_account, TestAccount, EPOCH, SLOT, UNIX_TIMESTAMP,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, AccountDeserialize, AccountSerialize};
+use anchor_spl::token::spl_token;
+
+/// Run the instruction the SDK built
+fn execute_swap(accounts: &mut [TestAccount], swap: &SwapAndAccountMetas) -> ProgramResult {
+    execute(accounts, &swap.account_metas, &swap.data)
+}
+
+/// Quote `amount_in` with the SDK, execute the swap, and compare the output
+///
+/// `external` holds the accounts the pool reads prices from (oracle, rate providers) with their owners.
+fn assert_quote_matches_swap(mut pool: Pool, external: &[(Pubkey, Pubkey, Vec<u8>)], amount_in: u64, a_to_b: bool) {
+    install_stubs();
+
+    let pool_key = pool_address(&mut pool);
+    let mut pool_data = Vec::new();
+    pool.try_serialize(&mut pool_data).unwrap();
+    pool_data.resize(Pool::LEN, 0);
+
+    let mut account_map = AccountMap::new();
+    account_map.insert(pool_key, pool_data.clone());
+    account_map.insert(sysvar::clock::ID, clock_data());
+    for (key, _, data) in external {
+        account_map.insert(*key, data.clone());
+    }
+
+    let mut amm = AnchorAmmPool::from_keyed_account(pool_key, &pool_data).unwrap();
+    for key in amm.get_accounts_to_update() {
+        assert!(account_map.contains_key(&key));
+    }
+    amm.update(&account_map).unwrap();
+
+    let (mint_in, mint_out) = if a_to_b {
+        (pool.token_a, pool.token_b)
+    } else {
+        (pool.token_b, pool.token_a)
+    };
+    let quote = amm
+        .quote(&QuoteParams {
+            amount: amount_in,
+            input_mint: mint_in,
+            output_mint: mint_out,
+            swap_mode: SwapMode::ExactIn,
+        })
+        .unwrap();
+
+    let user = Pubkey::new_unique();
+    let user_in = Pubkey::new_unique();
+    let user_out = Pubkey::new_unique();
+    let swap = amm
+        .get_swap_and_account_metas(&SwapParams {
+            source_mint: mint_in,
+            destination_mint: mint_out,
+            source_token_account: user_in,
+            destination_token_account: user_out,
+            token_transfer_authority: user,
+            in_amount: amount_in,
+            out_amount: quote.out_amount,
+        })
+        .unwrap();
+
+    let mut accounts = vec![
+        TestAccount::new(pool_key, anchor_amm::ID, pool_data),
+        token_account(pool.vault_a, pool.token_a, pool_key, pool.reserve_a),
+        token_account(pool.vault_b, pool.token_b, pool_key, pool.reserve_b),
+        token_account(user_in, mint_in, user, amount_in),
+        token_account(user_out, mint_out, user, 0),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        TestAccount::program(anchor_amm::ID),
+        TestAccount::program(spl_token::ID),
+    ];
+    for (key, owner, data) in external {
+        accounts.push(TestAccount::new(*key, *owner, data.clone()));
+    }
+
+    execute_swap(&mut accounts, &swap).unwrap();
+
+    let received = accounts.iter().find(|account| account.key == user_out).unwrap().token_amount();
+    assert_eq!(received, quote.out_amount, "{:?} quote differs from execution", pool.curve);
+    assert!(received > 0);
+
+    let stats = Pool::try_deserialize(&mut &accounts[0].data[..]).unwrap().stats;
+    assert_eq!(stats.swap_count, pool.stats.swap_count + 1);
+    assert_eq!(stats.last_trade_timestamp, UNIX_TIMESTAMP);
+    let (volume_in, lp_fees) = if a_to_b {
+        (stats.volume_a - pool.stats.volume_a, stats.lp_fees_a - pool.stats.lp_fees_a)
+    } else {
+        (stats.volume_b - pool.stats.volume_b, stats.lp_fees_b - pool.stats.lp_fees_b)
+    };
+    assert_eq!(volume_in, amount_in as u128);
+    assert_eq!(lp_fees, quote.fee_amount as u128);
+}
+
+/// Swap native SOL (token A) for token B, or back, without user WSOL accounts
+fn assert_native_swap_matches_quote(amount_in: u64, a_to_b: bool) {
+    install_stubs();
+
+    let mut pool = Pool { token_a: NATIVE_MINT, ..pool(CurveType::ConstantProduct) };
+    let pool_key = pool_address(&mut pool);
+    let mut pool_data = Vec::new();
+    pool.try_serialize(&mut pool_data).unwrap();
+    pool_data.resize(Pool::LEN, 0);
+
+    let mut account_map = AccountMap::new();
+    account_map.insert(pool_key, pool_data.clone());
+    account_map.insert(sysvar::clock::ID, clock_data());
+    let mut amm = AnchorAmmPool::from_keyed_account(pool_key, &pool_data).unwrap();
+    amm.update(&account_map).unwrap();
+
+    let (mint_in, mint_out) = if a_to_b {
+        (NATIVE_MINT, pool.token_b)
+    } else {
+        (pool.token_b, NATIVE_MINT)
+    };
+    let quote = amm
+        .quote(&QuoteParams {
+            amount: amount_in,
+            input_mint: mint_in,
+            output_mint: mint_out,
+            swap_mode: SwapMode::ExactIn,
+        })
+        .unwrap();
+
+    // The wallet itself stands in for the native SOL side
+    let user = Pubkey::new_unique();
+    let user_b = Pubkey::new_unique();
+    let (source, destination) = if a_to_b { (user, user_b) } else { (user_b, user) };
+    let swap = amm
+        .get_swap_and_account_metas(&SwapParams {
+            source_mint: mint_in,
+            destination_mint: mint_out,
+            source_token_account: source,
+            destination_token_account: destination,
+            token_transfer_authority: user,
+            in_amount: amount_in,
+            out_amount: quote.out_amount,
+        })
+        .unwrap();
+
+    let rent = Rent::default().minimum_balance(spl_token::state::Account::LEN);
+    let mut vault_a = token_account(pool.vault_a, NATIVE_MINT, pool_key, pool.reserve_a);
+    vault_a.lamports = rent + pool.reserve_a;
+    let mut vault_a_state = spl_token::state::Account::unpack(&vault_a.data).unwrap();
+    vault_a_state.is_native = COption::Some(rent);
+    vault_a_state.pack_into_slice(&mut vault_a.data);
+
+    let mut native_mint = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint { decimals: 9, is_initialized: true, ..Default::default() }
+        .pack_into_slice(&mut native_mint);
+
+    let user_lamports = 10_000_000_000;
+    let mut accounts = vec![
+        TestAccount::new(pool_key, anchor_amm::ID, pool_data),
+        vault_a,
+        token_account(pool.vault_b, pool.token_b, pool_key, pool.reserve_b),
+        token_account(user_b, pool.token_b, user, if a_to_b { 0 } else { amount_in }),
+        TestAccount { lamports: user_lamports, ..TestAccount::new(user, Pubkey::default(), vec![]) },
+        TestAccount {
+            lamports: 0,
+            ..TestAccount::new(wsol_account(&pool_key), spl_token::ID, vec![0u8; spl_token::state::Account::LEN])
+        },
+        TestAccount::new(NATIVE_MINT, spl_token::ID, native_mint),
+        TestAccount::program(anchor_amm::ID),
+        TestAccount::program(spl_token::ID),
+        TestAccount::program(system_program::ID),
+    ];
+    execute_swap(&mut accounts, &swap).unwrap();
+
+    let account = |key: Pubkey| accounts.iter().find(|account| account.key == key).unwrap();
+    let wsol = account(wsol_account(&pool_key));
+    assert_eq!(wsol.lamports, 0, "WSOL account is closed");
+    let user_lamports_after = account(user).lamports;
+    if a_to_b {
+        assert_eq!(user_lamports_after, user_lamports - amount_in);
+        assert_eq!(account(user_b).token_amount(), quote.out_amount);
+    } else {
+        assert_eq!(user_lamports_after, user_lamports + quote.out_amount);
+        assert_eq!(account(user_b).token_amount(), 0);
+    }
+}
+
+#[test]
+fn test_native_sol_swaps() {
+    assert_native_swap_matches_quote(1_000_000_000, true);
+    assert_native_swap_matches_quote(1_000_000_000_000, false);
+}
+
+#[test]
+fn test_constant_product_quotes_match_swaps() {
+    let pool = pool(CurveType::ConstantProduct);
+    assert_quote_matches_swap(pool.clone(), &[], 1_000_000_000, true);
+    assert_quote_matches_swap(pool, &[], 1_000_000_000_000, false);
+}
+
+#[test]
+fn test_dynamic_fee_quotes_match_swaps() {
+    // The last swap moved the price and left volatility behind
+    let pool = Pool {
+        fee_mode: FeeMode::Dynamic,
+        dynamic_fee: DynamicFeeParams {
+            max_fee_bps: 300,
+            filter_period: 10,
+            decay_period: 100,
+            reduction_factor: 5_000,
+            variable_fee_control: 1_000,
+            max_volatility_accumulator: 2_000,
+        },
+        volatility: VolatilityState {
+            volatility_accumulator: 800,
+            volatility_reference: 0,
+            price_reference: 900_000_000_000_000,
+            last_update_slot: SLOT - 20,
+        },
+        ..pool(CurveType::ConstantProduct)
+    };
+    assert_quote_matches_swap(pool, &[], 5_000_000_000, true);
+}
+
+#[test]
+fn test_strategy_quotes_match_swaps() {
+    for curve in [
+        CurveType::StableSwap,
+        CurveType::ConcentratedLiquidity,
+        CurveType::HybridCfmm,
+        CurveType::ConstantMean,
+        CurveType::SolidlyStable,
+    ] {
+        assert_quote_matches_swap(pool(curve), &[], 3_000_000_000, true);
+        assert_quote_matches_swap(pool(curve), &[], 3_000_000_000, false);
+    }
+}
+
+#[test]
+fn test_hybrid_gamma_quotes_match_swaps() {
+    // Gamma moved towards constant product by earlier cranks
+    let pool = Pool {
+        hybrid: HybridState {
+            gamma: 900_000,
+            target_price: HybridState::DEFAULT_TARGET_PRICE,
+            ema_price: 1_000_000_000,
+            last_sample_time: UNIX_TIMESTAMP - 60,
+            last_crank_slot: SLOT - 200,
+        },
+        ..pool(CurveType::HybridCfmm)
+    };
+    assert_quote_matches_swap(pool.clone(), &[], 3_000_000_000, true);
+    assert_quote_matches_swap(pool, &[], 3_000_000_000, false);
+}
+
+#[test]
+fn test_lmsr_quotes_match_swaps() {
+    let pool = Pool {
+        reserve_a: 1_000_000_000,
+        reserve_b: 1_000_000_000,
+        lmsr_b: 1_000_000_000,
+        ..pool(CurveType::Lmsr)
+    };
+    assert_quote_matches_swap(pool, &[], 10_000_000, true);
+}
+
+#[test]
+fn test_lbp_quotes_match_swaps() {
+    // Halfway through a 96/4 to 50/50 schedule
+    let pool = Pool {
+        lbp: LbpParams {
+            start_weight_a: 960_000,
+            end_weight_a: 500_000,
+            start_time: UNIX_TIMESTAMP - 500,
+            end_time: UNIX_TIMESTAMP + 500,
+        },
+        ..pool(CurveType::Lbp)
+    };
+    assert_quote_matches_swap(pool.clone(), &[], 1_000_000_000, true);
+    assert_quote_matches_swap(pool, &[], 1_000_000_000_000, false);
+}
+
+#[test]
+fn test_oracle_peg_quotes_match_swaps() {
+    let oracle = PythPrice {
+        price: 100_000_000, // 1.00000000
+        conf: 10_000,
+        expo: -8,
+        status: PYTH_STATUS_TRADING,
+        publish_slot: SLOT,
+    };
+    let pool = Pool {
+        oracle_peg: OraclePegParams {
+            oracle: Pubkey::new_unique(),
+            k: 100_000,
+            max_staleness_slots: 25,
+            max_confidence_bps: 100,
+        },
+        ..pool(CurveType::OraclePeg)
+    };
+    let external = [(pool.oracle_peg.oracle, Pubkey::default(), oracle.to_account_data())];
+    assert_quote_matches_swap(pool.clone(), &external, 1_000_000_000, true);
+    assert_quote_matches_swap(pool, &external, 1_000_000_000_000, false);
+}
+
+#[test]
+fn test_rate_provider_quotes_match_swaps() {
+    // Token A is a stake pool token worth 1.1 of token B
+    let pool = pool(CurveType::StableSwap);
+    let stake_pool = StakePoolState {
+        pool_mint: pool.token_a,
+        total_lamports: 1_100_000_000_000,
+        pool_token_supply: 1_000_000_000_000,
+        last_update_epoch: EPOCH,
+    };
+    let pool = Pool {
+        reserve_b: 1_100_000_000_000,
+        rate_a: RateProvider::new(RateProviderParams {
+            source: RateSource::SplStakePool,
+            account: Pubkey::new_unique(),
+            max_staleness_slots: 100,
+        }),
+        ..pool
+    };
+    let external = [(pool.rate_a.params.account, SPL_STAKE_POOL_PROGRAM_ID, stake_pool.to_account_data())];
+    assert_quote_matches_swap(pool.clone(), &external, 1_000_000_000, true);
+    assert_quote_matches_swap(pool.clone(), &external, 1_000_000_000, false);
+
+    // Without a cached rate, a stake pool not yet updated for the epoch halts quotes
+    let outdated = StakePoolState { last_update_epoch: EPOCH - 1, ..stake_pool };
+    let mut data = Vec::new();
+    pool.try_serialize(&mut data).unwrap();
+    let mut account_map = AccountMap::new();
+    account_map.insert(sysvar::clock::ID, clock_data());
+    account_map.insert(pool.rate_a.params.account, outdated.to_account_data());
+    let key = Pubkey::new_unique();
+    account_map.insert(key, data.clone());
+
+    let mut amm = AnchorAmmPool::from_keyed_account(key, &data).unwrap();
+    amm.update(&account_map).unwrap();
+    let quote = amm.quote(&QuoteParams {
+        amount: 1_000_000_000,
+        input_mint: pool.token_a,
+        output_mint: pool.token_b,
+        swap_mode: SwapMode::ExactIn,
+    });
+    assert!(quote.is_err());
+}
+
+#[test]
+fn test_circuit_breaker_quotes_match_swaps() {
+    // 1B of A moves the price by about 0.2%, 1T by about 50%
+    let pool = Pool {
+        circuit_breaker: CircuitBreaker {
+            max_slot_move_bps: 100,
+            max_trade_move_bps: 50,
+            ..CircuitBreaker::default()
+        },
+        ..pool(CurveType::ConstantProduct)
+    };
+    assert_quote_matches_swap(pool.clone(), &[], 1_000_000_000, true);
+
+    let mut data = Vec::new();
+    pool.try_serialize(&mut data).unwrap();
+    let mut account_map = AccountMap::new();
+    account_map.insert(sysvar::clock::ID, clock_data());
+    let key = Pubkey::new_unique();
+    account_map.insert(key, data.clone());
+
+    let mut amm = AnchorAmmPool::from_keyed_account(key, &data).unwrap();
+    amm.update(&account_map).unwrap();
+    let quote = amm.quote(&QuoteParams {
+        amount: 1_000_000_000_000,
+        input_mint: pool.token_a,
+        output_mint: pool.token_b,
+        swap_mode: SwapMode::ExactIn,
+    });
+    assert!(quote.is_err());
+}
+
+#[test]
+fn test_hooked_pool_is_not_quoted() {
+    let pool = Pool {
+        hook: HookParams {
+            program: Pubkey::new_unique(),
+            min_fee_bps: 0,
+            max_fee_bps: 100,
+        },
+        ..pool(CurveType::ConstantProduct)
+    };
+    let mut data = Vec::new();
+    pool.try_serialize(&mut data).unwrap();
+
+    let amm = AnchorAmmPool::from_keyed_account(Pubkey::new_unique(), &data).unwrap();
+    let quote = amm.quote(&QuoteParams {
+        amount: 1_000,
+        input_mint: pool.token_a,
+        output_mint: pool.token_b,
+        swap_mode: SwapMode::ExactIn,
+    });
+    assert!(quote.is_err());
+}
+
+#[test]
+fn test_permissioned_pool_is_not_quoted() {
+    // Aggregators can't route through it
+    let pool = Pool { permissioned: true, ..pool(CurveType::ConstantProduct) };
+    let mut data = Vec::new();
+    pool.try_serialize(&mut data).unwrap();
+
+    let amm = AnchorAmmPool::from_keyed_account(Pubkey::new_unique(), &data).unwrap();
+    let quote = amm.quote(&QuoteParams {
+        amount: 1_000,
+        input_mint: pool.token_a,
+        output_mint: pool.token_b,
+        swap_mode: SwapMode::ExactIn,
+    });
+    assert!(quote.is_err());
+}
+
+#[test]
+fn test_paused_pool_is_not_quoted() {
+    let pool = Pool { paused: true, ..pool(CurveType::Lbp) };
+    let mut data = Vec::new();
+    pool.try_serialize(&mut data).unwrap();
+
+    let amm = AnchorAmmPool::from_keyed_account(Pubkey::new_unique(), &data).unwrap();
+    let quote = amm.quote(&QuoteParams {
+        amount: 1_000,
+        input_mint: pool.token_a,
+        output_mint: pool.token_b,
+        swap_mode: SwapMode::ExactIn,
+    });
+    assert!(quote.is_err());
+}
@@ -0,0 +1,64 @@
+// sim/src/curve_shape.rs
+//! Curve samples for plotting
+use std::io::Write;
+
+use anchor_amm::instructions::strategy::CurveType;
+use anyhow::Result;
+use serde::Serialize;
+
+/// Pool state after one swap from the sampled reserves
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CurveSample {
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub execution_price: f64, // Token B per token A paid or received
+    pub reserve_a: u64,       // Reserves after the swap, tracing the curve
+    pub reserve_b: u64,
+}
+
+/// Swap `samples` evenly spaced inputs, up to the reserve of the input token,
+/// each way from (`reserve_a`, `reserve_b`)
+///
+/// Inputs the strategy rejects are left out.
+pub fn curve_shape(curve: CurveType, reserve_a: u64, reserve_b: u64, fee_bps: u64, samples: u64) -> Vec<CurveSample> {
+    let mut shape = Vec::new();
+    for a_to_b in [true, false] {
+        let (reserve_in, reserve_out) = if a_to_b { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+        for step in 1..=samples {
+            let amount_in = (reserve_in as u128 * step as u128 / samples as u128) as u64;
+            let Ok(amount_out) = curve.calculate_amount_out(amount_in, reserve_in, reserve_out, fee_bps) else {
+                continue;
+            };
+            let (reserve_in, reserve_out) = (reserve_in + amount_in, reserve_out - amount_out);
+            let (amount_a, amount_b) = if a_to_b { (amount_in, amount_out) } else { (amount_out, amount_in) };
+            shape.push(CurveSample {
+                a_to_b,
+                amount_in,
+                amount_out,
+                execution_price: amount_b as f64 / amount_a as f64,
+                reserve_a: if a_to_b { reserve_in } else { reserve_out },
+                reserve_b: if a_to_b { reserve_out } else { reserve_in },
+            });
+        }
+    }
+    shape
+}
+
+/// Write `samples` as CSV, one row per sample
+pub fn write_curve_csv(samples: &[CurveSample], mut writer: impl Write) -> Result<()> {
+    writeln!(writer, "a_to_b,amount_in,amount_out,execution_price,reserve_a,reserve_b")?;
+    for sample in samples {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            sample.a_to_b,
+            sample.amount_in,
+            sample.amount_out,
+            sample.execution_price,
+            sample.reserve_a,
+            sample.reserve_b,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+// sim/src/events.rs
+//! Trade and price streams
+//!
+//! Both formats hold the same records. JSON is an array of objects tagged by
+//! `kind`; CSV has the header `timestamp,kind,amount_in,a_to_b,price`, with the
+//! columns a record doesn't use left empty:
+//!
+//! ```text
+//! timestamp,kind,amount_in,a_to_b,price
+//! 0,price,,,1.02
+//! 1,trade,1000000,true,
+//! ```
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+
+const CSV_HEADER: [&str; 5] = ["timestamp", "kind", "amount_in", "a_to_b", "price"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Event {
+    /// A user swap of `amount_in` of token A (`a_to_b`) or token B
+    Trade { timestamp: i64, amount_in: u64, a_to_b: bool },
+    /// The market price of token A in token B moved
+    Price { timestamp: i64, price: f64 },
+}
+
+impl Event {
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            Event::Trade { timestamp, .. } | Event::Price { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+/// Events of a `.json` or `.csv` file, in timestamp order
+pub fn load_events(path: &Path) -> Result<Vec<Event>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut events = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => parse_json(&text)?,
+        Some("csv") => parse_csv(&text)?,
+        _ => bail!("{}: expected a .json or .csv file", path.display()),
+    };
+    // Stable, so events at the same timestamp keep their file order
+    events.sort_by_key(Event::timestamp);
+    Ok(events)
+}
+
+pub fn parse_json(text: &str) -> Result<Vec<Event>> {
+    Ok(serde_json::from_str(text)?)
+}
+
+pub fn parse_csv(text: &str) -> Result<Vec<Event>> {
+    let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(vec![]);
+    };
+    let header: Vec<&str> = header.split(',').map(str::trim).collect();
+    ensure!(header == CSV_HEADER, "expected the CSV header {}", CSV_HEADER.join(","));
+
+    lines
+        .map(|(index, line)| parse_csv_record(line).with_context(|| format!("line {}", index + 1)))
+        .collect()
+}
+
+fn parse_csv_record(line: &str) -> Result<Event> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [timestamp, kind, amount_in, a_to_b, price] = fields[..] else {
+        bail!("expected {} columns", CSV_HEADER.len());
+    };
+    let timestamp = timestamp.parse().context("timestamp")?;
+    match kind {
+        "trade" => Ok(Event::Trade {
+            timestamp,
+            amount_in: amount_in.parse().context("amount_in")?,
+            a_to_b: a_to_b.parse().context("a_to_b")?,
+        }),
+        "price" => {
+            let price: f64 = price.parse().context("price")?;
+            ensure!(price.is_finite() && price > 0.0, "price must be positive");
+            Ok(Event::Price { timestamp, price })
+        }
+        _ => bail!("unknown kind {kind}, expected trade or price"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_and_json_hold_the_same_events() {
+        let csv = "timestamp,kind,amount_in,a_to_b,price\n0,price,,,1.5\n\n1,trade,1000,false,\n";
+        let json = r#"[
+            {"kind": "price", "timestamp": 0, "price": 1.5},
+            {"kind": "trade", "timestamp": 1, "amount_in": 1000, "a_to_b": false}
+        ]"#;
+        let expected = vec![
+            Event::Price { timestamp: 0, price: 1.5 },
+            Event::Trade { timestamp: 1, amount_in: 1_000, a_to_b: false },
+        ];
+        assert_eq!(parse_csv(csv).unwrap(), expected);
+        assert_eq!(parse_json(json).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_csv_errors_name_the_line() {
+        let csv = "timestamp,kind,amount_in,a_to_b,price\n0,trade,lots,true,\n";
+        let error = parse_csv(csv).unwrap_err();
+        assert_eq!(error.to_string(), "line 2");
+        assert!(parse_csv("time,kind\n").is_err());
+        assert!(parse_csv("timestamp,kind,amount_in,a_to_b,price\n0,price,,,-1\n").is_err());
+    }
+}
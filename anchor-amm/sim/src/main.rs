@@ -0,0 +1,90 @@
+// sim/src/main.rs
+//! Backtest a strategy on a trade and price stream, printing the report as JSON
+//!
+//! ```text
+//! amm-sim --curve constant_product --reserves 1000000000,1000000000 --events trades.csv
+//!         [--fee-bps 30] [--no-arbitrage] [--curve-out shape.csv] [--samples 100]
+//! ```
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use anchor_amm_sim::{curve_shape, load_events, parse_curve, write_curve_csv, Simulator};
+use anyhow::{bail, Context, Result};
+
+const USAGE: &str = "usage: amm-sim --curve <name> --reserves <a>,<b> --events <file.csv|file.json> \
+    [--fee-bps <bps>] [--no-arbitrage] [--curve-out <file.csv>] [--samples <n>]";
+
+struct Args {
+    curve: String,
+    reserves: (u64, u64),
+    events: PathBuf,
+    fee_bps: u64,
+    arbitrage: bool,
+    curve_out: Option<PathBuf>,
+    samples: u64,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut curve = None;
+    let mut reserves = None;
+    let mut events = None;
+    let mut fee_bps = 30;
+    let mut arbitrage = true;
+    let mut curve_out = None;
+    let mut samples = 100;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--curve" => curve = Some(value()?),
+            "--reserves" => {
+                let value = value()?;
+                let Some((a, b)) = value.split_once(',') else {
+                    bail!("--reserves takes <a>,<b>");
+                };
+                reserves = Some((a.parse().context("reserve a")?, b.parse().context("reserve b")?));
+            }
+            "--events" => events = Some(PathBuf::from(value()?)),
+            "--fee-bps" => fee_bps = value()?.parse().context("--fee-bps")?,
+            "--no-arbitrage" => arbitrage = false,
+            "--curve-out" => curve_out = Some(PathBuf::from(value()?)),
+            "--samples" => samples = value()?.parse().context("--samples")?,
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => bail!("unknown argument {arg}\n{USAGE}"),
+        }
+    }
+
+    Ok(Args {
+        curve: curve.context(USAGE)?,
+        reserves: reserves.context(USAGE)?,
+        events: events.context(USAGE)?,
+        fee_bps,
+        arbitrage,
+        curve_out,
+        samples,
+    })
+}
+
+fn main() -> Result<()> {
+    let args = parse_args()?;
+    let curve = parse_curve(&args.curve)?;
+    let (reserve_a, reserve_b) = args.reserves;
+
+    if let Some(path) = &args.curve_out {
+        let samples = curve_shape(curve, reserve_a, reserve_b, args.fee_bps, args.samples);
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        write_curve_csv(&samples, BufWriter::new(file))?;
+    }
+
+    let events = load_events(&args.events)?;
+    let mut simulator = Simulator::new(curve, reserve_a, reserve_b, args.fee_bps)?;
+    simulator.arbitrage = args.arbitrage;
+    let report = simulator.run(&events)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -0,0 +1,225 @@
+// sim/src/simulator.rs
+//! Replays events against a single-LP pool
+use anchor_amm::instructions::strategy::math::{fee_amount, validate_fee};
+use anchor_amm::instructions::strategy::CurveType;
+use anyhow::{ensure, Context, Result};
+use serde::Serialize;
+
+use crate::{curve_name, invariant, pool_price, Event, CURVES};
+
+/// Pool owned by one LP, its market, and what happened to it so far
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    pub curve: CurveType,
+    pub fee_bps: u64,
+    pub arbitrage: bool, // Trade the pool back to the market price after each price event
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+    pub price: f64, // Market price of token A in token B
+    initial_a: u64,
+    initial_b: u64,
+    initial_value: f64,
+    initial_invariant: f64,
+    trades: u64,
+    rejected_trades: u64,
+    arbitrages: u64,
+    fees_a: u64,
+    fees_b: u64,
+    fee_income: f64,
+    arbitrage_leakage: f64,
+    invariant_decreases: u64,
+}
+
+/// Outcome of a run, with values in token B at the market price
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub curve: String,
+    pub fee_bps: u64,
+    pub trades: u64,          // User trades executed
+    pub rejected_trades: u64, // User trades the strategy rejected (e.g. more than the reserves)
+    pub arbitrages: u64,      // Arbitrage trades after price events
+    pub reserve_a: u64,       // Final reserves
+    pub reserve_b: u64,
+    pub price: f64,           // Final market price
+    pub initial_value: f64,   // Initial reserves at the initial price
+    pub final_value: f64,     // Final reserves at the final price
+    pub hodl_value: f64,      // Initial reserves at the final price
+    pub lp_pnl: f64,          // `final_value - initial_value`
+    pub impermanent_loss: f64, // `(final_value - fee_income) / hodl_value - 1`, negative for a loss
+    pub fee_income: f64,      // Fees at the market price when charged
+    pub fees_a: u64,          // Fees charged on token A inputs
+    pub fees_b: u64,          // Fees charged on token B inputs
+    pub arbitrage_leakage: f64, // Profit arbitrageurs made off the pool
+    pub invariant_drift: f64, // Relative change of the curve invariant over the run
+    pub invariant_decreases: u64, // Swaps that decreased the curve invariant
+}
+
+impl Simulator {
+    /// Pool seeded with `reserve_a` and `reserve_b`, the market starting at the pool's price
+    pub fn new(curve: CurveType, reserve_a: u64, reserve_b: u64, fee_bps: u64) -> Result<Self> {
+        ensure!(CURVES.iter().any(|(_, other)| *other == curve), "the {curve:?} curve can't be replayed");
+        validate_fee(fee_bps)?;
+        ensure!(reserve_a > 0 && reserve_b > 0, "both reserves must be positive");
+        let lp_supply = curve.calculate_initial_lp_supply(reserve_a, reserve_b)?;
+        let price = pool_price(curve, reserve_a, reserve_b)?;
+        Ok(Self {
+            curve,
+            fee_bps,
+            arbitrage: true,
+            reserve_a,
+            reserve_b,
+            lp_supply,
+            price,
+            initial_a: reserve_a,
+            initial_b: reserve_b,
+            initial_value: reserve_a as f64 * price + reserve_b as f64,
+            initial_invariant: invariant(curve, reserve_a, reserve_b)?,
+            trades: 0,
+            rejected_trades: 0,
+            arbitrages: 0,
+            fees_a: 0,
+            fees_b: 0,
+            fee_income: 0.0,
+            arbitrage_leakage: 0.0,
+            invariant_decreases: 0,
+        })
+    }
+
+    /// Replay `events` in order and report on the pool afterwards
+    pub fn run(&mut self, events: &[Event]) -> Result<Report> {
+        for event in events {
+            self.apply(event)?;
+        }
+        self.report()
+    }
+
+    pub fn apply(&mut self, event: &Event) -> Result<()> {
+        match *event {
+            Event::Trade { amount_in, a_to_b, .. } => {
+                match self.swap(amount_in, a_to_b) {
+                    Ok(_) => self.trades += 1,
+                    Err(_) => self.rejected_trades += 1,
+                }
+                Ok(())
+            }
+            Event::Price { price, .. } => {
+                ensure!(price.is_finite() && price > 0.0, "price must be positive");
+                self.price = price;
+                if self.arbitrage {
+                    self.arbitrage()?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Swap on the pool's curve as the program does: the whole input, fee
+    /// included, goes into the reserves
+    pub fn swap(&mut self, amount_in: u64, a_to_b: bool) -> Result<u64> {
+        let (reserve_in, reserve_out) = self.reserves(a_to_b);
+        let amount_out = self.curve.calculate_amount_out(amount_in, reserve_in, reserve_out, self.fee_bps)?;
+        let fee = fee_amount(amount_in, self.fee_bps)?;
+
+        let reserve_in = reserve_in.checked_add(amount_in).context("reserve overflow")?;
+        let reserve_out = reserve_out.checked_sub(amount_out).context("output exceeds the reserve")?;
+        let before = invariant(self.curve, self.reserve_a, self.reserve_b)?;
+        if a_to_b {
+            (self.reserve_a, self.reserve_b) = (reserve_in, reserve_out);
+            self.fees_a += fee;
+            self.fee_income += fee as f64 * self.price;
+        } else {
+            (self.reserve_b, self.reserve_a) = (reserve_in, reserve_out);
+            self.fees_b += fee;
+            self.fee_income += fee as f64;
+        }
+        if invariant(self.curve, self.reserve_a, self.reserve_b)? < before {
+            self.invariant_decreases += 1;
+        }
+        Ok(amount_out)
+    }
+
+    /// Make the most profitable trade against the pool at the market price, if any
+    fn arbitrage(&mut self) -> Result<()> {
+        for a_to_b in [true, false] {
+            let (reserve_in, _) = self.reserves(a_to_b);
+            let amount_in = self.best_arbitrage(a_to_b, reserve_in);
+            let profit = self.arbitrage_profit(amount_in, a_to_b);
+            if profit > 0.0 {
+                self.swap(amount_in, a_to_b)?;
+                self.arbitrages += 1;
+                self.arbitrage_leakage += profit;
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    /// Market value gained by swapping `amount_in`, or -inf if the strategy rejects it
+    fn arbitrage_profit(&self, amount_in: u64, a_to_b: bool) -> f64 {
+        let (reserve_in, reserve_out) = self.reserves(a_to_b);
+        match self.curve.calculate_amount_out(amount_in, reserve_in, reserve_out, self.fee_bps) {
+            Ok(out) if a_to_b => out as f64 - amount_in as f64 * self.price,
+            Ok(out) => out as f64 * self.price - amount_in as f64,
+            Err(_) => f64::NEG_INFINITY,
+        }
+    }
+
+    /// Input maximizing `arbitrage_profit`, which is concave in the input
+    fn best_arbitrage(&self, a_to_b: bool, reserve_in: u64) -> u64 {
+        let profit = |amount_in: u64| self.arbitrage_profit(amount_in, a_to_b);
+
+        // Double until the profit stops growing; the best input is then within [high / 2, high * 2]
+        let mut high = (reserve_in / 1_000_000).max(1);
+        while high < u64::MAX / 2 && profit(high * 2) > profit(high) {
+            high *= 2;
+        }
+        let mut low = high / 2;
+        high *= 2;
+
+        while high - low > 2 {
+            let third = (high - low) / 3;
+            if profit(low + third) < profit(high - third) {
+                low += third;
+            } else {
+                high -= third;
+            }
+        }
+        (low.max(1)..=high).max_by(|a, b| profit(*a).total_cmp(&profit(*b))).unwrap_or(1)
+    }
+
+    fn reserves(&self, a_to_b: bool) -> (u64, u64) {
+        if a_to_b {
+            (self.reserve_a, self.reserve_b)
+        } else {
+            (self.reserve_b, self.reserve_a)
+        }
+    }
+
+    pub fn report(&self) -> Result<Report> {
+        let final_value = self.reserve_a as f64 * self.price + self.reserve_b as f64;
+        let hodl_value = self.initial_a as f64 * self.price + self.initial_b as f64;
+        let final_invariant = invariant(self.curve, self.reserve_a, self.reserve_b)?;
+        Ok(Report {
+            curve: curve_name(self.curve).to_string(),
+            fee_bps: self.fee_bps,
+            trades: self.trades,
+            rejected_trades: self.rejected_trades,
+            arbitrages: self.arbitrages,
+            reserve_a: self.reserve_a,
+            reserve_b: self.reserve_b,
+            price: self.price,
+            initial_value: self.initial_value,
+            final_value,
+            hodl_value,
+            lp_pnl: final_value - self.initial_value,
+            impermanent_loss: (final_value - self.fee_income) / hodl_value - 1.0,
+            fee_income: self.fee_income,
+            fees_a: self.fees_a,
+            fees_b: self.fees_b,
+            arbitrage_leakage: self.arbitrage_leakage,
+            invariant_drift: final_invariant / self.initial_invariant - 1.0,
+            invariant_decreases: self.invariant_decreases,
+        })
+    }
+}
@@ -0,0 +1,110 @@
+// sim/tests/backtest_test.rs
+//! Replays synthetic and sample streams and checks the report against
+//! closed-form results for the constant product curve.
+use std::path::Path;
+
+use anchor_amm::instructions::strategy::math::fee_amount;
+use anchor_amm::instructions::strategy::CurveType;
+use anchor_amm_sim::{curve_shape, load_events, parse_curve, pool_price, Event, Simulator, CURVES};
+
+const RESERVE: u64 = 1_000_000_000_000;
+
+fn sample_events() -> Vec<Event> {
+    load_events(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data/sample_events.csv")).unwrap()
+}
+
+#[test]
+fn test_empty_stream_changes_nothing() {
+    let mut simulator = Simulator::new(CurveType::ConstantProduct, RESERVE, RESERVE, 30).unwrap();
+    let report = simulator.run(&[]).unwrap();
+    // The market starts at the pool's marginal price
+    assert!((report.price - 1.0).abs() < 1e-5);
+    assert_eq!(report.final_value, report.initial_value);
+    assert_eq!(report.hodl_value, report.initial_value);
+    assert_eq!((report.lp_pnl, report.impermanent_loss, report.invariant_drift), (0.0, 0.0, 0.0));
+}
+
+#[test]
+fn test_price_move_leaks_to_arbitrage() {
+    let mut simulator = Simulator::new(CurveType::ConstantProduct, RESERVE, RESERVE, 30).unwrap();
+    let report = simulator.run(&[Event::Price { timestamp: 0, price: 1.21 }]).unwrap();
+    assert_eq!(report.arbitrages, 1);
+    assert!(report.arbitrage_leakage > 0.0);
+
+    // The arbitrage stops where the fee eats the profit
+    let price = pool_price(CurveType::ConstantProduct, report.reserve_a, report.reserve_b).unwrap();
+    assert!((price / 1.21 - 1.0).abs() < 0.004, "pool price {price}");
+
+    // Impermanent loss of a constant product pool: 2 sqrt(r) / (1 + r) - 1
+    let expected = 2.0 * 1.1 / 2.21 - 1.0;
+    assert!((report.impermanent_loss - expected).abs() < 1e-4, "{} vs {expected}", report.impermanent_loss);
+}
+
+#[test]
+fn test_fees_accrue_to_the_lp() {
+    let mut simulator = Simulator::new(CurveType::ConstantProduct, RESERVE, RESERVE, 30).unwrap();
+    let amount_in = RESERVE / 100;
+    let events = [
+        Event::Trade { timestamp: 0, amount_in, a_to_b: true },
+        Event::Trade { timestamp: 1, amount_in, a_to_b: false },
+        Event::Trade { timestamp: 2, amount_in: u64::MAX, a_to_b: true },
+    ];
+    let report = simulator.run(&events).unwrap();
+
+    assert_eq!((report.trades, report.rejected_trades), (2, 1));
+    let fee = fee_amount(amount_in, 30).unwrap();
+    assert_eq!((report.fees_a, report.fees_b), (fee, fee));
+    // Fees in token A are valued at the pool's starting price, just under 1
+    assert!((report.fee_income / (2.0 * fee as f64) - 1.0).abs() < 1e-5);
+    assert!(report.lp_pnl > 0.0);
+    assert!(report.invariant_drift > 0.0);
+    assert_eq!(report.invariant_decreases, 0);
+}
+
+#[test]
+fn test_every_curve_replays_the_sample() {
+    let events = sample_events();
+    assert!(events.windows(2).all(|pair| pair[0].timestamp() <= pair[1].timestamp()));
+
+    for (name, curve) in CURVES {
+        let mut simulator = Simulator::new(curve, RESERVE, RESERVE, 30).unwrap();
+        let report = simulator.run(&events).unwrap();
+        assert_eq!(report.curve, name);
+        assert_eq!(report.trades + report.rejected_trades, 180, "{name}");
+        assert!(report.final_value.is_finite() && report.invariant_drift.is_finite(), "{name}");
+    }
+}
+
+#[test]
+fn test_stateful_curves_are_rejected() {
+    // Their prices depend on an oracle, a market, a schedule or gamma, none of which the simulator has
+    for (name, curve) in [
+        ("oracle_peg", CurveType::OraclePeg),
+        ("lmsr", CurveType::Lmsr),
+        ("lbp", CurveType::Lbp),
+        ("hybrid_cfmm", CurveType::HybridCfmm),
+    ] {
+        assert!(parse_curve(name).is_err(), "{name}");
+        assert!(Simulator::new(curve, RESERVE, RESERVE, 30).is_err(), "{name}");
+    }
+}
+
+#[test]
+fn test_curve_shape_traces_both_branches() {
+    let shape = curve_shape(CurveType::ConstantProduct, RESERVE, 2 * RESERVE, 0, 10);
+    assert_eq!(shape.len(), 20);
+
+    for branch in shape.chunks(10) {
+        // Larger inputs pay worse prices
+        let prices: Vec<f64> = branch.iter().map(|sample| sample.execution_price).collect();
+        if branch[0].a_to_b {
+            assert!(prices.windows(2).all(|pair| pair[1] < pair[0]));
+        } else {
+            assert!(prices.windows(2).all(|pair| pair[1] > pair[0]));
+        }
+        // Every point stays on x * y = k, rounded in the pool's favour
+        for sample in branch {
+            assert!(sample.reserve_a as u128 * sample.reserve_b as u128 >= 2 * RESERVE as u128 * RESERVE as u128);
+        }
+    }
+}
@@ -0,0 +1,530 @@
+// test-support/src/lib.rs
+//! Native execution harness for anchor-amm instruction tests
+//!
+//! The program's entrypoint runs natively: syscall stubs serve the clock and
+//! rent sysvars and return data, route CPIs to the SPL Token and Token-2022
+//! processors or to programs added with `register_program`, and emulate the
+//! system program instructions that account creation and native SOL swaps make.
+use std::cell::RefCell;
+use std::sync::{Mutex, Once};
+
+use anchor_amm::accounts::{Initialize, Swap};
+use anchor_amm::hooks::HookParams;
+use anchor_amm::instructions::strategy::{
+    CircuitBreaker, CurveType, DynamicFeeParams, HybridState, LbpParams, OraclePegParams, PegTarget,
+    RateProvider, VolatilityState,
+};
+use anchor_amm::state::{AmmConfig, FeeMode, Pool, PoolStats, ReferrerCap, MAX_REFERRER_CAPS};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::{system_program, AccountSerialize};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+
+pub const SLOT: u64 = 1_000;
+pub const EPOCH: u64 = 7;
+pub const UNIX_TIMESTAMP: i64 = 1_500;
+
+/// Upgrade authority of the AMM in `amm_program_accounts`
+pub const ADMIN: Pubkey = Pubkey::new_from_array([0xad; 32]);
+
+/// Entrypoint of a program that CPIs can be routed to
+pub type ProgramEntry = for<'a> fn(&Pubkey, &'a [AccountInfo<'a>], &[u8]) -> ProgramResult;
+
+static PROGRAMS: Mutex<Vec<(Pubkey, ProgramEntry)>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock {
+        slot: SLOT,
+        epoch: EPOCH,
+        unix_timestamp: UNIX_TIMESTAMP,
+        ..Clock::default()
+    });
+    // Programs currently executing, innermost last
+    static CALL_STACK: RefCell<Vec<Pubkey>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+}
+
+struct TestStubs;
+
+impl SyscallStubs for TestStubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = clock() };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program_id = CALL_STACK.with(|stack| stack.borrow().last().copied().unwrap_or(anchor_amm::ID));
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = Some((program_id, data.to_vec())));
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|return_data| return_data.borrow().clone())
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        // As the runtime does, the caller can only sign for its PDAs derived from the signer seeds
+        let caller = CALL_STACK.with(|stack| stack.borrow().last().copied().unwrap_or(anchor_amm::ID));
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+        let accounts: Vec<AccountInfo> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let mut info = account_infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .expect("CPI account not passed")
+                    .clone();
+                if meta.is_signer && !info.is_signer && !signers.contains(&meta.pubkey) {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                // The callee only sees the signatures its instruction asks for
+                info.is_signer = meta.is_signer;
+                Ok(info)
+            })
+            .collect::<std::result::Result<_, _>>()?;
+
+        // As on chain, every invocation starts without return data
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = None);
+        CALL_STACK.with(|stack| stack.borrow_mut().push(instruction.program_id));
+        let program_id = &instruction.program_id;
+        let registered = PROGRAMS
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(id, _)| id == program_id)
+            .map(|(_, entry)| *entry);
+        let result = if *program_id == system_program::ID {
+            process_system(&accounts, &instruction.data)
+        } else if *program_id == spl_token::ID {
+            spl_token::processor::Processor::process(program_id, &accounts, &instruction.data)
+        } else if *program_id == spl_token_2022::ID {
+            spl_token_2022::processor::Processor::process(program_id, &accounts, &instruction.data)
+        } else if let Some(entry) = registered {
+            // Anchor entry points borrow the account list for the accounts' lifetime
+            let accounts = Box::leak(accounts.into_boxed_slice());
+            entry(program_id, accounts, &instruction.data)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        };
+        CALL_STACK.with(|stack| stack.borrow_mut().pop());
+        result
+    }
+}
+
+/// `CreateAccount`, `Assign`, `Transfer` and `Allocate` of the system program
+fn process_system(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    match instruction {
+        0 => {
+            let lamports = u64::from_le_bytes(data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(data[12..20].try_into().unwrap());
+            let owner = Pubkey::try_from(&data[20..52]).unwrap();
+            **accounts[0].try_borrow_mut_lamports()? -= lamports;
+            **accounts[1].try_borrow_mut_lamports()? += lamports;
+            accounts[1].resize(space as usize)?;
+            accounts[1].assign(&owner);
+            Ok(())
+        }
+        1 => {
+            accounts[0].assign(&Pubkey::try_from(&data[4..36]).unwrap());
+            Ok(())
+        }
+        2 => {
+            let lamports = u64::from_le_bytes(data[4..12].try_into().unwrap());
+            **accounts[0].try_borrow_mut_lamports()? -= lamports;
+            **accounts[1].try_borrow_mut_lamports()? += lamports;
+            Ok(())
+        }
+        8 => accounts[0].resize(u64::from_le_bytes(data[4..12].try_into().unwrap()) as usize),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// The clock of the current test, at `SLOT`, `EPOCH` and `UNIX_TIMESTAMP` unless set
+pub fn clock() -> Clock {
+    CLOCK.with(|clock| clock.borrow().clone())
+}
+
+/// Set the clock of the current test
+pub fn set_clock(clock: Clock) {
+    CLOCK.with(|current| *current.borrow_mut() = clock);
+}
+
+/// The clock sysvar account data
+pub fn clock_data() -> Vec<u8> {
+    let clock = clock();
+    let mut data = vec![0u8; 40];
+    data[0..8].copy_from_slice(&clock.slot.to_le_bytes());
+    data[8..16].copy_from_slice(&clock.epoch_start_timestamp.to_le_bytes());
+    data[16..24].copy_from_slice(&clock.epoch.to_le_bytes());
+    data[24..32].copy_from_slice(&clock.leader_schedule_epoch.to_le_bytes());
+    data[32..40].copy_from_slice(&clock.unix_timestamp.to_le_bytes());
+    data
+}
+
+pub fn install_stubs() {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(TestStubs));
+    });
+}
+
+/// Route CPIs to `program_id` to `entry`
+pub fn register_program(program_id: Pubkey, entry: ProgramEntry) {
+    let mut programs = PROGRAMS.lock().unwrap();
+    if !programs.iter().any(|(id, _)| *id == program_id) {
+        programs.push((program_id, entry));
+    }
+}
+
+pub fn program_error(error: impl Into<anchor_lang::error::Error>) -> ProgramError {
+    error.into().into()
+}
+
+#[derive(Clone)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self { key, owner, lamports: 1_000_000_000, data, executable: false }
+    }
+
+    pub fn program(key: Pubkey) -> Self {
+        Self { executable: true, ..Self::new(key, Pubkey::default(), vec![]) }
+    }
+
+    /// An Anchor account, zero-padded to `len`
+    pub fn anchor<T: AccountSerialize>(key: Pubkey, owner: Pubkey, account: &T, len: usize) -> Self {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(len, 0);
+        Self::new(key, owner, data)
+    }
+
+    pub fn token_amount(&self) -> u64 {
+        spl_token::state::Account::unpack(&self.data).unwrap().amount
+    }
+
+    pub fn mint_supply(&self) -> u64 {
+        spl_token::state::Mint::unpack(&self.data).unwrap().supply
+    }
+}
+
+/// The account with address `key`
+pub fn find(accounts: &[TestAccount], key: Pubkey) -> &TestAccount {
+    accounts.iter().find(|account| account.key == key).unwrap()
+}
+
+pub fn token_account(key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) -> TestAccount {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    TestAccount::new(key, spl_token::ID, data)
+}
+
+/// A 6-decimal mint
+pub fn mint_account(key: Pubkey, authority: Pubkey, supply: u64) -> TestAccount {
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(authority),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    TestAccount::new(key, spl_token::ID, data)
+}
+
+/// Run an instruction of the AMM over `accounts`
+///
+/// Like the runtime, a failed instruction leaves the accounts as they were.
+pub fn execute(accounts: &mut [TestAccount], metas: &[AccountMeta], data: &[u8]) -> ProgramResult {
+    let snapshot = accounts.to_vec();
+    let mut buffers: Vec<Vec<u64>> = accounts.iter().map(serialize).collect();
+    let result = {
+        let infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .zip(buffers.iter_mut())
+            .map(|(account, buffer)| {
+                let (key, data) = account_fields(buffer);
+                // Like the runtime, an account passed twice gets the privileges of both metas
+                let metas = metas.iter().filter(|meta| meta.pubkey == account.key);
+                AccountInfo::new(
+                    key,
+                    metas.clone().any(|meta| meta.is_signer),
+                    metas.clone().any(|meta| meta.is_writable),
+                    &mut account.lamports,
+                    data,
+                    &account.owner,
+                    account.executable,
+                    0,
+                )
+            })
+            .collect();
+        let ordered: Vec<AccountInfo> = metas
+            .iter()
+            .map(|meta| infos.iter().find(|info| *info.key == meta.pubkey).unwrap().clone())
+            .collect();
+
+        anchor_amm::entry(&anchor_amm::ID, &ordered, data)
+    };
+
+    // Accounts may have been resized
+    for (account, buffer) in accounts.iter_mut().zip(buffers.iter_mut()) {
+        let bytes = as_bytes(buffer);
+        let len = u64::from_le_bytes(bytes[DATA_OFFSET - 8..DATA_OFFSET].try_into().unwrap()) as usize;
+        account.data = bytes[DATA_OFFSET..DATA_OFFSET + len].to_vec();
+    }
+    if result.is_err() {
+        accounts.clone_from_slice(&snapshot);
+    }
+    result
+}
+
+// Offsets in the runtime's input buffer layout that `AccountInfo::resize` relies on:
+// the original data length sits right before the key, the data length right before the data
+const KEY_OFFSET: usize = 8;
+const DATA_OFFSET: usize = 48;
+
+/// `account` in a buffer with room for its data to grow, 8-byte aligned like the runtime's
+fn serialize(account: &TestAccount) -> Vec<u64> {
+    let len = account.data.len();
+    let mut buffer = vec![0u64; (DATA_OFFSET + len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8)];
+    let bytes = as_bytes(&mut buffer);
+    bytes[KEY_OFFSET - 4..KEY_OFFSET].copy_from_slice(&(len as u32).to_le_bytes());
+    bytes[KEY_OFFSET..KEY_OFFSET + 32].copy_from_slice(account.key.as_ref());
+    bytes[DATA_OFFSET - 8..DATA_OFFSET].copy_from_slice(&(len as u64).to_le_bytes());
+    bytes[DATA_OFFSET..DATA_OFFSET + len].copy_from_slice(&account.data);
+    buffer
+}
+
+/// The key and data of an account serialized into `buffer`
+fn account_fields(buffer: &mut [u64]) -> (&Pubkey, &mut [u8]) {
+    let bytes = as_bytes(buffer);
+    let len = u64::from_le_bytes(bytes[DATA_OFFSET - 8..DATA_OFFSET].try_into().unwrap()) as usize;
+    let (header, data) = bytes.split_at_mut(DATA_OFFSET);
+    // SAFETY: `Pubkey` is a transparent 32-byte array, which has no alignment requirement
+    let key = unsafe { &*(header[KEY_OFFSET..].as_ptr() as *const Pubkey) };
+    (key, &mut data[..len])
+}
+
+fn as_bytes(buffer: &mut [u64]) -> &mut [u8] {
+    // SAFETY: any bytes are a valid u64 and the other way around
+    unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8) }
+}
+
+/// A pool over two fresh mints with 1M of each token (6 and 9 decimals)
+pub fn pool(curve: CurveType) -> Pool {
+    Pool {
+        token_a: Pubkey::new_unique(),
+        token_b: Pubkey::new_unique(),
+        reserve_a: 1_000_000_000_000,
+        reserve_b: 1_000_000_000_000_000,
+        lp_supply: 1_000_000_000,
+        fee: 30,
+        bump: 255,
+        fee_mode: FeeMode::Static,
+        dynamic_fee: DynamicFeeParams::default(),
+        volatility: VolatilityState::default(),
+        creator: Pubkey::new_unique(),
+        open_orders: 0,
+        curve,
+        decimals_a: 6,
+        decimals_b: 9,
+        oracle_peg: OraclePegParams::default(),
+        lmsr_b: 0,
+        lbp: LbpParams::default(),
+        paused: false,
+        amm_config: Pubkey::new_unique(),
+        fee_tier: 0,
+        tick_spacing: 1,
+        vault_a: Pubkey::new_unique(),
+        vault_b: Pubkey::new_unique(),
+        hook: HookParams::default(),
+        circuit_breaker: CircuitBreaker::default(),
+        stats: PoolStats::default(),
+        rate_a: RateProvider::default(),
+        rate_b: RateProvider::default(),
+        permissioned: false,
+        hybrid: HybridState::default(),
+        lp_mint: Pubkey::new_unique(),
+        peg_target: PegTarget::default(),
+        legacy_seeds: false,
+    }
+}
+
+/// Address of `pool`, the PDA of its mints and fee tier, setting `pool.bump` to match
+///
+/// CPIs fail unless the pool signs with the seeds of its own address, as on chain.
+pub fn pool_address(pool: &mut Pool) -> Pubkey {
+    let seeds: &[&[u8]] = &[b"pool", pool.token_a.as_ref(), pool.token_b.as_ref(), &pool.tier_seed()];
+    let (address, bump) = Pubkey::find_program_address(seeds, &anchor_amm::ID);
+    pool.bump = bump;
+    address
+}
+
+/// Program data account of the AMM, which admin instructions check the upgrade authority of
+pub fn program_data_key() -> Pubkey {
+    Pubkey::find_program_address(&[anchor_amm::ID.as_ref()], &ProgramData::owner()).0
+}
+
+/// The AMM deployed with the upgradeable loader, with `ADMIN` as its upgrade authority
+pub fn amm_program_accounts() -> [TestAccount; 2] {
+    // Bincode layouts of `UpgradeableLoaderState::Program` and `ProgramData`
+    let mut program = 2u32.to_le_bytes().to_vec();
+    program.extend_from_slice(program_data_key().as_ref());
+    let mut program_data = 3u32.to_le_bytes().to_vec();
+    program_data.extend_from_slice(&0u64.to_le_bytes());
+    program_data.push(1);
+    program_data.extend_from_slice(ADMIN.as_ref());
+    [
+        TestAccount { executable: true, ..TestAccount::new(anchor_amm::ID, ProgramData::owner(), program) },
+        TestAccount::new(program_data_key(), ProgramData::owner(), program_data),
+    ]
+}
+
+/// The accounts every test of `pool` at `pool_key` passes: the pool, its vaults and the programs
+pub fn pool_accounts(pool_key: Pubkey, pool: &Pool) -> Vec<TestAccount> {
+    let mut accounts = vec![
+        TestAccount::anchor(pool_key, anchor_amm::ID, pool, Pool::LEN),
+        token_account(pool.vault_a, pool.token_a, pool_key, pool.reserve_a),
+        token_account(pool.vault_b, pool.token_b, pool_key, pool.reserve_b),
+        TestAccount::program(spl_token::ID),
+    ];
+    accounts.extend(amm_program_accounts());
+    accounts
+}
+
+/// Accounts of a swap of token A for token B on `pool`, with every optional account left out
+pub fn swap_accounts(pool_key: Pubkey, pool: &Pool, user: Pubkey, user_in: Pubkey, user_out: Pubkey) -> Swap {
+    Swap {
+        pool: pool_key,
+        vault_in: pool.vault_a,
+        vault_out: pool.vault_b,
+        user_in: Some(user_in),
+        user_out: Some(user_out),
+        user,
+        oracle: None,
+        rate_provider_a: None,
+        rate_provider_b: None,
+        hook_program: None,
+        allowlist: None,
+        allowlist_entry: None,
+        token_program: spl_token::ID,
+        wsol_account: None,
+        native_mint: None,
+        system_program: None,
+        amm_config: None,
+        referrer: None,
+        order_book: None,
+    }
+}
+
+/// A fee tier at `index` charging 30 bps, without referrals
+pub fn amm_config(index: u16) -> AmmConfig {
+    AmmConfig {
+        index,
+        trade_fee_bps: 30,
+        tick_spacing: 1,
+        bump: 255,
+        max_referral_fee_bps: 0,
+        referrer_caps: [ReferrerCap::default(); MAX_REFERRER_CAPS],
+        lp_uri_base: String::new(),
+    }
+}
+
+/// Accounts of `initialize` for a pool of two fresh 6-decimal mints in `config`, funded by `payer`
+///
+/// Every account `initialize` creates is left empty, and the optional allowlist out.
+pub fn initialize_accounts(config: &AmmConfig, payer: Pubkey) -> (Vec<TestAccount>, Initialize) {
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &anchor_amm::ID).0;
+    let (token_a, token_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let config_key = pda(&[b"amm_config", &config.index.to_le_bytes()]);
+    let metas = Initialize {
+        pool: pda(&[b"pool", token_a.as_ref(), token_b.as_ref(), &config.index.to_le_bytes()]),
+        amm_config: config_key,
+        token_a,
+        token_b,
+        vault_a: Pubkey::new_unique(),
+        vault_b: Pubkey::new_unique(),
+        lp_mint: Pubkey::new_unique(),
+        pool_registry: pda(&[b"pool_registry"]),
+        registry_page: pda(&[b"registry_page", &0u32.to_le_bytes()]),
+        mint_pools_a: pda(&[b"mint_pools", token_a.as_ref()]),
+        mint_pools_b: pda(&[b"mint_pools", token_b.as_ref()]),
+        allowlist: None,
+        launch_a: pda(&[b"launch", token_a.as_ref()]),
+        launch_b: pda(&[b"launch", token_b.as_ref()]),
+        rate_provider_a: None,
+        rate_provider_b: None,
+        market: None,
+        payer,
+        system_program: system_program::ID,
+        token_program: spl_token::ID,
+        lp_token_program: spl_token_2022::ID,
+    };
+
+    let uncreated = |key| TestAccount { lamports: 0, ..TestAccount::new(key, system_program::ID, vec![]) };
+    let mut accounts = vec![
+        TestAccount::anchor(config_key, anchor_amm::ID, config, AmmConfig::LEN),
+        mint_account(token_a, Pubkey::new_unique(), 0),
+        mint_account(token_b, Pubkey::new_unique(), 0),
+        TestAccount::new(payer, system_program::ID, vec![]),
+        TestAccount::program(system_program::ID),
+        TestAccount::program(spl_token::ID),
+        TestAccount::program(spl_token_2022::ID),
+    ];
+    accounts.extend(
+        [
+            metas.pool,
+            metas.vault_a,
+            metas.vault_b,
+            metas.lp_mint,
+            metas.pool_registry,
+            metas.registry_page,
+            metas.mint_pools_a,
+            metas.mint_pools_b,
+            metas.launch_a,
+            metas.launch_b,
+        ]
+        .map(uncreated),
+    );
+    accounts.extend(amm_program_accounts());
+    (accounts, metas)
+}
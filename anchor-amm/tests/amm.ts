@@ -10,10 +10,13 @@ import {
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   createMint,
   createAccount,
   mintTo,
   getAccount,
+  getMint,
+  getTokenMetadata,
 } from "@solana/spl-token";
 import { expect } from "chai";
 
@@ -25,6 +28,7 @@ describe("amm", () => {
   let payer = provider.wallet as anchor.Wallet;
   let mintA: PublicKey;
   let mintB: PublicKey;
+  let ammConfig: PublicKey;
   let pool: PublicKey;
   let vaultA: PublicKey;
   let vaultB: PublicKey;
@@ -32,6 +36,10 @@ describe("amm", () => {
   let vaultAKeypair: Keypair;
   let vaultBKeypair: Keypair;
   let lpMintKeypair: Keypair;
+  let poolRegistry: PublicKey;
+  let registryPage: PublicKey;
+  let mintPoolsA: PublicKey;
+  let mintPoolsB: PublicKey;
 
   let user: Keypair;
   let userA: PublicKey;
@@ -54,13 +62,40 @@ describe("amm", () => {
       null,
       6
     );
+    // Pools take their mints sorted
+    if (Buffer.compare(mintA.toBuffer(), mintB.toBuffer()) > 0) {
+      [mintA, mintB] = [mintB, mintA];
+    }
 
-    // Derive PDAs
+    // Derive PDAs (fee tier 0)
+    const feeTier = Buffer.alloc(2);
+    [ammConfig] = await PublicKey.findProgramAddress(
+      [Buffer.from("amm_config"), feeTier],
+      program.programId
+    );
     [pool] = await PublicKey.findProgramAddress(
-      [Buffer.from("pool"), mintA.toBuffer(), mintB.toBuffer()],
+      [Buffer.from("pool"), mintA.toBuffer(), mintB.toBuffer(), feeTier],
       program.programId
     );
-    
+
+    // Pool discovery accounts; this is the first pool, so it goes to registry page 0
+    [poolRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_registry")],
+      program.programId
+    );
+    [registryPage] = PublicKey.findProgramAddressSync(
+      [Buffer.from("registry_page"), Buffer.alloc(4)],
+      program.programId
+    );
+    [mintPoolsA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_pools"), mintA.toBuffer()],
+      program.programId
+    );
+    [mintPoolsB] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_pools"), mintB.toBuffer()],
+      program.programId
+    );
+
     // Create new keypairs for vaults and LP mint (not PDAs)
     vaultAKeypair = Keypair.generate();
     vaultBKeypair = Keypair.generate();
@@ -105,35 +140,80 @@ describe("amm", () => {
     );
   });
 
+  it("Create fee tier", async () => {
+    // The test validator deploys the program with the provider wallet as upgrade authority
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+
+    await program.methods
+      .createAmmConfig(0, new BN(30), 10) // 0.3 % fee, tick spacing 10
+      .accounts({
+        ammConfig,
+        admin: payer.publicKey,
+        program: program.programId,
+        programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  });
+
   it("Initialize pool", async () => {
     await program.methods
-      .initialize(new BN(30)) // 0.3 % fee
+      .initialize(null, { constantProduct: {} }, null, null, null, null, null, null, null) // static fee mode, no hook, no rate providers or hybrid target
       .accounts({
         pool,
+        ammConfig,
         tokenA: mintA,
         tokenB: mintB,
         vaultA,
         vaultB,
         lpMint,
+        poolRegistry,
+        registryPage,
+        mintPoolsA,
+        mintPoolsB,
         payer: payer.publicKey,
         systemProgram: SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
+        lpTokenProgram: TOKEN_2022_PROGRAM_ID,
       })
       .signers([vaultAKeypair, vaultBKeypair, lpMintKeypair])
       .rpc();
+
+    // The pool is listed in the registry and under both of its mints
+    const page = await program.account.registryPage.fetch(registryPage);
+    expect(page.entries.map((entry) => entry.pool.toBase58())).to.include(pool.toBase58());
+    const listA = await program.account.mintPools.fetch(mintPoolsA);
+    const listB = await program.account.mintPools.fetch(mintPoolsB);
+    expect(listA.pools.map((key) => key.toBase58())).to.deep.equal([pool.toBase58()]);
+    expect(listB.pools.map((key) => key.toBase58())).to.deep.equal([pool.toBase58()]);
       
+    // The LP mint is a Token-2022 mint named after the pair, with the mean of the token decimals
+    const lpMintAcc = await getMint(provider.connection, lpMint, undefined, TOKEN_2022_PROGRAM_ID);
+    expect(lpMintAcc.decimals).to.equal(6);
+    const metadata = await getTokenMetadata(provider.connection, lpMint);
+    const label = (mint: PublicKey) => mint.toBase58().slice(0, 4);
+    expect(metadata.name).to.equal(`${label(mintA)}-${label(mintB)} LP`);
+    expect(metadata.symbol).to.equal(`${label(mintA)}${label(mintB)}LP`);
+    expect(metadata.uri).to.include(pool.toBase58());
+
     // Create LP token account after pool is initialized
     userLp = await createAccount(
       provider.connection,
       payer.payer,
       lpMint,
-      user.publicKey
+      user.publicKey,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
     );
   });
 
   it("Deposit liquidity", async () => {
     await program.methods
-      .deposit(new BN(100_000_000), new BN(100_000_000))
+      .deposit(new BN(100_000_000), new BN(100_000_000), [])
       .accounts({
         pool,
         vaultA,
@@ -143,19 +223,26 @@ describe("amm", () => {
         userB,
         userLp,
         user: user.publicKey,
+        hookProgram: null,
+        allowlist: null,
+        allowlistEntry: null,
         tokenProgram: TOKEN_PROGRAM_ID,
+        lpTokenProgram: TOKEN_2022_PROGRAM_ID,
+        wsolAccount: null,
+        nativeMint: null,
+        systemProgram: null,
       })
       .signers([user])
       .rpc();
 
-    const lpAcc = await getAccount(provider.connection, userLp);
+    const lpAcc = await getAccount(provider.connection, userLp, undefined, TOKEN_2022_PROGRAM_ID);
     expect(lpAcc.amount.toString()).to.equal("100000000");
   });
 
   it("Swap A for B", async () => {
     const before = await getAccount(provider.connection, userB);
     await program.methods
-      .swap(new BN(10_000_000), new BN(8_000_000)) // More realistic slippage tolerance
+      .swap(new BN(10_000_000), new BN(8_000_000), [], new BN(0)) // More realistic slippage tolerance
       .accounts({
         pool,
         vaultIn: vaultA,
@@ -163,7 +250,18 @@ describe("amm", () => {
         userIn: userA,
         userOut: userB,
         user: user.publicKey,
+        oracle: null,
+        rateProviderA: null,
+        rateProviderB: null,
+        hookProgram: null,
+        allowlist: null,
+        allowlistEntry: null,
         tokenProgram: TOKEN_PROGRAM_ID,
+        wsolAccount: null,
+        nativeMint: null,
+        systemProgram: null,
+        ammConfig: null,
+        referrer: null,
       })
       .signers([user])
       .rpc();
@@ -185,7 +283,7 @@ describe("amm", () => {
 
   it("Withdraw half of liquidity", async () => {
     await program.methods
-      .withdraw(new BN(50_000_000)) // 50 % of LP
+      .withdraw(new BN(50_000_000), []) // 50 % of LP
       .accounts({
         pool,
         vaultA,
@@ -195,19 +293,26 @@ describe("amm", () => {
         userA,
         userB,
         user: user.publicKey,
+        hookProgram: null,
+        allowlist: null,
+        allowlistEntry: null,
         tokenProgram: TOKEN_PROGRAM_ID,
+        lpTokenProgram: TOKEN_2022_PROGRAM_ID,
+        wsolAccount: null,
+        nativeMint: null,
+        systemProgram: null,
       })
       .signers([user])
       .rpc();
 
-    const lpAcc = await getAccount(provider.connection, userLp);
+    const lpAcc = await getAccount(provider.connection, userLp, undefined, TOKEN_2022_PROGRAM_ID);
     expect(lpAcc.amount.toString()).to.equal("50000000");
   });
 
   it("Fail swap with too high slippage", async () => {
     try {
       await program.methods
-        .swap(new BN(10_000_000), new BN(11_000_000))
+        .swap(new BN(10_000_000), new BN(11_000_000), [], new BN(0))
         .accounts({
           pool,
           vaultIn: vaultA,
@@ -215,7 +320,18 @@ describe("amm", () => {
           userIn: userA,
           userOut: userB,
           user: user.publicKey,
+          oracle: null,
+          rateProviderA: null,
+          rateProviderB: null,
+          hookProgram: null,
+          allowlist: null,
+          allowlistEntry: null,
           tokenProgram: TOKEN_PROGRAM_ID,
+          wsolAccount: null,
+          nativeMint: null,
+          systemProgram: null,
+          ammConfig: null,
+          referrer: null,
         })
         .signers([user])
         .rpc();
@@ -224,4 +340,59 @@ describe("amm", () => {
       expect(err.toString()).to.include("SlippageExceeded");
     }
   });
+
+  it("Close pool once all liquidity is withdrawn", async () => {
+    await program.methods
+      .withdraw(new BN(50_000_000), []) // Remaining LP
+      .accounts({
+        pool,
+        vaultA,
+        vaultB,
+        lpMint,
+        userLp,
+        userA,
+        userB,
+        user: user.publicKey,
+        hookProgram: null,
+        allowlist: null,
+        allowlistEntry: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        lpTokenProgram: TOKEN_2022_PROGRAM_ID,
+        wsolAccount: null,
+        nativeMint: null,
+        systemProgram: null,
+      })
+      .signers([user])
+      .rpc();
+
+    const creatorA = await createAccount(
+      provider.connection,
+      payer.payer,
+      mintA,
+      payer.publicKey
+    );
+    const creatorB = await createAccount(
+      provider.connection,
+      payer.payer,
+      mintB,
+      payer.publicKey
+    );
+
+    await program.methods
+      .closePool()
+      .accounts({
+        pool,
+        vaultA,
+        vaultB,
+        creatorA,
+        creatorB,
+        creator: payer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    expect(await provider.connection.getAccountInfo(pool)).to.be.null;
+    expect(await provider.connection.getAccountInfo(vaultA)).to.be.null;
+    expect(await provider.connection.getAccountInfo(vaultB)).to.be.null;
+  });
 });